@@ -0,0 +1,279 @@
+use crate::config::ApiConfig;
+use crate::engine::metrics;
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+
+/// Largest request line plus headers accepted
+const MAX_HEAD: usize = 8 * 1024;
+/// Largest request body read (and thrown away, no endpoint takes one)
+const MAX_BODY: usize = 64 * 1024;
+/// Longest a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest the TUI may take to answer (it polls once per frame)
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// One authenticated API call, handed to the TUI, which owns the engine
+/// state and sends the engine its commands
+pub struct ApiRequest {
+    pub method: String,
+    /// Path segments, percent-decoded ("/macros/My%20Macro" -> ["macros", "My Macro"])
+    pub path: Vec<String>,
+    pub reply: oneshot::Sender<ApiResponse>,
+}
+
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+    /// Plain text body sent instead of `body` (/metrics)
+    pub text: Option<String>,
+}
+
+impl ApiResponse {
+    pub fn ok(body: Value) -> Self {
+        Self {
+            status: 200,
+            body,
+            text: None,
+        }
+    }
+
+    /// Passed on to the engine, which acts on it in its own time
+    pub fn accepted(body: Value) -> Self {
+        Self {
+            status: 202,
+            ..Self::ok(body)
+        }
+    }
+
+    pub fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+            text: None,
+        }
+    }
+
+    fn text(text: String) -> Self {
+        Self {
+            status: 200,
+            body: Value::Null,
+            text: Some(text),
+        }
+    }
+}
+
+/// Serve the API on 127.0.0.1 until the runtime shuts down, passing every
+/// request with the right token on to `requests`
+pub async fn serve(config: ApiConfig, requests: mpsc::UnboundedSender<ApiRequest>) -> Result<()> {
+    if config.token.is_empty() {
+        bail!("api.token is empty, set one to turn the HTTP API on");
+    }
+    let listener = TcpListener::bind(("127.0.0.1", config.port))
+        .await
+        .with_context(|| format!("Failed to listen on 127.0.0.1:{}", config.port))?;
+    log::info!("HTTP API listening on 127.0.0.1:{}", config.port);
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                log::warn!("HTTP API accept failed: {}", e);
+                continue;
+            }
+        };
+        let token = config.token.clone();
+        let requests = requests.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &token, &requests).await {
+                log::debug!("HTTP API connection failed: {:#}", e);
+            }
+        });
+    }
+}
+
+/// The parts of an HTTP request the API looks at
+struct Request {
+    method: String,
+    target: String,
+    authorization: Option<String>,
+}
+
+/// Answer one request; every connection is closed after its response
+async fn handle_connection(
+    mut stream: TcpStream,
+    token: &str,
+    requests: &mpsc::UnboundedSender<ApiRequest>,
+) -> Result<()> {
+    let response = match tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream)).await {
+        Ok(Ok(request)) => respond(request, token, requests).await,
+        Ok(Err(e)) => ApiResponse::error(400, format!("{:#}", e)),
+        Err(_) => ApiResponse::error(408, "Request took too long"),
+    };
+    write_response(&mut stream, &response).await
+}
+
+async fn respond(
+    request: Request,
+    token: &str,
+    requests: &mpsc::UnboundedSender<ApiRequest>,
+) -> ApiResponse {
+    let authorized = request
+        .authorization
+        .as_deref()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| same_secret(given.trim(), token));
+    if !authorized {
+        return ApiResponse::error(401, "Missing or wrong bearer token");
+    }
+
+    let path = request.target.split('?').next().unwrap_or_default();
+    let Some(path) = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect::<Option<Vec<String>>>()
+    else {
+        return ApiResponse::error(400, "Malformed path");
+    };
+
+    // Counters live in the engine, the TUI doesn't need to answer this one
+    if request.method == "GET" && path == ["metrics"] {
+        return ApiResponse::text(metrics::render());
+    }
+
+    let (reply, reply_rx) = oneshot::channel();
+    let request = ApiRequest {
+        method: request.method,
+        path,
+        reply,
+    };
+    if requests.send(request).is_err() {
+        return ApiResponse::error(503, "mouse-mapper is shutting down");
+    }
+    match tokio::time::timeout(REPLY_TIMEOUT, reply_rx).await {
+        Ok(Ok(response)) => response,
+        _ => ApiResponse::error(503, "mouse-mapper didn't answer"),
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<Request> {
+    let mut buf = Vec::new();
+    let head_len = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > MAX_HEAD {
+            bail!("Request headers too large");
+        }
+        let mut chunk = [0u8; 1024];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            bail!("Connection closed mid-request");
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_len]);
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        bail!("Malformed request line");
+    };
+    let mut authorization = None;
+    let mut content_length = 0;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if name.eq_ignore_ascii_case("authorization") {
+            authorization = Some(value.trim().to_string());
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.trim().parse().context("Bad Content-Length")?;
+        }
+    }
+
+    // Read the body anyway: closing with unread data makes the client see a
+    // reset instead of the response
+    if content_length > MAX_BODY {
+        bail!("Request body too large");
+    }
+    let mut remaining = content_length.saturating_sub(buf.len() - head_len);
+    let mut chunk = [0u8; 1024];
+    while remaining > 0 {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        remaining = remaining.saturating_sub(n);
+    }
+
+    Ok(Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        authorization,
+    })
+}
+
+async fn write_response(stream: &mut TcpStream, response: &ApiResponse) -> Result<()> {
+    let (content_type, body) = match &response.text {
+        // The Prometheus text exposition format
+        Some(text) => ("text/plain; version=0.0.4", text.clone()),
+        None => ("application/json", response.body.to_string()),
+    };
+    let reason = match response.status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        _ => "Service Unavailable",
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: {}\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        response.status,
+        reason,
+        content_type,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Compare in time independent of where the first difference is
+fn same_secret(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Decode %XX escapes of a path segment (None if malformed or not UTF-8)
+fn percent_decode(segment: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut rest = segment.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
@@ -0,0 +1,314 @@
+use crate::config::Config;
+use crate::logging;
+use anyhow::{bail, Context, Result};
+use std::ffi::CString;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path, PathBuf};
+
+/// Name of the config file inside a backup, whatever it's called on disk
+const CONFIG_MEMBER: &str = "config.toml";
+const PROFILES_DIR: &str = "profiles";
+const BLOCK: usize = 512;
+
+/// What `backup` archived
+pub struct BackupSummary {
+    pub files: usize,
+    /// Include files outside the config's directory, which aren't archived
+    pub skipped: Vec<String>,
+}
+
+/// Archive the config file, its profiles/ directory and its include files
+/// into a tar file at `archive` (written next to it first, then renamed
+/// into place)
+pub fn backup(archive: &Path, config: &Config) -> Result<BackupSummary> {
+    let config_path = Config::config_path()?;
+    if !config_path.is_file() {
+        bail!(
+            "There is no config file at {} to back up",
+            config_path.display()
+        );
+    }
+    let dir = config_path.parent().unwrap_or(Path::new("."));
+    let mut members = vec![(CONFIG_MEMBER.to_string(), config_path.clone())];
+    if let Some(profiles) = Config::profile_dir(&config_path) {
+        for file in Config::profile_files(&profiles)? {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            members.push((format!("{}/{}", PROFILES_DIR, name), file.clone()));
+        }
+    }
+    let mut skipped = Vec::new();
+    for include in &config.include {
+        if is_inside(Path::new(include)) && include != CONFIG_MEMBER {
+            members.push((include.clone(), dir.join(include)));
+        } else {
+            skipped.push(include.clone());
+        }
+    }
+
+    let mut tar = Vec::new();
+    for (name, path) in &members {
+        let data =
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        append(&mut tar, name, &data)?;
+    }
+    // Two empty blocks end the archive
+    tar.resize(tar.len() + 2 * BLOCK, 0);
+
+    if let Some(parent) = archive.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let partial = archive.with_extension("partial");
+    std::fs::write(&partial, &tar)
+        .with_context(|| format!("Failed to write {}", partial.display()))?;
+    std::fs::rename(&partial, archive)
+        .with_context(|| format!("Failed to write {}", archive.display()))?;
+    Ok(BackupSummary {
+        files: members.len(),
+        skipped,
+    })
+}
+
+/// What `restore` replaced
+pub struct RestoreSummary {
+    pub files: usize,
+    /// Backup of the config as it was before, if there was one
+    pub previous: Option<PathBuf>,
+}
+
+/// Replace the config with the one in the backup at `archive`. The backup
+/// is unpacked next to the config and loaded there first, so a damaged or
+/// invalid one changes nothing; then each file, and the profiles/ directory
+/// as a whole, is moved into place with a single rename. The config in use
+/// until then is backed up to the state directory.
+pub fn restore(archive: &Path) -> Result<RestoreSummary> {
+    let data =
+        std::fs::read(archive).with_context(|| format!("Failed to read {}", archive.display()))?;
+    let members = read(&data).with_context(|| format!("{} isn't a backup", archive.display()))?;
+    if !members.iter().any(|(name, _)| name == CONFIG_MEMBER) {
+        bail!("{} has no {}", archive.display(), CONFIG_MEMBER);
+    }
+
+    let config_path = Config::config_path()?;
+    let dir = config_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let staging = dir.join(format!(".restore-{}", std::process::id()));
+    let result = stage(&members, &staging).and_then(|()| {
+        // Includes that weren't archived are still where the config is
+        Config::load_resolving(&staging.join(CONFIG_MEMBER), |include| {
+            if members.iter().any(|(name, _)| name == include) {
+                staging.join(include)
+            } else {
+                dir.join(include)
+            }
+        })
+        .context("The backup's config doesn't load")?;
+        // A config that doesn't load is still kept, without its includes
+        let previous = if config_path.is_file() {
+            let current = Config::load_from(&config_path).unwrap_or_default();
+            let path = default_path("pre-restore");
+            backup(&path, &current)?;
+            Some(path)
+        } else {
+            None
+        };
+        apply(&members, &staging, &dir, &config_path)?;
+        Ok(previous)
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    Ok(RestoreSummary {
+        files: members.len(),
+        previous: result?,
+    })
+}
+
+/// Where a backup goes when no file is given: the backups/ directory of the
+/// state directory, named after `kind` and the local time
+pub fn default_path(kind: &str) -> PathBuf {
+    logging::state_dir()
+        .join("backups")
+        .join(format!("{}-{}.tar", kind, local_timestamp()))
+}
+
+/// Write the members under `staging`
+fn stage(members: &[(String, Vec<u8>)], staging: &Path) -> Result<()> {
+    let _ = std::fs::remove_dir_all(staging);
+    for (name, data) in members {
+        let path = staging.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, data)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Move the staged files over the config's, the config file itself last
+fn apply(
+    members: &[(String, Vec<u8>)],
+    staging: &Path,
+    dir: &Path,
+    config_path: &Path,
+) -> Result<()> {
+    for (name, _) in members {
+        if name == CONFIG_MEMBER || name.starts_with(&format!("{}/", PROFILES_DIR)) {
+            continue;
+        }
+        let target = dir.join(name);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        rename(&staging.join(name), &target)?;
+    }
+
+    // The profiles in the backup replace all of the current ones, so the
+    // directory is swapped, not merged (the old one ends up in staging)
+    let new_profiles = staging.join(PROFILES_DIR);
+    let profiles = dir.join(PROFILES_DIR);
+    match (new_profiles.is_dir(), profiles.is_dir()) {
+        (true, true) => exchange(&new_profiles, &profiles)?,
+        (true, false) => rename(&new_profiles, &profiles)?,
+        (false, true) => rename(&profiles, &staging.join("profiles.old"))?,
+        (false, false) => {}
+    }
+
+    rename(&staging.join(CONFIG_MEMBER), config_path)
+}
+
+fn rename(from: &Path, to: &Path) -> Result<()> {
+    std::fs::rename(from, to)
+        .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))
+}
+
+/// Swap two directories in one step
+fn exchange(a: &Path, b: &Path) -> Result<()> {
+    let c_a = CString::new(a.as_os_str().as_bytes())?;
+    let c_b = CString::new(b.as_os_str().as_bytes())?;
+    let ret = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            c_a.as_ptr(),
+            libc::AT_FDCWD,
+            c_b.as_ptr(),
+            libc::RENAME_EXCHANGE,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error())
+            .with_context(|| format!("Failed to swap {} and {}", a.display(), b.display()));
+    }
+    Ok(())
+}
+
+/// Whether a relative path stays inside the directory it's relative to
+fn is_inside(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Add a file to a ustar archive
+fn append(tar: &mut Vec<u8>, name: &str, data: &[u8]) -> Result<()> {
+    if name.len() >= 100 {
+        bail!("{} is too long a path for a backup", name);
+    }
+    let mut header = [0u8; BLOCK];
+    let field = |header: &mut [u8; BLOCK], at: usize, value: &[u8]| {
+        header[at..at + value.len()].copy_from_slice(value);
+    };
+    field(&mut header, 0, name.as_bytes());
+    field(&mut header, 100, b"0000644\0");
+    field(&mut header, 108, b"0000000\0");
+    field(&mut header, 116, b"0000000\0");
+    field(
+        &mut header,
+        124,
+        format!("{:011o}\0", data.len()).as_bytes(),
+    );
+    let mtime = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    field(&mut header, 136, format!("{:011o}\0", mtime).as_bytes());
+    header[156] = b'0';
+    field(&mut header, 257, b"ustar\0");
+    field(&mut header, 263, b"00");
+    // The checksum is taken with its own field as spaces
+    field(&mut header, 148, b"        ");
+    let sum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+    field(&mut header, 148, format!("{:06o}\0 ", sum).as_bytes());
+
+    tar.extend_from_slice(&header);
+    tar.extend_from_slice(data);
+    tar.resize(tar.len().next_multiple_of(BLOCK), 0);
+    Ok(())
+}
+
+/// The regular files of a tar archive, with their names; anything that
+/// would land outside the directory it's unpacked in is refused
+fn read(mut tar: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut members = Vec::new();
+    while tar.len() >= BLOCK {
+        let header = &tar[..BLOCK];
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let text = |range: std::ops::Range<usize>| {
+            let bytes = &header[range];
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+        };
+        let stored: u32 = u32::from_str_radix(&text(148..156), 8).context("Bad header checksum")?;
+        let sum: u32 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u32::from(b)
+                }
+            })
+            .sum();
+        if sum != stored {
+            bail!("Corrupt header (checksum mismatch)");
+        }
+        let size = usize::from_str_radix(&text(124..136), 8).context("Bad file size")?;
+        let name = match text(345..500) {
+            prefix if prefix.is_empty() => text(0..100),
+            prefix => format!("{}/{}", prefix, text(0..100)),
+        };
+        let end = BLOCK + size;
+        if tar.len() < end {
+            bail!("Truncated at {}", name);
+        }
+        // Regular files only; directories are created as needed
+        if matches!(header[156], b'0' | 0) {
+            let name = name.trim_start_matches("./").to_string();
+            if !is_inside(Path::new(&name)) {
+                bail!("{} points outside the config directory", name);
+            }
+            members.push((name, tar[BLOCK..end].to_vec()));
+        }
+        tar = &tar[end.next_multiple_of(BLOCK).min(tar.len())..];
+    }
+    Ok(members)
+}
+
+/// Local time as "20240131-235959"
+fn local_timestamp() -> String {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    unsafe { libc::localtime_r(&now, &mut tm) };
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
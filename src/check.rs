@@ -0,0 +1,402 @@
+use crate::config::{
+    BindingOutput, ClickStep, Config, MacroAction, MacroDef, MacroType, Profile, Sound, WarpConfig,
+};
+use crate::device::leds;
+use crate::engine::macros::{expand_actions, MAX_SPEED, MIN_SPEED};
+use crate::engine::mapper::{parse_key_name, unknown_macro_keys};
+use crate::engine::sounds::TONE_RANGE;
+use crate::engine::warp::bad_warp_targets;
+use crate::tui::app::{binding_output_summary, macro_type_name, mode_switch_name};
+use ratatui::style::Color;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Effective mappings of the checked profiles and everything wrong with them
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    /// Printable table, one profile after another
+    pub lines: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Resolve every binding of the named profile (all profiles if None) the way
+/// the engine would, without opening any device
+pub fn check_config(config: &Config, profile: Option<&str>) -> CheckReport {
+    let mut report = CheckReport::default();
+
+    if let Some(active) = &config.active_profile
+        && !config.profiles.iter().any(|p| &p.name == active)
+    {
+        report
+            .warnings
+            .push(format!("active_profile '{}' doesn't exist", active));
+    }
+
+    if !(MIN_SPEED..=MAX_SPEED).contains(&config.macro_speed) {
+        report.warnings.push(format!(
+            "macro_speed {} is outside {}..{}, it will be clamped",
+            config.macro_speed, MIN_SPEED, MAX_SPEED
+        ));
+    }
+
+    if config.realtime && !(1..=99).contains(&config.realtime_priority) {
+        report.warnings.push(format!(
+            "realtime_priority {} is outside 1..99, it will be clamped",
+            config.realtime_priority
+        ));
+    }
+
+    let cpu_count = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_CONF) };
+    if cpu_count > 0 {
+        for cpu in config
+            .cpu_affinity
+            .iter()
+            .filter(|c| **c >= cpu_count as usize)
+        {
+            report.warnings.push(format!(
+                "cpu_affinity lists CPU {}, this machine has CPUs 0..{}",
+                cpu,
+                cpu_count - 1
+            ));
+        }
+    }
+
+    for led in &config.leds {
+        if leds::parse_led(&led.led).is_none() {
+            report
+                .warnings
+                .push(format!("leds: unknown LED '{}'", led.led));
+        }
+        match leds::LedSource::of(led) {
+            Err(e) => report.warnings.push(format!("leds: {:#}", e)),
+            Ok(leds::LedSource::Profile(name))
+                if !config.profiles.iter().any(|p| p.name == name) =>
+            {
+                report
+                    .warnings
+                    .push(format!("leds: profile '{}' doesn't exist", name));
+            }
+            Ok(leds::LedSource::Macro(name))
+                if !config
+                    .profiles
+                    .iter()
+                    .flat_map(|p| &p.macros)
+                    .chain(&config.included_macros)
+                    .any(|m| m.name == name) =>
+            {
+                report
+                    .warnings
+                    .push(format!("leds: macro '{}' isn't defined", name));
+            }
+            Ok(_) => {}
+        }
+    }
+
+    if let Some(ref name) = config.fullscreen_profile
+        && !config.profiles.iter().any(|p| p.name == *name)
+    {
+        report.warnings.push(format!(
+            "fullscreen_profile: profile '{}' doesn't exist",
+            name
+        ));
+    }
+
+    if let Some(ref sounds) = config.sounds {
+        let events = [
+            ("macro_start", &sounds.macro_start),
+            ("macro_stop", &sounds.macro_stop),
+            ("profile_switch", &sounds.profile_switch),
+        ];
+        for (event, sound) in events {
+            match sound {
+                Some(Sound::Beep { tone, .. }) if !TONE_RANGE.contains(tone) => {
+                    report.warnings.push(format!(
+                        "sounds.{}: tone {} Hz is outside {}-{} Hz",
+                        event,
+                        tone,
+                        TONE_RANGE.start(),
+                        TONE_RANGE.end()
+                    ));
+                }
+                Some(Sound::File { file }) if !Path::new(file).is_file() => {
+                    report
+                        .warnings
+                        .push(format!("sounds.{}: no such file '{}'", event, file));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let profiles: Vec<&Profile> = match profile {
+        Some(name) => config.profiles.iter().filter(|p| p.name == name).collect(),
+        None => config.profiles.iter().collect(),
+    };
+    if profiles.is_empty() {
+        report.warnings.push(match profile {
+            Some(name) => format!("profile '{}' doesn't exist", name),
+            None => "config has no profiles".to_string(),
+        });
+    }
+
+    let warp = config.warp.clone().unwrap_or_default();
+    let names: HashSet<&str> = config.profiles.iter().map(|p| p.name.as_str()).collect();
+    for profile in profiles {
+        check_profile(profile, &warp, &names, &mut report);
+    }
+    report
+}
+
+fn check_profile(
+    profile: &Profile,
+    warp: &WarpConfig,
+    profile_names: &HashSet<&str>,
+    report: &mut CheckReport,
+) {
+    let warn = |report: &mut CheckReport, msg: String| {
+        report.warnings.push(format!("[{}] {}", profile.name, msg));
+    };
+    let macros: HashMap<&str, &MacroDef> = profile
+        .macros
+        .iter()
+        .map(|m| (m.name.as_str(), m))
+        .collect();
+
+    report.lines.push(format!("Profile '{}':", profile.name));
+    if let Some(color) = &profile.color
+        && Color::from_str(color).is_err()
+    {
+        warn(
+            report,
+            format!("color '{}' isn't a color name or #rrggbb", color),
+        );
+    }
+    if profile.bindings.is_empty() {
+        report.lines.push("  (no bindings)".to_string());
+    }
+    let disabled = profile.bindings.len() - profile.enabled_bindings().count();
+    if disabled > 0 {
+        report.lines.push(format!(
+            "  ({} binding(s) off, tags disabled: {})",
+            disabled,
+            profile.disabled_tags.join(", ")
+        ));
+    }
+
+    // Modes something can switch on, for checking `when` conditions
+    let mut settable_modes: HashSet<&str> = HashSet::new();
+    for binding in &profile.bindings {
+        if let BindingOutput::SetMode { mode, .. } = &binding.output {
+            settable_modes.insert(mode);
+        }
+    }
+    for def in &profile.macros {
+        for action in &def.actions {
+            if let MacroAction::SetMode { mode, .. } = action {
+                settable_modes.insert(mode);
+            }
+        }
+    }
+
+    let mut seen: HashSet<String> = HashSet::new();
+    for binding in profile.enabled_bindings() {
+        let label = binding.input_label();
+        let codes: Vec<String> = binding
+            .input_keys()
+            .iter()
+            .map(|key| match parse_key_name(key) {
+                Some(code) => code.code().to_string(),
+                None => {
+                    warn(report, format!("{}: unknown input key '{}'", label, key));
+                    "?".to_string()
+                }
+            })
+            .collect();
+
+        if !seen.insert(label.clone()) {
+            warn(
+                report,
+                format!("{}: bound more than once, only the first applies", label),
+            );
+        }
+        if let Some(when) = &binding.when
+            && !settable_modes.contains(when.trim_start_matches('!'))
+        {
+            warn(
+                report,
+                format!("{}: nothing switches mode '{}'", label, when),
+            );
+        }
+
+        let (action, output) = binding_output_summary(&binding.output);
+        let detail = match &binding.output {
+            BindingOutput::Key { key } | BindingOutput::ToggleKey { toggle_key: key } => {
+                match parse_key_name(key) {
+                    Some(code) => format!("{} (code {})", key, code.code()),
+                    None => {
+                        warn(report, format!("{}: unknown output key '{}'", label, key));
+                        format!("{} (unknown)", key)
+                    }
+                }
+            }
+            BindingOutput::Macro { macro_name, args } => match macros.get(macro_name.as_str()) {
+                Some(def) => match def.with_args(args) {
+                    Ok(filled) => {
+                        if !def.params.is_empty() {
+                            for key in unknown_macro_keys(&filled) {
+                                warn(
+                                    report,
+                                    format!(
+                                        "{}: macro '{}' presses unknown key '{}'",
+                                        label, macro_name, key
+                                    ),
+                                );
+                            }
+                            for problem in bad_warp_targets(&filled, warp) {
+                                warn(
+                                    report,
+                                    format!("{}: macro '{}': {}", label, macro_name, problem),
+                                );
+                            }
+                        }
+                        format!("{}: {}", macro_name, describe_macro(&filled))
+                    }
+                    Err(e) => {
+                        warn(report, format!("{}: {}", label, e));
+                        format!("{} (bad arguments)", macro_name)
+                    }
+                },
+                None => {
+                    warn(
+                        report,
+                        format!("{}: macro '{}' isn't defined", label, macro_name),
+                    );
+                    format!("{} (undefined)", macro_name)
+                }
+            },
+            BindingOutput::Warp { warp: target } => match warp.resolve(target) {
+                Ok(_) if target.contains(',') => target.clone(),
+                Ok((x, y)) => format!("{} ({},{})", target, x, y),
+                Err(e) => {
+                    warn(report, format!("{}: {}", label, e));
+                    format!("{} (invalid)", target)
+                }
+            },
+            BindingOutput::ToggleProfile { toggle_profile } => {
+                if !profile_names.contains(toggle_profile.as_str()) {
+                    warn(
+                        report,
+                        format!("{}: profile '{}' doesn't exist", label, toggle_profile),
+                    );
+                    format!("{} (undefined)", toggle_profile)
+                } else if *toggle_profile == profile.name {
+                    format!("{} (back to the previous profile)", toggle_profile)
+                } else {
+                    output
+                }
+            }
+            _ => output,
+        };
+        report.lines.push(format!(
+            "  {:<28} [{}]  {:<12} {}",
+            label,
+            codes.join("+"),
+            action,
+            detail
+        ));
+    }
+
+    // Parameterized macros were checked above, once per binding
+    for def in profile.macros.iter().filter(|def| def.params.is_empty()) {
+        for key in unknown_macro_keys(def) {
+            warn(
+                report,
+                format!("macro '{}': unknown key '{}'", def.name, key),
+            );
+        }
+        for problem in bad_warp_targets(def, warp) {
+            warn(report, format!("macro '{}': {}", def.name, problem));
+        }
+    }
+    for def in &profile.macros {
+        let weights: Vec<u32> = def
+            .actions
+            .iter()
+            .filter_map(|a| match a {
+                MacroAction::Choice(weight) => Some(*weight),
+                _ => None,
+            })
+            .collect();
+        if def.macro_type == MacroType::Random && weights.is_empty() {
+            warn(
+                report,
+                format!("macro '{}': random but has no Choice steps", def.name),
+            );
+        } else if def.macro_type == MacroType::Sequence && !weights.is_empty() {
+            warn(
+                report,
+                format!(
+                    "macro '{}': Choice steps are ignored in sequences",
+                    def.name
+                ),
+            );
+        } else if !weights.is_empty() && weights.iter().all(|w| *w == 0) {
+            warn(
+                report,
+                format!("macro '{}': every Choice has weight 0", def.name),
+            );
+        }
+        if let Err(e) = expand_actions(def, &|name| macros.get(name).copied()) {
+            warn(report, e);
+        }
+    }
+    report.lines.push(String::new());
+}
+
+/// One-line expansion of a macro, e.g. "Toggle every 100ms: click BTN_LEFT"
+fn describe_macro(def: &MacroDef) -> String {
+    let actions: Vec<String> = def
+        .actions
+        .iter()
+        .map(|action| match action {
+            MacroAction::Click(ClickStep { key, hold_ms: 0 }) => format!("click {}", key),
+            MacroAction::Click(ClickStep { key, hold_ms }) => {
+                format!("hold {} {}ms", key, hold_ms)
+            }
+            MacroAction::Press(key) => format!("press {}", key),
+            MacroAction::Release(key) => format!("release {}", key),
+            MacroAction::Delay(ms) => format!("wait {}ms", ms),
+            MacroAction::RunMacro(name) => format!("run {}", name),
+            MacroAction::Choice(weight) => format!("| {}x:", weight),
+            MacroAction::SetMode { mode, set } => {
+                format!("mode {} {}", mode, mode_switch_name(*set))
+            }
+            MacroAction::Obs(request) => format!("obs {}", request),
+            MacroAction::Notify(message) => format!("notify \"{}\"", message),
+            MacroAction::Warp(target) => format!("warp to {}", target),
+        })
+        .collect();
+    let timing = match def.macro_type {
+        MacroType::Sequence | MacroType::Random => String::new(),
+        _ if def.ramp_from_ms > def.interval_ms && def.ramp_ms > 0 => format!(
+            " every {}ms ramping to {}ms over {}ms",
+            def.ramp_from_ms, def.interval_ms, def.ramp_ms
+        ),
+        _ if !def.interval_pattern_ms.is_empty() => {
+            let gaps: Vec<String> = def.interval_pattern_ms.iter().map(u64::to_string).collect();
+            format!(
+                " every {}ms{}",
+                gaps.join("/"),
+                if def.shuffle_pattern { " shuffled" } else { "" }
+            )
+        }
+        _ => format!(" every {}ms", def.interval_ms),
+    };
+    format!(
+        "{}{}: {}",
+        macro_type_name(&def.macro_type),
+        timing,
+        actions.join(", ").replace(":, ", ": ")
+    )
+}
@@ -0,0 +1,443 @@
+use crate::backup;
+use crate::check;
+use crate::config::{Binding, BindingOutput, Config, MacroDef, Profile};
+use crate::device::scanner;
+use crate::engine::mapper::parse_key_name;
+use crate::export::{self, ExportFormat};
+use crate::import::{self, ImportFormat};
+use crate::install::{self, InstallOptions, UninstallOptions};
+use crate::self_test;
+use crate::stress::{self, StressOptions, StressPattern};
+use crate::tui::templates;
+use anyhow::{Context, Result};
+use clap::{CommandFactory, Parser, Subcommand};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Linux mouse button remapper and macro engine with TUI.
+/// Run without a subcommand to start the TUI.
+#[derive(Debug, Parser)]
+#[command(name = "mouse-mapper", version, about)]
+pub struct Cli {
+    /// Config file to use instead of ~/.config/mouse-mapper/config.toml
+    /// (also settable with MOUSE_MAPPER_CONFIG)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Record the event path (read, map, emit) and macro runs to this file as
+    /// a Chrome trace, for chrome://tracing or ui.perfetto.dev
+    #[arg(long, global = true, value_name = "FILE")]
+    pub trace: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Import button mappings from input-remapper or xbindkeys
+    Import {
+        /// input-remapper preset (.json) or xbindkeysrc file
+        file: PathBuf,
+        /// File format (guessed from the content if omitted)
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+        /// Profile to import into (created if missing; default: active profile)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Export a profile's key remaps to keyd or evremap config syntax
+    Export {
+        /// Target format
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+        /// Profile to export (default: active profile)
+        #[arg(long)]
+        profile: Option<String>,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Validate the config: resolve every key name and macro, print the
+    /// effective mappings and exit non-zero if anything is wrong. No device
+    /// is opened.
+    Check {
+        /// Profile to check (default: all profiles)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Print a shell completion script to stdout
+    Completions { shell: clap_complete::Shell },
+    /// Print the man page (roff) to stdout
+    Manpage,
+    /// Run the engine headless on the config's active profile, or with a
+    /// one-off profile built from --map arguments (the config's bindings
+    /// aren't used then). Stop with Ctrl+C.
+    Run {
+        /// Remap INPUT=OUTPUT, where OUTPUT is a key/button name or
+        /// macro:NAME (a macro from the config, or a built-in template such
+        /// as macro:auto-clicker). Repeatable.
+        #[arg(long = "map", value_name = "INPUT=OUTPUT")]
+        maps: Vec<String>,
+        /// Device to grab, by path or name substring (default: the
+        /// configured device, else the first mouse)
+        #[arg(long)]
+        device: Option<String>,
+    },
+    /// Check the profile's bindings end to end: run the engine on a virtual
+    /// source mouse, press each binding's input on it and verify what comes
+    /// out of the engine's virtual device. Bindings that depend on keyboard
+    /// modifiers or modes, or only change engine state, are skipped. Exits
+    /// non-zero if any binding fails. Needs write access to /dev/uinput.
+    SelfTest {
+        /// Profile to test (default: active profile)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Developer tool: create a virtual source mouse, run the engine on it
+    /// with the active profile and write synthetic events to it as fast as
+    /// asked, then report throughput, dropped events and latency. The mapped
+    /// output reaches the desktop. Needs write access to /dev/uinput.
+    Stress {
+        /// Reports (batches ending in SYN_REPORT) per second, like a mouse's
+        /// polling rate
+        #[arg(long, default_value_t = 1000)]
+        rate: u32,
+        /// Seconds to write for
+        #[arg(long, default_value_t = 5.0)]
+        duration: f64,
+        /// Events to write
+        #[arg(long, value_enum, default_value_t = StressPattern::Mixed)]
+        pattern: StressPattern,
+        /// Button to press and release in the buttons and mixed patterns
+        /// (repeatable; default BTN_TASK, BTN_0 and BTN_1, which desktops
+        /// rarely act on)
+        #[arg(long = "button", value_name = "NAME")]
+        buttons: Vec<String>,
+    },
+    /// Set the system up to run mouse-mapper without sudo and at startup:
+    /// install a udev rule giving the input group /dev/uinput and the input
+    /// devices, load uinput at boot, create the input group and add the user
+    /// to it, and install a systemd unit running `mouse-mapper run`. Needs
+    /// root.
+    Install {
+        /// Install a system unit that runs as root from boot, instead of a
+        /// user unit that runs while the user is logged in
+        #[arg(long)]
+        system: bool,
+        /// Print the files and commands without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// User to add to the input group (default: the one running sudo)
+        #[arg(long)]
+        user: Option<String>,
+    },
+    /// Archive the config file, its profiles/ directory and include files
+    /// into one tar file
+    Backup {
+        /// Archive to write (default: a timestamped file in
+        /// ~/.local/state/mouse-mapper/backups)
+        file: Option<PathBuf>,
+    },
+    /// Replace the config with the one in a backup. The backup is checked
+    /// before anything is changed, and the current config is backed up
+    /// first.
+    Restore {
+        /// Archive written by `backup`
+        file: PathBuf,
+    },
+    /// Undo `install`: disable and remove the systemd units, the udev rule
+    /// and the uinput entry, then check that no virtual device, grab or
+    /// running mouse-mapper is left (exits non-zero if one is). The input
+    /// group stays. Needs root.
+    Uninstall {
+        /// Also delete the config directory (profiles included), the log and
+        /// other state
+        #[arg(long)]
+        purge: bool,
+        /// Print the files and commands without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Run a CLI subcommand (everything except the TUI)
+pub fn run_command(command: Command, loaded: Result<Config>) -> Result<()> {
+    let mut config = match (&command, loaded) {
+        (_, Ok(config)) => config,
+        // Saving defaults in place of a config that doesn't parse would lose it
+        (Command::Import { .. } | Command::Restore { .. }, Err(e)) => {
+            return Err(e.context("The config doesn't load, so it's left alone; fix it first"));
+        }
+        // Finding that out is check's job
+        (Command::Check { .. }, Err(e)) => return Err(e),
+        (_, Err(e)) => {
+            eprintln!("Warning: Failed to load config: {:#}. Using defaults.", e);
+            Config::default()
+        }
+    };
+    match command {
+        Command::Import {
+            file,
+            format,
+            profile,
+        } => {
+            let result = import::import_file(&file, format)?;
+
+            let target = match profile {
+                Some(name) => {
+                    if !config.profiles.iter().any(|p| p.name == name) {
+                        config.profiles.push(Profile {
+                            name: name.clone(),
+                            ..Profile::default()
+                        });
+                    }
+                    config.profiles.iter_mut().find(|p| p.name == name)
+                }
+                None => config.active_profile_mut(),
+            }
+            .context("No profile to import into")?;
+
+            import::apply(target, &result);
+            let profile_name = target.name.clone();
+            config.save()?;
+
+            println!(
+                "Imported {} binding(s) and {} macro(s) into profile '{}'",
+                result.bindings.len(),
+                result.macros.len(),
+                profile_name
+            );
+            for warning in &result.warnings {
+                println!("  warning: {}", warning);
+            }
+            Ok(())
+        }
+        Command::Export {
+            format,
+            profile,
+            output,
+        } => {
+            let source = match profile {
+                Some(name) => config.profiles.iter().find(|p| p.name == name),
+                None => config.active_profile(),
+            }
+            .context("Profile not found")?;
+
+            let result = export::export_profile(source, &config.device, format);
+            export::write_output(&result, output.as_deref())?;
+
+            // Keep stdout clean for redirection
+            if let Some(path) = &output {
+                eprintln!(
+                    "Exported {} binding(s) from profile '{}' to {}",
+                    result.exported,
+                    source.name,
+                    path.display()
+                );
+            }
+            for warning in &result.warnings {
+                eprintln!("  warning: {}", warning);
+            }
+            Ok(())
+        }
+        Command::Completions { shell } => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+            Ok(())
+        }
+        Command::Manpage => clap_mangen::Man::new(Cli::command())
+            .render(&mut std::io::stdout())
+            .context("Failed to write man page"),
+        Command::Check { profile } => {
+            let report = check::check_config(&config, profile.as_deref());
+            for line in &report.lines {
+                println!("{}", line);
+            }
+            for warning in &report.warnings {
+                eprintln!("  warning: {}", warning);
+            }
+            if !report.warnings.is_empty() {
+                anyhow::bail!("{} problem(s) found", report.warnings.len());
+            }
+            println!("Config OK");
+            Ok(())
+        }
+        Command::Run { maps, device } => {
+            if !maps.is_empty() {
+                let profile = transient_profile(&maps, &config)?;
+                config.active_profile = Some(profile.name.clone());
+                config.profiles = vec![profile];
+            }
+            let profile = config
+                .active_profile()
+                .context("The config has no profile; pass --map")?;
+            let device = match device {
+                Some(d) if d.starts_with('/') => d,
+                Some(name) => scanner::find_device(Some(&name), None, None, None)?
+                    .with_context(|| format!("No mouse matching '{}'", name))?
+                    .path
+                    .to_string_lossy()
+                    .to_string(),
+                None => {
+                    let devices = scanner::scan_devices()?;
+                    scanner::configured_device(&devices, &config.device)
+                        .or_else(|| devices.iter().find(|d| d.is_mouse))
+                        .context("No mouse found; pass --device")?
+                        .path
+                        .to_string_lossy()
+                        .to_string()
+                }
+            };
+
+            eprintln!(
+                "Running {} mapping(s) on {} (Ctrl+C to stop)",
+                profile.bindings.len(),
+                device
+            );
+            crate::run_headless(config, device)
+        }
+        Command::SelfTest { profile } => {
+            if let Some(name) = profile {
+                if !config.profiles.iter().any(|p| p.name == name) {
+                    anyhow::bail!("Profile '{}' doesn't exist", name);
+                }
+                config.active_profile = Some(name);
+            }
+            self_test::run(config)
+        }
+        Command::Stress {
+            rate,
+            duration,
+            pattern,
+            buttons,
+        } => {
+            let buttons = if buttons.is_empty() {
+                stress::DEFAULT_BUTTONS.to_vec()
+            } else {
+                buttons
+                    .iter()
+                    .map(|name| {
+                        parse_key_name(name).with_context(|| format!("Unknown button '{}'", name))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            };
+            let duration = Duration::try_from_secs_f64(duration)
+                .ok()
+                .filter(|d| !d.is_zero())
+                .context("--duration must be a positive number of seconds")?;
+            stress::run(
+                config,
+                StressOptions {
+                    rate,
+                    duration,
+                    pattern,
+                    buttons,
+                },
+            )
+        }
+        Command::Install {
+            system,
+            dry_run,
+            user,
+        } => install::run(InstallOptions {
+            system,
+            dry_run,
+            user,
+        }),
+        Command::Backup { file } => {
+            let file = file.unwrap_or_else(|| backup::default_path("mouse-mapper"));
+            let summary = backup::backup(&file, &config)?;
+            for include in &summary.skipped {
+                eprintln!(
+                    "  warning: {} is outside the config directory, not backed up",
+                    include
+                );
+            }
+            println!("Backed up {} file(s) to {}", summary.files, file.display());
+            Ok(())
+        }
+        Command::Restore { file } => {
+            let summary = backup::restore(&file)?;
+            if let Some(previous) = &summary.previous {
+                println!("Previous config backed up to {}", previous.display());
+            }
+            println!("Restored {} file(s) from {}", summary.files, file.display());
+            Ok(())
+        }
+        Command::Uninstall { purge, dry_run } => {
+            install::uninstall(UninstallOptions { purge, dry_run })
+        }
+    }
+}
+
+/// Build the profile for `run --map` arguments
+fn transient_profile(maps: &[String], config: &Config) -> Result<Profile> {
+    let mut profile = Profile {
+        name: "cli".to_string(),
+        ..Profile::default()
+    };
+
+    for map in maps {
+        let (input, output) = map
+            .split_once('=')
+            .with_context(|| format!("Expected INPUT=OUTPUT, got '{}'", map))?;
+        for key in input.split('+') {
+            parse_key_name(key).with_context(|| format!("Unknown input key '{}'", key))?;
+        }
+
+        let output = match output.strip_prefix("macro:") {
+            Some(name) => {
+                if !profile.macros.iter().any(|m| m.name == name) {
+                    profile.macros.push(find_macro(name, config)?);
+                }
+                BindingOutput::Macro {
+                    macro_name: name.to_string(),
+                    args: BTreeMap::new(),
+                }
+            }
+            None => {
+                parse_key_name(output)
+                    .with_context(|| format!("Unknown output key '{}'", output))?;
+                BindingOutput::Key {
+                    key: output.to_string(),
+                }
+            }
+        };
+        profile.bindings.push(Binding {
+            input: input.to_string(),
+            output,
+            modifiers: vec![],
+            when: None,
+            cooldown_ms: 0,
+            description: None,
+            tags: Vec::new(),
+        });
+    }
+    Ok(profile)
+}
+
+/// A macro by name from the config (active profile first), falling back to
+/// a built-in template whose name contains it (e.g. "clicker")
+fn find_macro(name: &str, config: &Config) -> Result<MacroDef> {
+    let from_config = config
+        .active_profile()
+        .into_iter()
+        .chain(config.profiles.iter())
+        .flat_map(|p| p.macros.iter())
+        .find(|m| m.name == name);
+    if let Some(def) = from_config {
+        return Ok(def.clone());
+    }
+
+    let wanted = name.to_lowercase().replace('_', "-");
+    let template = templates::all()
+        .iter()
+        .find(|t| t.name.to_lowercase().contains(&wanted))
+        .with_context(|| format!("No macro or template named '{}'", name))?;
+    let mut def = template.build(&[]).map_err(anyhow::Error::msg)?;
+    def.name = name.to_string();
+    Ok(def)
+}
@@ -1,25 +1,447 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Config file given with --config, overriding MOUSE_MAPPER_CONFIG and the default
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
 
 /// Top-level configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     /// Which device to grab
     #[serde(default)]
     pub device: DeviceConfig,
 
-    /// Named profiles
-    #[serde(default)]
+    /// Named profiles. With a profiles/ directory next to the config file
+    /// these live in their own files there instead (see [`Config::load`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub profiles: Vec<Profile>,
 
     /// Which profile is active (by name)
     #[serde(default)]
     pub active_profile: Option<String>,
+
+    /// TUI colors
+    #[serde(default)]
+    pub theme: ThemeConfig,
+
+    /// Log file settings
+    #[serde(default)]
+    pub logging: LoggingConfig,
+
+    /// TUI keys: action name -> key or list of keys, replacing that action's
+    /// default keys (`up = ["Up", "w"]`, `delete = "x"`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keybindings: BTreeMap<String, KeyList>,
+
+    /// Playback speed of every macro: 2.0 runs intervals and delays twice
+    /// as fast, 0.5 at half speed
+    #[serde(default = "default_macro_speed")]
+    pub macro_speed: f64,
+
+    /// Most repeat, toggle and abortable sequence macros one device may run
+    /// at once (0 = no limit)
+    #[serde(default, skip_serializing_if = "is_zero_usize")]
+    pub max_running_macros: usize,
+
+    /// What starting one macro too many does
+    #[serde(default)]
+    pub macro_limit: MacroLimitPolicy,
+
+    /// Send keyboard keys out through a virtual keyboard of their own and
+    /// only buttons, motion and scrolling through the virtual mouse, so the
+    /// desktop treats remapped keys like those of a real keyboard (layout,
+    /// key repeat settings)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub virtual_keyboard: bool,
+
+    /// MSC_SCAN scancodes: forward the device's, synthesize them for the
+    /// emitted keys, or drop them
+    #[serde(default, skip_serializing_if = "is_default_scancodes")]
+    pub scancodes: ScancodePolicy,
+
+    /// Build a new virtual device on every engine start instead of keeping
+    /// the one of the last run. A new device makes the desktop enumerate it
+    /// again, resetting its settings for it.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub fresh_virtual_device: bool,
+
+    /// Run every device's engine (reader, mapping, macros, output) on a
+    /// thread of its own under SCHED_FIFO, for steadier latency when the
+    /// system is busy. Needs root or CAP_SYS_NICE, else it runs as usual.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub realtime: bool,
+
+    /// SCHED_FIFO priority used with `realtime` (1-99). The default stays
+    /// below the kernel's interrupt threads (50).
+    #[serde(
+        default = "default_realtime_priority",
+        skip_serializing_if = "is_default_realtime_priority"
+    )]
+    pub realtime_priority: u8,
+
+    /// CPUs (numbered from 0) the engine threads may run on, to keep them
+    /// away from busy cores; each device's engine then gets a thread of its
+    /// own like with `realtime`. Empty = any CPU.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpu_affinity: Vec<usize>,
+
+    /// obs-websocket server that Obs macro steps talk to (default:
+    /// localhost:4455 without a password)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obs: Option<ObsConfig>,
+
+    /// Desktop size and named positions for Warp outputs and steps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warp: Option<WarpConfig>,
+
+    /// HTTP API on localhost for Stream Deck, Home Assistant & co. (off if
+    /// unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api: Option<ApiConfig>,
+
+    /// Keyboard LEDs lit while a mode, profile or macro is active
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub leds: Vec<LedConfig>,
+
+    /// Sounds for toggle macros starting and stopping and profile switches
+    /// (silent if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sounds: Option<SoundsConfig>,
+
+    /// Profile switched to while the focused window is fullscreen, and back
+    /// from when it isn't anymore
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fullscreen_profile: Option<String>,
+
+    /// Programs (e.g. games with strict anti-cheat) during which the mouse
+    /// is let go of and left unmapped, by executable name or full path
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_processes: Vec<String>,
+
+    /// Files of shared macros (`[[macros]]` tables) added to every profile,
+    /// relative to the config file's directory. A profile's own macro of the
+    /// same name wins.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Macros read from the `include` files, kept to leave them out on save
+    #[serde(skip)]
+    pub included_macros: Vec<MacroDef>,
+}
+
+/// A file named in `include`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IncludeFile {
+    #[serde(default)]
+    macros: Vec<MacroDef>,
+}
+
+fn default_macro_speed() -> f64 {
+    1.0
+}
+
+fn is_zero_usize(value: &usize) -> bool {
+    *value == 0
+}
+
+fn default_realtime_priority() -> u8 {
+    10
+}
+
+fn is_default_realtime_priority(value: &u8) -> bool {
+    *value == default_realtime_priority()
+}
+
+fn is_default_scancodes(value: &ScancodePolicy) -> bool {
+    *value == ScancodePolicy::default()
+}
+
+/// What happens when a macro would exceed max_running_macros
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MacroLimitPolicy {
+    /// The new macro doesn't start
+    #[default]
+    Reject,
+    /// The macro running the longest is stopped to make room
+    CancelOldest,
+}
+
+/// What happens to the MSC_SCAN scancodes that come with key and button
+/// events, which some applications read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScancodePolicy {
+    /// The device's own scancode goes out in the same report as what its
+    /// key maps to (a remapped key keeps the scancode of the original one)
+    #[default]
+    Forward,
+    /// The device's scancodes are dropped and every emitted key gets the one
+    /// a real device would send for it, where there is one: AT set 1 for
+    /// keyboard keys, the USB button page for mouse buttons
+    Synthesize,
+    /// No scancodes at all
+    Drop,
+}
+
+/// Screen area the cursor can be warped to. Warps go through a virtual
+/// absolute pointer whose range the desktop maps onto its full extent, so
+/// width and height are those of all monitors together.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WarpConfig {
+    #[serde(default = "default_warp_width")]
+    pub width: u32,
+    #[serde(default = "default_warp_height")]
+    pub height: u32,
+    /// Named spots, e.g. `second_monitor = [3839, 0]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub positions: BTreeMap<String, [u32; 2]>,
+}
+
+impl Default for WarpConfig {
+    fn default() -> Self {
+        WarpConfig {
+            width: default_warp_width(),
+            height: default_warp_height(),
+            positions: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_warp_width() -> u32 {
+    1920
+}
+
+fn default_warp_height() -> u32 {
+    1080
+}
+
+impl WarpConfig {
+    /// Pixel position of a warp target: "x,y" or a position name
+    pub fn resolve(&self, target: &str) -> Result<(u32, u32), String> {
+        let target = target.trim();
+        let coordinates = target.split_once(',');
+        let (x, y) = match coordinates {
+            Some((x, y)) => match (x.trim().parse(), y.trim().parse()) {
+                (Ok(x), Ok(y)) => (x, y),
+                _ => return Err(format!("warp target '{}' isn't x,y or a name", target)),
+            },
+            None => match self.positions.get(target) {
+                Some([x, y]) => (*x, *y),
+                None => return Err(format!("warp position '{}' isn't defined", target)),
+            },
+        };
+        if x >= self.width || y >= self.height {
+            let position = match coordinates {
+                Some(_) => format!("warp target {}", target),
+                None => format!("warp position '{}' ({},{})", target, x, y),
+            };
+            return Err(format!(
+                "{} is outside the {}x{} desktop",
+                position, self.width, self.height
+            ));
+        }
+        Ok((x, y))
+    }
+}
+
+/// Where OBS Studio's websocket server listens (Tools > WebSocket Server Settings)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObsConfig {
+    #[serde(default = "default_obs_host")]
+    pub host: String,
+    #[serde(default = "default_obs_port")]
+    pub port: u16,
+    /// Server password, if authentication is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+impl Default for ObsConfig {
+    fn default() -> Self {
+        ObsConfig {
+            host: default_obs_host(),
+            port: default_obs_port(),
+            password: None,
+        }
+    }
+}
+
+fn default_obs_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_obs_port() -> u16 {
+    4455
+}
+
+/// The JSON API served on 127.0.0.1 while the TUI runs. Every request needs
+/// an `Authorization: Bearer <token>` header.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiConfig {
+    #[serde(default = "default_api_port")]
+    pub port: u16,
+    /// Shared secret clients send; the API stays off while it's empty
+    #[serde(default)]
+    pub token: String,
+}
+
+fn default_api_port() -> u16 {
+    7373
+}
+
+/// Sound played on each event; events without one stay silent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoundsConfig {
+    /// Program that plays `file` sounds, run in the desktop session
+    #[serde(default = "default_sound_player")]
+    pub player: String,
+    /// A toggle macro started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub macro_start: Option<Sound>,
+    /// A toggle macro stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub macro_stop: Option<Sound>,
+    /// The active profile changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_switch: Option<Sound>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+fn default_sound_player() -> String {
+    "paplay".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Sound {
+    /// Tone in Hz on the PC speaker, e.g. `{ tone = 880, ms = 80 }`
+    Beep {
+        tone: u32,
+        #[serde(default = "default_beep_ms")]
+        ms: u64,
+    },
+    /// Sound file played with `player`
+    File { file: String },
+}
+
+fn default_beep_ms() -> u64 {
+    100
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Global log level: off, error, warn, info, debug, trace
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    /// Rotate the log file once it grows past this size (0 = never)
+    #[serde(default = "default_log_max_size_kb")]
+    pub max_size_kb: u64,
+    /// Number of rotated files to keep (mouse-mapper.log.1, .2, ...)
+    #[serde(default = "default_log_keep_files")]
+    pub keep_files: usize,
+    /// Log the engine (mapper/macros) at debug level regardless of `level`
+    #[serde(default)]
+    pub engine_debug: bool,
+    /// Log file (default: $XDG_STATE_HOME/mouse-mapper/mouse-mapper.log)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_log_max_size_kb() -> u64 {
+    1024
+}
+
+fn default_log_keep_files() -> usize {
+    3
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            level: default_log_level(),
+            max_size_kb: default_log_max_size_kb(),
+            keep_files: default_log_keep_files(),
+            engine_debug: false,
+            file: None,
+        }
+    }
+}
+
+/// TUI color settings. `preset` picks a built-in palette ("dark", "light",
+/// "high-contrast"); the individual fields override single colors of it.
+/// Colors accept names ("cyan", "lightblue"), 256-color indices ("244") or
+/// RGB hex ("#00afff").
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct ThemeConfig {
+    pub preset: Option<String>,
+    /// Titles, active tab, selected items (default: cyan)
+    pub accent: Option<String>,
+    /// Labels, table headers, warnings (default: yellow)
+    pub highlight: Option<String>,
+    /// Running engine, selected device, key presses (default: green)
+    pub success: Option<String>,
+    /// Errors, stopped engine, key releases (default: red)
+    pub error: Option<String>,
+    /// Informational messages (default: blue)
+    pub info: Option<String>,
+    /// Focused/normal text (default: white)
+    pub text: Option<String>,
+    /// Unfocused text (default: gray)
+    pub muted: Option<String>,
+    /// Hints and secondary text (default: darkgray)
+    pub hint: Option<String>,
+    /// Background of the highlighted table row (default: darkgray)
+    pub selection: Option<String>,
+}
+
+/// One key or several for a `[keybindings]` action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    pub fn keys(&self) -> &[String] {
+        match self {
+            KeyList::One(key) => std::slice::from_ref(key),
+            KeyList::Many(keys) => keys,
+        }
+    }
+}
+
+/// A LED that shows engine state, e.g. `{ led = "scrolllock", mode = "fps" }`.
+/// Set one of `mode`, `profile` or `macro`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedConfig {
+    /// "scrolllock", "compose", "numlock", "capslock" or "kana"
+    pub led: String,
+    /// Keyboard it's on (path or name substring; default: the [device]
+    /// keyboard, else every keyboard), or "virtual" for a LED-only virtual
+    /// device that status bars and /sys/class/leds can follow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// Lit while this mode is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    /// Lit while this profile is the device's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Lit while this repeat or toggle macro runs
+    #[serde(rename = "macro", default, skip_serializing_if = "Option::is_none")]
+    pub macro_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct DeviceConfig {
     /// Match device by name substring (e.g. "G502")
     pub name: Option<String>,
@@ -29,35 +451,417 @@ pub struct DeviceConfig {
     pub vendor_id: Option<u16>,
     /// Match by product ID
     pub product_id: Option<u16>,
+    /// Keyboard to read modifier state from for modifier-conditioned
+    /// bindings (path or name substring; default: every keyboard)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyboard: Option<String>,
+    /// Devices hidden from the Devices tab (exact path or name substring,
+    /// e.g. "Lid Switch")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+}
+
+impl DeviceConfig {
+    /// Whether the device is on the ignore list
+    pub fn is_ignored(&self, path: &str, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.ignore
+            .iter()
+            .any(|pattern| pattern == path || name.contains(&pattern.to_lowercase()))
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Profile {
     pub name: String,
+    /// Color of the profile's name in the status bar: a name ("magenta") or
+    /// "#rrggbb", like the [theme] colors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Short label shown before the profile's name, e.g. an emoji
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
     #[serde(default)]
     pub bindings: Vec<Binding>,
     #[serde(default)]
     pub macros: Vec<MacroDef>,
+    /// Pointer sensitivity multipliers cycled by `dpi_stage` bindings
+    /// (e.g. [0.5, 1.0, 2.0]). The engine starts at the 1.0 stage if present.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dpi_stages: Vec<f64>,
+    /// Turn pointer movement into key presses (see `pointer_keys` bindings)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pointer_keys: Option<PointerKeysConfig>,
+    /// Middle-click style autoscroll tuning (defaults if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autoscroll: Option<AutoscrollConfig>,
+    /// Autorepeat for buttons remapped to keyboard keys (off if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_repeat: Option<KeyRepeatConfig>,
+    /// Hold time of synthesized clicks (instant if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub click_hold: Option<ClickHoldConfig>,
+    /// Left-handed mode: BTN_LEFT and BTN_RIGHT trade places
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub swap_buttons: bool,
+    /// Also press the modifiers held on the keyboard on the virtual device
+    /// around every key a button is remapped to, for programs that only look
+    /// at the modifiers of the device a key comes from (the desktop combines
+    /// those of all devices by itself)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub wrap_modifiers: bool,
+    /// Tags whose bindings are switched off
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_tags: Vec<String>,
+    /// File in the profiles/ directory this profile was loaded from, so a
+    /// save writes it back there
+    #[serde(skip)]
+    pub file: Option<PathBuf>,
+}
+
+impl Profile {
+    /// Whether none of the binding's tags is switched off
+    pub fn binding_enabled(&self, binding: &Binding) -> bool {
+        !binding.tags.iter().any(|t| self.disabled_tags.contains(t))
+    }
+
+    /// The bindings the engine uses, leaving out those of disabled tags
+    pub fn enabled_bindings(&self) -> impl Iterator<Item = &Binding> {
+        self.bindings.iter().filter(|b| self.binding_enabled(b))
+    }
+
+    /// The name with the icon in front, if there is one
+    pub fn label(&self) -> String {
+        match &self.icon {
+            Some(icon) => format!("{} {}", icon, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Every tag used by a binding, sorted
+    pub fn binding_tags(&self) -> Vec<String> {
+        let tags: BTreeSet<&String> = self.bindings.iter().flat_map(|b| &b.tags).collect();
+        tags.into_iter().cloned().collect()
+    }
+}
+
+/// Autoscroll speed: moving the pointer past the dead zone scrolls, faster
+/// the further it's moved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutoscrollConfig {
+    /// Vertical movement (in device counts) that doesn't scroll yet
+    #[serde(default = "default_autoscroll_dead_zone")]
+    pub dead_zone: u32,
+    /// Notches per second for every 100 counts past the dead zone
+    #[serde(default = "default_autoscroll_speed")]
+    pub speed: f64,
+}
+
+impl Default for AutoscrollConfig {
+    fn default() -> Self {
+        AutoscrollConfig {
+            dead_zone: default_autoscroll_dead_zone(),
+            speed: default_autoscroll_speed(),
+        }
+    }
+}
+
+fn default_autoscroll_dead_zone() -> u32 {
+    15
+}
+
+fn default_autoscroll_speed() -> f64 {
+    4.0
+}
+
+/// Repeat timing for held remapped keys, like a keyboard's autorepeat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyRepeatConfig {
+    /// Hold time before the first repeat
+    #[serde(default = "default_key_repeat_delay_ms")]
+    pub delay_ms: u64,
+    /// Repeats per second after that (1-100)
+    #[serde(default = "default_key_repeat_rate_hz")]
+    pub rate_hz: u32,
+}
+
+/// How long the clicks mouse-mapper makes up itself (macro Click steps,
+/// pointer-keys taps) hold their key, since some applications ignore a
+/// press and release in the same instant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClickHoldConfig {
+    #[serde(default = "default_click_hold_ms")]
+    pub hold_ms: u64,
+    /// Random variance of each hold (±jitter_ms)
+    #[serde(default)]
+    pub jitter_ms: u64,
+}
+
+fn default_click_hold_ms() -> u64 {
+    30
+}
+
+fn default_key_repeat_delay_ms() -> u64 {
+    500
+}
+
+fn default_key_repeat_rate_hz() -> u32 {
+    25
+}
+
+/// Pointer-to-keys mode: while active, sustained movement in one direction
+/// presses a key (arrows by default) instead of moving the pointer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointerKeysConfig {
+    /// Start in pointer-to-keys mode when the engine starts
+    #[serde(default)]
+    pub enabled: bool,
+    /// Movement (in device counts) needed for one key press
+    #[serde(default = "default_pointer_keys_threshold")]
+    pub threshold: u32,
+    /// Forget partial movement after this long without motion
+    #[serde(default = "default_pointer_keys_reset_ms")]
+    pub reset_ms: u64,
+    #[serde(default = "default_pointer_key_up")]
+    pub up: String,
+    #[serde(default = "default_pointer_key_down")]
+    pub down: String,
+    #[serde(default = "default_pointer_key_left")]
+    pub left: String,
+    #[serde(default = "default_pointer_key_right")]
+    pub right: String,
+}
+
+fn default_pointer_keys_threshold() -> u32 {
+    60
+}
+
+fn default_pointer_keys_reset_ms() -> u64 {
+    250
+}
+
+fn default_pointer_key_up() -> String {
+    "KEY_UP".to_string()
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn default_pointer_key_down() -> String {
+    "KEY_DOWN".to_string()
+}
+
+fn default_pointer_key_left() -> String {
+    "KEY_LEFT".to_string()
+}
+
+fn default_pointer_key_right() -> String {
+    "KEY_RIGHT".to_string()
+}
+
+impl Default for PointerKeysConfig {
+    fn default() -> Self {
+        PointerKeysConfig {
+            enabled: false,
+            threshold: default_pointer_keys_threshold(),
+            reset_ms: default_pointer_keys_reset_ms(),
+            up: default_pointer_key_up(),
+            down: default_pointer_key_down(),
+            left: default_pointer_key_left(),
+            right: default_pointer_key_right(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Binding {
-    /// Input event code name, e.g. "BTN_LEFT", "BTN_EXTRA", "BTN_SIDE"
+    /// Input event code name, e.g. "BTN_LEFT", "BTN_EXTRA", "BTN_SIDE", or a
+    /// chord like "BTN_SIDE+BTN_EXTRA" (the last button, pressed while the
+    /// others are held)
     pub input: String,
     /// What to do when this button is pressed
     pub output: BindingOutput,
+    /// Only apply while these keyboard modifiers are held
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<KeyModifier>,
+    /// Only apply while this mode is on ("name") or off ("!name")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    /// Ignore presses until this long after the binding last fired
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub cooldown_ms: u64,
+    /// Free-form note on what the binding is for, shown in the Bindings tab
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Labels for grouping, e.g. "work" or "fps"; see `Profile::disabled_tags`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Keyboard modifier a binding can require (left or right key both count)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyModifier {
+    Ctrl,
+    Shift,
+    Alt,
+    Meta,
+}
+
+impl KeyModifier {
+    pub fn all() -> &'static [KeyModifier] {
+        &[
+            KeyModifier::Ctrl,
+            KeyModifier::Shift,
+            KeyModifier::Alt,
+            KeyModifier::Meta,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyModifier::Ctrl => "Ctrl",
+            KeyModifier::Shift => "Shift",
+            KeyModifier::Alt => "Alt",
+            KeyModifier::Meta => "Meta",
+        }
+    }
+}
+
+impl Binding {
+    /// Buttons of the input; more than one for a chord, the trigger last
+    pub fn input_keys(&self) -> Vec<&str> {
+        self.input.split('+').map(str::trim).collect()
+    }
+
+    /// Input with its modifiers and mode condition, e.g. "Ctrl+BTN_EXTRA [layer]"
+    pub fn input_label(&self) -> String {
+        let mut parts: Vec<&str> = self.modifiers.iter().map(|m| m.label()).collect();
+        parts.push(&self.input);
+        let label = parts.join("+");
+        match &self.when {
+            Some(when) => format!("{} [{}]", label, when),
+            None => label,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum BindingOutput {
     /// Remap to a different key/button
     Key { key: String },
-    /// Trigger a named macro
-    Macro { macro_name: String },
+    /// Trigger a named macro, filling in its parameters from `args`
+    Macro {
+        macro_name: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        args: BTreeMap<String, String>,
+    },
+    /// Switch to the next/previous software DPI stage of the profile
+    DpiStage { dpi_stage: DpiStep },
+    /// Switch pointer-to-keys mode
+    PointerKeys { pointer_keys: ModeSwitch },
+    /// Switch autoscroll: pointer movement scrolls continuously while on
+    Autoscroll { autoscroll: ModeSwitch },
+    /// Switch a named mode that other bindings can depend on with `when`
+    SetMode {
+        mode: String,
+        #[serde(default = "default_mode_switch")]
+        set: ModeSwitch,
+    },
+    /// Press a key on the first press of the button and keep it held until
+    /// the next press (e.g. latched push-to-talk)
+    ToggleKey { toggle_key: String },
+    /// Scroll a number of wheel notches per press
+    Scroll { scroll: ScrollOutput },
+    /// Switch the left-handed BTN_LEFT/BTN_RIGHT swap
+    SwapButtons { swap_buttons: ModeSwitch },
+    /// Pause or resume all running repeat and toggle macros
+    PauseMacros { pause_macros: ModeSwitch },
+    /// Arm a one-shot modifier that's held for the next key or click only
+    Sticky { sticky: KeyModifier },
+    /// Move the cursor to "x,y" or a named position of the `[warp]` section
+    Warp { warp: String },
+    /// Switch the device to this profile, or back to the one it was switched
+    /// from when it's already on it
+    ToggleProfile { toggle_profile: String },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Wheel notches a scroll binding sends, e.g. `{ axis = "wheel", amount = -3 }`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScrollOutput {
+    #[serde(default)]
+    pub axis: ScrollAxis,
+    /// Notches to scroll; negative = down (or left)
+    pub amount: i32,
+}
+
+impl ScrollOutput {
+    /// Parse "wheel -3", "hwheel 2" or just "-3" (vertical)
+    pub fn parse(text: &str) -> Option<ScrollOutput> {
+        let text = text.trim();
+        let (axis, amount) = match text.split_once(' ') {
+            Some(("wheel", amount)) => (ScrollAxis::Wheel, amount),
+            Some(("hwheel", amount)) => (ScrollAxis::HWheel, amount),
+            Some(_) => return None,
+            None => (ScrollAxis::Wheel, text),
+        };
+        let amount = amount.trim().parse().ok()?;
+        Some(ScrollOutput { axis, amount })
+    }
+}
+
+impl std::fmt::Display for ScrollOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.axis.name(), self.amount)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrollAxis {
+    /// Vertical wheel (REL_WHEEL)
+    #[default]
+    Wheel,
+    /// Horizontal wheel (REL_HWHEEL)
+    HWheel,
+}
+
+impl ScrollAxis {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScrollAxis::Wheel => "wheel",
+            ScrollAxis::HWheel => "hwheel",
+        }
+    }
+}
+
+fn default_mode_switch() -> ModeSwitch {
+    ModeSwitch::Toggle
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DpiStep {
+    Next,
+    Prev,
+}
+
+/// How a binding changes an on/off mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModeSwitch {
+    Toggle,
+    On,
+    Off,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MacroDef {
     pub name: String,
     #[serde(rename = "type")]
@@ -67,18 +871,165 @@ pub struct MacroDef {
     /// For repeat_on_hold: interval between repeats in milliseconds
     #[serde(default = "default_interval")]
     pub interval_ms: u64,
+    /// For repeat_on_hold/toggle: gaps between repeats to go through in turn
+    /// instead of the single interval_ms, e.g. [45, 60, 52, 70]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interval_pattern_ms: Vec<u64>,
+    /// Go through interval_pattern_ms in a new random order every round
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub shuffle_pattern: bool,
+    /// For repeat_on_hold/toggle: start with this gap between repeats and
+    /// shrink it to interval_ms over ramp_ms of holding (0 = no ramp)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub ramp_from_ms: u64,
+    /// How long the ramp from ramp_from_ms down to interval_ms takes
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub ramp_ms: u64,
     /// Optional initial delay before first repeat
     #[serde(default)]
     pub initial_delay_ms: u64,
     /// Random jitter added to interval (±jitter_ms) to make timing look human
     #[serde(default)]
     pub jitter_ms: u64,
+    /// For repeat_on_hold/toggle: how long each click holds its key down
+    /// (0 = press and release at once)
+    #[serde(default)]
+    pub press_ms: u64,
+    /// For repeat_on_hold/toggle: how long the key stays up before the next
+    /// repeat (0 = use interval_ms)
+    #[serde(default)]
+    pub release_ms: u64,
+    /// For sequence: releasing the trigger cancels the remaining steps and
+    /// releases the keys the sequence is holding
+    #[serde(default)]
+    pub abort_on_release: bool,
+    /// For toggle: stop after this many minutes without real input on the
+    /// grabbed device (0 = never)
+    #[serde(default)]
+    pub idle_stop_min: u64,
+    /// Parameters bindings can pass, with their default ("" = required).
+    /// `{name}` in a key or mode name or a notification is replaced by the value; a
+    /// parameter named after a timing setting (e.g. interval_ms) sets that setting.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    /// Free-form note on what the macro is for, shown in the Macros tab
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
 }
 
 fn default_interval() -> u64 {
     50
 }
 
+/// An unnamed sequence with no steps, everything else as a config file that
+/// leaves it out gets it
+impl Default for MacroDef {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            macro_type: MacroType::Sequence,
+            actions: Vec::new(),
+            interval_ms: default_interval(),
+            interval_pattern_ms: Vec::new(),
+            shuffle_pattern: false,
+            ramp_from_ms: 0,
+            ramp_ms: 0,
+            initial_delay_ms: 0,
+            jitter_ms: 0,
+            press_ms: 0,
+            release_ms: 0,
+            abort_on_release: false,
+            idle_stop_min: 0,
+            params: BTreeMap::new(),
+            description: None,
+        }
+    }
+}
+
+impl MacroDef {
+    /// This macro with its parameters filled in from a binding's arguments,
+    /// the declared defaults standing in for the ones left out
+    pub fn with_args(&self, args: &BTreeMap<String, String>) -> Result<MacroDef, String> {
+        if let Some(unknown) = args.keys().find(|k| !self.params.contains_key(*k)) {
+            return Err(format!(
+                "macro '{}' has no parameter '{}'",
+                self.name, unknown
+            ));
+        }
+        let mut def = self.clone();
+        def.params.clear();
+
+        let mut values = Vec::new();
+        for (name, default) in &self.params {
+            let value = args.get(name).unwrap_or(default).trim();
+            if value.is_empty() {
+                return Err(format!(
+                    "macro '{}' needs a value for '{}'",
+                    self.name, name
+                ));
+            }
+            let setting = match name.as_str() {
+                "interval_ms" => Some(&mut def.interval_ms),
+                "ramp_from_ms" => Some(&mut def.ramp_from_ms),
+                "ramp_ms" => Some(&mut def.ramp_ms),
+                "initial_delay_ms" => Some(&mut def.initial_delay_ms),
+                "jitter_ms" => Some(&mut def.jitter_ms),
+                "press_ms" => Some(&mut def.press_ms),
+                "release_ms" => Some(&mut def.release_ms),
+                "idle_stop_min" => Some(&mut def.idle_stop_min),
+                _ => None,
+            };
+            if let Some(setting) = setting {
+                *setting = value
+                    .parse()
+                    .map_err(|_| format!("{} must be a whole number (got '{}')", name, value))?;
+            }
+            values.push((format!("{{{}}}", name), value));
+        }
+
+        for action in &mut def.actions {
+            let text = match action {
+                MacroAction::Click(ClickStep { key, .. })
+                | MacroAction::Press(key)
+                | MacroAction::Release(key) => key,
+                MacroAction::SetMode { mode, .. }
+                | MacroAction::RunMacro(mode)
+                | MacroAction::Notify(mode)
+                | MacroAction::Warp(mode) => mode,
+                MacroAction::Obs(ObsRequest::SwitchScene(scene)) => scene,
+                MacroAction::Delay(_) | MacroAction::Choice(_) | MacroAction::Obs(_) => continue,
+            };
+            for (placeholder, value) in &values {
+                *text = text.replace(placeholder.as_str(), value);
+            }
+        }
+        Ok(def)
+    }
+}
+
+/// Parse "name=value, name=value" (macro parameters and binding arguments)
+pub fn parse_macro_params(text: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut params = BTreeMap::new();
+    for part in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (name, value) = part.split_once('=').unwrap_or((part, ""));
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("'{}' isn't a valid parameter name", name));
+        }
+        params.insert(name.to_string(), value.trim().to_string());
+    }
+    Ok(params)
+}
+
+/// Inverse of `parse_macro_params`
+pub fn format_macro_params(params: &BTreeMap<String, String>) -> String {
+    params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum MacroType {
@@ -88,54 +1039,367 @@ pub enum MacroType {
     Sequence,
     /// Toggle: first press starts repeating, second press stops
     Toggle,
+    /// Fire once on button press, running one of the Choice groups picked
+    /// at random by weight
+    Random,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum MacroAction {
-    /// Click a button (press + release)
-    Click(String),
+    /// Click a button (press + release), optionally holding it down for a while
+    Click(ClickStep),
     /// Press a key/button (down only)
     Press(String),
     /// Release a key/button (up only)
     Release(String),
     /// Wait for a duration in milliseconds
     Delay(u64),
+    /// Switch a named mode (see `Binding::when`)
+    SetMode {
+        mode: String,
+        #[serde(default = "default_mode_switch")]
+        set: ModeSwitch,
+    },
+    /// Run the steps of another macro of the profile, once
+    RunMacro(String),
+    /// Start a group of steps with this weight. Random macros run one group
+    /// per press, repeat and toggle macros one per repetition; steps before
+    /// the first Choice always run.
+    Choice(u32),
+    /// Send a request to OBS Studio over obs-websocket:
+    /// `obs = "start_recording"` or `obs = { switch_scene = "Gaming" }`
+    Obs(ObsRequest),
+    /// Show a desktop notification; `{macro}` is replaced by the macro's name
+    Notify(String),
+    /// Move the cursor to "x,y" or a named position of the `[warp]` section
+    Warp(String),
+}
+
+/// What an Obs macro step asks OBS to do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObsRequest {
+    StartRecording,
+    StopRecording,
+    ToggleRecording,
+    SwitchScene(String),
+}
+
+impl std::fmt::Display for ObsRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObsRequest::StartRecording => write!(f, "start recording"),
+            ObsRequest::StopRecording => write!(f, "stop recording"),
+            ObsRequest::ToggleRecording => write!(f, "toggle recording"),
+            ObsRequest::SwitchScene(scene) => write!(f, "switch to scene {}", scene),
+        }
+    }
+}
+
+/// Key of a Click step and how long it's held. Written as just the key name
+/// unless it has a hold: `click = "BTN_LEFT"` or
+/// `click = { key = "BTN_LEFT", hold_ms = 200 }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ClickStepRepr", into = "ClickStepRepr")]
+pub struct ClickStep {
+    pub key: String,
+    /// How long to hold the key down (0 = the macro's or profile's default)
+    pub hold_ms: u64,
+}
+
+impl ClickStep {
+    pub fn new(key: impl Into<String>) -> Self {
+        ClickStep {
+            key: key.into(),
+            hold_ms: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ClickStepRepr {
+    Key(String),
+    Held {
+        key: String,
+        #[serde(default)]
+        hold_ms: u64,
+    },
+}
+
+impl From<ClickStepRepr> for ClickStep {
+    fn from(repr: ClickStepRepr) -> Self {
+        match repr {
+            ClickStepRepr::Key(key) => ClickStep::new(key),
+            ClickStepRepr::Held { key, hold_ms } => ClickStep { key, hold_ms },
+        }
+    }
+}
+
+impl From<ClickStep> for ClickStepRepr {
+    fn from(step: ClickStep) -> Self {
+        if step.hold_ms == 0 {
+            ClickStepRepr::Key(step.key)
+        } else {
+            ClickStepRepr::Held {
+                key: step.key,
+                hold_ms: step.hold_ms,
+            }
+        }
+    }
 }
 
 impl Config {
-    /// Load config from the default path (~/.config/mouse-mapper/config.toml)
+    /// Use this config file for every load and save of the process (the
+    /// --config flag). Only the first call has an effect.
+    pub fn set_path_override(path: PathBuf) {
+        let _ = CONFIG_PATH_OVERRIDE.set(path);
+    }
+
+    /// Load config from the config path (--config, else $MOUSE_MAPPER_CONFIG,
+    /// else ~/.config/mouse-mapper/config.toml).
+    ///
+    /// If a profiles/ directory sits next to the config file, every *.toml in
+    /// it holds one profile and is added to those of the config file, in file
+    /// name order. Creating the directory is all it takes to switch: the next
+    /// save moves the config file's profiles there.
     pub fn load() -> Result<Self> {
-        let path = Self::config_path()?;
-        if path.exists() {
-            let content = std::fs::read_to_string(&path)
+        Self::load_from(&Self::config_path()?)
+    }
+
+    /// Load the config file at `path` with its profiles/ directory and
+    /// includes, like `load` does for the one in use
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        Self::load_resolving(path, |include| dir.join(include))
+    }
+
+    /// Like `load_from`, with the `include` files found by `resolve` instead
+    /// of next to the config file (restore checks a backup unpacked
+    /// elsewhere, whose includes outside the config directory weren't
+    /// archived)
+    pub fn load_resolving(path: &Path, resolve: impl Fn(&str) -> PathBuf) -> Result<Self> {
+        let profiles = Self::load_profile_dir(path)?;
+        let mut config = if path.exists() {
+            let content = std::fs::read_to_string(path)
                 .with_context(|| format!("Failed to read config from {}", path.display()))?;
-            let config: Config = toml::from_str(&content)
-                .with_context(|| format!("Failed to parse config from {}", path.display()))?;
-            Ok(config)
+            toml::from_str(&content)
+                .with_context(|| format!("Failed to parse config from {}", path.display()))?
+        } else if profiles.is_empty() {
+            Self::default()
         } else {
-            Ok(Self::default())
+            Self {
+                profiles: vec![],
+                active_profile: None,
+                ..Self::default()
+            }
+        };
+        for profile in profiles {
+            if let Some(other) = config.profiles.iter().find(|p| p.name == profile.name) {
+                bail!(
+                    "Profile {} is defined in both {} and {}",
+                    profile.name,
+                    other.file.as_deref().unwrap_or(path).display(),
+                    profile.file.as_deref().unwrap_or(path).display()
+                );
+            }
+            config.profiles.push(profile);
         }
+        config.load_includes(resolve)?;
+        Ok(config)
     }
 
-    /// Save config to the default path
+    /// Read the `include` files and add their macros to every profile that
+    /// doesn't define one of the same name itself
+    fn load_includes(&mut self, resolve: impl Fn(&str) -> PathBuf) -> Result<()> {
+        for include in &self.include {
+            let file = resolve(include);
+            let content = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read included file {}", file.display()))?;
+            let included: IncludeFile = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse included file {}", file.display()))?;
+            for m in included.macros {
+                if self
+                    .included_macros
+                    .iter()
+                    .any(|other| other.name == m.name)
+                {
+                    bail!(
+                        "Macro {} is included twice (again in {})",
+                        m.name,
+                        file.display()
+                    );
+                }
+                self.included_macros.push(m);
+            }
+        }
+        for profile in &mut self.profiles {
+            for m in &self.included_macros {
+                if !profile.macros.iter().any(|own| own.name == m.name) {
+                    profile.macros.push(m.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// This config as it's written to disk: included macros are left out of
+    /// the profiles unless they were changed there, which makes them the
+    /// profile's own
+    fn without_included(&self) -> Self {
+        let mut config = self.clone();
+        for profile in &mut config.profiles {
+            profile.macros.retain(|m| !self.included_macros.contains(m));
+        }
+        config
+    }
+
+    /// Profiles of the profiles/ directory next to the config file at `path`
+    fn load_profile_dir(path: &Path) -> Result<Vec<Profile>> {
+        let Some(dir) = Self::profile_dir(path) else {
+            return Ok(vec![]);
+        };
+        let mut profiles = Vec::new();
+        for file in Self::profile_files(&dir)? {
+            let content = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read profile from {}", file.display()))?;
+            let mut profile: Profile = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse profile from {}", file.display()))?;
+            profile.file = Some(file);
+            profiles.push(profile);
+        }
+        Ok(profiles)
+    }
+
+    /// The profiles/ directory next to the config file, if there is one
+    pub fn profile_dir(path: &Path) -> Option<PathBuf> {
+        let dir = path.parent()?.join("profiles");
+        dir.is_dir().then_some(dir)
+    }
+
+    /// The *.toml files in a profiles directory, sorted by name
+    pub fn profile_files(dir: &Path) -> Result<Vec<PathBuf>> {
+        let entries = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read profiles from {}", dir.display()))?;
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "toml") && p.is_file())
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// Save config to the default path. With a profiles/ directory every
+    /// profile is written to its own file there (the one it was loaded from,
+    /// else one named after it) and files of deleted profiles are removed.
     pub fn save(&self) -> Result<()> {
         let path = Self::config_path()?;
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)
                 .with_context(|| format!("Failed to create config dir {}", parent.display()))?;
         }
-        let content = toml::to_string_pretty(self).context("Failed to serialize config")?;
-        std::fs::write(&path, content)
-            .with_context(|| format!("Failed to write config to {}", path.display()))?;
+        for (file, content) in self.planned_files()? {
+            match content {
+                Some(content) => std::fs::write(&file, content)
+                    .with_context(|| format!("Failed to write {}", file.display()))?,
+                None => std::fs::remove_file(&file).with_context(|| {
+                    format!("Failed to remove deleted profile {}", file.display())
+                })?,
+            }
+        }
         Ok(())
     }
 
-    fn config_path() -> Result<PathBuf> {
+    /// The files `save` writes, in order, with their new content (None for
+    /// a profile file it removes); the config file comes last
+    pub fn planned_files(&self) -> Result<Vec<(PathBuf, Option<String>)>> {
+        let path = Self::config_path()?;
+        let config = self.without_included();
+        let (mut files, content) = match Self::profile_dir(&path) {
+            Some(dir) => {
+                let files = config.planned_profile_files(&dir)?;
+                let rest = Self {
+                    profiles: vec![],
+                    ..config
+                };
+                (files, toml::to_string_pretty(&rest))
+            }
+            None => (Vec::new(), toml::to_string_pretty(&config)),
+        };
+        files.push((path, Some(content.context("Failed to serialize config")?)));
+        Ok(files)
+    }
+
+    fn planned_profile_files(&self, dir: &Path) -> Result<Vec<(PathBuf, Option<String>)>> {
+        // Profiles keep the file they came from unless an earlier one already
+        // claimed it; the rest get a free file named after them
+        let mut taken = HashSet::new();
+        let mut files: Vec<Option<PathBuf>> = self
+            .profiles
+            .iter()
+            .map(|p| p.file.clone().filter(|f| taken.insert(f.clone())))
+            .collect();
+        for (profile, file) in self.profiles.iter().zip(files.iter_mut()) {
+            if file.is_none() {
+                let stem = profile_file_stem(&profile.name);
+                let mut candidate = dir.join(format!("{}.toml", stem));
+                let mut n = 2;
+                while taken.contains(&candidate) {
+                    candidate = dir.join(format!("{}-{}.toml", stem, n));
+                    n += 1;
+                }
+                taken.insert(candidate.clone());
+                *file = Some(candidate);
+            }
+        }
+
+        let mut planned = Vec::new();
+        for (profile, file) in self.profiles.iter().zip(files.into_iter().flatten()) {
+            let content = toml::to_string_pretty(profile)
+                .with_context(|| format!("Failed to serialize profile {}", profile.name))?;
+            planned.push((file, Some(content)));
+        }
+        for file in Self::profile_files(dir)? {
+            if !taken.contains(&file) {
+                planned.push((file, None));
+            }
+        }
+        Ok(planned)
+    }
+
+    /// Path for an export of `profile` written from the TUI:
+    /// exports/<profile>.<extension> next to config.toml (directory created)
+    pub fn export_path(profile: &str, extension: &str) -> Result<PathBuf> {
+        let path = Self::config_path()?;
+        let dir = path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("exports");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create export dir {}", dir.display()))?;
+        Ok(dir.join(format!("{}.{}", profile_file_stem(profile), extension)))
+    }
+
+    /// The config file in use: --config, else $MOUSE_MAPPER_CONFIG, else
+    /// ~/.config/mouse-mapper/config.toml
+    pub fn config_path() -> Result<PathBuf> {
+        if let Some(path) = Self::explicit_config_path() {
+            return Ok(path);
+        }
         let config_dir = dirs::config_dir().context("Could not determine config directory")?;
         Ok(config_dir.join("mouse-mapper").join("config.toml"))
     }
 
+    /// Config file given with --config or MOUSE_MAPPER_CONFIG, if any
+    pub fn explicit_config_path() -> Option<PathBuf> {
+        CONFIG_PATH_OVERRIDE.get().cloned().or_else(|| {
+            std::env::var_os("MOUSE_MAPPER_CONFIG")
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+        })
+    }
+
     /// Get the active profile
     pub fn active_profile(&self) -> Option<&Profile> {
         if let Some(ref name) = self.active_profile {
@@ -155,12 +1419,16 @@ impl Config {
         }
     }
 
-    /// Build a lookup map: input code name -> BindingOutput for the active profile
-    pub fn build_binding_map(&self) -> HashMap<String, BindingOutput> {
-        let mut map = HashMap::new();
+    /// Build a lookup map: trigger button name -> bindings for that button in
+    /// the active profile (several when they differ by modifiers or chord)
+    pub fn build_binding_map(&self) -> HashMap<String, Vec<Binding>> {
+        let mut map: HashMap<String, Vec<Binding>> = HashMap::new();
         if let Some(profile) = self.active_profile() {
-            for binding in &profile.bindings {
-                map.insert(binding.input.clone(), binding.output.clone());
+            for binding in profile.enabled_bindings() {
+                let trigger = binding.input_keys().last().copied().unwrap_or_default();
+                map.entry(trigger.to_string())
+                    .or_default()
+                    .push(binding.clone());
             }
         }
         map
@@ -178,16 +1446,55 @@ impl Config {
     }
 }
 
+/// File name (without .toml) for a profile saved to the profiles directory
+fn profile_file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.trim_matches('_').is_empty() {
+        "profile".to_string()
+    } else {
+        stem
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
             device: DeviceConfig::default(),
             profiles: vec![Profile {
                 name: "Default".to_string(),
-                bindings: vec![],
-                macros: vec![],
+                ..Profile::default()
             }],
             active_profile: Some("Default".to_string()),
+            theme: ThemeConfig::default(),
+            logging: LoggingConfig::default(),
+            keybindings: BTreeMap::new(),
+            macro_speed: default_macro_speed(),
+            max_running_macros: 0,
+            macro_limit: MacroLimitPolicy::default(),
+            virtual_keyboard: false,
+            scancodes: ScancodePolicy::default(),
+            realtime: false,
+            realtime_priority: default_realtime_priority(),
+            fresh_virtual_device: false,
+            cpu_affinity: vec![],
+            obs: None,
+            warp: None,
+            api: None,
+            leds: vec![],
+            sounds: None,
+            fullscreen_profile: None,
+            blocked_processes: vec![],
+            include: vec![],
+            included_macros: vec![],
         }
     }
 }
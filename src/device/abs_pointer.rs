@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use evdev::{
+    uinput::VirtualDevice, AbsInfo, AbsoluteAxisCode, AttributeSet, EventType, InputEvent, KeyCode,
+    UinputAbsSetup,
+};
+
+/// Second virtual device, with absolute axes spanning the whole desktop, for
+/// putting the cursor at exact screen coordinates. Kept apart from
+/// DeviceWriter because a device with both relative and absolute axes isn't
+/// treated as a plain mouse.
+pub struct AbsPointer {
+    virtual_device: VirtualDevice,
+    /// Last position sent, see `move_to`
+    last: Option<(i32, i32)>,
+}
+
+impl AbsPointer {
+    /// Create the device; (0, 0) is the top left of the desktop and
+    /// (width - 1, height - 1) the bottom right
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        // A button makes libinput treat it as a pointer rather than a touchscreen
+        let mut keys = AttributeSet::<KeyCode>::new();
+        keys.insert(KeyCode::BTN_LEFT);
+
+        let axis = |code, max: u32| {
+            UinputAbsSetup::new(
+                code,
+                AbsInfo::new(0, 0, max.saturating_sub(1) as i32, 0, 0, 0),
+            )
+        };
+        let virtual_device = VirtualDevice::builder()
+            .context("Failed to create VirtualDeviceBuilder")?
+            .name("MouseMapper Absolute Pointer")
+            .with_keys(&keys)?
+            .with_absolute_axis(&axis(AbsoluteAxisCode::ABS_X, width))?
+            .with_absolute_axis(&axis(AbsoluteAxisCode::ABS_Y, height))?
+            .build()
+            .context("Failed to build absolute pointer device")?;
+
+        log::info!("Created absolute pointer device ({}x{})", width, height);
+
+        Ok(Self {
+            virtual_device,
+            last: None,
+        })
+    }
+
+    pub fn move_to(&mut self, x: i32, y: i32) -> Result<()> {
+        let position = |x: i32, y: i32| {
+            [
+                InputEvent::new(EventType::ABSOLUTE.0, AbsoluteAxisCode::ABS_X.0, x),
+                InputEvent::new(EventType::ABSOLUTE.0, AbsoluteAxisCode::ABS_Y.0, y),
+                InputEvent::new(EventType::SYNCHRONIZATION.0, 0, 0),
+            ]
+        };
+        // The kernel drops values equal to the axis' current one, so warping
+        // to the same spot twice would do nothing after the mouse moved away.
+        // Nudge it first so the real position counts as a change.
+        if self.last == Some((x, y)) {
+            let nudge = if x > 0 { x - 1 } else { x + 1 };
+            self.virtual_device
+                .emit(&position(nudge, y))
+                .context("Failed to move absolute pointer")?;
+        }
+        self.virtual_device
+            .emit(&position(x, y))
+            .context("Failed to move absolute pointer")?;
+        self.last = Some((x, y));
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use crate::device::hidpp::{self, get_feature_index, hidpp_request, LOGITECH_VENDOR_ID};
+use anyhow::{bail, Result};
+use std::path::Path;
+
+/// At or below this percentage the TUI shows a low-battery warning
+pub const LOW_BATTERY_PERCENT: u8 = 15;
+
+// HID++ 2.0 feature ids
+const FEATURE_BATTERY_STATUS: u16 = 0x1000;
+const FEATURE_BATTERY_VOLTAGE: u16 = 0x1001;
+const FEATURE_UNIFIED_BATTERY: u16 = 0x1004;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryInfo {
+    pub percent: u8,
+    pub charging: bool,
+}
+
+impl BatteryInfo {
+    pub fn is_low(&self) -> bool {
+        !self.charging && self.percent <= LOW_BATTERY_PERCENT
+    }
+}
+
+/// Read the battery level of the mouse behind an evdev node. Tries the
+/// kernel's power_supply entry first, then asks Logitech devices directly
+/// over HID++. Returns None for wired mice and unsupported devices.
+pub fn read_battery(event_path: &Path, vendor_id: u16) -> Option<BatteryInfo> {
+    let hid_dir = hidpp::hid_device_dir(event_path)?;
+
+    if let Some(info) = read_power_supply(&hid_dir) {
+        return Some(info);
+    }
+
+    if vendor_id != LOGITECH_VENDOR_ID {
+        return None;
+    }
+    let hidraw = hidpp::find_hidraw(&hid_dir)?;
+    match read_hidpp_battery(&hidraw) {
+        Ok(info) => Some(info),
+        Err(e) => {
+            log::debug!(
+                "HID++ battery query on {} failed: {:#}",
+                hidraw.display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Battery as reported by the kernel driver (hid-logitech-hidpp, hid-generic
+/// battery strength, ...)
+fn read_power_supply(hid_dir: &Path) -> Option<BatteryInfo> {
+    for entry in std::fs::read_dir(hid_dir.join("power_supply")).ok()? {
+        let dir = entry.ok()?.path();
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).ok();
+
+        let percent = read("capacity")
+            .and_then(|s| s.trim().parse::<u8>().ok())
+            .or_else(|| {
+                // Some drivers only report coarse levels
+                read("capacity_level").and_then(|s| match s.trim() {
+                    "Full" => Some(100),
+                    "High" => Some(80),
+                    "Normal" => Some(50),
+                    "Low" => Some(15),
+                    "Critical" => Some(5),
+                    _ => None,
+                })
+            });
+        if let Some(percent) = percent {
+            let charging = read("status").is_some_and(|s| s.trim() == "Charging");
+            return Some(BatteryInfo { percent, charging });
+        }
+    }
+    None
+}
+
+// === HID++ 2.0 ===
+
+fn read_hidpp_battery(hidraw: &Path) -> Result<BatteryInfo> {
+    let mut dev = hidpp::open(hidraw)?;
+
+    if let Some(index) = get_feature_index(&mut dev, FEATURE_UNIFIED_BATTERY)? {
+        // get_status: [state_of_charge %, level flags, charging status, external power]
+        let reply = hidpp_request(&mut dev, index, 1, &[])?;
+        return Ok(BatteryInfo {
+            percent: reply[0].min(100),
+            charging: matches!(reply[2], 1 | 2),
+        });
+    }
+
+    if let Some(index) = get_feature_index(&mut dev, FEATURE_BATTERY_STATUS)? {
+        // getBatteryLevelStatus: [level %, next level %, status]
+        let reply = hidpp_request(&mut dev, index, 0, &[])?;
+        return Ok(BatteryInfo {
+            percent: reply[0].min(100),
+            charging: matches!(reply[2], 1..=3),
+        });
+    }
+
+    if let Some(index) = get_feature_index(&mut dev, FEATURE_BATTERY_VOLTAGE)? {
+        // getBatteryInfo: [voltage mV (big endian), flags]
+        let reply = hidpp_request(&mut dev, index, 0, &[])?;
+        let millivolts = u16::from_be_bytes([reply[0], reply[1]]);
+        return Ok(BatteryInfo {
+            percent: voltage_to_percent(millivolts),
+            charging: reply[2] & 0x80 != 0,
+        });
+    }
+
+    bail!("device has no HID++ battery feature")
+}
+
+/// Rough discharge curve of a single Li-ion cell
+fn voltage_to_percent(millivolts: u16) -> u8 {
+    const CURVE: &[(u16, u8)] = &[
+        (4186, 100),
+        (4067, 90),
+        (3989, 80),
+        (3922, 70),
+        (3859, 60),
+        (3811, 50),
+        (3778, 40),
+        (3751, 30),
+        (3717, 20),
+        (3671, 10),
+        (3500, 0),
+    ];
+    for window in CURVE.windows(2) {
+        let (hi_mv, hi_pct) = window[0];
+        let (lo_mv, lo_pct) = window[1];
+        if millivolts >= hi_mv {
+            return hi_pct;
+        }
+        if millivolts >= lo_mv {
+            let span = (hi_mv - lo_mv) as u32;
+            let above = (millivolts - lo_mv) as u32;
+            return lo_pct + ((hi_pct - lo_pct) as u32 * above / span) as u8;
+        }
+    }
+    0
+}
@@ -0,0 +1,114 @@
+use anyhow::{bail, Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub const LOGITECH_VENDOR_ID: u16 = 0x046d;
+
+// HID++ report ids and lengths
+const HIDPP_SHORT: u8 = 0x10;
+const HIDPP_LONG: u8 = 0x11;
+const HIDPP_SHORT_LEN: usize = 7;
+const HIDPP_LONG_LEN: usize = 20;
+/// Device index for devices connected directly (USB cable, Bluetooth). The
+/// receiver driver rewrites it for devices paired through a receiver.
+const HIDPP_DIRECT_INDEX: u8 = 0xff;
+/// Arbitrary non-zero software id so we can recognise our replies
+const HIDPP_SW_ID: u8 = 0x0a;
+const HIDPP_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// /sys/class/input/eventN/device/device is the HID device the input node belongs to
+pub fn hid_device_dir(event_path: &Path) -> Option<PathBuf> {
+    let event = event_path.file_name()?.to_str()?;
+    std::fs::canonicalize(format!("/sys/class/input/{}/device/device", event)).ok()
+}
+
+/// The hidraw node of a HID device, e.g. /dev/hidraw3
+pub fn find_hidraw(hid_dir: &Path) -> Option<PathBuf> {
+    let entry = std::fs::read_dir(hid_dir.join("hidraw"))
+        .ok()?
+        .next()?
+        .ok()?;
+    Some(Path::new("/dev").join(entry.file_name()))
+}
+
+/// Open a hidraw node for HID++ requests
+pub fn open(hidraw: &Path) -> Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(hidraw)
+        .with_context(|| format!("Failed to open {}", hidraw.display()))
+}
+
+/// Ask the root feature (index 0) which index a feature lives at
+pub fn get_feature_index(dev: &mut File, feature: u16) -> Result<Option<u8>> {
+    let reply = hidpp_request(dev, 0x00, 0, &feature.to_be_bytes())?;
+    Ok((reply[0] != 0).then_some(reply[0]))
+}
+
+/// Send a short HID++ request and return the parameter bytes of the reply
+pub fn hidpp_request(
+    dev: &mut File,
+    feature_index: u8,
+    function: u8,
+    params: &[u8],
+) -> Result<Vec<u8>> {
+    let fn_sw = (function << 4) | HIDPP_SW_ID;
+    let mut request = [0u8; HIDPP_SHORT_LEN];
+    request[0] = HIDPP_SHORT;
+    request[1] = HIDPP_DIRECT_INDEX;
+    request[2] = feature_index;
+    request[3] = fn_sw;
+    request[4..4 + params.len()].copy_from_slice(params);
+    dev.write_all(&request)
+        .context("Failed to send HID++ request")?;
+
+    let deadline = Instant::now() + HIDPP_TIMEOUT;
+    let mut buf = [0u8; 64];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            bail!("timed out waiting for HID++ reply");
+        }
+        let mut pfd = libc::pollfd {
+            fd: dev.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: pfd is a valid pollfd for the lifetime of the call
+        let ready = unsafe { libc::poll(&mut pfd, 1, remaining.as_millis() as i32) };
+        if ready <= 0 {
+            continue;
+        }
+
+        let n = match dev.read(&mut buf) {
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+            Err(e) => return Err(e).context("Failed to read HID++ reply"),
+        };
+        // Other input reports (mouse movement, receiver notifications) arrive
+        // on the same node; skip everything that isn't our reply
+        if n < HIDPP_SHORT_LEN || !matches!(buf[0], HIDPP_SHORT | HIDPP_LONG) {
+            continue;
+        }
+        if buf[2] == 0xff && buf[3] == feature_index && buf[4] == fn_sw {
+            bail!("HID++ error {:#04x}", buf[5]);
+        }
+        if buf[2] == 0x8f && buf[3] == feature_index {
+            bail!("device does not speak HID++ 2.0 (error {:#04x})", buf[5]);
+        }
+        if buf[2] == feature_index && buf[3] == fn_sw {
+            let len = if buf[0] == HIDPP_LONG {
+                HIDPP_LONG_LEN
+            } else {
+                HIDPP_SHORT_LEN
+            };
+            return Ok(buf[4..len.min(n)].to_vec());
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::device::hidpp;
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Read;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use tokio::sync::{mpsc, watch};
+
+/// How long a read waits before checking for cancellation again
+const POLL_TIMEOUT_MS: i32 = 200;
+
+/// Read-only view of the hidraw node behind an evdev device. Shows every HID
+/// report the device sends, including ones the kernel never turns into
+/// input events.
+pub struct HidrawReader {
+    file: File,
+    path: PathBuf,
+}
+
+impl HidrawReader {
+    /// Open the hidraw node belonging to an evdev node
+    pub fn open(event_path: &Path) -> Result<Self> {
+        let path = hidpp::hid_device_dir(event_path)
+            .and_then(|dir| hidpp::find_hidraw(&dir))
+            .with_context(|| format!("No hidraw node found for {}", event_path.display()))?;
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        log::info!("Opened {} for {}", path.display(), event_path.display());
+        Ok(Self { file, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Send every report read until cancelled or the receiver is dropped.
+    /// This should be called from a blocking tokio task.
+    pub fn read_loop(
+        mut self,
+        tx: mpsc::UnboundedSender<Vec<u8>>,
+        cancel_rx: watch::Receiver<bool>,
+    ) -> Result<()> {
+        let mut buf = [0u8; 4096];
+        while !*cancel_rx.borrow() {
+            let mut pfd = libc::pollfd {
+                fd: self.file.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: pfd is a valid pollfd for the lifetime of the call
+            let ready = unsafe { libc::poll(&mut pfd, 1, POLL_TIMEOUT_MS) };
+            if ready <= 0 {
+                continue;
+            }
+            let n = match self.file.read(&mut buf) {
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to read {}", self.path.display()));
+                }
+            };
+            if tx.send(buf[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use crate::config::KeyModifier;
+use crate::device::writer::{VIRTUAL_DEVICE_NAME, VIRTUAL_KEYBOARD_NAME};
+use anyhow::{Context, Result};
+use evdev::{Device, KeyCode};
+use std::path::{Path, PathBuf};
+
+/// Physical modifier keys, in ModifierSet bit order
+const MODIFIER_KEYS: [KeyCode; 8] = [
+    KeyCode::KEY_LEFTCTRL,
+    KeyCode::KEY_RIGHTCTRL,
+    KeyCode::KEY_LEFTSHIFT,
+    KeyCode::KEY_RIGHTSHIFT,
+    KeyCode::KEY_LEFTALT,
+    KeyCode::KEY_RIGHTALT,
+    KeyCode::KEY_LEFTMETA,
+    KeyCode::KEY_RIGHTMETA,
+];
+
+/// Set of held modifier keys (one bit per entry of MODIFIER_KEYS)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModifierSet(u8);
+
+impl ModifierSet {
+    /// Bit for a physical modifier key, None for any other key
+    fn bit(key: KeyCode) -> Option<u8> {
+        MODIFIER_KEYS.iter().position(|k| *k == key).map(|i| 1 << i)
+    }
+
+    /// Record a press or release of `key`; returns false if it isn't a modifier
+    pub fn update(&mut self, key: KeyCode, pressed: bool) -> bool {
+        let Some(bit) = Self::bit(key) else {
+            return false;
+        };
+        if pressed {
+            self.0 |= bit;
+        } else {
+            self.0 &= !bit;
+        }
+        true
+    }
+
+    /// Whether `key` is one of the physical modifier keys
+    pub fn is_modifier(key: KeyCode) -> bool {
+        Self::bit(key).is_some()
+    }
+
+    pub fn union(self, other: ModifierSet) -> ModifierSet {
+        ModifierSet(self.0 | other.0)
+    }
+
+    /// The modifiers of `self` that aren't in `other`
+    pub fn without(self, other: ModifierSet) -> ModifierSet {
+        ModifierSet(self.0 & !other.0)
+    }
+
+    /// The modifier keys in the set, in MODIFIER_KEYS order
+    pub fn keys(self) -> impl Iterator<Item = KeyCode> {
+        MODIFIER_KEYS
+            .into_iter()
+            .enumerate()
+            .filter(move |(i, _)| self.0 & (1 << i) != 0)
+            .map(|(_, key)| key)
+    }
+
+    /// Whether either the left or the right key of `modifier` is held
+    pub fn contains(&self, modifier: KeyModifier) -> bool {
+        let mask = match modifier {
+            KeyModifier::Ctrl => 0b0000_0011,
+            KeyModifier::Shift => 0b0000_1100,
+            KeyModifier::Alt => 0b0011_0000,
+            KeyModifier::Meta => 0b1100_0000,
+        };
+        self.0 & mask != 0
+    }
+}
+
+/// Key pressed to apply `modifier` on output (the left one)
+pub fn modifier_key(modifier: KeyModifier) -> KeyCode {
+    match modifier {
+        KeyModifier::Ctrl => KeyCode::KEY_LEFTCTRL,
+        KeyModifier::Shift => KeyCode::KEY_LEFTSHIFT,
+        KeyModifier::Alt => KeyCode::KEY_LEFTALT,
+        KeyModifier::Meta => KeyCode::KEY_LEFTMETA,
+    }
+}
+
+/// Reads which modifiers are held on the keyboards, without grabbing them.
+/// The state is queried from the kernel (EVIOCGKEY) when a binding needs it,
+/// so no events have to be read in the background.
+pub struct KeyboardMonitor {
+    keyboards: Vec<Device>,
+}
+
+impl KeyboardMonitor {
+    /// Open the keyboards matching `filter` (path or name substring, all
+    /// keyboards if None), skipping the grabbed mouse at `exclude`
+    pub fn open(filter: Option<&str>, exclude: &Path) -> Result<Self> {
+        let mut keyboards = Vec::new();
+        for (path, device) in find_keyboards(filter, exclude)? {
+            log::info!(
+                "Reading modifier state from {} ({})",
+                device.name().unwrap_or("Unknown"),
+                path.display()
+            );
+            keyboards.push(device);
+        }
+        if keyboards.is_empty() {
+            anyhow::bail!("No keyboard found for modifier-conditioned bindings");
+        }
+        Ok(Self { keyboards })
+    }
+
+    /// Modifiers currently held on any of the monitored keyboards
+    pub fn held(&self) -> ModifierSet {
+        let mut held = ModifierSet::default();
+        for keyboard in &self.keyboards {
+            let Ok(state) = keyboard.get_key_state() else {
+                continue;
+            };
+            for key in MODIFIER_KEYS {
+                if state.contains(key) {
+                    held.update(key, true);
+                }
+            }
+        }
+        held
+    }
+}
+
+/// Open the keyboards matching `filter` (path or name substring, all
+/// keyboards if None), skipping the grabbed mouse at `exclude` and our own
+/// virtual devices (they hold the modifiers the engine presses)
+pub fn find_keyboards(filter: Option<&str>, exclude: &Path) -> Result<Vec<(PathBuf, Device)>> {
+    let mut keyboards = Vec::new();
+    for entry in std::fs::read_dir("/dev/input").context("Failed to read /dev/input")? {
+        let path = entry?.path();
+        let is_event = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with("event"));
+        if !is_event || path == exclude {
+            continue;
+        }
+        let Ok(device) = Device::open(&path) else {
+            continue;
+        };
+        let ours = device
+            .name()
+            .is_some_and(|name| name == VIRTUAL_DEVICE_NAME || name == VIRTUAL_KEYBOARD_NAME);
+        if is_keyboard(&device) && !ours && matches_filter(&device, &path, filter) {
+            keyboards.push((path, device));
+        }
+    }
+    Ok(keyboards)
+}
+
+fn is_keyboard(device: &Device) -> bool {
+    device
+        .supported_keys()
+        .is_some_and(|keys| keys.contains(KeyCode::KEY_A) && keys.contains(KeyCode::KEY_LEFTCTRL))
+}
+
+fn matches_filter(device: &Device, path: &Path, filter: Option<&str>) -> bool {
+    match filter {
+        None => true,
+        Some(filter) => {
+            path.to_str() == Some(filter)
+                || device
+                    .name()
+                    .is_some_and(|n| n.to_lowercase().contains(&filter.to_lowercase()))
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use crate::config::LedConfig;
+use crate::device::keyboard;
+use anyhow::{bail, Context, Result};
+use evdev::{AttributeSet, Device, EventType, InputEvent, LedCode};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+/// Name of the LED-only virtual device (`device = "virtual"`)
+pub const VIRTUAL_LEDS_NAME: &str = "MouseMapper LEDs";
+
+// From linux/uinput.h and linux/input.h
+const UI_DEV_CREATE: libc::c_ulong = 0x5501;
+const UI_DEV_DESTROY: libc::c_ulong = 0x5502;
+const UI_DEV_SETUP: libc::c_ulong = 0x405c_5503;
+const UI_SET_EVBIT: libc::c_ulong = 0x4004_5564;
+const UI_SET_LEDBIT: libc::c_ulong = 0x4004_5569;
+const BUS_VIRTUAL: u16 = 0x06;
+
+/// LED names accepted in `led`
+pub const LED_NAMES: [(&str, LedCode); 5] = [
+    ("numlock", LedCode::LED_NUML),
+    ("capslock", LedCode::LED_CAPSL),
+    ("scrolllock", LedCode::LED_SCROLLL),
+    ("compose", LedCode::LED_COMPOSE),
+    ("kana", LedCode::LED_KANA),
+];
+
+pub fn parse_led(name: &str) -> Option<LedCode> {
+    let name = name.trim().to_lowercase();
+    LED_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, led)| *led)
+}
+
+/// What lights a LED
+#[derive(Debug, Clone, PartialEq)]
+pub enum LedSource {
+    Mode(String),
+    Profile(String),
+    Macro(String),
+}
+
+impl LedSource {
+    /// The one of `mode`, `profile` and `macro` that's set
+    pub fn of(config: &LedConfig) -> Result<Self> {
+        match (&config.mode, &config.profile, &config.macro_name) {
+            (Some(mode), None, None) => Ok(LedSource::Mode(mode.clone())),
+            (None, Some(profile), None) => Ok(LedSource::Profile(profile.clone())),
+            (None, None, Some(name)) => Ok(LedSource::Macro(name.clone())),
+            (None, None, None) => bail!("LED {} has no mode, profile or macro", config.led),
+            _ => bail!(
+                "LED {} has more than one of mode, profile and macro",
+                config.led
+            ),
+        }
+    }
+
+    fn lit(&self, profile: Option<&str>, modes: &[String], macros: &[String]) -> bool {
+        match self {
+            LedSource::Mode(mode) => modes.contains(mode),
+            LedSource::Profile(name) => profile == Some(name.as_str()),
+            LedSource::Macro(name) => macros.contains(name),
+        }
+    }
+}
+
+/// Where a LED is switched
+enum Target {
+    /// Keyboards, with the LEDs they had lit before, put back at the end
+    Keyboards(Vec<(Device, AttributeSet<LedCode>)>),
+    Virtual(VirtualLeds),
+}
+
+impl Target {
+    fn set(&mut self, led: LedCode, on: bool) -> io::Result<()> {
+        match self {
+            Target::Keyboards(keyboards) => {
+                let event = [InputEvent::new(EventType::LED.0, led.0, on as i32)];
+                for (keyboard, _) in keyboards {
+                    keyboard.send_events(&event)?;
+                }
+                Ok(())
+            }
+            Target::Virtual(leds) => leds.set(led, on),
+        }
+    }
+}
+
+struct Indicator {
+    led: LedCode,
+    source: LedSource,
+    /// Index into `Leds::targets`
+    target: usize,
+    /// Last state written, None before the first
+    lit: Option<bool>,
+}
+
+/// The `[[leds]]` of the config: LEDs on keyboards or a virtual device that
+/// follow the engine's modes, profile and running macros
+pub struct Leds {
+    /// Device setting of each target, and the target
+    targets: Vec<(Option<String>, Target)>,
+    indicators: Vec<Indicator>,
+}
+
+impl Leds {
+    /// Open the devices of the configured LEDs (the [device] `keyboard` for
+    /// those without a device of their own), skipping the grabbed mouse at
+    /// `exclude`. A LED that can't be used is left out with a warning.
+    pub fn open(
+        configs: &[LedConfig],
+        keyboard: Option<&str>,
+        exclude: &Path,
+    ) -> (Self, Vec<String>) {
+        let mut leds = Leds {
+            targets: Vec::new(),
+            indicators: Vec::new(),
+        };
+        let mut warnings = Vec::new();
+        let virtual_leds: Vec<LedCode> = configs
+            .iter()
+            .filter(|c| c.device.as_deref() == Some("virtual"))
+            .filter_map(|c| parse_led(&c.led))
+            .collect();
+        for config in configs {
+            let Some(led) = parse_led(&config.led) else {
+                warnings.push(format!(
+                    "Unknown LED '{}' (one of {})",
+                    config.led,
+                    LED_NAMES.map(|(name, _)| name).join(", ")
+                ));
+                continue;
+            };
+            let source = match LedSource::of(config) {
+                Ok(source) => source,
+                Err(e) => {
+                    warnings.push(format!("{:#}", e));
+                    continue;
+                }
+            };
+            let device = config.device.clone().or(keyboard.map(str::to_string));
+            let target = match leds.targets.iter().position(|(d, _)| *d == device) {
+                Some(index) => index,
+                None => {
+                    let target = match device.as_deref() {
+                        Some("virtual") => VirtualLeds::new(&virtual_leds).map(Target::Virtual),
+                        filter => open_keyboards(filter, exclude).map(Target::Keyboards),
+                    };
+                    match target {
+                        Ok(target) => {
+                            leds.targets.push((device, target));
+                            leds.targets.len() - 1
+                        }
+                        Err(e) => {
+                            warnings.push(format!("LED {}: {:#}", config.led, e));
+                            continue;
+                        }
+                    }
+                }
+            };
+            leds.indicators.push(Indicator {
+                led,
+                source,
+                target,
+                lit: None,
+            });
+        }
+        for warning in &warnings {
+            log::warn!("{}", warning);
+        }
+        (leds, warnings)
+    }
+
+    /// Light or put out every LED for this state; only changes are written
+    pub fn show(&mut self, profile: Option<&str>, modes: &[String], macros: &[String]) {
+        for indicator in &mut self.indicators {
+            let lit = indicator.source.lit(profile, modes, macros);
+            if indicator.lit == Some(lit) {
+                continue;
+            }
+            indicator.lit = Some(lit);
+            if let Err(e) = self.targets[indicator.target].1.set(indicator.led, lit) {
+                log::error!("Failed to switch LED {:?}: {}", indicator.led, e);
+            }
+        }
+    }
+}
+
+impl Drop for Leds {
+    fn drop(&mut self) {
+        // The virtual device goes with its file; keyboards get their LEDs back
+        for indicator in &self.indicators {
+            if let Target::Keyboards(keyboards) = &mut self.targets[indicator.target].1 {
+                for (keyboard, before) in keyboards {
+                    let on = before.contains(indicator.led);
+                    let event = [InputEvent::new(
+                        EventType::LED.0,
+                        indicator.led.0,
+                        on as i32,
+                    )];
+                    let _ = keyboard.send_events(&event);
+                }
+            }
+        }
+    }
+}
+
+fn open_keyboards(
+    filter: Option<&str>,
+    exclude: &Path,
+) -> Result<Vec<(Device, AttributeSet<LedCode>)>> {
+    let mut keyboards = Vec::new();
+    for (path, device) in keyboard::find_keyboards(filter, exclude)? {
+        if device.supported_leds().is_none() {
+            continue;
+        }
+        let before = device
+            .get_led_state()
+            .with_context(|| format!("Failed to read the LEDs of {}", path.display()))?;
+        log::info!(
+            "Showing LEDs on {} ({})",
+            device.name().unwrap_or("Unknown"),
+            path.display()
+        );
+        keyboards.push((device, before));
+    }
+    if keyboards.is_empty() {
+        bail!("No keyboard with LEDs found");
+    }
+    Ok(keyboards)
+}
+
+/// uinput device with nothing but LEDs. evdev's VirtualDeviceBuilder can't
+/// declare LEDs, so it's set up with the uinput ioctls directly.
+struct VirtualLeds {
+    file: File,
+}
+
+impl VirtualLeds {
+    fn new(leds: &[LedCode]) -> Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/uinput")
+            .context("Failed to open /dev/uinput")?;
+        let fd = file.as_raw_fd();
+        let ioctl = |request: libc::c_ulong, arg: libc::c_ulong, what: &str| -> Result<()> {
+            if unsafe { libc::ioctl(fd, request as _, arg) } < 0 {
+                return Err(io::Error::last_os_error())
+                    .with_context(|| format!("Failed to {} the virtual LED device", what));
+            }
+            Ok(())
+        };
+        ioctl(UI_SET_EVBIT, EventType::LED.0.into(), "set up")?;
+        for led in leds {
+            ioctl(UI_SET_LEDBIT, led.0.into(), "set up")?;
+        }
+        let mut setup: libc::uinput_setup = unsafe { std::mem::zeroed() };
+        setup.id.bustype = BUS_VIRTUAL;
+        for (dst, src) in setup.name.iter_mut().zip(VIRTUAL_LEDS_NAME.bytes()) {
+            *dst = src as libc::c_char;
+        }
+        ioctl(UI_DEV_SETUP, &setup as *const _ as libc::c_ulong, "set up")?;
+        ioctl(UI_DEV_CREATE, 0, "create")?;
+        log::info!("Created virtual device: {}", VIRTUAL_LEDS_NAME);
+        Ok(Self { file })
+    }
+
+    fn set(&mut self, led: LedCode, on: bool) -> io::Result<()> {
+        let event = |type_: u16, code: u16, value: i32| {
+            let mut event: libc::input_event = unsafe { std::mem::zeroed() };
+            event.type_ = type_;
+            event.code = code;
+            event.value = value;
+            event
+        };
+        let events = [
+            event(EventType::LED.0, led.0, on as i32),
+            event(EventType::SYNCHRONIZATION.0, 0, 0),
+        ];
+        let bytes = unsafe {
+            std::slice::from_raw_parts(events.as_ptr() as *const u8, std::mem::size_of_val(&events))
+        };
+        self.file.write_all(bytes)
+    }
+}
+
+impl Drop for VirtualLeds {
+    fn drop(&mut self) {
+        unsafe { libc::ioctl(self.file.as_raw_fd(), UI_DEV_DESTROY as _) };
+    }
+}
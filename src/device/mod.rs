@@ -1,3 +1,13 @@
-pub mod scanner;
+pub mod abs_pointer;
+pub mod battery;
+pub mod hidpp;
+pub mod hidraw;
+pub mod keyboard;
+pub mod leds;
+pub mod ratbag;
 pub mod reader;
+pub mod scanner;
+pub mod sensor;
+pub mod synthetic;
+pub mod watch;
 pub mod writer;
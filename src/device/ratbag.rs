@@ -0,0 +1,320 @@
+use anyhow::{Context, Result};
+use zbus::blocking::{Connection, Proxy};
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, StructureBuilder, Value};
+
+const DESTINATION: &str = "org.freedesktop.ratbag1";
+const MANAGER_PATH: &str = "/org/freedesktop/ratbag1";
+
+/// Special button functions understood by libratbag (RATBAG_BUTTON_ACTION_SPECIAL_*)
+const SPECIAL_NAMES: &[(u32, &str)] = &[
+    (0x4000_0001, "Double click"),
+    (0x4000_0002, "Wheel left"),
+    (0x4000_0003, "Wheel right"),
+    (0x4000_0004, "Wheel up"),
+    (0x4000_0005, "Wheel down"),
+    (0x4000_0006, "Ratchet mode switch"),
+    (0x4000_0007, "Resolution cycle up"),
+    (0x4000_0008, "Resolution cycle down"),
+    (0x4000_0009, "Resolution up"),
+    (0x4000_000a, "Resolution down"),
+    (0x4000_000b, "Resolution alternate"),
+    (0x4000_000c, "Resolution default"),
+    (0x4000_000d, "Profile cycle up"),
+    (0x4000_000e, "Profile cycle down"),
+    (0x4000_000f, "Profile up"),
+    (0x4000_0010, "Profile down"),
+    (0x4000_0011, "Second mode"),
+    (0x4000_0012, "Battery level"),
+];
+
+/// What an onboard button does, as reported by ratbagd
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ButtonAction {
+    None,
+    /// Acts as mouse button N (1 = left, 2 = right, 3 = middle, ...)
+    Button(u32),
+    Special(u32),
+    /// Emits an evdev key code
+    Key(u32),
+    /// Onboard macro as (event type, key code) pairs
+    Macro(Vec<(u32, u32)>),
+    Unknown,
+}
+
+impl ButtonAction {
+    pub fn describe(&self) -> String {
+        match self {
+            ButtonAction::None => "Disabled".to_string(),
+            ButtonAction::Button(n) => format!("Button {}", n),
+            ButtonAction::Special(code) => SPECIAL_NAMES
+                .iter()
+                .find(|(c, _)| c == code)
+                .map(|(_, name)| name.to_string())
+                .unwrap_or_else(|| format!("Special {:#x}", code)),
+            ButtonAction::Key(code) => format!("{:?}", evdev::KeyCode::new(*code as u16)),
+            ButtonAction::Macro(events) => format!("Macro ({} events)", events.len()),
+            ButtonAction::Unknown => "Unknown".to_string(),
+        }
+    }
+}
+
+/// One DPI stage of a profile
+#[derive(Debug, Clone)]
+pub struct RatbagResolution {
+    pub path: OwnedObjectPath,
+    pub index: u32,
+    pub is_active: bool,
+    pub is_default: bool,
+    /// (x, y) DPI; y == x for devices without separate axes
+    pub dpi: (u32, u32),
+    /// Whether the device takes separate x/y values
+    pub separate_xy: bool,
+    /// DPI values the device supports
+    pub supported: Vec<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RatbagButton {
+    pub path: OwnedObjectPath,
+    pub index: u32,
+    pub action: ButtonAction,
+}
+
+#[derive(Debug, Clone)]
+pub struct RatbagProfile {
+    pub path: OwnedObjectPath,
+    pub index: u32,
+    pub name: String,
+    pub is_active: bool,
+    pub report_rate: u32,
+    pub report_rates: Vec<u32>,
+    pub resolutions: Vec<RatbagResolution>,
+    pub buttons: Vec<RatbagButton>,
+}
+
+/// Snapshot of a device managed by ratbagd
+#[derive(Debug, Clone)]
+pub struct RatbagDevice {
+    pub path: OwnedObjectPath,
+    pub name: String,
+    pub model: String,
+    pub profiles: Vec<RatbagProfile>,
+}
+
+impl RatbagDevice {
+    pub fn active_profile_index(&self) -> usize {
+        self.profiles.iter().position(|p| p.is_active).unwrap_or(0)
+    }
+}
+
+/// Client for ratbagd, the libratbag D-Bus daemon that configures the
+/// onboard memory (DPI stages, button functions, profiles) of gaming mice.
+/// Setters only change ratbagd's copy; `commit` writes it to the device.
+#[derive(Clone)]
+pub struct Ratbag {
+    conn: Connection,
+}
+
+impl Ratbag {
+    pub fn connect() -> Result<Self> {
+        let conn = Connection::system().context("Failed to connect to the system D-Bus")?;
+        let ratbag = Self { conn };
+        // Fail early with a clear message if ratbagd isn't running
+        ratbag
+            .proxy(MANAGER_PATH, "Manager")?
+            .get_property::<i32>("APIVersion")
+            .context("ratbagd is not running (install libratbag / start ratbagd.service)")?;
+        Ok(ratbag)
+    }
+
+    fn proxy(&self, path: &str, interface: &str) -> Result<Proxy<'static>> {
+        let proxy = zbus::blocking::proxy::Builder::<Proxy>::new(&self.conn)
+            .destination(DESTINATION)?
+            .path(path.to_string())?
+            .interface(format!("{}.{}", DESTINATION, interface))?
+            .cache_properties(CacheProperties::No)
+            .build()?;
+        Ok(proxy)
+    }
+
+    /// Read every device with all of its profiles, DPI stages and buttons
+    pub fn devices(&self) -> Result<Vec<RatbagDevice>> {
+        let paths: Vec<OwnedObjectPath> = self
+            .proxy(MANAGER_PATH, "Manager")?
+            .get_property("Devices")
+            .context("Failed to list ratbagd devices")?;
+
+        paths
+            .into_iter()
+            .map(|path| self.read_device(path))
+            .collect()
+    }
+
+    fn read_device(&self, path: OwnedObjectPath) -> Result<RatbagDevice> {
+        let proxy = self.proxy(path.as_str(), "Device")?;
+        let profile_paths: Vec<OwnedObjectPath> = proxy.get_property("Profiles")?;
+        Ok(RatbagDevice {
+            name: proxy.get_property("Name")?,
+            model: proxy.get_property("Model").unwrap_or_default(),
+            profiles: profile_paths
+                .into_iter()
+                .map(|p| self.read_profile(p))
+                .collect::<Result<_>>()?,
+            path,
+        })
+    }
+
+    fn read_profile(&self, path: OwnedObjectPath) -> Result<RatbagProfile> {
+        let proxy = self.proxy(path.as_str(), "Profile")?;
+        let resolution_paths: Vec<OwnedObjectPath> = proxy.get_property("Resolutions")?;
+        let button_paths: Vec<OwnedObjectPath> = proxy.get_property("Buttons")?;
+        Ok(RatbagProfile {
+            index: proxy.get_property("Index")?,
+            name: proxy.get_property("Name").unwrap_or_default(),
+            is_active: proxy.get_property("IsActive")?,
+            report_rate: proxy.get_property("ReportRate").unwrap_or(0),
+            report_rates: proxy.get_property("ReportRates").unwrap_or_default(),
+            resolutions: resolution_paths
+                .into_iter()
+                .map(|p| self.read_resolution(p))
+                .collect::<Result<_>>()?,
+            buttons: button_paths
+                .into_iter()
+                .map(|p| self.read_button(p))
+                .collect::<Result<_>>()?,
+            path,
+        })
+    }
+
+    fn read_resolution(&self, path: OwnedObjectPath) -> Result<RatbagResolution> {
+        let proxy = self.proxy(path.as_str(), "Resolution")?;
+        // "Resolution" is a variant: u for single-axis devices, (uu) otherwise
+        let value: OwnedValue = proxy.get_property("Resolution")?;
+        let (dpi, separate_xy) = match &*value {
+            Value::U32(dpi) => ((*dpi, *dpi), false),
+            Value::Structure(s) => match s.fields() {
+                [Value::U32(x), Value::U32(y)] => ((*x, *y), true),
+                _ => ((0, 0), false),
+            },
+            _ => ((0, 0), false),
+        };
+        Ok(RatbagResolution {
+            index: proxy.get_property("Index")?,
+            is_active: proxy.get_property("IsActive")?,
+            is_default: proxy.get_property("IsDefault").unwrap_or(false),
+            dpi,
+            separate_xy,
+            supported: proxy.get_property("Resolutions").unwrap_or_default(),
+            path,
+        })
+    }
+
+    fn read_button(&self, path: OwnedObjectPath) -> Result<RatbagButton> {
+        let proxy = self.proxy(path.as_str(), "Button")?;
+        // "Mapping" is (uv): action type + type-specific value
+        let value: OwnedValue = proxy.get_property("Mapping")?;
+        let action = match &*value {
+            Value::Structure(s) => match s.fields() {
+                [Value::U32(kind), Value::Value(inner)] => parse_action(*kind, inner),
+                _ => ButtonAction::Unknown,
+            },
+            _ => ButtonAction::Unknown,
+        };
+        Ok(RatbagButton {
+            index: proxy.get_property("Index")?,
+            action,
+            path,
+        })
+    }
+
+    pub fn set_active_profile(&self, profile: &RatbagProfile) -> Result<()> {
+        self.proxy(profile.path.as_str(), "Profile")?
+            .call_method("SetActive", &())
+            .context("Failed to activate profile")?;
+        Ok(())
+    }
+
+    pub fn set_report_rate(&self, profile: &RatbagProfile, rate: u32) -> Result<()> {
+        self.proxy(profile.path.as_str(), "Profile")?
+            .set_property("ReportRate", rate)
+            .context("Failed to set report rate")?;
+        Ok(())
+    }
+
+    pub fn set_active_resolution(&self, resolution: &RatbagResolution) -> Result<()> {
+        self.proxy(resolution.path.as_str(), "Resolution")?
+            .call_method("SetActive", &())
+            .context("Failed to activate DPI stage")?;
+        Ok(())
+    }
+
+    pub fn set_default_resolution(&self, resolution: &RatbagResolution) -> Result<()> {
+        self.proxy(resolution.path.as_str(), "Resolution")?
+            .call_method("SetDefault", &())
+            .context("Failed to set default DPI stage")?;
+        Ok(())
+    }
+
+    pub fn set_dpi(&self, resolution: &RatbagResolution, dpi: u32) -> Result<()> {
+        let proxy = self.proxy(resolution.path.as_str(), "Resolution")?;
+        let result = if resolution.separate_xy {
+            proxy.set_property("Resolution", (dpi, dpi))
+        } else {
+            proxy.set_property("Resolution", dpi)
+        };
+        result.context("Failed to set DPI")?;
+        Ok(())
+    }
+
+    /// Only plain button, special and key actions can be written back
+    pub fn set_button_action(&self, button: &RatbagButton, action: &ButtonAction) -> Result<()> {
+        let (kind, value) = match action {
+            ButtonAction::None => (0u32, 0u32),
+            ButtonAction::Button(n) => (1, *n),
+            ButtonAction::Special(code) => (2, *code),
+            ButtonAction::Key(code) => (3, *code),
+            ButtonAction::Macro(_) | ButtonAction::Unknown => {
+                anyhow::bail!("Can't assign {} from mouse-mapper", action.describe())
+            }
+        };
+        let mapping = StructureBuilder::new()
+            .add_field(kind)
+            .append_field(Value::Value(Box::new(Value::U32(value))))
+            .build()?;
+        self.proxy(button.path.as_str(), "Button")?
+            .set_property("Mapping", mapping)
+            .context("Failed to set button mapping")?;
+        Ok(())
+    }
+
+    /// Write ratbagd's pending changes to the device's onboard memory
+    pub fn commit(&self, device: &RatbagDevice) -> Result<()> {
+        self.proxy(device.path.as_str(), "Device")?
+            .call_method("Commit", &())
+            .context("Failed to commit changes to the device")?;
+        Ok(())
+    }
+}
+
+fn parse_action(kind: u32, value: &Value) -> ButtonAction {
+    match (kind, value) {
+        (0, _) => ButtonAction::None,
+        (1, Value::U32(n)) => ButtonAction::Button(*n),
+        (2, Value::U32(code)) => ButtonAction::Special(*code),
+        (3, Value::U32(code)) => ButtonAction::Key(*code),
+        (4, Value::Array(events)) => ButtonAction::Macro(
+            events
+                .iter()
+                .filter_map(|event| match event {
+                    Value::Structure(s) => match s.fields() {
+                        [Value::U32(t), Value::U32(c)] => Some((*t, *c)),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect(),
+        ),
+        _ => ButtonAction::Unknown,
+    }
+}
@@ -1,8 +1,12 @@
 use anyhow::{Context, Result};
 use evdev::Device;
+use std::os::fd::{AsFd, AsRawFd, OwnedFd};
 use std::path::{Path, PathBuf};
 use tokio::sync::mpsc;
 
+/// EVIOCGRAB: _IOW('E', 0x90, int)
+const EVIOCGRAB: u64 = 0x4004_4590;
+
 /// Wrapper around an evdev device with exclusive grab support.
 /// Releasing the grab on Drop ensures the mouse always returns to normal.
 pub struct DeviceReader {
@@ -32,16 +36,31 @@ impl DeviceReader {
 
     /// Grab the device exclusively. While grabbed, events are only delivered to us,
     /// not to the rest of the system.
+    /// Fails with EBUSY (see [`is_busy`]) if another process holds a grab; the
+    /// error then names the processes that have the device open.
     pub fn grab(&mut self) -> Result<()> {
-        self.device
-            .grab()
-            .with_context(|| format!("Failed to grab device {}", self.path.display()))?;
+        if let Err(e) = self.device.grab() {
+            let mut msg = format!("Failed to grab device {}", self.path.display());
+            if e.raw_os_error() == Some(libc::EBUSY) {
+                let holders = device_holders(&self.path);
+                if holders.is_empty() {
+                    msg.push_str(" (grabbed by another process)");
+                } else {
+                    msg.push_str(&format!(
+                        " (grabbed by another process; open in {})",
+                        holders.join(", ")
+                    ));
+                }
+            }
+            return Err(anyhow::Error::new(e).context(msg));
+        }
         self.grabbed = true;
         log::info!("Grabbed device: {}", self.path.display());
         Ok(())
     }
 
     /// Release the exclusive grab
+    #[allow(dead_code)]
     pub fn ungrab(&mut self) -> Result<()> {
         if self.grabbed {
             self.device
@@ -53,6 +72,7 @@ impl DeviceReader {
         Ok(())
     }
 
+    #[allow(dead_code)]
     pub fn is_grabbed(&self) -> bool {
         self.grabbed
     }
@@ -61,10 +81,26 @@ impl DeviceReader {
         self.device.name().unwrap_or("Unknown")
     }
 
+    #[allow(dead_code)]
     pub fn path(&self) -> &Path {
         &self.path
     }
 
+    /// A way to let go of the grab (and take it again) from another thread
+    /// while this reader is blocked in [`read_loop`](Self::read_loop), which
+    /// only returns with the next event
+    pub fn grab_release(&self) -> Result<GrabRelease> {
+        let fd = self
+            .device
+            .as_fd()
+            .try_clone_to_owned()
+            .with_context(|| format!("Failed to duplicate {}", self.path.display()))?;
+        Ok(GrabRelease {
+            fd,
+            path: self.path.clone(),
+        })
+    }
+
     /// Get a reference to the underlying evdev device
     pub fn device(&self) -> &Device {
         &self.device
@@ -101,9 +137,97 @@ impl Drop for DeviceReader {
     fn drop(&mut self) {
         if self.grabbed {
             log::info!("Drop: releasing grab on {}", self.path.display());
-            if let Err(e) = self.device.ungrab() {
-                log::error!("Failed to ungrab device on drop: {}", e);
+            match self.device.ungrab() {
+                Ok(()) => {}
+                // Already let go of through a GrabRelease
+                Err(e) if e.raw_os_error() == Some(libc::EINVAL) => {}
+                Err(e) => log::error!("Failed to ungrab device on drop: {}", e),
             }
         }
     }
 }
+
+/// Releases the grab of a [`DeviceReader`] that's owned by its reader thread.
+/// The grab belongs to the open file, which this shares.
+pub struct GrabRelease {
+    fd: OwnedFd,
+    path: PathBuf,
+}
+
+impl GrabRelease {
+    /// Take the grab again after [`release`](Self::release)
+    pub fn regrab(&self) -> Result<()> {
+        if unsafe { libc::ioctl(self.fd.as_raw_fd(), EVIOCGRAB as _, 1) } != 0 {
+            return Err(std::io::Error::last_os_error())
+                .with_context(|| format!("Failed to grab device {}", self.path.display()));
+        }
+        log::info!("Grabbed device: {}", self.path.display());
+        Ok(())
+    }
+
+    pub fn release(&self) {
+        let result = unsafe { libc::ioctl(self.fd.as_raw_fd(), EVIOCGRAB as _, 0) };
+        if result == 0 {
+            log::info!("Released grab on device: {}", self.path.display());
+            return;
+        }
+        let e = std::io::Error::last_os_error();
+        // Not grabbed anymore, or unplugged: nothing to let go of
+        if !matches!(e.raw_os_error(), Some(libc::EINVAL) | Some(libc::ENODEV)) {
+            log::error!("Failed to ungrab {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+impl Drop for GrabRelease {
+    /// The engine holding this may have died in a panic, while the reader
+    /// thread keeps the grab until the next event
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Whether a [`DeviceReader::grab`] error means someone else holds the grab
+pub fn is_busy(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<std::io::Error>()
+        .and_then(|e| e.raw_os_error())
+        == Some(libc::EBUSY)
+}
+
+/// Whether a [`DeviceReader::read_loop`] error means the device was unplugged
+pub fn is_disconnect(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<std::io::Error>()
+            .and_then(|e| e.raw_os_error()),
+        Some(libc::ENODEV) | Some(libc::EIO)
+    )
+}
+
+/// Other processes with the device node open, as "name (pid N)", found by
+/// scanning /proc/*/fd. The kernel doesn't say which fd holds the grab, so
+/// this lists every reader.
+pub fn device_holders(path: &Path) -> Vec<String> {
+    let own_pid = std::process::id().to_string();
+    let Ok(procs) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    let mut holders = Vec::new();
+    for entry in procs.flatten() {
+        let pid = entry.file_name().to_string_lossy().to_string();
+        if !pid.chars().all(|c| c.is_ascii_digit()) || pid == own_pid {
+            continue;
+        }
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let has_open = fds
+            .flatten()
+            .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|target| target == path));
+        if has_open {
+            let name = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+            holders.push(format!("{} (pid {})", name.trim(), pid));
+        }
+    }
+    holders
+}
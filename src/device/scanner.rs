@@ -1,6 +1,7 @@
+use crate::config::DeviceConfig;
 use anyhow::{Context, Result};
 use evdev::Device;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Information about a discovered input device
 #[derive(Debug, Clone)]
@@ -10,8 +11,14 @@ pub struct DeviceInfo {
     pub vendor_id: u16,
     pub product_id: u16,
     pub is_mouse: bool,
+    /// Pen tablet (or the pen of a touchscreen): absolute X/Y and a pen tool
+    /// or stylus button, which bindings can remap like mouse buttons
+    pub is_tablet: bool,
     /// Human readable capabilities summary
     pub capabilities: String,
+    /// Someone already held an exclusive grab on the device when it was scanned
+    /// (another remapper, or our own running engine)
+    pub grabbed: bool,
 }
 
 /// Scan /dev/input for available input devices, filtering for mice
@@ -45,12 +52,13 @@ pub fn scan_devices() -> Result<Vec<DeviceInfo>> {
 }
 
 /// Scan and return only mouse devices
+#[allow(dead_code)]
 pub fn scan_mice() -> Result<Vec<DeviceInfo>> {
     Ok(scan_devices()?.into_iter().filter(|d| d.is_mouse).collect())
 }
 
 fn open_device_info(path: &PathBuf) -> Result<DeviceInfo> {
-    let device =
+    let mut device =
         Device::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
 
     let name = device.name().unwrap_or("Unknown").to_string();
@@ -69,6 +77,24 @@ fn open_device_info(path: &PathBuf) -> Result<DeviceInfo> {
 
     let is_mouse = has_rel && has_mouse_btn;
 
+    let has_abs_xy = device.supported_absolute_axes().is_some_and(|abs| {
+        abs.contains(evdev::AbsoluteAxisCode::ABS_X) && abs.contains(evdev::AbsoluteAxisCode::ABS_Y)
+    });
+    let is_tablet = has_abs_xy
+        && device.supported_keys().is_some_and(|keys| {
+            keys.contains(evdev::KeyCode::BTN_TOOL_PEN) || keys.contains(evdev::KeyCode::BTN_STYLUS)
+        });
+
+    // There is no query for EVIOCGRAB, so probe it: a grab fails with EBUSY
+    // while another fd holds one. Release ours straight away.
+    let grabbed = match device.grab() {
+        Ok(()) => {
+            let _ = device.ungrab();
+            false
+        }
+        Err(e) => e.raw_os_error() == Some(libc::EBUSY),
+    };
+
     // Build capabilities summary
     let mut caps = Vec::new();
     if has_rel {
@@ -86,6 +112,9 @@ fn open_device_info(path: &PathBuf) -> Result<DeviceInfo> {
     if device.supported_absolute_axes().is_some() {
         caps.push("absolute-axes");
     }
+    if is_tablet {
+        caps.push("tablet");
+    }
 
     Ok(DeviceInfo {
         path: path.clone(),
@@ -93,11 +122,43 @@ fn open_device_info(path: &PathBuf) -> Result<DeviceInfo> {
         vendor_id,
         product_id,
         is_mouse,
+        is_tablet,
         capabilities: caps.join(", "),
+        grabbed,
     })
 }
 
+/// Event node of the device with this name and vendor/product IDs, preferring
+/// `preferred` if it matches. Cheaper than a full scan (no capability summary
+/// or grab probe), for polling until an unplugged device is back.
+pub fn find_node(name: &str, vendor_id: u16, product_id: u16, preferred: &Path) -> Option<PathBuf> {
+    let matches = |path: &Path| {
+        Device::open(path).is_ok_and(|d| {
+            d.name() == Some(name)
+                && d.input_id().vendor() == vendor_id
+                && d.input_id().product() == product_id
+        })
+    };
+    if matches(preferred) {
+        return Some(preferred.to_path_buf());
+    }
+
+    let mut nodes: Vec<PathBuf> = std::fs::read_dir("/dev/input")
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("event"))
+        })
+        .collect();
+    nodes.sort();
+    nodes.into_iter().find(|p| matches(p))
+}
+
 /// Find a device matching the given config criteria
+#[allow(dead_code)]
 pub fn find_device(
     name: Option<&str>,
     path: Option<&str>,
@@ -105,34 +166,55 @@ pub fn find_device(
     product_id: Option<u16>,
 ) -> Result<Option<DeviceInfo>> {
     let devices = scan_devices()?;
+    Ok(find_in(&devices, name, path, vendor_id, product_id).cloned())
+}
 
-    for device in &devices {
-        // If path is specified, match exactly
-        if let Some(p) = path {
-            if device.path.to_str() == Some(p) {
-                return Ok(Some(device.clone()));
-            }
-        }
+/// The already scanned device the `[device]` config section refers to
+pub fn configured_device<'a>(
+    devices: &'a [DeviceInfo],
+    config: &DeviceConfig,
+) -> Option<&'a DeviceInfo> {
+    find_in(
+        devices,
+        config.name.as_deref(),
+        config.path.as_deref(),
+        config.vendor_id,
+        config.product_id,
+    )
+}
 
-        // If vendor/product specified, match those
-        if let (Some(vid), Some(pid)) = (vendor_id, product_id) {
-            if device.vendor_id == vid && device.product_id == pid {
-                return Ok(Some(device.clone()));
-            }
-        }
+fn find_in<'a>(
+    devices: &'a [DeviceInfo],
+    name: Option<&str>,
+    path: Option<&str>,
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+) -> Option<&'a DeviceInfo> {
+    // Most specific criterion first: the same mouse usually exposes several
+    // event nodes sharing its name and vendor/product IDs
+    if let Some(p) = path
+        && let Some(device) = devices.iter().find(|d| d.path.to_str() == Some(p))
+    {
+        return Some(device);
+    }
 
-        // Match by name substring
-        if let Some(n) = name {
-            if device.name.to_lowercase().contains(&n.to_lowercase()) && device.is_mouse {
-                return Ok(Some(device.clone()));
-            }
-        }
+    if let (Some(vid), Some(pid)) = (vendor_id, product_id)
+        && let Some(device) = devices
+            .iter()
+            .find(|d| d.vendor_id == vid && d.product_id == pid && (d.is_mouse || d.is_tablet))
+    {
+        return Some(device);
     }
 
-    Ok(None)
+    // Match by name substring
+    let name = name?.to_lowercase();
+    devices
+        .iter()
+        .find(|d| (d.is_mouse || d.is_tablet) && d.name.to_lowercase().contains(&name))
 }
 
 /// List all button/key codes supported by a device at the given path
+#[allow(dead_code)]
 pub fn get_device_buttons(path: &PathBuf) -> Result<Vec<evdev::KeyCode>> {
     let device =
         Device::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
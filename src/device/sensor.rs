@@ -0,0 +1,62 @@
+use crate::device::hidpp::{self, get_feature_index, hidpp_request, LOGITECH_VENDOR_ID};
+use anyhow::{bail, Result};
+use std::path::Path;
+
+// HID++ 2.0 feature ids
+const FEATURE_ADJUSTABLE_DPI: u16 = 0x2201;
+const FEATURE_REPORT_RATE: u16 = 0x8060;
+
+/// Sensor settings read straight from the mouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SensorInfo {
+    pub dpi: Option<u32>,
+    /// DPI the mouse falls back to after a reset
+    pub default_dpi: Option<u32>,
+    pub report_rate_hz: Option<u32>,
+}
+
+/// Read the current DPI and report rate of the mouse behind an evdev node.
+/// Only Logitech devices (HID++ 2.0) are supported; returns None for
+/// everything else and when the mouse answers neither query.
+pub fn read_sensor(event_path: &Path, vendor_id: u16) -> Option<SensorInfo> {
+    if vendor_id != LOGITECH_VENDOR_ID {
+        return None;
+    }
+    let hid_dir = hidpp::hid_device_dir(event_path)?;
+    let hidraw = hidpp::find_hidraw(&hid_dir)?;
+    match read_hidpp_sensor(&hidraw) {
+        Ok(info) => Some(info),
+        Err(e) => {
+            log::debug!("HID++ sensor query on {} failed: {:#}", hidraw.display(), e);
+            None
+        }
+    }
+}
+
+fn read_hidpp_sensor(hidraw: &Path) -> Result<SensorInfo> {
+    let mut dev = hidpp::open(hidraw)?;
+    let mut info = SensorInfo::default();
+
+    if let Some(index) = get_feature_index(&mut dev, FEATURE_ADJUSTABLE_DPI)? {
+        // getSensorDpi(sensor 0): [sensor, dpi (big endian), default dpi (big endian)]
+        let reply = hidpp_request(&mut dev, index, 2, &[0])?;
+        let dpi = u16::from_be_bytes([reply[1], reply[2]]) as u32;
+        let default_dpi = u16::from_be_bytes([reply[3], reply[4]]) as u32;
+        info.dpi = (dpi != 0).then_some(dpi);
+        // Older firmware leaves the default out
+        info.default_dpi = (default_dpi != 0).then_some(default_dpi);
+    }
+
+    if let Some(index) = get_feature_index(&mut dev, FEATURE_REPORT_RATE)? {
+        // getReportRate: [interval ms]
+        let reply = hidpp_request(&mut dev, index, 1, &[])?;
+        if reply[0] != 0 {
+            info.report_rate_hz = Some(1000 / reply[0] as u32);
+        }
+    }
+
+    if info == SensorInfo::default() {
+        bail!("device has no HID++ DPI or report rate feature");
+    }
+    Ok(info)
+}
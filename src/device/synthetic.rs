@@ -0,0 +1,57 @@
+use anyhow::{bail, Context, Result};
+use evdev::uinput::VirtualDevice;
+use evdev::{AttributeSet, KeyCode, RelativeAxisCode};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Longest to wait for udev to create the event node of a new device
+const NODE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A virtual mouse to feed the engine made-up input (`stress`, `self-test`):
+/// motion, both wheels, left/right/middle so it's taken for a mouse, and
+/// `buttons`
+pub fn create_source(name: &str, buttons: &[KeyCode]) -> Result<VirtualDevice> {
+    let mut keys = AttributeSet::<KeyCode>::new();
+    keys.insert(KeyCode::BTN_LEFT);
+    keys.insert(KeyCode::BTN_RIGHT);
+    keys.insert(KeyCode::BTN_MIDDLE);
+    for button in buttons {
+        keys.insert(*button);
+    }
+
+    let mut rel = AttributeSet::<RelativeAxisCode>::new();
+    rel.insert(RelativeAxisCode::REL_X);
+    rel.insert(RelativeAxisCode::REL_Y);
+    rel.insert(RelativeAxisCode::REL_WHEEL);
+    rel.insert(RelativeAxisCode::REL_HWHEEL);
+
+    VirtualDevice::builder()
+        .context("Failed to create VirtualDeviceBuilder (is /dev/uinput writable?)")?
+        .name(name)
+        .with_keys(&keys)?
+        .with_relative_axes(&rel)?
+        .build()
+        .with_context(|| format!("Failed to build virtual device '{}'", name))
+}
+
+/// The /dev/input/eventN node of a virtual device, once udev has created it
+pub fn event_node(device: &mut VirtualDevice) -> Result<PathBuf> {
+    let started = Instant::now();
+    loop {
+        let node = device
+            .enumerate_dev_nodes_blocking()
+            .context("Failed to list the virtual device's nodes")?
+            .filter_map(|path| path.ok())
+            .find(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("event"))
+            });
+        match node {
+            Some(path) if path.exists() => return Ok(path),
+            _ if started.elapsed() > NODE_TIMEOUT => {
+                bail!("The virtual device's event node didn't show up")
+            }
+            _ => std::thread::sleep(Duration::from_millis(50)),
+        }
+    }
+}
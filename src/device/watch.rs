@@ -0,0 +1,60 @@
+use anyhow::{Context, Result};
+use std::ffi::CString;
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::mpsc;
+
+/// Size of struct inotify_event without its name
+const EVENT_HEADER: usize = 16;
+
+/// Watch `dir` (/dev/input) with inotify from a background thread. A message
+/// arrives whenever an event* node in it appears, disappears or changes
+/// permissions (udev sets them just after creating the node). The thread
+/// notices a dropped receiver at the next change and ends.
+pub fn watch_event_nodes(dir: &Path) -> Result<mpsc::Receiver<()>> {
+    let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error()).context("Failed to set up inotify");
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    let path = CString::new(dir.as_os_str().as_bytes())?;
+    let mask = libc::IN_CREATE | libc::IN_DELETE | libc::IN_ATTRIB | libc::IN_MOVE;
+    if unsafe { libc::inotify_add_watch(fd.as_raw_fd(), path.as_ptr(), mask) } < 0 {
+        return Err(io::Error::last_os_error())
+            .with_context(|| format!("Failed to watch {}", dir.display()));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("device-watch".to_string())
+        .spawn(move || {
+            let mut inotify = File::from(fd);
+            let mut buf = [0u8; 4096];
+            while let Ok(n) = inotify.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                if event_node_changed(&buf[..n]) && tx.send(()).is_err() {
+                    break;
+                }
+            }
+        })
+        .context("Failed to start the device watch thread")?;
+    Ok(rx)
+}
+
+/// Whether a buffer of inotify events names an event* node
+fn event_node_changed(mut buf: &[u8]) -> bool {
+    while buf.len() >= EVENT_HEADER {
+        let len = u32::from_ne_bytes([buf[12], buf[13], buf[14], buf[15]]) as usize;
+        let end = (EVENT_HEADER + len).min(buf.len());
+        if buf[EVENT_HEADER..end].starts_with(b"event") {
+            return true;
+        }
+        buf = &buf[end..];
+    }
+    false
+}
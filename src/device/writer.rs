@@ -1,21 +1,185 @@
+use crate::engine::metrics;
 use anyhow::{Context, Result};
 use evdev::{
-    uinput::VirtualDevice, AttributeSet, InputEvent, KeyCode, RelativeAxisCode, UinputAbsSetup,
+    uinput::VirtualDevice, AttributeSet, InputEvent, KeyCode, MiscCode, PropType, RelativeAxisCode,
+    UinputAbsSetup,
 };
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Write;
+use std::mem::ManuallyDrop;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::sync::{Arc, Mutex};
+
+/// Name of the virtual device the engine writes its output to
+pub const VIRTUAL_DEVICE_NAME: &str = "MouseMapper Virtual Device";
+/// Name of the virtual device keyboard keys go out through with
+/// `virtual_keyboard`
+pub const VIRTUAL_KEYBOARD_NAME: &str = "MouseMapper Virtual Keyboard";
+
+/// Events gathered to go out through the virtual device in a single write,
+/// ending in a SYN_REPORT (see `DeviceWriter::emit_frame`)
+#[derive(Debug, Default, Clone)]
+pub struct Frame {
+    events: Vec<InputEvent>,
+}
+
+impl Frame {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a key/button event (1 = down, 0 = up)
+    pub fn key(&mut self, key: KeyCode, value: i32) -> &mut Self {
+        self.events
+            .push(InputEvent::new(evdev::EventType::KEY.0, key.code(), value));
+        self
+    }
+
+    /// Add a relative axis event
+    pub fn relative(&mut self, axis: RelativeAxisCode, value: i32) -> &mut Self {
+        self.events
+            .push(InputEvent::new(evdev::EventType::RELATIVE.0, axis.0, value));
+        self
+    }
+
+    /// End the report so far with a SYN_REPORT; what follows becomes the
+    /// next report of the same write. Needed between a press and the release
+    /// of the same key, which applications would miss inside one report.
+    pub fn sync(&mut self) -> &mut Self {
+        if self
+            .events
+            .last()
+            .is_some_and(|e| e.event_type() != evdev::EventType::SYNCHRONIZATION)
+        {
+            self.events
+                .push(InputEvent::new(evdev::EventType::SYNCHRONIZATION.0, 0, 0));
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+/// What a virtual device made for a source device can emit; a parked device
+/// is only reused for a source with the same capabilities
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Capabilities {
+    keys: Option<Vec<u16>>,
+    relative: Option<Vec<u16>>,
+    /// Axis, minimum, maximum, fuzz, flat, resolution
+    absolute: Vec<(u16, [i32; 5])>,
+    properties: Vec<u16>,
+    misc: Option<Vec<u16>>,
+}
+
+impl Capabilities {
+    fn of(source: &evdev::Device) -> Result<Self> {
+        let abs_state = source.get_abs_state()?;
+        let absolute = source
+            .supported_absolute_axes()
+            .map(|axes| {
+                axes.iter()
+                    .filter_map(|axis| {
+                        let info = abs_state.get(axis.0 as usize)?;
+                        Some((
+                            axis.0,
+                            [
+                                info.minimum,
+                                info.maximum,
+                                info.fuzz,
+                                info.flat,
+                                info.resolution,
+                            ],
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self {
+            keys: source
+                .supported_keys()
+                .map(|keys| keys.iter().map(|k| k.code()).collect()),
+            relative: source
+                .supported_relative_axes()
+                .map(|axes| axes.iter().map(|a| a.0).collect()),
+            absolute,
+            properties: source.properties().iter().map(|p| p.0).collect(),
+            misc: source
+                .misc_properties()
+                .map(|misc| misc.iter().map(|m| m.0).collect()),
+        })
+    }
+}
+
+/// Virtual devices of stopped engines, kept so the next start doesn't make
+/// the desktop see a new device (and reset its settings for it)
+static PARKED: Mutex<Vec<(Capabilities, Arc<Mutex<DeviceWriter>>)>> = Mutex::new(Vec::new());
+
+/// A virtual device for `source`: a parked one with the same capabilities
+/// if there is one, otherwise a new one. Hand it back with `park`.
+pub fn acquire(source: &evdev::Device) -> Result<Arc<Mutex<DeviceWriter>>> {
+    let capabilities = Capabilities::of(source)?;
+    if let Ok(mut parked) = PARKED.lock()
+        && let Some(i) = parked.iter().position(|(c, _)| *c == capabilities)
+    {
+        log::info!("Reusing virtual device: {}", VIRTUAL_DEVICE_NAME);
+        return Ok(parked.swap_remove(i).1);
+    }
+    let writer = DeviceWriter::from_source(source)?;
+    Ok(Arc::new(Mutex::new(writer)))
+}
+
+/// Keep `writer` for the next engine start instead of destroying it, with
+/// nothing held down on it
+pub fn park(writer: Arc<Mutex<DeviceWriter>>) {
+    let capabilities = match writer.lock() {
+        Ok(mut w) => {
+            if let Err(e) = w.release_all() {
+                log::error!("{:#}", e);
+            }
+            w.capabilities.clone()
+        }
+        Err(_) => return,
+    };
+    if let Ok(mut parked) = PARKED.lock() {
+        parked.push((capabilities, writer));
+    }
+}
+
+/// Destroy the parked virtual devices
+pub fn discard_parked() {
+    if let Ok(mut parked) = PARKED.lock() {
+        parked.clear();
+    }
+}
 
 /// Virtual device that emits events via uinput.
 /// Events injected through this device are kernel-level input events,
 /// indistinguishable from real hardware to any userspace application.
 pub struct DeviceWriter {
     virtual_device: VirtualDevice,
+    /// Keys/buttons currently held down on the virtual device
+    pressed: HashSet<u16>,
+    /// Reused by `emit` to append the SYN_REPORT without allocating
+    buffer: Vec<InputEvent>,
+    /// Of the source device it was made for (none for `new_standard`)
+    capabilities: Capabilities,
+    /// Put an MSC_SCAN before every key event written (`scancodes = "synthesize"`)
+    synthesize_scancodes: bool,
+    /// Keyboard-only device that keyboard keys are routed to instead (`virtual_keyboard`)
+    keyboard: Option<VirtualDevice>,
 }
 
 impl DeviceWriter {
     /// Create a virtual device that mirrors the capabilities of the given source device.
     pub fn from_source(source: &evdev::Device) -> Result<Self> {
+        let capabilities = Capabilities::of(source)?;
         let mut builder = VirtualDevice::builder()
             .context("Failed to create VirtualDeviceBuilder")?
-            .name("MouseMapper Virtual Device");
+            .name(VIRTUAL_DEVICE_NAME);
 
         // Mirror key/button capabilities
         if let Some(keys) = source.supported_keys() {
@@ -23,10 +187,14 @@ impl DeviceWriter {
             for key in keys.iter() {
                 attr.insert(key);
             }
-            // Also add all common keyboard keys so we can remap mouse buttons to keys
+            // Also add all common keyboard keys so we can remap mouse buttons
+            // to keys, and the mouse buttons for pens and tablets
             for code in 1..=248u16 {
                 attr.insert(KeyCode::new(code));
             }
+            for code in KeyCode::BTN_LEFT.code()..=KeyCode::BTN_TASK.code() {
+                attr.insert(KeyCode::new(code));
+            }
             builder = builder.with_keys(&attr)?;
         }
 
@@ -36,6 +204,11 @@ impl DeviceWriter {
             for axis in rel_axes.iter() {
                 attr.insert(axis);
             }
+            // Also add both wheels so buttons can be bound to scrolling
+            attr.insert(RelativeAxisCode::REL_WHEEL);
+            attr.insert(RelativeAxisCode::REL_HWHEEL);
+            attr.insert(RelativeAxisCode::REL_WHEEL_HI_RES);
+            attr.insert(RelativeAxisCode::REL_HWHEEL_HI_RES);
             builder = builder.with_relative_axes(&attr)?;
         }
 
@@ -59,15 +232,39 @@ impl DeviceWriter {
             }
         }
 
+        // Properties (INPUT_PROP_DIRECT of a screen tablet, ...) decide how
+        // the desktop treats the device, and tablets send MSC_SERIAL for the
+        // tool in use
+        let mut properties = AttributeSet::<PropType>::new();
+        for property in source.properties().iter() {
+            properties.insert(property);
+        }
+        builder = builder.with_properties(&properties)?;
+        let mut misc = AttributeSet::<MiscCode>::new();
+        for code in source.misc_properties().iter().flat_map(|m| m.iter()) {
+            misc.insert(code);
+        }
+        // Also for synthesized scancodes
+        misc.insert(MiscCode::MSC_SCAN);
+        builder = builder.with_msc(&misc)?;
+
         let virtual_device = builder.build().context("Failed to build virtual device")?;
 
-        log::info!("Created virtual device: MouseMapper Virtual Device");
+        log::info!("Created virtual device: {}", VIRTUAL_DEVICE_NAME);
 
-        Ok(Self { virtual_device })
+        Ok(Self {
+            virtual_device,
+            pressed: HashSet::new(),
+            buffer: Vec::new(),
+            capabilities,
+            synthesize_scancodes: false,
+            keyboard: None,
+        })
     }
 
     /// Create a virtual device with standard mouse + keyboard capabilities.
     /// Used when we don't have a source device to mirror.
+    #[allow(dead_code)]
     pub fn new_standard() -> Result<Self> {
         let mut keys = AttributeSet::<KeyCode>::new();
         // All mouse buttons
@@ -94,7 +291,7 @@ impl DeviceWriter {
 
         let virtual_device = VirtualDevice::builder()
             .context("Failed to create VirtualDeviceBuilder")?
-            .name("MouseMapper Virtual Device")
+            .name(VIRTUAL_DEVICE_NAME)
             .with_keys(&keys)?
             .with_relative_axes(&rel)?
             .build()
@@ -102,14 +299,40 @@ impl DeviceWriter {
 
         log::info!("Created standard virtual device");
 
-        Ok(Self { virtual_device })
+        Ok(Self {
+            virtual_device,
+            pressed: HashSet::new(),
+            buffer: Vec::new(),
+            capabilities: Capabilities {
+                keys: None,
+                relative: None,
+                absolute: Vec::new(),
+                properties: Vec::new(),
+                misc: None,
+            },
+            synthesize_scancodes: false,
+            keyboard: None,
+        })
     }
 
-    /// Emit a slice of events through the virtual device
+    /// Emit a slice of events through the virtual device, ending in a
+    /// SYN_REPORT (added unless the events already end in one)
     pub fn emit(&mut self, events: &[InputEvent]) -> Result<()> {
-        self.virtual_device
-            .emit(events)
-            .context("Failed to emit events through virtual device")?;
+        for event in events {
+            self.track(*event);
+        }
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        buffer.extend_from_slice(events);
+        if buffer
+            .last()
+            .is_some_and(|e| e.event_type() != evdev::EventType::SYNCHRONIZATION)
+        {
+            buffer.push(InputEvent::new(evdev::EventType::SYNCHRONIZATION.0, 0, 0));
+        }
+        let written = self.write(&buffer);
+        self.buffer = buffer;
+        written.context("Failed to emit events through virtual device")?;
         Ok(())
     }
 
@@ -120,27 +343,160 @@ impl DeviceWriter {
             0, // SYN_REPORT
             0,
         );
-        self.virtual_device
-            .emit(&[event, syn])
-            .context("Failed to emit event")?;
+        self.track(event);
+        self.write(&[event, syn]).context("Failed to emit event")?;
+        Ok(())
+    }
+
+    /// Emit everything gathered in `frame` with a single write, closing its
+    /// last report, and empty it for reuse
+    pub fn emit_frame(&mut self, frame: &mut Frame) -> Result<()> {
+        if frame.is_empty() {
+            return Ok(());
+        }
+        frame.sync();
+        for event in &frame.events {
+            self.track(*event);
+        }
+        let written = self.write(&frame.events);
+        frame.events.clear();
+        written.context("Failed to emit events through virtual device")?;
         Ok(())
     }
 
-    /// Emit a key/button press (value=1) + release (value=0) with SYN_REPORT after each
-    pub fn click(&mut self, key: KeyCode) -> Result<()> {
-        let press = InputEvent::new(evdev::EventType::KEY.0, key.code(), 1);
-        let release = InputEvent::new(evdev::EventType::KEY.0, key.code(), 0);
-        let syn = InputEvent::new(evdev::EventType::SYNCHRONIZATION.0, 0, 0);
+    /// Whether to give every key event written an MSC_SCAN of its own
+    pub fn set_synthesize_scancodes(&mut self, synthesize: bool) {
+        self.synthesize_scancodes = synthesize;
+    }
 
-        self.virtual_device.emit(&[press, syn])?;
-        self.virtual_device.emit(&[release, syn])?;
+    /// Route keyboard keys to a virtual keyboard of their own (created on
+    /// first use) or back to the main device. Whatever is held is released
+    /// first, so no key stays down on the device it no longer goes to.
+    pub fn set_virtual_keyboard(&mut self, enabled: bool) -> Result<()> {
+        if enabled == self.keyboard.is_some() {
+            return Ok(());
+        }
+        self.release_all()?;
+        if !enabled {
+            self.keyboard = None;
+            log::info!("Removed virtual keyboard");
+            return Ok(());
+        }
+        let mut keys = AttributeSet::<KeyCode>::new();
+        for code in (1..=248u16).filter(|code| is_keyboard_key(*code)) {
+            keys.insert(KeyCode::new(code));
+        }
+        let mut misc = AttributeSet::<MiscCode>::new();
+        misc.insert(MiscCode::MSC_SCAN);
+        let keyboard = VirtualDevice::builder()
+            .context("Failed to create VirtualDeviceBuilder")?
+            .name(VIRTUAL_KEYBOARD_NAME)
+            .with_keys(&keys)?
+            .with_msc(&misc)?
+            .build()
+            .context("Failed to build virtual keyboard")?;
+        log::info!("Created virtual keyboard: {}", VIRTUAL_KEYBOARD_NAME);
+        self.keyboard = Some(keyboard);
         Ok(())
     }
 
-    /// Emit a key/button down event
-    pub fn press(&mut self, key: KeyCode) -> Result<()> {
-        let event = InputEvent::new(evdev::EventType::KEY.0, key.code(), 1);
-        self.emit_event(event)
+    fn track(&mut self, event: InputEvent) {
+        if event.event_type() == evdev::EventType::KEY {
+            match event.value() {
+                0 => {
+                    self.pressed.remove(&event.code());
+                }
+                1 => {
+                    self.pressed.insert(event.code());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether `key` is down on the virtual device
+    pub fn is_pressed(&self, key: KeyCode) -> bool {
+        self.pressed.contains(&key.code())
+    }
+
+    /// Release every key/button still held on the virtual device, so nothing
+    /// stays stuck when the source device goes away mid-press
+    pub fn release_all(&mut self) -> Result<()> {
+        let releases: Vec<InputEvent> = self
+            .pressed
+            .drain()
+            .flat_map(|code| {
+                [
+                    InputEvent::new(evdev::EventType::KEY.0, code, 0),
+                    InputEvent::new(evdev::EventType::SYNCHRONIZATION.0, 0, 0),
+                ]
+            })
+            .collect();
+        if !releases.is_empty() {
+            self.write(&releases)
+                .context("Failed to release held keys")?;
+        }
+        Ok(())
+    }
+
+    /// Write the events to the virtual device as they are, in one write(2),
+    /// counting them for the metrics. (`VirtualDevice::emit` would follow
+    /// them with a SYN_REPORT of its own in a second write.)
+    fn write(&mut self, events: &[InputEvent]) -> std::io::Result<()> {
+        if self.synthesize_scancodes && events.iter().any(|e| scancode_of(e).is_some()) {
+            let with_scancodes: Vec<InputEvent> = events
+                .iter()
+                .flat_map(|e| {
+                    scancode_of(e)
+                        .map(|scan| {
+                            InputEvent::new(evdev::EventType::MISC.0, MiscCode::MSC_SCAN.0, scan)
+                        })
+                        .into_iter()
+                        .chain([*e])
+                })
+                .collect();
+            return self.write_events(&with_scancodes);
+        }
+        self.write_events(events)
+    }
+
+    fn write_events(&mut self, events: &[InputEvent]) -> std::io::Result<()> {
+        metrics::events_emitted(
+            events
+                .iter()
+                .filter(|e| e.event_type() != evdev::EventType::SYNCHRONIZATION)
+                .count(),
+        );
+        let Some(keyboard) = &self.keyboard else {
+            return write_to(&self.virtual_device, events);
+        };
+        let (keys, rest) = route(events);
+        if !keys.is_empty() {
+            write_to(keyboard, &keys)?;
+        }
+        if !rest.is_empty() {
+            write_to(&self.virtual_device, &rest)?;
+        }
+        Ok(())
+    }
+
+    /// Scroll `ticks` wheel notches on REL_WHEEL or REL_HWHEEL (negative =
+    /// down/left), one SYN_REPORT per notch, with the matching hi-res events
+    pub fn scroll(&mut self, axis: RelativeAxisCode, ticks: i32) -> Result<()> {
+        let hi_res = if axis == RelativeAxisCode::REL_HWHEEL {
+            RelativeAxisCode::REL_HWHEEL_HI_RES
+        } else {
+            RelativeAxisCode::REL_WHEEL_HI_RES
+        };
+        let step = ticks.signum();
+        let mut frame = Frame::new();
+        for _ in 0..ticks.unsigned_abs() {
+            frame
+                .relative(axis, step)
+                .relative(hi_res, step * 120)
+                .sync();
+        }
+        self.emit_frame(&mut frame).context("Failed to emit scroll")
     }
 
     /// Emit a key/button up event
@@ -149,3 +505,88 @@ impl DeviceWriter {
         self.emit_event(event)
     }
 }
+
+/// Write the events to `device` in one write(2)
+fn write_to(device: &VirtualDevice, events: &[InputEvent]) -> std::io::Result<()> {
+    // InputEvent is a transparent wrapper around the kernel's input_event
+    let bytes = unsafe {
+        std::slice::from_raw_parts(events.as_ptr().cast::<u8>(), std::mem::size_of_val(events))
+    };
+    // Borrow the uinput fd without taking ownership of it
+    let mut file = ManuallyDrop::new(unsafe { File::from_raw_fd(device.as_raw_fd()) });
+    file.write_all(bytes)
+}
+
+/// Keyboard keys as opposed to the BTN_* buttons (mouse, joystick, pen)
+fn is_keyboard_key(code: u16) -> bool {
+    code < KeyCode::BTN_0.code() || code >= KeyCode::KEY_OK.code()
+}
+
+/// Split events into those for the virtual keyboard (keyboard keys with
+/// their scancodes) and those for the main device (everything else); every
+/// report ends in a SYN_REPORT on the device(s) it has events for
+fn route(events: &[InputEvent]) -> (Vec<InputEvent>, Vec<InputEvent>) {
+    let (mut keyboard, mut main) = (Vec::new(), Vec::new());
+    let (mut keyboard_open, mut main_open) = (false, false);
+    // An MSC_SCAN goes where the key event after it goes
+    let mut scan = None;
+    for event in events {
+        match event.event_type() {
+            evdev::EventType::SYNCHRONIZATION => {
+                if let Some(scan) = scan.take() {
+                    main.push(scan);
+                    main_open = true;
+                }
+                if keyboard_open {
+                    keyboard.push(*event);
+                }
+                if main_open {
+                    main.push(*event);
+                }
+                (keyboard_open, main_open) = (false, false);
+            }
+            evdev::EventType::MISC if event.code() == MiscCode::MSC_SCAN.0 => {
+                main.extend(scan.replace(*event));
+            }
+            evdev::EventType::KEY if is_keyboard_key(event.code()) => {
+                keyboard.extend(scan.take());
+                keyboard.push(*event);
+                keyboard_open = true;
+            }
+            _ => {
+                main.extend(scan.take());
+                main.push(*event);
+                main_open = true;
+            }
+        }
+    }
+    main.extend(scan);
+    (keyboard, main)
+}
+
+/// The scancode a real device sends with this key event, if it is one:
+/// keyboard keys below 89 have their AT set 1 scancode as key code, mouse
+/// buttons are numbered from 1 on the USB button page (0x9)
+fn scancode_of(event: &InputEvent) -> Option<i32> {
+    if event.event_type() != evdev::EventType::KEY {
+        return None;
+    }
+    let code = event.code();
+    if (1..89).contains(&code) {
+        Some(i32::from(code))
+    } else if (KeyCode::BTN_LEFT.code()..=KeyCode::BTN_TASK.code()).contains(&code) {
+        Some(0x90001 + i32::from(code - KeyCode::BTN_LEFT.code()))
+    } else {
+        None
+    }
+}
+
+impl Drop for DeviceWriter {
+    /// Also when the engine dies in a panic: let go of held keys before the
+    /// virtual device disappears
+    fn drop(&mut self) {
+        if let Err(e) = self.release_all() {
+            log::error!("{:#}", e);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::config::{AutoscrollConfig, ModeSwitch};
+use crate::device::writer::DeviceWriter;
+use evdev::{EventType, InputEvent, RelativeAxisCode};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// How often the scroll task sends scroll while autoscroll is on
+const TICK: Duration = Duration::from_millis(16);
+
+/// Hi-res wheel units per notch
+const NOTCH: i32 = 120;
+
+/// Windows-style middle-click scrolling: while on, the pointer stays put and
+/// how far it's been moved up or down sets a continuous scroll speed.
+pub struct Autoscroll {
+    writer: Arc<Mutex<DeviceWriter>>,
+    dead_zone: i32,
+    speed: f64,
+    /// Vertical movement since autoscroll went on, shared with the scroll task
+    offset: Arc<AtomicI32>,
+    /// Cancel signal of the scroll task (None = off)
+    task: Option<watch::Sender<bool>>,
+}
+
+impl Autoscroll {
+    pub fn new(writer: Arc<Mutex<DeviceWriter>>, config: &AutoscrollConfig) -> Self {
+        Self {
+            writer,
+            dead_zone: config.dead_zone as i32,
+            speed: config.speed.max(0.0),
+            offset: Arc::new(AtomicI32::new(0)),
+            task: None,
+        }
+    }
+
+    pub fn switch(&mut self, switch: ModeSwitch) {
+        let on = match switch {
+            ModeSwitch::Toggle => self.task.is_none(),
+            ModeSwitch::On => true,
+            ModeSwitch::Off => false,
+        };
+        if on {
+            self.start();
+        } else {
+            self.stop();
+        }
+        log::info!("Autoscroll {}", if on { "on" } else { "off" });
+    }
+
+    fn start(&mut self) {
+        if self.task.is_some() {
+            return;
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            log::error!("No tokio runtime available for autoscroll");
+            return;
+        };
+        self.offset.store(0, Ordering::Relaxed);
+        let (cancel, mut cancel_rx) = watch::channel(false);
+        self.task = Some(cancel);
+
+        let writer = self.writer.clone();
+        let offset = self.offset.clone();
+        let (dead_zone, speed) = (self.dead_zone, self.speed);
+        handle.spawn(async move {
+            // Hi-res units owed but not sent yet, and the part of a notch sent
+            let (mut owed, mut partial) = (0.0, 0);
+            let mut ticker = tokio::time::interval(TICK);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = cancel_rx.changed() => break,
+                }
+                let distance = offset.load(Ordering::Relaxed);
+                let beyond = (distance.abs() - dead_zone).max(0);
+                if beyond == 0 {
+                    continue;
+                }
+                // speed notches per second for every 100 counts past the dead
+                // zone; moving the pointer down scrolls down (negative wheel)
+                owed -= distance.signum() as f64
+                    * (beyond as f64 / 100.0)
+                    * speed
+                    * NOTCH as f64
+                    * TICK.as_secs_f64();
+                let hi_res = owed.trunc() as i32;
+                if hi_res == 0 {
+                    continue;
+                }
+                owed -= hi_res as f64;
+                partial += hi_res;
+                let notches = partial / NOTCH;
+                partial %= NOTCH;
+
+                let mut events = vec![InputEvent::new(
+                    EventType::RELATIVE.0,
+                    RelativeAxisCode::REL_WHEEL_HI_RES.0,
+                    hi_res,
+                )];
+                if notches != 0 {
+                    events.push(InputEvent::new(
+                        EventType::RELATIVE.0,
+                        RelativeAxisCode::REL_WHEEL.0,
+                        notches,
+                    ));
+                }
+                events.push(InputEvent::new(EventType::SYNCHRONIZATION.0, 0, 0));
+                if let Ok(mut w) = writer.lock()
+                    && let Err(e) = w.emit(&events)
+                {
+                    log::error!("Autoscroll failed: {}", e);
+                    break;
+                }
+            }
+        });
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(cancel) = self.task.take() {
+            let _ = cancel.send(true);
+        }
+    }
+
+    /// Consume pointer movement while on: REL_Y steers the scroll speed and
+    /// REL_X is dropped. Returns None when off and for other axes.
+    pub fn process(&mut self, event: &InputEvent) -> Option<Vec<InputEvent>> {
+        self.task.as_ref()?;
+        let axis = RelativeAxisCode(event.code());
+        if axis == RelativeAxisCode::REL_Y {
+            self.offset.fetch_add(event.value(), Ordering::Relaxed);
+        } else if axis != RelativeAxisCode::REL_X {
+            return None;
+        }
+        Some(vec![])
+    }
+}
@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::Path;
+use tokio::sync::mpsc;
+
+/// How often /proc is scanned for `blocked_processes`
+pub const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Scan for `blocked` every CHECK_INTERVAL, off the caller's thread, and
+/// send what [`running`] finds after each scan. Stops once the receiver is
+/// gone; with nothing blocked, the receiver never gets anything.
+pub fn watch(blocked: &[String]) -> mpsc::UnboundedReceiver<Option<String>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    if blocked.is_empty() {
+        return rx;
+    }
+    let blocked = blocked.to_vec();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let list = blocked.clone();
+            let Ok(now) = tokio::task::spawn_blocking(move || running(&list)).await else {
+                break;
+            };
+            if tx.send(now).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// The first of `blocked` that's running, as written in the config. A name
+/// matches a process's comm, its executable's file name or that of its first
+/// argument (Wine games run as C:\...\game.exe), ignoring case; a name with a
+/// `/` matches the executable's full path.
+pub fn running(blocked: &[String]) -> Option<String> {
+    if blocked.is_empty() {
+        return None;
+    }
+    let procs = fs::read_dir("/proc").ok()?;
+    for entry in procs.flatten() {
+        let is_pid = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.chars().all(|c| c.is_ascii_digit()));
+        if !is_pid {
+            continue;
+        }
+        let names = process_names(&entry.path());
+        if let Some(name) = blocked.iter().find(|b| matches(b, &names)) {
+            return Some(name.clone());
+        }
+    }
+    None
+}
+
+/// What a blocked name can match of one process
+struct ProcessNames {
+    comm: String,
+    exe: Option<String>,
+    argv0: Option<String>,
+}
+
+fn process_names(proc_dir: &Path) -> ProcessNames {
+    let comm = fs::read_to_string(proc_dir.join("comm")).unwrap_or_default();
+    let exe = fs::read_link(proc_dir.join("exe"))
+        .ok()
+        .map(|path| path.to_string_lossy().into_owned());
+    let argv0 = fs::read(proc_dir.join("cmdline")).ok().and_then(|cmdline| {
+        let first = cmdline.split(|&b| b == 0).next()?;
+        (!first.is_empty()).then(|| String::from_utf8_lossy(first).into_owned())
+    });
+    ProcessNames {
+        comm: comm.trim().to_string(),
+        exe,
+        argv0,
+    }
+}
+
+fn matches(blocked: &str, names: &ProcessNames) -> bool {
+    if blocked.contains('/') {
+        return names.exe.as_deref() == Some(blocked);
+    }
+    let file_name = |path: &str| path.rsplit(['/', '\\']).next().unwrap_or(path).to_string();
+    // comm is cut to 15 bytes
+    let comm_match = !names.comm.is_empty()
+        && blocked.len() >= names.comm.len()
+        && blocked.is_char_boundary(names.comm.len())
+        && blocked[..names.comm.len()].eq_ignore_ascii_case(&names.comm)
+        && (blocked.len() == names.comm.len() || names.comm.len() == 15);
+    comm_match
+        || names
+            .exe
+            .iter()
+            .chain(&names.argv0)
+            .any(|path| file_name(path).eq_ignore_ascii_case(blocked))
+}
@@ -0,0 +1,48 @@
+use crate::config::ClickHoldConfig;
+use crate::device::writer::DeviceWriter;
+use evdev::{EventType, InputEvent, KeyCode};
+use rand::Rng;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long one synthesized click holds its key down: hold_ms give or take
+/// a random jitter_ms
+pub fn hold_duration(config: &ClickHoldConfig) -> Duration {
+    let jitter = config.jitter_ms.min(config.hold_ms) as i64;
+    let offset = if jitter > 0 {
+        rand::thread_rng().gen_range(-jitter..=jitter)
+    } else {
+        0
+    };
+    Duration::from_millis((config.hold_ms as i64 + offset).max(0) as u64)
+}
+
+/// Turn the instant clicks in `events` into held ones: the releases are
+/// taken out and sent through the writer once the hold has passed
+pub fn hold_clicks(
+    events: Vec<InputEvent>,
+    config: &ClickHoldConfig,
+    writer: &Arc<Mutex<DeviceWriter>>,
+) -> Vec<InputEvent> {
+    let Ok(handle) = tokio::runtime::Handle::try_current() else {
+        return events;
+    };
+    let (releases, rest): (Vec<InputEvent>, Vec<InputEvent>) = events
+        .into_iter()
+        .partition(|e| e.event_type() == EventType::KEY && e.value() == 0);
+
+    for release in releases {
+        let key = KeyCode::new(release.code());
+        let hold = hold_duration(config);
+        let writer = writer.clone();
+        handle.spawn(async move {
+            tokio::time::sleep(hold).await;
+            if let Ok(mut w) = writer.lock()
+                && let Err(e) = w.release(key)
+            {
+                log::error!("Held click release of {:?} failed: {}", key, e);
+            }
+        });
+    }
+    rest
+}
@@ -0,0 +1,134 @@
+use crate::engine::notify;
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// How often the focused window is checked for `fullscreen_profile`
+pub const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Check the focused window every `CHECK_INTERVAL` and send the profile to
+/// switch to when it goes fullscreen (`profile`) and back (the one active
+/// before, starting from `active`). Only the first of a run of failed checks
+/// is sent. Stops when the receiver is dropped.
+pub fn watch(
+    profile: String,
+    mut active: Option<String>,
+) -> mpsc::UnboundedReceiver<Result<String>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut check = tokio::time::interval(CHECK_INTERVAL);
+        let mut fullscreen = false;
+        let mut before: Option<String> = None;
+        let mut failed = false;
+        while !tx.is_closed() {
+            check.tick().await;
+            let now = match tokio::task::spawn_blocking(focused_is_fullscreen).await {
+                Ok(Ok(now)) => now,
+                Ok(Err(e)) => {
+                    if !failed {
+                        let _ = tx.send(Err(e));
+                    }
+                    failed = true;
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            failed = false;
+            if now == fullscreen {
+                continue;
+            }
+            fullscreen = now;
+            let target = if now {
+                if active.as_ref() == Some(&profile) {
+                    continue;
+                }
+                before = active.clone();
+                profile.clone()
+            } else {
+                let Some(target) = before.take() else {
+                    continue;
+                };
+                target
+            };
+            active = Some(target.clone());
+            let _ = tx.send(Ok(target));
+        }
+    });
+    rx
+}
+
+/// Whether the focused window is fullscreen. Hyprland and Sway are asked
+/// through their IPC; anything else through X11 (on GNOME and KDE Wayland
+/// that covers XWayland windows, which is where most games run).
+pub fn focused_is_fullscreen() -> Result<bool> {
+    if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        hyprland()
+    } else if std::env::var_os("SWAYSOCK").is_some() {
+        sway()
+    } else {
+        x11()
+    }
+}
+
+fn hyprland() -> Result<bool> {
+    let window: Value = serde_json::from_str(&run("hyprctl", &["activewindow", "-j"])?)
+        .context("Unexpected output from hyprctl")?;
+    // A bool before Hyprland 0.42, the fullscreen mode (0 = none) after
+    Ok(match &window["fullscreen"] {
+        Value::Bool(fullscreen) => *fullscreen,
+        Value::Number(mode) => mode.as_u64() != Some(0),
+        _ => false,
+    })
+}
+
+fn sway() -> Result<bool> {
+    let tree: Value = serde_json::from_str(&run("swaymsg", &["-t", "get_tree"])?)
+        .context("Unexpected output from swaymsg")?;
+    Ok(focused_node(&tree).is_some_and(|node| {
+        node["fullscreen_mode"]
+            .as_u64()
+            .is_some_and(|mode| mode != 0)
+    }))
+}
+
+fn focused_node(node: &Value) -> Option<&Value> {
+    if node["focused"].as_bool() == Some(true) {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[key].as_array())
+        .flatten()
+        .find_map(focused_node)
+}
+
+fn x11() -> Result<bool> {
+    // "_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007"
+    let active = run("xprop", &["-root", "_NET_ACTIVE_WINDOW"])?;
+    let Some(window) = active
+        .split_whitespace()
+        .last()
+        .filter(|id| id.starts_with("0x") && *id != "0x0")
+    else {
+        return Ok(false);
+    };
+    let state = run("xprop", &["-id", window, "_NET_WM_STATE"])?;
+    Ok(state.contains("_NET_WM_STATE_FULLSCREEN"))
+}
+
+/// Output of `program` run in the desktop session
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let mut command = notify::session_command(program);
+    command.args(args);
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,86 @@
+use crate::config::KeyRepeatConfig;
+use crate::device::writer::{DeviceWriter, Frame};
+use evdev::KeyCode;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Synthesizes autorepeat for mouse buttons remapped to keyboard keys, since
+/// the mouse itself never sends repeat events while a button is held. Each
+/// repeat is a release and press of the key: libinput, and so the desktop,
+/// drops the kernel's repeat events (value 2) and runs its own autorepeat.
+pub struct KeyRepeat {
+    writer: Arc<Mutex<DeviceWriter>>,
+    delay: Duration,
+    interval: Duration,
+    /// Held trigger button -> cancel signal of its repeat task
+    held: HashMap<KeyCode, watch::Sender<bool>>,
+}
+
+impl KeyRepeat {
+    pub fn new(writer: Arc<Mutex<DeviceWriter>>, config: &KeyRepeatConfig) -> Self {
+        Self {
+            writer,
+            delay: Duration::from_millis(config.delay_ms),
+            interval: Duration::from_secs_f64(1.0 / config.rate_hz.clamp(1, 100) as f64),
+            held: HashMap::new(),
+        }
+    }
+
+    /// Start repeating `target` while `trigger` is held. Buttons (BTN_*)
+    /// aren't repeated, only keyboard keys.
+    pub fn start(&mut self, trigger: KeyCode, target: KeyCode) {
+        if target.code() >= KeyCode::BTN_0.code() || self.held.contains_key(&trigger) {
+            return;
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            log::error!("No tokio runtime available for key repeat");
+            return;
+        };
+        let (cancel, mut cancel_rx) = watch::channel(false);
+        self.held.insert(trigger, cancel);
+
+        let writer = self.writer.clone();
+        let (delay, interval) = (self.delay, self.interval);
+        handle.spawn(async move {
+            let mut wait = delay;
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = cancel_rx.changed() => break,
+                }
+                if *cancel_rx.borrow() {
+                    break;
+                }
+                let Ok(mut w) = writer.lock() else {
+                    break;
+                };
+                // Released in the meantime: pressing it again would leave it stuck
+                if !w.is_pressed(target) {
+                    break;
+                }
+                let mut repeat = Frame::new();
+                repeat.key(target, 0).sync().key(target, 1);
+                if let Err(e) = w.emit_frame(&mut repeat) {
+                    log::error!("Key repeat failed: {}", e);
+                    break;
+                }
+                wait = interval;
+            }
+        });
+    }
+
+    /// Stop repeating for a released trigger
+    pub fn stop(&mut self, trigger: KeyCode) {
+        if let Some(cancel) = self.held.remove(&trigger) {
+            let _ = cancel.send(true);
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        for (_, cancel) in self.held.drain() {
+            let _ = cancel.send(true);
+        }
+    }
+}
@@ -1,32 +1,326 @@
-use crate::config::{MacroAction, MacroDef, MacroType};
-use crate::device::writer::DeviceWriter;
+use crate::config::{
+    ClickHoldConfig, ClickStep, MacroAction, MacroDef, MacroLimitPolicy, MacroType, ModeSwitch,
+    ObsConfig,
+};
+use crate::device::writer::{DeviceWriter, Frame};
+use crate::engine::click_hold::hold_duration;
 use crate::engine::mapper::parse_key_name;
+use crate::engine::metrics;
+use crate::engine::modes::Modes;
+use crate::engine::notify;
+use crate::engine::obs;
+use crate::engine::warp::Warp;
 use anyhow::Result;
 use evdev::KeyCode;
 use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use tokio::sync::watch;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+
+/// Macro playback speed in percent (100 = as configured). Process-wide, so
+/// every engine picks up a change, including macros already running.
+static SPEED_PERCENT: AtomicU32 = AtomicU32::new(100);
+
+/// Slowest and fastest playback speeds accepted
+pub const MIN_SPEED: f64 = 0.1;
+pub const MAX_SPEED: f64 = 10.0;
+
+/// Set the playback speed of all macros (2.0 = twice as fast)
+pub fn set_speed(speed: f64) {
+    let speed = if speed.is_finite() { speed } else { 1.0 };
+    let percent = (speed.clamp(MIN_SPEED, MAX_SPEED) * 100.0).round() as u32;
+    SPEED_PERCENT.store(percent, Ordering::Relaxed);
+    log::info!("Macro speed x{}", percent as f64 / 100.0);
+}
+
+/// Whether repeat and toggle macros are paused. Process-wide like the speed;
+/// paused macros keep running but fire nothing until resumed.
+static PAUSED: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::Sender::new(false));
+
+pub fn paused() -> bool {
+    *PAUSED.borrow()
+}
+
+/// Pause or resume all repeat and toggle macros; returns whether they're
+/// paused now
+pub fn switch_paused(switch: ModeSwitch) -> bool {
+    let paused = match switch {
+        ModeSwitch::Toggle => !paused(),
+        ModeSwitch::On => true,
+        ModeSwitch::Off => false,
+    };
+    PAUSED.send_replace(paused);
+    log::info!("Macros {}", if paused { "paused" } else { "resumed" });
+    paused
+}
+
+/// Wait until macros are resumed; false if the macro was cancelled meanwhile
+async fn wait_while_paused(cancel_rx: &mut watch::Receiver<bool>) -> bool {
+    let mut paused = PAUSED.subscribe();
+    tokio::select! {
+        result = paused.wait_for(|p| !*p) => result.is_ok(),
+        _ = cancel_rx.changed() => false,
+    }
+}
+
+/// A configured interval or delay, scaled by the playback speed
+fn scaled(ms: u64) -> Duration {
+    scaled_at(ms, SPEED_PERCENT.load(Ordering::Relaxed))
+}
+
+/// `ms` at `percent` playback speed. Computed wide and saturated: the step
+/// editor allows delays up to u64::MAX.
+fn scaled_at(ms: u64, percent: u32) -> Duration {
+    let scaled = ms as u128 * 100 / percent.max(1) as u128;
+    Duration::from_millis(u64::try_from(scaled).unwrap_or(u64::MAX))
+}
+
+/// The actions of a macro with every RunMacro step replaced by the steps of
+/// the macro it names, recursively. Fails on unknown macros and on loops.
+pub fn expand_actions<'a>(
+    def: &MacroDef,
+    lookup: &dyn Fn(&str) -> Option<&'a MacroDef>,
+) -> Result<Vec<MacroAction>, String> {
+    let mut actions = Vec::new();
+    expand_into(
+        &def.actions,
+        lookup,
+        &mut vec![def.name.clone()],
+        &mut actions,
+    )?;
+    Ok(actions)
+}
+
+fn expand_into<'a>(
+    actions: &[MacroAction],
+    lookup: &dyn Fn(&str) -> Option<&'a MacroDef>,
+    chain: &mut Vec<String>,
+    out: &mut Vec<MacroAction>,
+) -> Result<(), String> {
+    for action in actions {
+        let MacroAction::RunMacro(name) = action else {
+            out.push(action.clone());
+            continue;
+        };
+        if chain.contains(name) {
+            return Err(format!(
+                "macro chain loops: {} -> {}",
+                chain.join(" -> "),
+                name
+            ));
+        }
+        let Some(nested) = lookup(name) else {
+            return Err(format!(
+                "macro '{}' runs macro '{}', which isn't defined",
+                chain[chain.len() - 1],
+                name
+            ));
+        };
+        // Nested macros run with their parameter defaults
+        let nested = nested.with_args(&BTreeMap::new())?;
+        chain.push(name.clone());
+        expand_into(&nested.actions, lookup, chain, out)?;
+        chain.pop();
+    }
+    Ok(())
+}
+
+/// The steps to run for one press or repetition: the steps before the first
+/// Choice, then the steps of one Choice group picked at random by weight.
+/// Without Choice steps, all of them.
+fn pick_group<'a>(actions: &'a [MacroAction], rng: &mut StdRng) -> Vec<&'a MacroAction> {
+    let Some(first) = actions
+        .iter()
+        .position(|a| matches!(a, MacroAction::Choice(_)))
+    else {
+        return actions.iter().collect();
+    };
+
+    // Each group starts at its Choice step
+    let mut groups: Vec<(u32, &[MacroAction])> = Vec::new();
+    let mut rest = &actions[first..];
+    while let Some((MacroAction::Choice(weight), after)) = rest.split_first() {
+        let len = after
+            .iter()
+            .position(|a| matches!(a, MacroAction::Choice(_)))
+            .unwrap_or(after.len());
+        groups.push((*weight, &after[..len]));
+        rest = &after[len..];
+    }
+
+    let total: u32 = groups.iter().map(|(weight, _)| weight).sum();
+    let mut picked: &[MacroAction] = &[];
+    if total > 0 {
+        let mut roll = rng.gen_range(0..total);
+        for (weight, group) in groups {
+            if roll < weight {
+                picked = group;
+                break;
+            }
+            roll -= weight;
+        }
+    }
+    actions[..first].iter().chain(picked).collect()
+}
+
+/// Lifecycle of a macro run, streamed to the TUI
+#[derive(Debug, Clone)]
+pub enum MacroEvent {
+    Started(String),
+    /// A run finished its nth pass over the actions
+    Iteration(String, u64),
+    Stopped {
+        name: String,
+        iterations: u64,
+    },
+    Error {
+        name: String,
+        message: String,
+    },
+    /// Starting `name` hit max_running_macros; `cancelled` is the macro
+    /// stopped to make room (None = `name` didn't start)
+    LimitReached {
+        name: String,
+        cancelled: Option<String>,
+    },
+}
+
+/// What a running macro task needs: where to write, the shared modes and
+/// where to report its lifecycle
+#[derive(Clone)]
+struct MacroContext {
+    writer: Arc<Mutex<DeviceWriter>>,
+    modes: Modes,
+    name: String,
+    events: Option<mpsc::UnboundedSender<MacroEvent>>,
+    click_hold: Option<ClickHoldConfig>,
+    obs: ObsConfig,
+    warp: Option<Warp>,
+}
+
+impl MacroContext {
+    /// How long a Click step holds its key: its own hold_ms, else `fallback`
+    /// (the macro's press_ms), else the profile's click hold (zero = instant)
+    fn click_hold(&self, step: &ClickStep, fallback: Duration) -> Duration {
+        if step.hold_ms > 0 {
+            scaled(step.hold_ms)
+        } else if !fallback.is_zero() {
+            fallback
+        } else {
+            self.click_hold
+                .as_ref()
+                .map(hold_duration)
+                .unwrap_or_default()
+        }
+    }
+
+    fn report(&self, event: MacroEvent) {
+        if let Some(ref events) = self.events {
+            let _ = events.send(event);
+        }
+    }
+
+    fn error(&self, message: String) {
+        log::error!("Macro {}: {}", self.name, message);
+        self.report(MacroEvent::Error {
+            name: self.name.clone(),
+            message,
+        });
+    }
+}
+
+/// A macro that runs until cancelled (repeat, toggle, abortable sequence)
+struct RunningMacro {
+    name: String,
+    macro_type: MacroType,
+    /// Stop a toggle once the user has been idle this long
+    idle_stop: Option<Duration>,
+    started: Instant,
+    cancel: watch::Sender<bool>,
+}
 
 /// Manages running macro instances
 pub struct MacroEngine {
     writer: Arc<Mutex<DeviceWriter>>,
-    /// Active macros: trigger key -> cancel sender
-    active: HashMap<KeyCode, watch::Sender<bool>>,
+    /// Named modes that SetMode actions switch
+    modes: Modes,
+    /// Active macros: trigger key -> running instance
+    active: HashMap<KeyCode, RunningMacro>,
     /// Toggle state for toggle macros
     toggle_state: HashMap<KeyCode, bool>,
     /// Tokio runtime handle for spawning tasks
     runtime: Option<tokio::runtime::Handle>,
+    /// Where running macros report their lifecycle (None = nobody listens)
+    events: Option<mpsc::UnboundedSender<MacroEvent>>,
+    /// Macros of the profile by name, for RunMacro steps
+    library: HashMap<String, MacroDef>,
+    /// Hold time of Click steps (None = instant)
+    click_hold: Option<ClickHoldConfig>,
+    /// Server that Obs steps send their requests to
+    obs: ObsConfig,
+    /// Absolute pointer for Warp steps (None unless the profile uses them)
+    warp: Option<Warp>,
+    /// Most tracked macros running at once (0 = no limit)
+    max_running: usize,
+    limit_policy: MacroLimitPolicy,
 }
 
 impl MacroEngine {
-    pub fn new(writer: Arc<Mutex<DeviceWriter>>) -> Self {
+    pub fn new(writer: Arc<Mutex<DeviceWriter>>, modes: Modes) -> Self {
         Self {
             writer,
+            modes,
             active: HashMap::new(),
             toggle_state: HashMap::new(),
             runtime: tokio::runtime::Handle::try_current().ok(),
+            events: None,
+            library: HashMap::new(),
+            click_hold: None,
+            obs: ObsConfig::default(),
+            warp: None,
+            max_running: 0,
+            limit_policy: MacroLimitPolicy::default(),
+        }
+    }
+
+    pub fn set_library(&mut self, library: HashMap<String, MacroDef>) {
+        self.library = library;
+    }
+
+    pub fn set_click_hold(&mut self, click_hold: Option<ClickHoldConfig>) {
+        self.click_hold = click_hold;
+    }
+
+    pub fn set_obs(&mut self, obs: ObsConfig) {
+        self.obs = obs;
+    }
+
+    pub fn set_warp(&mut self, warp: Option<Warp>) {
+        self.warp = warp;
+    }
+
+    pub fn set_limit(&mut self, max_running: usize, policy: MacroLimitPolicy) {
+        self.max_running = max_running;
+        self.limit_policy = policy;
+    }
+
+    pub fn set_event_sender(&mut self, events: mpsc::UnboundedSender<MacroEvent>) {
+        self.events = Some(events);
+    }
+
+    fn context(&self, macro_def: &MacroDef) -> MacroContext {
+        MacroContext {
+            writer: self.writer.clone(),
+            modes: self.modes.clone(),
+            name: macro_def.name.clone(),
+            events: self.events.clone(),
+            click_hold: self.click_hold.clone(),
+            obs: self.obs.clone(),
+            warp: self.warp.clone(),
         }
     }
 
@@ -50,38 +344,69 @@ impl MacroEngine {
             }
         };
 
+        // Inline the steps of the macros this one runs
+        let expanded;
+        let macro_def = if macro_def
+            .actions
+            .iter()
+            .any(|a| matches!(a, MacroAction::RunMacro(_)))
+        {
+            match expand_actions(macro_def, &|name| self.library.get(name)) {
+                Ok(actions) => {
+                    expanded = MacroDef {
+                        actions,
+                        ..macro_def.clone()
+                    };
+                    &expanded
+                }
+                Err(e) => {
+                    self.context(macro_def).error(e);
+                    return Ok(());
+                }
+            }
+        } else {
+            macro_def
+        };
+
         match macro_def.macro_type {
             MacroType::RepeatOnHold => {
                 // If already running, ignore (key repeat events)
-                if self.active.contains_key(&trigger) {
+                if self.active.contains_key(&trigger) || !self.make_room(macro_def) {
                     return Ok(());
                 }
 
-                let (cancel_tx, cancel_rx) = watch::channel(false);
-                self.active.insert(trigger, cancel_tx);
+                let cancel_rx = self.track(trigger, macro_def);
 
-                let writer = self.writer.clone();
-                let actions = macro_def.actions.clone();
-                let interval = std::time::Duration::from_millis(macro_def.interval_ms);
-                let jitter_ms = macro_def.jitter_ms;
-                let initial_delay = if macro_def.initial_delay_ms > 0 {
-                    Some(std::time::Duration::from_millis(macro_def.initial_delay_ms))
-                } else {
-                    None
-                };
+                let ctx = self.context(macro_def);
+                let macro_def = macro_def.clone();
+                let initial_delay =
+                    (macro_def.initial_delay_ms > 0).then(|| scaled(macro_def.initial_delay_ms));
 
                 handle.spawn(async move {
-                    run_repeat_macro(writer, actions, interval, jitter_ms, initial_delay, cancel_rx)
-                        .await;
+                    run_repeat_macro(ctx, macro_def, initial_delay, cancel_rx).await;
                 });
             }
 
-            MacroType::Sequence => {
-                let writer = self.writer.clone();
-                let actions = macro_def.actions.clone();
+            MacroType::Sequence | MacroType::Random => {
+                let ctx = self.context(macro_def);
+                let actions = if macro_def.macro_type == MacroType::Random {
+                    pick_group(&macro_def.actions, &mut StdRng::from_entropy())
+                        .into_iter()
+                        .cloned()
+                        .collect()
+                } else {
+                    macro_def.actions.clone()
+                };
+                // Only abortable sequences are tracked, so stop_macro can cancel them
+                if macro_def.abort_on_release && !self.make_room(macro_def) {
+                    return Ok(());
+                }
+                let cancel_rx = macro_def
+                    .abort_on_release
+                    .then(|| self.track(trigger, macro_def));
 
                 handle.spawn(async move {
-                    run_sequence_macro(writer, actions).await;
+                    run_sequence_macro(ctx, actions, cancel_rx).await;
                 });
             }
 
@@ -91,24 +416,20 @@ impl MacroEngine {
                 if is_active {
                     // Stop the toggle
                     self.toggle_state.insert(trigger, false);
-                    if let Some(tx) = self.active.remove(&trigger) {
-                        let _ = tx.send(true); // Signal cancellation
+                    if let Some(running) = self.active.remove(&trigger) {
+                        let _ = running.cancel.send(true); // Signal cancellation
                     }
-                } else {
+                } else if self.make_room(macro_def) {
                     // Start the toggle
                     self.toggle_state.insert(trigger, true);
 
-                    let (cancel_tx, cancel_rx) = watch::channel(false);
-                    self.active.insert(trigger, cancel_tx);
+                    let cancel_rx = self.track(trigger, macro_def);
 
-                    let writer = self.writer.clone();
-                    let actions = macro_def.actions.clone();
-                    let interval = std::time::Duration::from_millis(macro_def.interval_ms);
-                    let jitter_ms = macro_def.jitter_ms;
+                    let ctx = self.context(macro_def);
+                    let macro_def = macro_def.clone();
 
                     handle.spawn(async move {
-                        run_repeat_macro(writer, actions, interval, jitter_ms, None, cancel_rx)
-                            .await;
+                        run_repeat_macro(ctx, macro_def, None, cancel_rx).await;
                     });
                 }
             }
@@ -117,6 +438,11 @@ impl MacroEngine {
         Ok(())
     }
 
+    /// Whether a macro started by the given trigger key is running
+    pub fn is_active(&self, trigger: KeyCode) -> bool {
+        self.active.contains_key(&trigger)
+    }
+
     /// Stop a macro for the given trigger key
     pub fn stop_macro(&mut self, trigger: KeyCode) {
         // For toggle macros, don't stop on release - they stop on next press
@@ -124,46 +450,227 @@ impl MacroEngine {
             return;
         }
 
-        if let Some(tx) = self.active.remove(&trigger) {
-            let _ = tx.send(true); // Signal cancellation
+        if let Some(running) = self.active.remove(&trigger) {
+            let _ = running.cancel.send(true); // Signal cancellation
+        }
+    }
+
+    /// Register a running macro under its trigger; returns its cancel receiver
+    fn track(&mut self, trigger: KeyCode, macro_def: &MacroDef) -> watch::Receiver<bool> {
+        let (cancel, cancel_rx) = watch::channel(false);
+        self.active.insert(
+            trigger,
+            RunningMacro {
+                name: macro_def.name.clone(),
+                macro_type: macro_def.macro_type.clone(),
+                idle_stop: (macro_def.idle_stop_min > 0)
+                    .then(|| Duration::from_secs(macro_def.idle_stop_min * 60)),
+                started: Instant::now(),
+                cancel,
+            },
+        );
+        cancel_rx
+    }
+
+    /// Enforce max_running before `macro_def` starts: false if it mustn't
+    /// start, otherwise the oldest runs are stopped as needed
+    fn make_room(&mut self, macro_def: &MacroDef) -> bool {
+        if self.max_running == 0 || self.active.len() < self.max_running {
+            return true;
+        }
+        let ctx = self.context(macro_def);
+        if self.limit_policy == MacroLimitPolicy::Reject {
+            log::warn!(
+                "Not starting macro {}: {} already running",
+                macro_def.name,
+                self.active.len()
+            );
+            ctx.report(MacroEvent::LimitReached {
+                name: macro_def.name.clone(),
+                cancelled: None,
+            });
+            return false;
+        }
+        while self.active.len() >= self.max_running {
+            let Some(oldest) = self
+                .active
+                .iter()
+                .min_by_key(|(_, r)| r.started)
+                .map(|(trigger, _)| *trigger)
+            else {
+                break;
+            };
+            self.toggle_state.insert(oldest, false);
+            if let Some(running) = self.active.remove(&oldest) {
+                let _ = running.cancel.send(true);
+                log::info!(
+                    "Stopped macro {} to make room for {}",
+                    running.name,
+                    macro_def.name
+                );
+                ctx.report(MacroEvent::LimitReached {
+                    name: macro_def.name.clone(),
+                    cancelled: Some(running.name),
+                });
+            }
+        }
+        true
+    }
+
+    /// Names of the repeat and toggle macros currently running, sorted
+    pub fn running(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .active
+            .values()
+            .filter(|r| matches!(r.macro_type, MacroType::RepeatOnHold | MacroType::Toggle))
+            .map(|r| r.name.clone())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Stop the toggles whose idle limit has passed since `last_input`;
+    /// returns their names
+    pub fn stop_idle(&mut self, last_input: Instant) -> Vec<String> {
+        let idle = last_input.elapsed();
+        let expired: Vec<KeyCode> = self
+            .active
+            .iter()
+            .filter(|(_, r)| r.idle_stop.is_some_and(|limit| idle >= limit))
+            .map(|(trigger, _)| *trigger)
+            .collect();
+
+        let mut stopped = Vec::new();
+        for trigger in expired {
+            self.toggle_state.insert(trigger, false);
+            if let Some(running) = self.active.remove(&trigger) {
+                let _ = running.cancel.send(true);
+                log::info!(
+                    "Stopped toggle macro {} after {}s without input",
+                    running.name,
+                    idle.as_secs()
+                );
+                stopped.push(running.name);
+            }
         }
+        stopped
     }
 
     /// Stop all running macros
     pub fn stop_all(&mut self) {
-        for (_, tx) in self.active.drain() {
-            let _ = tx.send(true);
+        for (_, running) in self.active.drain() {
+            let _ = running.cancel.send(true);
         }
         self.toggle_state.clear();
     }
 }
 
+/// The gap between repeats after `held` of holding: ramp_from_ms shrinking
+/// linearly to `floor_ms` over ramp_ms, then `floor_ms` for good
+fn ramped_gap(macro_def: &MacroDef, floor_ms: u64, held: Duration) -> u64 {
+    if macro_def.ramp_from_ms <= floor_ms || macro_def.ramp_ms == 0 {
+        return floor_ms;
+    }
+    let progress = (held.as_millis() as f64 / macro_def.ramp_ms as f64).min(1.0);
+    let span = (macro_def.ramp_from_ms - floor_ms) as f64;
+    macro_def.ramp_from_ms - (span * progress).round() as u64
+}
+
 /// Run a repeating macro (used for both RepeatOnHold and Toggle)
+/// With press_ms set, each Click holds its key down that long (instead of the
+/// profile's click hold) and release_ms (if set) replaces interval_ms as the
+/// gap, shaping the turbo duty cycle.
+#[tracing::instrument(name = "repeat_macro", skip_all, fields(name = %ctx.name))]
 async fn run_repeat_macro(
-    writer: Arc<Mutex<DeviceWriter>>,
-    actions: Vec<MacroAction>,
-    interval: std::time::Duration,
-    jitter_ms: u64,
-    initial_delay: Option<std::time::Duration>,
+    ctx: MacroContext,
+    macro_def: MacroDef,
+    initial_delay: Option<Duration>,
     mut cancel_rx: watch::Receiver<bool>,
 ) {
+    let actions = &macro_def.actions;
+    let interval_ms = if macro_def.release_ms > 0 {
+        macro_def.release_ms
+    } else {
+        macro_def.interval_ms
+    };
+
+    ctx.report(MacroEvent::Started(ctx.name.clone()));
+    let mut iterations = 0;
+
     if let Some(delay) = initial_delay {
         tokio::select! {
             _ = tokio::time::sleep(delay) => {}
-            _ = cancel_rx.changed() => { return; }
+            _ = cancel_rx.changed() => {
+                ctx.report(MacroEvent::Stopped { name: ctx.name.clone(), iterations });
+                return;
+            }
         }
     }
 
     let mut rng = StdRng::from_entropy();
+    let mut frame = Frame::new();
+    let mut started = Instant::now();
+    // Gaps of the interval pattern left in this round, next one last
+    let mut round: Vec<u64> = Vec::new();
 
-    loop {
-        // Execute all actions in the sequence
-        for action in &actions {
+    'repeat: loop {
+        if paused() {
+            let pause_began = Instant::now();
+            if !wait_while_paused(&mut cancel_rx).await {
+                break;
+            }
+            // Time spent paused doesn't count towards the ramp
+            started += pause_began.elapsed();
+        }
+        let gap_ms = if macro_def.interval_pattern_ms.is_empty() {
+            interval_ms
+        } else {
+            if round.is_empty() {
+                round = macro_def.interval_pattern_ms.clone();
+                if macro_def.shuffle_pattern {
+                    round.shuffle(&mut rng);
+                } else {
+                    round.reverse();
+                }
+            }
+            round.pop().unwrap_or(interval_ms)
+        };
+        // Re-read the playback speed every pass so a change applies at once
+        let interval = scaled(ramped_gap(&macro_def, gap_ms, started.elapsed()));
+        let jitter_ms = scaled(macro_def.jitter_ms).as_millis() as u64;
+        let press = scaled(macro_def.press_ms);
+
+        // Execute the actions (one Choice group of them, if grouped)
+        for action in pick_group(actions, &mut rng) {
             if *cancel_rx.borrow() {
-                return;
+                break 'repeat;
+            }
+            match action {
+                MacroAction::Click(step) => {
+                    let hold = ctx.click_hold(step, press);
+                    if hold.is_zero() {
+                        execute_action(&ctx, &mut frame, action);
+                        continue;
+                    }
+                    execute_action(&ctx, &mut frame, &MacroAction::Press(step.key.clone()));
+                    flush(&ctx, &mut frame);
+                    let cancelled = tokio::select! {
+                        _ = tokio::time::sleep(hold) => false,
+                        _ = cancel_rx.changed() => true,
+                    };
+                    execute_action(&ctx, &mut frame, &MacroAction::Release(step.key.clone()));
+                    if cancelled {
+                        break 'repeat;
+                    }
+                }
+                _ => execute_action(&ctx, &mut frame, action),
             }
-            execute_action(&writer, action);
         }
+        flush(&ctx, &mut frame);
+        iterations += 1;
+        metrics::macro_iteration();
+        ctx.report(MacroEvent::Iteration(ctx.name.clone(), iterations));
 
         // Compute sleep duration with random jitter
         let sleep_duration = if jitter_ms > 0 {
@@ -178,7 +685,7 @@ async fn run_repeat_macro(
                 jitter_ms,
                 offset
             );
-            std::time::Duration::from_millis(actual_ms)
+            Duration::from_millis(actual_ms)
         } else {
             interval
         };
@@ -186,64 +693,247 @@ async fn run_repeat_macro(
         // Wait for the (jittered) interval or cancellation
         tokio::select! {
             _ = tokio::time::sleep(sleep_duration) => {}
-            _ = cancel_rx.changed() => { return; }
+            _ = cancel_rx.changed() => { break 'repeat; }
         }
     }
+
+    release_held_keys(&ctx, &mut frame, actions);
+    flush(&ctx, &mut frame);
+    ctx.report(MacroEvent::Stopped {
+        name: ctx.name.clone(),
+        iterations,
+    });
 }
 
-/// Run a sequence macro (fires once)
-async fn run_sequence_macro(writer: Arc<Mutex<DeviceWriter>>, actions: Vec<MacroAction>) {
+/// Release every key the actions press but never release themselves, so a
+/// cancelled macro (e.g. push-to-talk) doesn't leave a key stuck down.
+fn release_held_keys(ctx: &MacroContext, frame: &mut Frame, actions: &[MacroAction]) {
+    for action in actions {
+        if let MacroAction::Press(key_name) = action {
+            let released_later = actions
+                .iter()
+                .any(|a| matches!(a, MacroAction::Release(k) if k == key_name));
+            if !released_later {
+                execute_action(ctx, frame, &MacroAction::Release(key_name.clone()));
+            }
+        }
+    }
+}
+
+/// Run a sequence macro (fires once). With a cancel receiver the sequence
+/// stops early when cancelled and releases the keys it still holds.
+#[tracing::instrument(name = "sequence_macro", skip_all, fields(name = %ctx.name))]
+async fn run_sequence_macro(
+    ctx: MacroContext,
+    actions: Vec<MacroAction>,
+    cancel_rx: Option<watch::Receiver<bool>>,
+) {
+    ctx.report(MacroEvent::Started(ctx.name.clone()));
+    let mut frame = Frame::new();
+    let Some(mut cancel_rx) = cancel_rx else {
+        for action in &actions {
+            execute_action_async(&ctx, &mut frame, action).await;
+        }
+        flush(&ctx, &mut frame);
+        ctx.report(MacroEvent::Stopped {
+            name: ctx.name.clone(),
+            iterations: 1,
+        });
+        return;
+    };
+
+    let mut held: Vec<&str> = Vec::new();
+    let mut completed = true;
     for action in &actions {
-        execute_action_async(&writer, action).await;
+        if *cancel_rx.borrow() {
+            completed = false;
+            break;
+        }
+        match action {
+            MacroAction::Delay(ms) => {
+                flush(&ctx, &mut frame);
+                tokio::select! {
+                    _ = tokio::time::sleep(scaled(*ms)) => {}
+                    _ = cancel_rx.changed() => {
+                        completed = false;
+                        break;
+                    }
+                }
+            }
+            MacroAction::Press(key_name) => {
+                execute_action(&ctx, &mut frame, action);
+                held.push(key_name);
+            }
+            MacroAction::Release(key_name) => {
+                execute_action(&ctx, &mut frame, action);
+                held.retain(|k| k != key_name);
+            }
+            MacroAction::Click(step) => {
+                let hold = ctx.click_hold(step, Duration::ZERO);
+                if hold.is_zero() {
+                    execute_action(&ctx, &mut frame, action);
+                    continue;
+                }
+                execute_action(&ctx, &mut frame, &MacroAction::Press(step.key.clone()));
+                flush(&ctx, &mut frame);
+                let cancelled = tokio::select! {
+                    _ = tokio::time::sleep(hold) => false,
+                    _ = cancel_rx.changed() => true,
+                };
+                execute_action(&ctx, &mut frame, &MacroAction::Release(step.key.clone()));
+                if cancelled {
+                    completed = false;
+                    break;
+                }
+            }
+            _ => execute_action(&ctx, &mut frame, action),
+        }
     }
+
+    for key_name in held.into_iter().rev() {
+        execute_action(
+            &ctx,
+            &mut frame,
+            &MacroAction::Release(key_name.to_string()),
+        );
+    }
+    flush(&ctx, &mut frame);
+    ctx.report(MacroEvent::Stopped {
+        name: ctx.name.clone(),
+        iterations: completed as u64,
+    });
+}
+
+/// Execute a single macro action (blocking). Key steps are only added to
+/// `frame`, one report each, so a run of them goes out in a single write
+/// when the frame is flushed; anything else flushes the frame first to keep
+/// the order.
+#[tracing::instrument(name = "macro_step", level = "trace", skip(ctx, frame))]
+fn execute_action(ctx: &MacroContext, frame: &mut Frame, action: &MacroAction) {
+    let key_name = match action {
+        MacroAction::Click(step) => &step.key,
+        MacroAction::Press(k) => k,
+        MacroAction::Release(k) => k,
+        _ => {
+            flush(ctx, frame);
+            execute_other_action(ctx, action);
+            return;
+        }
+    };
+    let Some(key) = parse_key_name(key_name) else {
+        ctx.error(format!("Unknown key {}", key_name));
+        return;
+    };
+    match action {
+        MacroAction::Click(_) => frame.key(key, 1).sync().key(key, 0).sync(),
+        MacroAction::Press(_) => frame.key(key, 1).sync(),
+        _ => frame.key(key, 0).sync(),
+    };
 }
 
-/// Execute a single macro action (blocking)
-fn execute_action(writer: &Arc<Mutex<DeviceWriter>>, action: &MacroAction) {
-    let mut writer = match writer.lock() {
+/// Emit the key steps gathered in `frame`
+fn flush(ctx: &MacroContext, frame: &mut Frame) {
+    if frame.is_empty() {
+        return;
+    }
+    let mut writer = match ctx.writer.lock() {
         Ok(w) => w,
         Err(e) => {
-            log::error!("Failed to lock writer: {}", e);
+            ctx.error(format!("Failed to lock writer: {}", e));
             return;
         }
     };
+    if let Err(e) = writer.emit_frame(frame) {
+        drop(writer);
+        ctx.error(format!("Failed to emit key steps: {}", e));
+    }
+}
 
+/// Execute a macro action that doesn't touch keys
+fn execute_other_action(ctx: &MacroContext, action: &MacroAction) {
     match action {
-        MacroAction::Click(key_name) => {
-            if let Some(key) = parse_key_name(key_name) {
-                if let Err(e) = writer.click(key) {
-                    log::error!("Failed to click {}: {}", key_name, e);
+        MacroAction::SetMode { mode, set } => {
+            ctx.modes.switch(mode, *set);
+        }
+        MacroAction::Choice(_) => {}
+        MacroAction::Obs(request) => {
+            // Talking to OBS takes a round trip or three; don't hold up the macro
+            let Ok(handle) = tokio::runtime::Handle::try_current() else {
+                ctx.error("No tokio runtime available for OBS requests".to_string());
+                return;
+            };
+            let (ctx, request) = (ctx.clone(), request.clone());
+            handle.spawn(async move {
+                match obs::send(&ctx.obs, &request).await {
+                    Ok(()) => log::info!("Macro {}: OBS {}", ctx.name, request),
+                    Err(e) => ctx.error(format!("OBS {}: {:#}", request, e)),
                 }
-            }
+            });
         }
-        MacroAction::Press(key_name) => {
-            if let Some(key) = parse_key_name(key_name) {
-                if let Err(e) = writer.press(key) {
-                    log::error!("Failed to press {}: {}", key_name, e);
+        MacroAction::Notify(message) => {
+            let message = message.replace("{macro}", &ctx.name);
+            log::info!("Macro {}: notify \"{}\"", ctx.name, message);
+            // notify-send waits for the notification daemon; keep the macro going
+            let ctx = ctx.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = notify::send(&format!("Macro {}", ctx.name), &message) {
+                    ctx.error(format!("Notification failed: {:#}", e));
                 }
-            }
+            });
         }
-        MacroAction::Release(key_name) => {
-            if let Some(key) = parse_key_name(key_name) {
-                if let Err(e) = writer.release(key) {
-                    log::error!("Failed to release {}: {}", key_name, e);
+        MacroAction::Warp(target) => match &ctx.warp {
+            Some(warp) => {
+                if let Err(e) = warp.warp_to(target) {
+                    ctx.error(format!("Warp to {}: {:#}", target, e));
                 }
             }
+            None => ctx.error(format!("No absolute pointer to warp to {}", target)),
+        },
+        MacroAction::RunMacro(name) => {
+            // Inlined by MacroEngine::start_macro before the run starts
+            log::warn!("Macro {}: RunMacro {} wasn't expanded", ctx.name, name);
         }
-        MacroAction::Delay(_) => {
-            // Delays are handled in the async version
-        }
+        // Delays are handled in the async version
+        MacroAction::Delay(_) => {}
+        MacroAction::Click(_) | MacroAction::Press(_) | MacroAction::Release(_) => {}
     }
 }
 
 /// Execute a single macro action (async, supports delays)
-async fn execute_action_async(writer: &Arc<Mutex<DeviceWriter>>, action: &MacroAction) {
+async fn execute_action_async(ctx: &MacroContext, frame: &mut Frame, action: &MacroAction) {
     match action {
         MacroAction::Delay(ms) => {
-            tokio::time::sleep(std::time::Duration::from_millis(*ms)).await;
+            flush(ctx, frame);
+            tokio::time::sleep(scaled(*ms)).await;
+        }
+        MacroAction::Click(step) => {
+            let hold = ctx.click_hold(step, Duration::ZERO);
+            if hold.is_zero() {
+                execute_action(ctx, frame, action);
+            } else {
+                execute_action(ctx, frame, &MacroAction::Press(step.key.clone()));
+                flush(ctx, frame);
+                tokio::time::sleep(hold).await;
+                execute_action(ctx, frame, &MacroAction::Release(step.key.clone()));
+            }
         }
         other => {
-            execute_action(writer, other);
+            execute_action(ctx, frame, other);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_saturates_huge_delays() {
+        assert_eq!(scaled_at(u64::MAX, 1), Duration::from_millis(u64::MAX));
+        assert_eq!(
+            scaled_at(u64::MAX, 500),
+            Duration::from_millis(u64::MAX / 5)
+        );
+        assert_eq!(scaled_at(1000, 500), Duration::from_millis(200));
+    }
+}
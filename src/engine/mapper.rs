@@ -1,10 +1,46 @@
-use crate::config::{BindingOutput, Config, MacroDef};
+use crate::config::{
+    AutoscrollConfig, Binding, BindingOutput, ClickHoldConfig, ClickStep, Config, DpiStep,
+    KeyModifier, MacroAction, MacroDef, MacroType, ModeSwitch, PointerKeysConfig, ScancodePolicy,
+    ScrollAxis,
+};
+use crate::device::keyboard::{modifier_key, KeyboardMonitor, ModifierSet};
 use crate::device::writer::DeviceWriter;
-use crate::engine::macros::MacroEngine;
-use anyhow::Result;
-use evdev::{EventType, InputEvent, KeyCode};
-use std::collections::HashMap;
+use crate::engine::autoscroll::Autoscroll;
+use crate::engine::click_hold::hold_clicks;
+use crate::engine::key_repeat::KeyRepeat;
+use crate::engine::macros::{self, expand_actions, MacroEngine, MacroEvent};
+use crate::engine::modes::Modes;
+use crate::engine::pointer_keys::PointerKeys;
+use crate::engine::warp::{self, Warp};
+use anyhow::{Context, Result};
+use evdev::{EventType, InputEvent, KeyCode, MiscCode, RelativeAxisCode};
+use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Pseudo buttons for tilting the wheel (REL_HWHEEL), so tilt can be bound
+/// like a button; the codes are unused by real devices
+pub const WHEEL_LEFT: KeyCode = KeyCode::new(0x2f0);
+pub const WHEEL_RIGHT: KeyCode = KeyCode::new(0x2f1);
+
+/// First of the pseudo trigger keys for macros started by name, past
+/// KEY_MAX so no device can send them
+const NAMED_TRIGGER_BASE: u16 = 0x300;
+
+/// The tilt pseudo button a horizontal wheel event stands for, if any
+fn wheel_tilt(event: &InputEvent) -> Option<KeyCode> {
+    let code = event.code();
+    if code != RelativeAxisCode::REL_HWHEEL.0 && code != RelativeAxisCode::REL_HWHEEL_HI_RES.0 {
+        return None;
+    }
+    match event.value() {
+        v if v < 0 => Some(WHEEL_LEFT),
+        v if v > 0 => Some(WHEEL_RIGHT),
+        _ => None,
+    }
+}
 
 /// Resolve a key name string (e.g. "BTN_LEFT", "KEY_Q") to an evdev KeyCode.
 pub fn parse_key_name(name: &str) -> Option<KeyCode> {
@@ -22,6 +58,8 @@ pub fn parse_key_name(name: &str) -> Option<KeyCode> {
         "BTN_FORWARD" => return Some(KeyCode::BTN_FORWARD),
         "BTN_BACK" => return Some(KeyCode::BTN_BACK),
         "BTN_TASK" => return Some(KeyCode::BTN_TASK),
+        "WHEEL_LEFT" => return Some(WHEEL_LEFT),
+        "WHEEL_RIGHT" => return Some(WHEEL_RIGHT),
         _ => {}
     }
 
@@ -116,17 +154,36 @@ pub fn parse_key_name(name: &str) -> Option<KeyCode> {
         "KEY_INSERT" => Some(KeyCode::KEY_INSERT),
         "KEY_DELETE" => Some(KeyCode::KEY_DELETE),
         _ => {
-            // Try parsing as raw code number
+            // Try parsing as raw code number, then any other evdev key name
+            // (KEY_F13, KEY_VOLUMEUP, BTN_0, ...)
             if let Ok(code) = name.parse::<u16>() {
                 Some(KeyCode::new(code))
             } else {
-                None
+                with_prefix
+                    .parse::<KeyCode>()
+                    .or_else(|_| name_upper.parse::<KeyCode>())
+                    .ok()
             }
         }
     }
 }
 
+/// Key names in a macro's actions that don't resolve to a key
+pub fn unknown_macro_keys(def: &MacroDef) -> Vec<&str> {
+    def.actions
+        .iter()
+        .filter_map(|action| match action {
+            MacroAction::Click(ClickStep { key, .. })
+            | MacroAction::Press(key)
+            | MacroAction::Release(key) => Some(key.as_str()),
+            _ => None,
+        })
+        .filter(|key| parse_key_name(key).is_none())
+        .collect()
+}
+
 /// Get the human-readable name for a KeyCode
+#[allow(dead_code)]
 pub fn key_name(key: KeyCode) -> String {
     format!("{:?}", key)
 }
@@ -134,74 +191,788 @@ pub fn key_name(key: KeyCode) -> String {
 /// The event mapper: takes raw input events and produces output events,
 /// handling remapping and macro triggers.
 pub struct EventMapper {
-    /// Binding map: input KeyCode -> output action
-    bindings: HashMap<KeyCode, BindingOutput>,
+    /// Binding map: input KeyCode -> bindings for it (differing by modifiers)
+    bindings: HashMap<KeyCode, Vec<Binding>>,
+    /// Output chosen when each bound button was pressed, so its release and
+    /// repeats go to the same action even if the modifiers changed since
+    held_bindings: HashMap<KeyCode, BindingOutput>,
+    /// Keyboards to read modifier state from (only set up when a binding
+    /// requires modifiers)
+    keyboard: Option<KeyboardMonitor>,
+    /// Modifiers held on the grabbed device itself
+    own_modifiers: ModifierSet,
+    /// Buttons held on the grabbed device, for chord bindings
+    held_buttons: HashSet<KeyCode>,
+    /// Left-handed mode: BTN_LEFT and BTN_RIGHT trade places
+    swap_buttons: bool,
+    /// BTN_LEFT/BTN_RIGHT still down -> the button their press was sent as,
+    /// so switching the swap mid-drag releases the button that was pressed
+    swapped_held: HashMap<KeyCode, KeyCode>,
+    /// One-shot modifiers armed by sticky bindings, waiting for the next key or click
+    sticky_armed: Vec<KeyModifier>,
+    /// Output key the armed modifiers were applied to and the modifier keys
+    /// pressed for it; they're released along with it
+    sticky_held: Option<(u16, Vec<KeyCode>)>,
+    /// When the grabbed device last sent input (for idle auto-stop)
+    last_input: Instant,
+    /// When each binding with a cooldown last fired, by `Binding::input_label`
+    last_fired: HashMap<String, Instant>,
+    /// Buttons whose press was swallowed by a cooldown, so their release is too
+    suppressed: HashSet<KeyCode>,
+    /// Keys held down by toggle-key bindings until their next press
+    latched: HashSet<KeyCode>,
     /// Macro definitions: macro name -> MacroDef
     macro_defs: HashMap<String, MacroDef>,
+    /// Pseudo trigger key of each macro started by name (see trigger_macro)
+    named_triggers: HashMap<String, KeyCode>,
     /// Macro engine for handling active macros
     macro_engine: MacroEngine,
+    /// Named modes that `when` conditions check
+    modes: Modes,
+    /// Software DPI stages (pointer sensitivity multipliers) of the profile
+    dpi_stages: Vec<f64>,
+    dpi_stage: usize,
+    /// Fractional pointer movement carried over between events, so slow
+    /// movement isn't lost when scaling down
+    pointer_remainder: (f64, f64),
+    writer: Arc<Mutex<DeviceWriter>>,
+    /// Autorepeat for held remapped keys (None unless the profile enables it)
+    key_repeat: Option<KeyRepeat>,
+    /// Pointer-to-keys mode (None until the profile configures or a binding uses it)
+    pointer_keys: Option<PointerKeys>,
+    /// Hold time of the clicks the mapper makes up (None = instant)
+    click_hold: Option<ClickHoldConfig>,
+    /// Middle-click style autoscroll (None until the profile configures or a binding uses it)
+    autoscroll: Option<Autoscroll>,
+    /// Absolute pointer for Warp outputs and steps (None unless the profile uses them)
+    warp: Option<Warp>,
+    /// Press the keyboard's held modifiers around remapped keys (wrap_modifiers)
+    wrap_modifiers: bool,
+    /// Modifiers pressed around each held remapped button's key, to release
+    /// after it
+    wrapped: HashMap<KeyCode, Vec<KeyCode>>,
+    scancodes: ScancodePolicy,
+    /// MSC_SCAN of the report in progress, waiting for its key event
+    pending_scan: Option<InputEvent>,
+    /// Name of the loaded profile
+    profile: Option<String>,
+    /// Profile a switch_profile replaced, where a toggle_profile binding of
+    /// the alternate profile goes back to
+    previous_profile: Option<String>,
+    /// Profile a toggle_profile binding asked for, see take_profile_switch
+    profile_switch: Option<String>,
 }
 
 impl EventMapper {
     pub fn new(writer: Arc<Mutex<DeviceWriter>>) -> Self {
+        let modes = Modes::default();
         Self {
             bindings: HashMap::new(),
+            held_bindings: HashMap::new(),
+            keyboard: None,
+            own_modifiers: ModifierSet::default(),
+            held_buttons: HashSet::new(),
+            swap_buttons: false,
+            swapped_held: HashMap::new(),
+            sticky_armed: Vec::new(),
+            sticky_held: None,
+            last_input: Instant::now(),
+            last_fired: HashMap::new(),
+            suppressed: HashSet::new(),
+            latched: HashSet::new(),
             macro_defs: HashMap::new(),
-            macro_engine: MacroEngine::new(writer),
+            named_triggers: HashMap::new(),
+            macro_engine: MacroEngine::new(writer.clone(), modes.clone()),
+            modes,
+            dpi_stages: Vec::new(),
+            dpi_stage: 0,
+            pointer_remainder: (0.0, 0.0),
+            writer,
+            key_repeat: None,
+            pointer_keys: None,
+            click_hold: None,
+            autoscroll: None,
+            warp: None,
+            wrap_modifiers: false,
+            wrapped: HashMap::new(),
+            scancodes: ScancodePolicy::default(),
+            pending_scan: None,
+            profile: None,
+            previous_profile: None,
+            profile_switch: None,
         }
     }
 
-    /// Update bindings from config
-    pub fn load_config(&mut self, config: &Config) {
+    /// Update bindings from config. Returns a warning for every binding,
+    /// macro or setting that won't work as written (they're also logged).
+    pub fn load_config(&mut self, config: &Config) -> Vec<String> {
+        let mut warnings = Vec::new();
         self.bindings.clear();
+        self.held_bindings.clear();
+        self.last_fired.clear();
+        self.suppressed.clear();
+        self.sticky_armed.clear();
+        self.swapped_held.clear();
         self.macro_defs.clear();
+        // The binding that would release a latched key may be gone now
+        self.release_latched();
 
+        let warp_config = config.warp.clone().unwrap_or_default();
         let binding_map = config.build_binding_map();
         let macro_map = config.build_macro_map();
 
-        for (key_name_str, output) in binding_map {
+        for (key_name_str, bindings) in binding_map {
             if let Some(key) = parse_key_name(&key_name_str) {
-                self.bindings.insert(key, output);
+                self.bindings.insert(key, bindings);
             } else {
-                log::warn!("Unknown key name in binding: {}", key_name_str);
+                for binding in &bindings {
+                    warnings.push(format!(
+                        "{}: unknown key '{}', binding skipped",
+                        binding.input_label(),
+                        key_name_str
+                    ));
+                }
+            }
+        }
+
+        // Bindings that load but would fail or never fire when triggered
+        for binding in self.bindings.values().flatten() {
+            let label = binding.input_label();
+            for key in binding.input_keys() {
+                if parse_key_name(key).is_none() {
+                    warnings.push(format!("{}: unknown chord key '{}'", label, key));
+                }
+            }
+            match &binding.output {
+                BindingOutput::Key { key } | BindingOutput::ToggleKey { toggle_key: key }
+                    if parse_key_name(key).is_none() =>
+                {
+                    warnings.push(format!("{}: unknown output key '{}'", label, key));
+                }
+                BindingOutput::Macro { macro_name, args } => match macro_map.get(macro_name) {
+                    None => {
+                        warnings.push(format!("{}: macro '{}' isn't defined", label, macro_name));
+                    }
+                    Some(def) => match def.with_args(args) {
+                        // Keys filled in from parameters can only be checked per binding
+                        Ok(filled) if !def.params.is_empty() => {
+                            for key in unknown_macro_keys(&filled) {
+                                warnings.push(format!(
+                                    "{}: macro '{}' presses unknown key '{}'",
+                                    label, macro_name, key
+                                ));
+                            }
+                            for problem in warp::bad_warp_targets(&filled, &warp_config) {
+                                warnings.push(format!(
+                                    "{}: macro '{}': {}",
+                                    label, macro_name, problem
+                                ));
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => warnings.push(format!("{}: {}", label, e)),
+                    },
+                },
+                BindingOutput::Warp { warp } => {
+                    if let Err(e) = warp_config.resolve(warp) {
+                        warnings.push(format!("{}: {}", label, e));
+                    }
+                }
+                BindingOutput::ToggleProfile { toggle_profile }
+                    if !config.profiles.iter().any(|p| p.name == *toggle_profile) =>
+                {
+                    warnings.push(format!(
+                        "{}: profile '{}' doesn't exist",
+                        label, toggle_profile
+                    ));
+                }
+                _ => {}
+            }
+        }
+        for def in macro_map.values().filter(|def| def.params.is_empty()) {
+            for key in unknown_macro_keys(def) {
+                warnings.push(format!("macro '{}': unknown key '{}'", def.name, key));
+            }
+            for problem in warp::bad_warp_targets(def, &warp_config) {
+                warnings.push(format!("macro '{}': {}", def.name, problem));
+            }
+        }
+        for def in macro_map.values() {
+            if let Err(e) = expand_actions(def, &|name| macro_map.get(name)) {
+                warnings.push(e);
             }
         }
+        self.macro_engine.set_library(macro_map.clone());
 
         self.macro_defs = macro_map;
+
+        self.dpi_stages = config
+            .active_profile()
+            .map(|p| p.dpi_stages.clone())
+            .unwrap_or_default();
+        self.dpi_stages.retain(|m| {
+            let valid = m.is_finite() && *m > 0.0;
+            if !valid {
+                warnings.push(format!("Ignoring invalid DPI stage multiplier: {}", m));
+            }
+            valid
+        });
+        self.dpi_stage = self.dpi_stages.iter().position(|m| *m == 1.0).unwrap_or(0);
+        self.pointer_remainder = (0.0, 0.0);
+
+        self.profile = config.active_profile().map(|p| p.name.clone());
+        self.swap_buttons = config.active_profile().is_some_and(|p| p.swap_buttons);
+        self.wrap_modifiers = config.active_profile().is_some_and(|p| p.wrap_modifiers);
+
+        self.scancodes = config.scancodes;
+        self.pending_scan = None;
+        if let Ok(mut w) = self.writer.lock() {
+            w.set_synthesize_scancodes(config.scancodes == ScancodePolicy::Synthesize);
+            if let Err(e) = w.set_virtual_keyboard(config.virtual_keyboard) {
+                warnings.push(format!("{:#}; keys go out through the virtual mouse", e));
+            }
+        }
+
+        if let Some(ref mut key_repeat) = self.key_repeat {
+            key_repeat.stop_all();
+        }
+        self.key_repeat = config
+            .active_profile()
+            .and_then(|p| p.key_repeat.as_ref())
+            .map(|c| KeyRepeat::new(self.writer.clone(), c));
+
+        self.pointer_keys = config
+            .active_profile()
+            .and_then(|p| p.pointer_keys.as_ref())
+            .map(PointerKeys::from_config);
+
+        if let Some(ref mut autoscroll) = self.autoscroll {
+            autoscroll.stop();
+        }
+        self.autoscroll = config
+            .active_profile()
+            .and_then(|p| p.autoscroll.as_ref())
+            .map(|c| Autoscroll::new(self.writer.clone(), c));
+
+        self.click_hold = config.active_profile().and_then(|p| p.click_hold.clone());
+        self.macro_engine.set_click_hold(self.click_hold.clone());
+        self.macro_engine
+            .set_limit(config.max_running_macros, config.macro_limit);
+        self.macro_engine
+            .set_obs(config.obs.clone().unwrap_or_default());
+
+        if !config.active_profile().is_some_and(warp::profile_uses_warp) {
+            self.warp = None;
+        } else if self
+            .warp
+            .as_ref()
+            .is_none_or(|w| w.config() != &warp_config)
+        {
+            self.warp = match Warp::new(&warp_config) {
+                Ok(warp) => Some(warp),
+                Err(e) => {
+                    warnings.push(format!("Warp outputs won't work: {:#}", e));
+                    None
+                }
+            };
+        }
+        self.macro_engine.set_warp(self.warp.clone());
+
         log::info!(
             "Loaded {} bindings, {} macros",
-            self.bindings.len(),
+            self.bindings.values().map(Vec::len).sum::<usize>(),
             self.macro_defs.len()
         );
+        for warning in &warnings {
+            log::warn!("{}", warning);
+        }
+        warnings
+    }
+
+    /// Whether any binding only applies while keyboard modifiers are held
+    pub fn needs_keyboard_monitor(&self) -> bool {
+        self.bindings.values().flatten().any(|b| {
+            !b.modifiers.is_empty()
+                || (self.wrap_modifiers && matches!(b.output, BindingOutput::Key { .. }))
+        })
+    }
+
+    pub fn set_keyboard_monitor(&mut self, keyboard: KeyboardMonitor) {
+        self.keyboard = Some(keyboard);
+    }
+
+    /// Pick the binding for a pressed button among those whose mode condition
+    /// holds: the one requiring the most modifiers and chord buttons that are
+    /// all held, with mode-conditioned bindings winning ties, else the plain
+    /// binding
+    fn select_binding(&self, key: KeyCode) -> Option<&Binding> {
+        let candidates = self.bindings.get(&key)?;
+        let held = if candidates.iter().any(|b| !b.modifiers.is_empty()) {
+            self.keyboard
+                .as_ref()
+                .map(|k| k.held())
+                .unwrap_or_default()
+                .union(self.own_modifiers)
+        } else {
+            ModifierSet::default()
+        };
+        candidates
+            .iter()
+            .filter(|b| b.modifiers.iter().all(|m| held.contains(*m)))
+            .filter(|b| b.when.as_ref().is_none_or(|w| self.modes.matches(w)))
+            .filter(|b| self.chord_held(b))
+            .max_by_key(|b| (b.modifiers.len() + b.input_keys().len(), b.when.is_some()))
+    }
+
+    /// With wrap_modifiers, press the modifiers held on the keyboard (but not
+    /// on the mouse itself, those are passed through already) in a report
+    /// before the press of the key `button` is remapped to, and release them
+    /// in one after its release
+    fn wrap_in_modifiers(&mut self, button: KeyCode, event: InputEvent) -> Vec<InputEvent> {
+        let syn = InputEvent::new(EventType::SYNCHRONIZATION.0, 0, 0);
+        match event.value() {
+            1 if self.wrap_modifiers => {
+                let target = KeyCode::new(event.code());
+                let held: Vec<KeyCode> = self
+                    .keyboard
+                    .as_ref()
+                    .map(|k| k.held().without(self.own_modifiers))
+                    .unwrap_or_default()
+                    .keys()
+                    .filter(|m| *m != target)
+                    .collect();
+                if held.is_empty() {
+                    return vec![event];
+                }
+                let mut events: Vec<InputEvent> = held
+                    .iter()
+                    .map(|m| InputEvent::new(EventType::KEY.0, m.code(), 1))
+                    .collect();
+                events.extend([syn, event]);
+                self.wrapped.insert(button, held);
+                events
+            }
+            0 => match self.wrapped.remove(&button) {
+                Some(held) => {
+                    let mut events = vec![event, syn];
+                    events.extend(
+                        held.iter()
+                            .map(|m| InputEvent::new(EventType::KEY.0, m.code(), 0)),
+                    );
+                    events
+                }
+                None => vec![event],
+            },
+            _ => vec![event],
+        }
+    }
+
+    /// Whether the other buttons of a chord binding are held (true for single buttons)
+    fn chord_held(&self, binding: &Binding) -> bool {
+        let keys = binding.input_keys();
+        keys[..keys.len() - 1]
+            .iter()
+            .all(|name| parse_key_name(name).is_some_and(|k| self.held_buttons.contains(&k)))
+    }
+
+    /// Whether a press of `binding` falls inside its cooldown; records the
+    /// press as the last firing otherwise
+    fn in_cooldown(&mut self, binding: &Binding) -> bool {
+        if binding.cooldown_ms == 0 {
+            return false;
+        }
+        let label = binding.input_label();
+        let cooldown = Duration::from_millis(binding.cooldown_ms);
+        if self
+            .last_fired
+            .get(&label)
+            .is_some_and(|fired| fired.elapsed() < cooldown)
+        {
+            log::debug!("{} ignored: cooling down", label);
+            return true;
+        }
+        self.last_fired.insert(label, Instant::now());
+        false
+    }
+
+    /// Stream macro lifecycle events (started, iterations, stopped, errors)
+    pub fn set_macro_events(&mut self, events: mpsc::UnboundedSender<MacroEvent>) {
+        self.macro_engine.set_event_sender(events);
+    }
+
+    /// Stop toggle macros that have run past their idle limit; returns their names
+    pub fn stop_idle_macros(&mut self) -> Vec<String> {
+        self.macro_engine.stop_idle(self.last_input)
+    }
+
+    /// Names of the repeat/toggle macros currently running
+    pub fn running_macros(&self) -> Vec<String> {
+        self.macro_engine.running()
+    }
+
+    /// Run a macro by name rather than from a button (the HTTP API). Like a
+    /// button press, a toggle starts or stops; other macros stop if still
+    /// running from the last call, else start (repeat macros until the next).
+    pub fn trigger_macro(&mut self, name: &str) -> Result<()> {
+        let macro_def = self
+            .macro_defs
+            .get(name)
+            .with_context(|| format!("Macro not found: {}", name))?
+            .with_args(&BTreeMap::new())
+            .map_err(anyhow::Error::msg)?;
+        let next = KeyCode::new(NAMED_TRIGGER_BASE + self.named_triggers.len() as u16);
+        let trigger = *self.named_triggers.entry(name.to_string()).or_insert(next);
+        if macro_def.macro_type != MacroType::Toggle && self.macro_engine.is_active(trigger) {
+            self.macro_engine.stop_macro(trigger);
+            return Ok(());
+        }
+        self.macro_engine.start_macro(trigger, &macro_def)
+    }
+
+    /// Whether `name` is a toggle macro of the loaded profile
+    pub fn is_toggle_macro(&self, name: &str) -> bool {
+        self.macro_defs
+            .get(name)
+            .is_some_and(|m| m.macro_type == MacroType::Toggle)
+    }
+
+    /// Name of the loaded profile
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Profile a toggle_profile binding asked to switch to since the last call
+    pub fn take_profile_switch(&mut self) -> Option<String> {
+        self.profile_switch.take()
+    }
+
+    /// Load the active profile of `config` in place of the current one,
+    /// remembering the current one for toggle_profile bindings. Everything
+    /// pressed is let go, and the buttons still held have their release
+    /// swallowed (the binding that saw their press is gone).
+    pub fn switch_profile(&mut self, config: &Config) -> Vec<String> {
+        let held = self.held_buttons.clone();
+        self.device_lost();
+        let previous = self.profile.take();
+        let warnings = self.load_config(config);
+        if previous != self.profile {
+            self.previous_profile = previous;
+        }
+        self.suppressed.extend(held.iter().copied());
+        self.held_buttons = held;
+        warnings
+    }
+
+    fn toggle_profile(&mut self, alternate: &str) {
+        if self.profile.as_deref() != Some(alternate) {
+            self.profile_switch = Some(alternate.to_string());
+        } else if let Some(ref previous) = self.previous_profile {
+            self.profile_switch = Some(previous.clone());
+        } else {
+            log::warn!(
+                "Already on profile {} and no profile to switch back to",
+                alternate
+            );
+        }
+    }
+
+    /// Number of bindings and macros loaded from the profile
+    pub fn loaded_counts(&self) -> (usize, usize) {
+        let bindings = self.bindings.values().map(Vec::len).sum();
+        (bindings, self.macro_defs.len())
+    }
+
+    /// Names of the modes that are currently on
+    pub fn active_modes(&self) -> Vec<String> {
+        self.modes.active()
+    }
+
+    /// Current software DPI stage as (index, number of stages, multiplier),
+    /// or None if the profile has no stages
+    pub fn dpi_stage(&self) -> Option<(usize, usize, f64)> {
+        let multiplier = *self.dpi_stages.get(self.dpi_stage)?;
+        Some((self.dpi_stage, self.dpi_stages.len(), multiplier))
+    }
+
+    fn step_dpi_stage(&mut self, step: DpiStep) {
+        let count = self.dpi_stages.len();
+        if count == 0 {
+            log::warn!("DPI stage binding used, but the profile has no dpi_stages");
+            return;
+        }
+        self.dpi_stage = match step {
+            DpiStep::Next => (self.dpi_stage + 1) % count,
+            DpiStep::Prev => (self.dpi_stage + count - 1) % count,
+        };
+        self.pointer_remainder = (0.0, 0.0);
+        log::info!(
+            "DPI stage {}/{} (x{})",
+            self.dpi_stage + 1,
+            count,
+            self.dpi_stages[self.dpi_stage]
+        );
+    }
+
+    /// Pointer scaling stage: apply the DPI stage multiplier to REL_X/REL_Y
+    fn scale_pointer(&mut self, event: InputEvent) -> Vec<InputEvent> {
+        let multiplier = self.dpi_stages.get(self.dpi_stage).copied().unwrap_or(1.0);
+        if multiplier == 1.0 {
+            return vec![event];
+        }
+
+        let remainder = match RelativeAxisCode(event.code()) {
+            RelativeAxisCode::REL_X => &mut self.pointer_remainder.0,
+            RelativeAxisCode::REL_Y => &mut self.pointer_remainder.1,
+            _ => return vec![event],
+        };
+        let scaled = event.value() as f64 * multiplier + *remainder;
+        let whole = scaled.trunc();
+        *remainder = scaled - whole;
+
+        if whole == 0.0 {
+            return vec![];
+        }
+        vec![InputEvent::new(
+            EventType::RELATIVE.0,
+            event.code(),
+            whole as i32,
+        )]
+    }
+
+    fn switch_swap_buttons(&mut self, switch: ModeSwitch) {
+        self.swap_buttons = match switch {
+            ModeSwitch::Toggle => !self.swap_buttons,
+            ModeSwitch::On => true,
+            ModeSwitch::Off => false,
+        };
+        log::info!(
+            "Left/right button swap {}",
+            if self.swap_buttons { "on" } else { "off" }
+        );
+    }
+
+    /// Left-handed swap stage: a release or repeat follows its press, whatever
+    /// the swap state is now
+    fn swap_button(&mut self, event: InputEvent) -> InputEvent {
+        let key = KeyCode::new(event.code());
+        let other = match key {
+            KeyCode::BTN_LEFT => KeyCode::BTN_RIGHT,
+            KeyCode::BTN_RIGHT => KeyCode::BTN_LEFT,
+            _ => return event,
+        };
+        let sent_as = match event.value() {
+            1 => {
+                let sent_as = if self.swap_buttons { other } else { key };
+                self.swapped_held.insert(key, sent_as);
+                sent_as
+            }
+            0 => self.swapped_held.remove(&key).unwrap_or(key),
+            _ => self.swapped_held.get(&key).copied().unwrap_or(key),
+        };
+        InputEvent::new(EventType::KEY.0, sent_as.code(), event.value())
+    }
+
+    /// Toggle a one-shot modifier: arms it, or disarms it if it already was
+    fn toggle_sticky(&mut self, modifier: KeyModifier) {
+        if let Some(pos) = self.sticky_armed.iter().position(|m| *m == modifier) {
+            self.sticky_armed.remove(pos);
+            log::info!("Sticky {} disarmed", modifier.label());
+        } else {
+            self.sticky_armed.push(modifier);
+            log::info!("Sticky {} armed", modifier.label());
+        }
+    }
+
+    /// Wrap the next key/button press in the armed sticky modifiers, and
+    /// release them after that key is released
+    fn apply_sticky(&mut self, events: Vec<InputEvent>) -> Vec<InputEvent> {
+        if self.sticky_armed.is_empty() && self.sticky_held.is_none() {
+            return events;
+        }
+        let mut out = Vec::with_capacity(events.len() + 2);
+        for event in events {
+            if event.event_type() != EventType::KEY {
+                out.push(event);
+                continue;
+            }
+            let code = event.code();
+            match event.value() {
+                1 if self.sticky_held.is_none()
+                    && !self.sticky_armed.is_empty()
+                    && !ModifierSet::is_modifier(KeyCode::new(code)) =>
+                {
+                    let keys: Vec<KeyCode> =
+                        self.sticky_armed.drain(..).map(modifier_key).collect();
+                    for key in &keys {
+                        out.push(InputEvent::new(EventType::KEY.0, key.code(), 1));
+                    }
+                    out.push(event);
+                    self.sticky_held = Some((code, keys));
+                }
+                0 if self
+                    .sticky_held
+                    .as_ref()
+                    .is_some_and(|(held, _)| *held == code) =>
+                {
+                    out.push(event);
+                    if let Some((_, keys)) = self.sticky_held.take() {
+                        for key in keys.iter().rev() {
+                            out.push(InputEvent::new(EventType::KEY.0, key.code(), 0));
+                        }
+                    }
+                }
+                _ => out.push(event),
+            }
+        }
+        out
     }
 
     /// Process an input event. Returns events to emit (may be empty if handled by macro).
     pub fn process_event(&mut self, event: InputEvent) -> Result<Vec<InputEvent>> {
+        // A device sends MSC_SCAN right before the key event it belongs to:
+        // hold it back to go out with what the key maps to, or not at all
+        // when the key is swallowed
+        if event.event_type() == EventType::MISC && event.code() == MiscCode::MSC_SCAN.0 {
+            if self.scancodes == ScancodePolicy::Forward {
+                self.pending_scan = Some(event);
+            }
+            return Ok(vec![]);
+        }
+        let scan = self.pending_scan.take();
+        let mut events = self.map_event(event)?;
+        if let Some(scan) = scan
+            && !events.is_empty()
+        {
+            events.insert(0, scan);
+        }
+        Ok(events)
+    }
+
+    fn map_event(&mut self, event: InputEvent) -> Result<Vec<InputEvent>> {
+        if event.event_type() != EventType::SYNCHRONIZATION {
+            self.last_input = Instant::now();
+        }
+
+        if event.event_type() == EventType::RELATIVE {
+            // Bound wheel tilt: every notch is a press and release of its
+            // pseudo button, and the hi-res twin of the notch is dropped
+            if let Some(tilt) = wheel_tilt(&event)
+                && self.select_binding(tilt).is_some()
+            {
+                if event.code() != RelativeAxisCode::REL_HWHEEL.0 {
+                    return Ok(vec![]);
+                }
+                let mut events =
+                    self.map_key_event(InputEvent::new(EventType::KEY.0, tilt.code(), 1))?;
+                events.extend(self.map_key_event(InputEvent::new(
+                    EventType::KEY.0,
+                    tilt.code(),
+                    0,
+                ))?);
+                return Ok(self.apply_sticky(events));
+            }
+            if let Some(ref mut autoscroll) = self.autoscroll
+                && let Some(events) = autoscroll.process(&event)
+            {
+                return Ok(events);
+            }
+            if let Some(ref mut pointer_keys) = self.pointer_keys
+                && let Some(events) = pointer_keys.process(&event)
+            {
+                return Ok(match self.click_hold {
+                    Some(ref hold) => hold_clicks(events, hold, &self.writer),
+                    None => events,
+                });
+            }
+            return Ok(self.scale_pointer(event));
+        }
+
         // Only process key/button events for mapping
         if event.event_type() != EventType::KEY {
             // Pass through non-key events unchanged (mouse movement, scroll, sync, etc.)
             return Ok(vec![event]);
         }
 
+        let event = self.swap_button(event);
+        let events = self.map_key_event(event)?;
+        Ok(self.apply_sticky(events))
+    }
+
+    /// Key/button stage: apply the binding of the button, if any
+    fn map_key_event(&mut self, event: InputEvent) -> Result<Vec<InputEvent>> {
         let key = KeyCode::new(event.code());
         let value = event.value(); // 0=release, 1=press, 2=repeat
 
+        self.own_modifiers.update(key, value != 0);
+        match value {
+            1 => self.held_buttons.insert(key),
+            0 => self.held_buttons.remove(&key),
+            _ => false,
+        };
+
         // Check if this key has a binding
-        if let Some(binding) = self.bindings.get(&key).cloned() {
+        // Presses swallowed by a cooldown take their repeats and release along
+        if (value == 0 && self.suppressed.remove(&key))
+            || (value == 2 && self.suppressed.contains(&key))
+        {
+            return Ok(vec![]);
+        }
+
+        let binding = match value {
+            1 => {
+                let selected = self.select_binding(key).cloned();
+                if let Some(ref b) = selected
+                    && self.in_cooldown(b)
+                {
+                    self.suppressed.insert(key);
+                    return Ok(vec![]);
+                }
+                let binding = selected.map(|b| b.output);
+                match binding {
+                    Some(ref output) => self.held_bindings.insert(key, output.clone()),
+                    None => self.held_bindings.remove(&key),
+                };
+                binding
+            }
+            0 => self
+                .held_bindings
+                .remove(&key)
+                .or_else(|| self.select_binding(key).map(|b| b.output.clone())),
+            _ => self.held_bindings.get(&key).cloned(),
+        };
+        if let Some(binding) = binding {
             match binding {
                 BindingOutput::Key { key: ref key_name } => {
                     // Simple remap: translate to a different key
                     if let Some(target_key) = parse_key_name(key_name) {
+                        if let Some(ref mut key_repeat) = self.key_repeat {
+                            match value {
+                                1 => key_repeat.start(key, target_key),
+                                0 => key_repeat.stop(key),
+                                _ => {}
+                            }
+                        }
                         let remapped = InputEvent::new(EventType::KEY.0, target_key.code(), value);
-                        return Ok(vec![remapped]);
+                        return Ok(self.wrap_in_modifiers(key, remapped));
                     } else {
                         log::warn!("Unknown target key: {}", key_name);
                         return Ok(vec![event]);
                     }
                 }
-                BindingOutput::Macro { ref macro_name } => {
-                    // Trigger macro
-                    if let Some(macro_def) = self.macro_defs.get(macro_name).cloned() {
+                BindingOutput::Macro {
+                    ref macro_name,
+                    ref args,
+                } => {
+                    // Trigger macro, its parameters filled in from the binding
+                    let macro_def = self
+                        .macro_defs
+                        .get(macro_name)
+                        .map(|def| def.with_args(args));
+                    if let Some(Err(e)) = macro_def {
+                        log::warn!("{}", e);
+                        return Ok(vec![event]);
+                    }
+                    if let Some(Ok(macro_def)) = macro_def {
                         match value {
                             1 => {
                                 // Button pressed - start macro
@@ -223,6 +994,109 @@ impl EventMapper {
                         return Ok(vec![event]);
                     }
                 }
+                BindingOutput::DpiStage { dpi_stage } => {
+                    if value == 1 {
+                        self.step_dpi_stage(dpi_stage);
+                    }
+                    return Ok(vec![]);
+                }
+                BindingOutput::SetMode { ref mode, set } => {
+                    if value == 1 {
+                        self.modes.switch(mode, set);
+                    }
+                    return Ok(vec![]);
+                }
+                BindingOutput::ToggleKey { ref toggle_key } => {
+                    let Some(target_key) = parse_key_name(toggle_key) else {
+                        log::warn!("Unknown target key: {}", toggle_key);
+                        return Ok(vec![event]);
+                    };
+                    // Only presses switch the latch; releases and repeats are swallowed
+                    if value != 1 {
+                        return Ok(vec![]);
+                    }
+                    let pressed = self.latched.insert(target_key);
+                    if !pressed {
+                        self.latched.remove(&target_key);
+                    }
+                    return Ok(vec![InputEvent::new(
+                        EventType::KEY.0,
+                        target_key.code(),
+                        pressed as i32,
+                    )]);
+                }
+                BindingOutput::Scroll { scroll } => {
+                    if value == 1 {
+                        let axis = match scroll.axis {
+                            ScrollAxis::Wheel => RelativeAxisCode::REL_WHEEL,
+                            ScrollAxis::HWheel => RelativeAxisCode::REL_HWHEEL,
+                        };
+                        if let Ok(mut w) = self.writer.lock()
+                            && let Err(e) = w.scroll(axis, scroll.amount)
+                        {
+                            log::error!("{:#}", e);
+                        }
+                    }
+                    return Ok(vec![]);
+                }
+                BindingOutput::SwapButtons { swap_buttons } => {
+                    if value == 1 {
+                        self.switch_swap_buttons(swap_buttons);
+                    }
+                    return Ok(vec![]);
+                }
+                BindingOutput::PauseMacros { pause_macros } => {
+                    if value == 1 {
+                        macros::switch_paused(pause_macros);
+                    }
+                    return Ok(vec![]);
+                }
+                BindingOutput::Sticky { sticky } => {
+                    if value == 1 {
+                        self.toggle_sticky(sticky);
+                    }
+                    return Ok(vec![]);
+                }
+                BindingOutput::Warp { ref warp } => {
+                    if value == 1 {
+                        match self.warp {
+                            Some(ref w) => {
+                                if let Err(e) = w.warp_to(warp) {
+                                    log::error!("{:#}", e);
+                                }
+                            }
+                            None => log::warn!("No absolute pointer to warp to {}", warp),
+                        }
+                    }
+                    return Ok(vec![]);
+                }
+                BindingOutput::ToggleProfile { ref toggle_profile } => {
+                    if value == 1 {
+                        self.toggle_profile(toggle_profile);
+                    }
+                    return Ok(vec![]);
+                }
+                BindingOutput::Autoscroll { autoscroll } => {
+                    if value == 1 {
+                        let writer = self.writer.clone();
+                        self.autoscroll
+                            .get_or_insert_with(|| {
+                                Autoscroll::new(writer, &AutoscrollConfig::default())
+                            })
+                            .switch(autoscroll);
+                    }
+                    return Ok(vec![]);
+                }
+                BindingOutput::PointerKeys { pointer_keys } => {
+                    if value == 1 {
+                        self.pointer_keys
+                            .get_or_insert_with(|| {
+                                PointerKeys::from_config(&PointerKeysConfig::default())
+                            })
+                            .switch(pointer_keys);
+                    }
+                    return Ok(vec![]);
+                }
             }
         }
 
@@ -230,8 +1104,49 @@ impl EventMapper {
         Ok(vec![event])
     }
 
+    /// Let go of the keys toggle-key bindings are holding
+    fn release_latched(&mut self) {
+        if self.latched.is_empty() {
+            return;
+        }
+        let Ok(mut w) = self.writer.lock() else {
+            return;
+        };
+        for key in self.latched.drain() {
+            if let Err(e) = w.release(key) {
+                log::error!("Failed to release latched {:?}: {}", key, e);
+            }
+        }
+    }
+
     /// Stop all running macros (for clean shutdown)
     pub fn stop_all(&mut self) {
+        self.release_latched();
+        if let Some(ref mut autoscroll) = self.autoscroll {
+            autoscroll.stop();
+        }
+        if let Some(ref mut key_repeat) = self.key_repeat {
+            key_repeat.stop_all();
+        }
         self.macro_engine.stop_all();
     }
+
+    /// The device went away: stop everything, forget what was held on it and
+    /// release whatever the virtual device still has pressed
+    pub fn device_lost(&mut self) {
+        self.stop_all();
+        self.held_bindings.clear();
+        self.own_modifiers = ModifierSet::default();
+        self.held_buttons.clear();
+        self.wrapped.clear();
+        self.swapped_held.clear();
+        self.sticky_armed.clear();
+        self.sticky_held = None;
+        self.suppressed.clear();
+        if let Ok(mut w) = self.writer.lock()
+            && let Err(e) = w.release_all()
+        {
+            log::error!("{:#}", e);
+        }
+    }
 }
@@ -0,0 +1,180 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Process-wide counters for the HTTP API's /metrics, in the Prometheus
+/// text format. Every device adds to the same totals.
+static EVENTS_PROCESSED: AtomicU64 = AtomicU64::new(0);
+static EVENTS_EMITTED: AtomicU64 = AtomicU64::new(0);
+static MESSAGES_DROPPED: AtomicU64 = AtomicU64::new(0);
+static MACRO_ITERATIONS: AtomicU64 = AtomicU64::new(0);
+static MAPPING_LATENCY: Histogram = Histogram::new();
+
+/// Upper bounds (seconds) of the latency histogram buckets
+pub const LATENCY_BUCKETS: [f64; 10] = [
+    0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1,
+];
+
+struct Histogram {
+    /// Observations per bucket (not cumulative, that's done when rendering)
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    /// Observations above the last bucket
+    overflow: AtomicU64,
+    sum_nanos: AtomicU64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; LATENCY_BUCKETS.len()],
+            overflow: AtomicU64::new(0),
+            sum_nanos: AtomicU64::new(0),
+        }
+    }
+
+    fn observe(&self, value: Duration) {
+        let seconds = value.as_secs_f64();
+        match LATENCY_BUCKETS.iter().position(|le| seconds <= *le) {
+            Some(i) => self.buckets[i].fetch_add(1, Ordering::Relaxed),
+            None => self.overflow.fetch_add(1, Ordering::Relaxed),
+        };
+        self.sum_nanos
+            .fetch_add(value.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+/// An input event read from a grabbed device went through the mapper
+pub fn event_processed() {
+    EVENTS_PROCESSED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Events (SYN_REPORTs not counted) written to a virtual device
+pub fn events_emitted(count: usize) {
+    EVENTS_EMITTED.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+/// Monitor messages thrown away because its buffer was full
+pub fn messages_dropped(count: usize) {
+    MESSAGES_DROPPED.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+/// A repeat or toggle macro finished a pass over its actions
+pub fn macro_iteration() {
+    MACRO_ITERATIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Time from the kernel timestamping an input event to the mapper's output
+/// for it being written
+pub fn observe_latency(latency: Duration) {
+    MAPPING_LATENCY.observe(latency);
+}
+
+/// Counter values at one point in time; the difference of two measures what
+/// happened in between (the `stress` command)
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot {
+    pub events_processed: u64,
+    pub events_emitted: u64,
+    /// Latency observations per bucket, the last one for those above all
+    pub latency_buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    pub latency_sum: Duration,
+}
+
+pub fn snapshot() -> Snapshot {
+    let mut latency_buckets = [0; LATENCY_BUCKETS.len() + 1];
+    for (count, bucket) in latency_buckets.iter_mut().zip(&MAPPING_LATENCY.buckets) {
+        *count = bucket.load(Ordering::Relaxed);
+    }
+    latency_buckets[LATENCY_BUCKETS.len()] = MAPPING_LATENCY.overflow.load(Ordering::Relaxed);
+    Snapshot {
+        events_processed: EVENTS_PROCESSED.load(Ordering::Relaxed),
+        events_emitted: EVENTS_EMITTED.load(Ordering::Relaxed),
+        latency_buckets,
+        latency_sum: Duration::from_nanos(MAPPING_LATENCY.sum_nanos.load(Ordering::Relaxed)),
+    }
+}
+
+impl Snapshot {
+    /// What was counted between `earlier` and this snapshot
+    pub fn since(&self, earlier: &Snapshot) -> Snapshot {
+        let mut latency_buckets = self.latency_buckets;
+        for (count, before) in latency_buckets.iter_mut().zip(&earlier.latency_buckets) {
+            *count -= before;
+        }
+        Snapshot {
+            events_processed: self.events_processed - earlier.events_processed,
+            events_emitted: self.events_emitted - earlier.events_emitted,
+            latency_buckets,
+            latency_sum: self.latency_sum - earlier.latency_sum,
+        }
+    }
+
+    pub fn latency_count(&self) -> u64 {
+        self.latency_buckets.iter().sum()
+    }
+
+    /// Upper bound (seconds) of the bucket holding the `q` quantile of the
+    /// latencies; None without observations or when it's past the last bucket
+    pub fn latency_quantile(&self, q: f64) -> Option<f64> {
+        let target = (self.latency_count() as f64 * q).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (le, count) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+            seen += count;
+            if seen >= target {
+                return Some(*le);
+            }
+        }
+        None
+    }
+}
+
+/// All metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    let mut out = String::new();
+    let counters = [
+        (
+            "mouse_mapper_events_processed_total",
+            "Input events read from grabbed devices and run through the mapper",
+            &EVENTS_PROCESSED,
+        ),
+        (
+            "mouse_mapper_events_emitted_total",
+            "Events written to the virtual devices, SYN_REPORT not counted",
+            &EVENTS_EMITTED,
+        ),
+        (
+            "mouse_mapper_dropped_messages_total",
+            "Monitor messages discarded because the monitor buffer was full",
+            &MESSAGES_DROPPED,
+        ),
+        (
+            "mouse_mapper_macro_iterations_total",
+            "Passes over their actions completed by repeat and toggle macros",
+            &MACRO_ITERATIONS,
+        ),
+    ];
+    for (name, help, counter) in counters {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+    }
+
+    let name = "mouse_mapper_mapping_latency_seconds";
+    let _ = writeln!(
+        out,
+        "# HELP {} Time from an input event's kernel timestamp to writing the mapper's output for it",
+        name
+    );
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    let mut cumulative = 0;
+    for (le, bucket) in LATENCY_BUCKETS.iter().zip(&MAPPING_LATENCY.buckets) {
+        cumulative += bucket.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, le, cumulative);
+    }
+    cumulative += MAPPING_LATENCY.overflow.load(Ordering::Relaxed);
+    let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, cumulative);
+    let sum = MAPPING_LATENCY.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9;
+    let _ = writeln!(out, "{}_sum {}", name, sum);
+    let _ = writeln!(out, "{}_count {}", name, cumulative);
+    out
+}
@@ -1,2 +1,15 @@
-pub mod mapper;
+pub mod autoscroll;
+pub mod blocklist;
+pub mod click_hold;
+pub mod fullscreen;
+pub mod key_repeat;
 pub mod macros;
+pub mod mapper;
+pub mod metrics;
+pub mod modes;
+pub mod notify;
+pub mod obs;
+pub mod pointer_keys;
+pub mod realtime;
+pub mod sounds;
+pub mod warp;
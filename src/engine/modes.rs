@@ -0,0 +1,46 @@
+use crate::config::ModeSwitch;
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+/// Named on/off modes set by bindings and macros. Shared between the mapper
+/// and running macro tasks, so a macro can flip a mode mid-sequence.
+#[derive(Debug, Clone, Default)]
+pub struct Modes(Arc<Mutex<BTreeSet<String>>>);
+
+impl Modes {
+    pub fn switch(&self, mode: &str, switch: ModeSwitch) {
+        let Ok(mut modes) = self.0.lock() else {
+            return;
+        };
+        let on = match switch {
+            ModeSwitch::Toggle => !modes.contains(mode),
+            ModeSwitch::On => true,
+            ModeSwitch::Off => false,
+        };
+        if on {
+            modes.insert(mode.to_string());
+        } else {
+            modes.remove(mode);
+        }
+        log::info!("Mode {} {}", mode, if on { "on" } else { "off" });
+    }
+
+    /// Whether a binding's `when` condition holds: "name" needs the mode on,
+    /// "!name" needs it off
+    pub fn matches(&self, when: &str) -> bool {
+        let (negated, mode) = match when.strip_prefix('!') {
+            Some(mode) => (true, mode.trim()),
+            None => (false, when.trim()),
+        };
+        let on = self.0.lock().is_ok_and(|modes| modes.contains(mode));
+        on != negated
+    }
+
+    /// Names of the modes that are on, sorted
+    pub fn active(&self) -> Vec<String> {
+        self.0
+            .lock()
+            .map(|modes| modes.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
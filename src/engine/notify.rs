@@ -0,0 +1,57 @@
+use anyhow::{bail, Context, Result};
+use std::process::Command;
+
+/// Show a desktop notification through notify-send. Blocks until it has
+/// been handed to the notification daemon.
+pub fn send(summary: &str, body: &str) -> Result<()> {
+    let mut command = session_command("notify-send");
+    command.args(["--app-name", "mouse-mapper", summary, body]);
+    let output = command
+        .output()
+        .context("Failed to run notify-send (is libnotify installed?)")?;
+    if !output.status.success() {
+        bail!(
+            "notify-send failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Where the desktop session's windows are, kept under sudo
+const DISPLAY_VARS: [&str; 5] = [
+    "DISPLAY",
+    "XAUTHORITY",
+    "WAYLAND_DISPLAY",
+    "SWAYSOCK",
+    "HYPRLAND_INSTANCE_SIGNATURE",
+];
+
+/// Command that runs `program` in the desktop session. Under sudo the
+/// session bus belongs to the user who ran sudo, and root can't talk to it,
+/// so the program runs as that user, with its session bus, runtime
+/// directory (where the sound server listens) and display.
+pub fn session_command(program: &str) -> Command {
+    // SAFETY: geteuid has no preconditions
+    let root = unsafe { libc::geteuid() } == 0;
+    if root && let (Ok(user), Ok(uid)) = (std::env::var("SUDO_USER"), std::env::var("SUDO_UID")) {
+        let mut command = Command::new("sudo");
+        command.args([
+            "-u",
+            &user,
+            "env",
+            &format!("DBUS_SESSION_BUS_ADDRESS=unix:path=/run/user/{}/bus", uid),
+            &format!("XDG_RUNTIME_DIR=/run/user/{}", uid),
+        ]);
+        // sudo drops these; they're the user's own anyway
+        for var in DISPLAY_VARS {
+            if let Ok(value) = std::env::var(var) {
+                command.arg(format!("{}={}", var, value));
+            }
+        }
+        command.arg(program);
+        command
+    } else {
+        Command::new(program)
+    }
+}
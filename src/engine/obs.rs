@@ -0,0 +1,228 @@
+use crate::config::{ObsConfig, ObsRequest};
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Longest a whole request (connect, identify, reply) may take
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+// obs-websocket 5 opcodes
+const OP_HELLO: u64 = 0;
+const OP_IDENTIFY: u64 = 1;
+const OP_IDENTIFIED: u64 = 2;
+const OP_REQUEST: u64 = 6;
+const OP_REQUEST_RESPONSE: u64 = 7;
+
+/// Largest frame accepted; obs-websocket replies are a few hundred bytes
+const MAX_FRAME: usize = 1 << 20;
+
+// WebSocket frame opcodes
+const FRAME_TEXT: u8 = 0x1;
+const FRAME_CLOSE: u8 = 0x8;
+const FRAME_PING: u8 = 0x9;
+const FRAME_PONG: u8 = 0xa;
+
+/// Send one request to OBS: connect, identify, send it and wait for OBS to
+/// confirm it. A connection per request keeps nothing open while OBS isn't
+/// running, and a button press or two a minute doesn't need more.
+pub async fn send(config: &ObsConfig, request: &ObsRequest) -> Result<()> {
+    tokio::time::timeout(TIMEOUT, send_inner(config, request))
+        .await
+        .context("OBS didn't answer in time")?
+}
+
+async fn send_inner(config: &ObsConfig, request: &ObsRequest) -> Result<()> {
+    let mut socket = WebSocket::connect(&config.host, config.port).await?;
+
+    let hello = socket.read_message(OP_HELLO).await?;
+    let mut identify = json!({ "rpcVersion": 1, "eventSubscriptions": 0 });
+    if let Some(auth) = hello.get("authentication") {
+        let Some(password) = &config.password else {
+            bail!("OBS asks for a password, set obs.password in the config");
+        };
+        let challenge = auth["challenge"].as_str().unwrap_or_default();
+        let salt = auth["salt"].as_str().unwrap_or_default();
+        identify["authentication"] = json!(auth_response(password, salt, challenge));
+    }
+    socket.send_message(OP_IDENTIFY, identify).await?;
+    socket.read_message(OP_IDENTIFIED).await?;
+
+    let (request_type, request_data) = match request {
+        ObsRequest::StartRecording => ("StartRecord", json!({})),
+        ObsRequest::StopRecording => ("StopRecord", json!({})),
+        ObsRequest::ToggleRecording => ("ToggleRecord", json!({})),
+        ObsRequest::SwitchScene(scene) => ("SetCurrentProgramScene", json!({ "sceneName": scene })),
+    };
+    socket
+        .send_message(
+            OP_REQUEST,
+            json!({
+                "requestType": request_type,
+                "requestId": "mouse-mapper",
+                "requestData": request_data,
+            }),
+        )
+        .await?;
+    let response = socket.read_message(OP_REQUEST_RESPONSE).await?;
+    let status = &response["requestStatus"];
+    if status["result"].as_bool() != Some(true) {
+        bail!(
+            "{} failed: {} (code {})",
+            request_type,
+            status["comment"].as_str().unwrap_or("no reason given"),
+            status["code"]
+        );
+    }
+
+    socket.close().await;
+    Ok(())
+}
+
+/// base64(sha256(base64(sha256(password + salt)) + challenge))
+fn auth_response(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = BASE64.encode(Sha256::digest(format!("{}{}", password, salt)));
+    BASE64.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+/// Just enough of a WebSocket client (RFC 6455) for obs-websocket's JSON
+/// messages: unfragmented text frames, answering pings
+struct WebSocket {
+    stream: TcpStream,
+}
+
+impl WebSocket {
+    async fn connect(host: &str, port: u16) -> Result<Self> {
+        let mut stream = TcpStream::connect((host, port))
+            .await
+            .with_context(|| format!("Failed to connect to OBS at {}:{}", host, port))?;
+
+        let key = BASE64.encode(rand::random::<[u8; 16]>());
+        let handshake = format!(
+            "GET / HTTP/1.1\r\n\
+             Host: {}:{}\r\n\
+             Upgrade: websocket\r\n\
+             Connection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\n\
+             Sec-WebSocket-Version: 13\r\n\
+             Sec-WebSocket-Protocol: obswebsocket.json\r\n\r\n",
+            host, port, key
+        );
+        stream.write_all(handshake.as_bytes()).await?;
+
+        // The server sends nothing after its response until we identify, so
+        // reading byte by byte can't swallow the first frame
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() > 4096 {
+                bail!("OBS sent an oversized handshake response");
+            }
+            response.push(
+                stream
+                    .read_u8()
+                    .await
+                    .context("OBS closed the connection")?,
+            );
+        }
+        let status = String::from_utf8_lossy(&response);
+        let status = status.lines().next().unwrap_or_default();
+        if !status.contains(" 101 ") {
+            bail!("OBS refused the WebSocket upgrade: {}", status);
+        }
+        Ok(Self { stream })
+    }
+
+    async fn send_message(&mut self, op: u64, data: Value) -> Result<()> {
+        let text = json!({ "op": op, "d": data }).to_string();
+        self.send_frame(FRAME_TEXT, text.as_bytes()).await
+    }
+
+    /// Read messages until one with opcode `op` arrives and return its data
+    async fn read_message(&mut self, op: u64) -> Result<Value> {
+        loop {
+            let (opcode, payload) = self.read_frame().await?;
+            match opcode {
+                FRAME_TEXT => {
+                    let message: Value =
+                        serde_json::from_slice(&payload).context("OBS sent invalid JSON")?;
+                    if message["op"].as_u64() == Some(op) {
+                        return Ok(message["d"].clone());
+                    }
+                }
+                FRAME_PING => self.send_frame(FRAME_PONG, &payload).await?,
+                FRAME_CLOSE => {
+                    let reason = payload.get(2..).map(String::from_utf8_lossy);
+                    bail!(
+                        "OBS closed the connection: {}",
+                        reason.as_deref().unwrap_or("no reason given")
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn close(&mut self) {
+        let _ = self.send_frame(FRAME_CLOSE, &1000u16.to_be_bytes()).await;
+    }
+
+    /// Client frames are always masked
+    async fn send_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<()> {
+        let mut frame = vec![0x80 | opcode];
+        match payload.len() {
+            len @ 0..=125 => frame.push(0x80 | len as u8),
+            len @ 126..=0xffff => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+        let mask: [u8; 4] = rand::random();
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        self.stream
+            .write_all(&frame)
+            .await
+            .context("Failed to send to OBS")
+    }
+
+    async fn read_frame(&mut self) -> Result<(u8, Vec<u8>)> {
+        let mut header = [0u8; 2];
+        self.stream
+            .read_exact(&mut header)
+            .await
+            .context("OBS closed the connection")?;
+        let opcode = header[0] & 0x0f;
+        let len = match header[1] & 0x7f {
+            126 => self.stream.read_u16().await? as usize,
+            127 => self.stream.read_u64().await? as usize,
+            len => len as usize,
+        };
+        if len > MAX_FRAME {
+            bail!("OBS sent an oversized frame ({} bytes)", len);
+        }
+        // Servers don't mask, but skip a mask if one is there anyway
+        let mask = if header[1] & 0x80 != 0 {
+            let mut mask = [0u8; 4];
+            self.stream.read_exact(&mut mask).await?;
+            Some(mask)
+        } else {
+            None
+        };
+        let mut payload = vec![0u8; len];
+        self.stream.read_exact(&mut payload).await?;
+        if let Some(mask) = mask {
+            for (i, b) in payload.iter_mut().enumerate() {
+                *b ^= mask[i % 4];
+            }
+        }
+        Ok((opcode, payload))
+    }
+}
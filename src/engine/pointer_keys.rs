@@ -0,0 +1,100 @@
+use crate::config::{ModeSwitch, PointerKeysConfig};
+use crate::engine::mapper::parse_key_name;
+use evdev::{EventType, InputEvent, KeyCode, RelativeAxisCode};
+use std::time::{Duration, SystemTime};
+
+/// Converts sustained pointer movement into key presses while active.
+pub struct PointerKeys {
+    active: bool,
+    threshold: i32,
+    reset_after: Duration,
+    up: KeyCode,
+    down: KeyCode,
+    left: KeyCode,
+    right: KeyCode,
+    /// Movement accumulated since the last key press, per axis
+    acc: (i32, i32),
+    last_motion: Option<SystemTime>,
+}
+
+impl PointerKeys {
+    pub fn from_config(config: &PointerKeysConfig) -> Self {
+        let key = |name: &str, fallback: KeyCode| {
+            parse_key_name(name).unwrap_or_else(|| {
+                log::warn!("Unknown pointer_keys key: {}, using {:?}", name, fallback);
+                fallback
+            })
+        };
+        Self {
+            active: config.enabled,
+            threshold: config.threshold.max(1) as i32,
+            reset_after: Duration::from_millis(config.reset_ms),
+            up: key(&config.up, KeyCode::KEY_UP),
+            down: key(&config.down, KeyCode::KEY_DOWN),
+            left: key(&config.left, KeyCode::KEY_LEFT),
+            right: key(&config.right, KeyCode::KEY_RIGHT),
+            acc: (0, 0),
+            last_motion: None,
+        }
+    }
+
+    pub fn switch(&mut self, switch: ModeSwitch) {
+        self.active = match switch {
+            ModeSwitch::Toggle => !self.active,
+            ModeSwitch::On => true,
+            ModeSwitch::Off => false,
+        };
+        self.acc = (0, 0);
+        log::info!(
+            "Pointer-to-keys mode {}",
+            if self.active { "on" } else { "off" }
+        );
+    }
+
+    /// Consume a REL_X/REL_Y event and return the key presses it triggers.
+    /// Returns None for events this mode doesn't handle (wheel, inactive).
+    pub fn process(&mut self, event: &InputEvent) -> Option<Vec<InputEvent>> {
+        if !self.active {
+            return None;
+        }
+        let axis = RelativeAxisCode(event.code());
+        if axis != RelativeAxisCode::REL_X && axis != RelativeAxisCode::REL_Y {
+            return None;
+        }
+
+        // Only sustained movement counts: drop leftovers after a pause
+        let now = event.timestamp();
+        if let Some(last) = self.last_motion
+            && now.duration_since(last).unwrap_or_default() > self.reset_after
+        {
+            self.acc = (0, 0);
+        }
+        self.last_motion = Some(now);
+
+        let (acc, other, negative, positive) = if axis == RelativeAxisCode::REL_X {
+            (&mut self.acc.0, &mut self.acc.1, self.left, self.right)
+        } else {
+            (&mut self.acc.1, &mut self.acc.0, self.up, self.down)
+        };
+        *acc += event.value();
+
+        let mut events = Vec::new();
+        while acc.abs() >= self.threshold {
+            let key = if *acc < 0 { negative } else { positive };
+            *acc -= self.threshold * acc.signum();
+            // Drift on the other axis shouldn't add up to a stray press
+            *other = 0;
+            events.extend(click(key));
+        }
+        Some(events)
+    }
+}
+
+/// Press + SYN + release; the source's own SYN_REPORT closes the release frame
+fn click(key: KeyCode) -> [InputEvent; 3] {
+    [
+        InputEvent::new(EventType::KEY.0, key.code(), 1),
+        InputEvent::new(EventType::SYNCHRONIZATION.0, 0, 0),
+        InputEvent::new(EventType::KEY.0, key.code(), 0),
+    ]
+}
@@ -0,0 +1,66 @@
+use anyhow::{bail, Context, Result};
+use std::io;
+
+/// Put the calling thread under SCHED_FIFO at `priority` (clamped to 1-99).
+/// Without CAP_SYS_NICE the kernel only allows up to RLIMIT_RTPRIO, so a
+/// refused priority is retried at that limit. Returns the priority in use.
+pub fn set_current_thread(priority: u8) -> Result<u8> {
+    let max = unsafe { libc::sched_get_priority_max(libc::SCHED_FIFO) }.max(1);
+    let mut priority = i32::from(priority).clamp(1, max);
+    let mut ret = set_fifo(priority);
+    if ret == libc::EPERM {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        let limit = if unsafe { libc::getrlimit(libc::RLIMIT_RTPRIO, &mut limit) } == 0 {
+            limit.rlim_cur.min(max as libc::rlim_t) as i32
+        } else {
+            0
+        };
+        if limit > 0 && limit < priority {
+            priority = limit;
+            ret = set_fifo(priority);
+        }
+    }
+    if ret != 0 {
+        let error = io::Error::from_raw_os_error(ret);
+        return Err(error).with_context(|| {
+            if ret == libc::EPERM {
+                format!(
+                    "Not allowed to use SCHED_FIFO priority {} (needs root or CAP_SYS_NICE)",
+                    priority
+                )
+            } else {
+                format!("Failed to set SCHED_FIFO priority {}", priority)
+            }
+        });
+    }
+    Ok(priority as u8)
+}
+
+/// 0 or the error number
+fn set_fifo(priority: i32) -> i32 {
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) }
+}
+
+/// Restrict the calling thread to the given CPUs (numbered from 0)
+pub fn pin_current_thread(cpus: &[usize]) -> Result<()> {
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            bail!("CPU {} in cpu_affinity doesn't exist", cpu);
+        }
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    // pid 0 is the calling thread
+    let ret = unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) };
+    if ret != 0 {
+        return Err(io::Error::last_os_error())
+            .with_context(|| format!("Failed to pin the engine to CPU(s) {:?}", cpus));
+    }
+    Ok(())
+}
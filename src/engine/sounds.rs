@@ -0,0 +1,110 @@
+use crate::config::{Sound, SoundsConfig};
+use crate::engine::notify;
+use anyhow::{bail, Context, Result};
+use evdev::{EventType, InputEvent, SoundCode};
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+
+/// Tones the PC speaker can be asked for, in Hz
+pub const TONE_RANGE: std::ops::RangeInclusive<u32> = 20..=20_000;
+
+/// Play `sound` without waiting for it. Failures are only logged: a missing
+/// beep mustn't get in the way of the mapping.
+pub fn play(sound: &Sound, player: &str) {
+    let sound = sound.clone();
+    let player = player.to_string();
+    thread::spawn(move || {
+        let result = match sound {
+            Sound::Beep { tone, ms } => beep(tone, Duration::from_millis(ms)),
+            Sound::File { ref file } => play_file(&player, file),
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to play sound: {:#}", e);
+        }
+    });
+}
+
+/// Sound a tone on the PC speaker (the pcspkr input device)
+fn beep(tone: u32, length: Duration) -> Result<()> {
+    let Some((path, mut speaker)) = evdev::enumerate().find(|(_, device)| {
+        device
+            .supported_sounds()
+            .is_some_and(|sounds| sounds.contains(SoundCode::SND_TONE))
+    }) else {
+        bail!("No PC speaker found (is the pcspkr module loaded?)");
+    };
+    let tone_event = |hz: u32| {
+        [InputEvent::new(
+            EventType::SOUND.0,
+            SoundCode::SND_TONE.0,
+            hz as i32,
+        )]
+    };
+    speaker
+        .send_events(&tone_event(tone))
+        .with_context(|| format!("Failed to beep on {}", path.display()))?;
+    thread::sleep(length);
+    speaker
+        .send_events(&tone_event(0))
+        .with_context(|| format!("Failed to stop the beep on {}", path.display()))?;
+    Ok(())
+}
+
+/// Play a sound file with `player` in the desktop session, where the sound
+/// server is
+fn play_file(player: &str, file: &str) -> Result<()> {
+    let mut command = notify::session_command(player);
+    command
+        .arg(file)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run {}", player))?;
+    if !output.status.success() {
+        bail!(
+            "{} {} failed: {}",
+            player,
+            file,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// The `[sounds]` of the config, played as the engine's state changes
+pub struct Sounds {
+    config: SoundsConfig,
+    /// Toggle macros running at the last call of `toggles_running`
+    toggles: Vec<String>,
+}
+
+impl Sounds {
+    pub fn new(config: SoundsConfig) -> Self {
+        Self {
+            config,
+            toggles: Vec::new(),
+        }
+    }
+
+    /// Note the toggle macros now running, sounding `macro_start` if one
+    /// started and `macro_stop` if one stopped since the last call
+    pub fn toggles_running(&mut self, toggles: Vec<String>) {
+        let started = toggles.iter().any(|name| !self.toggles.contains(name));
+        let stopped = self.toggles.iter().any(|name| !toggles.contains(name));
+        if started && let Some(ref sound) = self.config.macro_start {
+            play(sound, &self.config.player);
+        } else if stopped && let Some(ref sound) = self.config.macro_stop {
+            play(sound, &self.config.player);
+        }
+        self.toggles = toggles;
+    }
+
+    pub fn profile_switched(&self) {
+        if let Some(ref sound) = self.config.profile_switch {
+            play(sound, &self.config.player);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::config::{BindingOutput, MacroAction, MacroDef, Profile, WarpConfig};
+use crate::device::abs_pointer::AbsPointer;
+use anyhow::{anyhow, Result};
+use std::sync::{Arc, Mutex};
+
+/// Moves the cursor to absolute screen positions for Warp outputs and steps
+#[derive(Clone)]
+pub struct Warp {
+    pointer: Arc<Mutex<AbsPointer>>,
+    config: WarpConfig,
+}
+
+impl Warp {
+    pub fn new(config: &WarpConfig) -> Result<Self> {
+        Ok(Self {
+            pointer: Arc::new(Mutex::new(AbsPointer::new(config.width, config.height)?)),
+            config: config.clone(),
+        })
+    }
+
+    pub fn config(&self) -> &WarpConfig {
+        &self.config
+    }
+
+    /// Move the cursor to "x,y" or a named position
+    pub fn warp_to(&self, target: &str) -> Result<()> {
+        let (x, y) = self.config.resolve(target).map_err(|e| anyhow!(e))?;
+        self.pointer
+            .lock()
+            .map_err(|e| anyhow!("Failed to lock absolute pointer: {}", e))?
+            .move_to(x as i32, y as i32)
+    }
+}
+
+/// Whether a binding or macro of the profile warps the cursor, so the
+/// absolute pointer device is only created when something needs it
+pub fn profile_uses_warp(profile: &Profile) -> bool {
+    profile
+        .enabled_bindings()
+        .any(|b| matches!(b.output, BindingOutput::Warp { .. }))
+        || profile
+            .macros
+            .iter()
+            .flat_map(|m| &m.actions)
+            .any(|a| matches!(a, MacroAction::Warp(_)))
+}
+
+/// Why each Warp step of a macro that doesn't resolve won't work
+pub fn bad_warp_targets(def: &MacroDef, config: &WarpConfig) -> Vec<String> {
+    def.actions
+        .iter()
+        .filter_map(|action| match action {
+            MacroAction::Warp(target) => config.resolve(target).err(),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,254 @@
+use crate::config::{BindingOutput, DeviceConfig, Profile};
+use crate::engine::mapper::parse_key_name;
+use anyhow::{Context, Result};
+use evdev::KeyCode;
+use std::path::Path;
+
+/// Supported foreign config formats for export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// keyd (/etc/keyd/*.conf)
+    Keyd,
+    /// evremap (TOML)
+    Evremap,
+}
+
+/// A profile translated into another tool's config syntax
+#[derive(Debug, Default)]
+pub struct ExportResult {
+    pub text: String,
+    /// Number of bindings written
+    pub exported: usize,
+    /// Bindings that could not be translated, with the reason
+    pub warnings: Vec<String>,
+}
+
+/// Translate the simple key remaps of a profile. Macro bindings have no
+/// equivalent in either format and are reported as warnings.
+pub fn export_profile(
+    profile: &Profile,
+    device: &DeviceConfig,
+    format: ExportFormat,
+) -> ExportResult {
+    let mut result = ExportResult::default();
+    let mut remaps = Vec::new();
+
+    for binding in profile.enabled_bindings() {
+        if binding.when.is_some() {
+            result.warnings.push(format!(
+                "{}: mode-conditioned bindings can't be exported",
+                binding.input_label()
+            ));
+            continue;
+        }
+        if binding.input_keys().len() > 1 {
+            result.warnings.push(format!(
+                "{}: chord bindings can't be exported",
+                binding.input_label()
+            ));
+            continue;
+        }
+        if !binding.modifiers.is_empty() {
+            // Both tools see only the mouse, so they can't check the keyboard
+            result.warnings.push(format!(
+                "{}: modifier-conditioned bindings can't be exported",
+                binding.input_label()
+            ));
+            continue;
+        }
+        let target = match &binding.output {
+            BindingOutput::Key { key } => key,
+            BindingOutput::Macro { macro_name, .. } => {
+                result.warnings.push(format!(
+                    "{}: macro '{}' can't be exported, only key remaps are supported",
+                    binding.input, macro_name
+                ));
+                continue;
+            }
+            BindingOutput::ToggleKey { toggle_key } => {
+                result.warnings.push(format!(
+                    "{}: toggle key '{}' can't be exported",
+                    binding.input, toggle_key
+                ));
+                continue;
+            }
+            BindingOutput::DpiStage { .. }
+            | BindingOutput::PointerKeys { .. }
+            | BindingOutput::Autoscroll { .. }
+            | BindingOutput::SwapButtons { .. }
+            | BindingOutput::PauseMacros { .. }
+            | BindingOutput::SetMode { .. } => {
+                result.warnings.push(format!(
+                    "{}: mode switches can't be exported",
+                    binding.input
+                ));
+                continue;
+            }
+            BindingOutput::Scroll { .. } => {
+                result.warnings.push(format!(
+                    "{}: scroll bindings can't be exported",
+                    binding.input
+                ));
+                continue;
+            }
+            BindingOutput::Warp { .. } => {
+                result
+                    .warnings
+                    .push(format!("{}: cursor warps can't be exported", binding.input));
+                continue;
+            }
+            BindingOutput::Sticky { .. } => {
+                result.warnings.push(format!(
+                    "{}: sticky modifiers can't be exported",
+                    binding.input
+                ));
+                continue;
+            }
+            BindingOutput::ToggleProfile { .. } => {
+                result.warnings.push(format!(
+                    "{}: profile switches can't be exported",
+                    binding.input
+                ));
+                continue;
+            }
+        };
+        match (parse_key_name(&binding.input), parse_key_name(target)) {
+            (Some(input), Some(output)) => remaps.push((binding.input.as_str(), input, output)),
+            _ => result.warnings.push(format!(
+                "{}: unknown key name in '{} -> {}'",
+                binding.input, binding.input, target
+            )),
+        }
+    }
+
+    match format {
+        ExportFormat::Keyd => write_keyd(profile, device, &remaps, &mut result),
+        ExportFormat::Evremap => write_evremap(profile, device, &remaps, &mut result),
+    }
+    result
+}
+
+/// Write an export to a file, or to stdout when no path is given
+pub fn write_output(result: &ExportResult, path: Option<&Path>) -> Result<()> {
+    match path {
+        Some(path) => std::fs::write(path, &result.text)
+            .with_context(|| format!("Failed to write {}", path.display())),
+        None => {
+            print!("{}", result.text);
+            Ok(())
+        }
+    }
+}
+
+// === keyd ===
+
+fn write_keyd(
+    profile: &Profile,
+    device: &DeviceConfig,
+    remaps: &[(&str, KeyCode, KeyCode)],
+    result: &mut ExportResult,
+) {
+    let mut lines = Vec::new();
+    lines.push(format!(
+        "# Exported from mouse-mapper profile '{}'",
+        profile.name
+    ));
+    lines.push("[ids]".to_string());
+    match (device.vendor_id, device.product_id) {
+        // keyd only applies to mice when they are listed explicitly
+        (Some(vid), Some(pid)) => {
+            lines.push(format!("m:{:04x}:{:04x}", vid, pid));
+        }
+        _ => {
+            lines.push("# Replace '*' with your mouse's m:<vendor>:<product> id".to_string());
+            lines.push("# (see `keyd monitor`), keyd skips mice matched by '*'".to_string());
+            lines.push("*".to_string());
+        }
+    }
+    lines.push(String::new());
+    lines.push("[main]".to_string());
+
+    for (name, input, output) in remaps {
+        match (keyd_key_name(*input), keyd_key_name(*output)) {
+            (Some(input), Some(output)) => {
+                lines.push(format!("{} = {}", input, output));
+                result.exported += 1;
+            }
+            _ => result
+                .warnings
+                .push(format!("{}: key has no keyd name", name)),
+        }
+    }
+
+    lines.push(String::new());
+    result.text = lines.join("\n");
+}
+
+/// keyd names are the lowercase evdev names without the KEY_ prefix, except
+/// for control/meta and the mouse buttons
+fn keyd_key_name(key: KeyCode) -> Option<String> {
+    let name = match key {
+        KeyCode::BTN_LEFT => "leftmouse",
+        KeyCode::BTN_MIDDLE => "middlemouse",
+        KeyCode::BTN_RIGHT => "rightmouse",
+        KeyCode::BTN_SIDE => "mouse1",
+        KeyCode::BTN_EXTRA => "mouse2",
+        KeyCode::KEY_LEFTCTRL => "leftcontrol",
+        KeyCode::KEY_RIGHTCTRL => "rightcontrol",
+        KeyCode::KEY_LEFTMETA => "leftmeta",
+        KeyCode::KEY_RIGHTMETA => "rightmeta",
+        _ => {
+            let debug = format!("{:?}", key);
+            return debug.strip_prefix("KEY_").map(|name| name.to_lowercase());
+        }
+    };
+    Some(name.to_string())
+}
+
+// === evremap ===
+
+fn write_evremap(
+    profile: &Profile,
+    device: &DeviceConfig,
+    remaps: &[(&str, KeyCode, KeyCode)],
+    result: &mut ExportResult,
+) {
+    let mut lines = Vec::new();
+    lines.push(format!(
+        "# Exported from mouse-mapper profile '{}'",
+        profile.name
+    ));
+    match &device.name {
+        Some(name) => {
+            lines.push(format!("device_name = {:?}", name));
+        }
+        None => {
+            lines.push("# Set this to the name shown by `evremap list-devices`".to_string());
+            lines.push("device_name = \"\"".to_string());
+        }
+    }
+
+    for (name, input, output) in remaps {
+        match (evdev_key_name(*input), evdev_key_name(*output)) {
+            (Some(input), Some(output)) => {
+                lines.push(String::new());
+                lines.push("[[remap]]".to_string());
+                lines.push(format!("input = [\"{}\"]", input));
+                lines.push(format!("output = [\"{}\"]", output));
+                result.exported += 1;
+            }
+            _ => result
+                .warnings
+                .push(format!("{}: key has no evdev name", name)),
+        }
+    }
+
+    lines.push(String::new());
+    result.text = lines.join("\n");
+}
+
+/// evremap uses the kernel names (KEY_A, BTN_SIDE)
+fn evdev_key_name(key: KeyCode) -> Option<String> {
+    let name = format!("{:?}", key);
+    (name.starts_with("KEY_") || name.starts_with("BTN_")).then_some(name)
+}
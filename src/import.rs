@@ -0,0 +1,534 @@
+use crate::config::{
+    Binding, BindingOutput, ClickStep, KeyModifier, MacroAction, MacroDef, MacroType, Profile,
+};
+use crate::engine::mapper::parse_key_name;
+use anyhow::{bail, Context, Result};
+use evdev::KeyCode;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Supported foreign config formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// input-remapper preset (JSON)
+    InputRemapper,
+    /// ~/.xbindkeysrc
+    Xbindkeys,
+}
+
+/// Bindings (and the macros they need) converted from a foreign config
+#[derive(Debug, Default)]
+pub struct ImportResult {
+    pub bindings: Vec<Binding>,
+    pub macros: Vec<MacroDef>,
+    /// Entries that could not be converted, with the reason
+    pub warnings: Vec<String>,
+}
+
+/// Read and convert a file, guessing the format from its content if not given
+pub fn import_file(path: &Path, format: Option<ImportFormat>) -> Result<ImportResult> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let format = format.unwrap_or_else(|| {
+        let trimmed = content.trim_start();
+        if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            ImportFormat::InputRemapper
+        } else {
+            ImportFormat::Xbindkeys
+        }
+    });
+
+    match format {
+        ImportFormat::InputRemapper => parse_input_remapper(&content),
+        ImportFormat::Xbindkeys => Ok(parse_xbindkeys(&content)),
+    }
+}
+
+/// Merge imported bindings and macros into a profile. Bindings for an input
+/// that already exists replace the old one; macros are renamed on collision,
+/// unless the profile already has the same macro (from an earlier import).
+pub fn apply(profile: &mut Profile, result: &ImportResult) {
+    for macro_def in &result.macros {
+        let mut m = macro_def.clone();
+        let base = m.name.clone();
+        let mut n = 2;
+        let reused = loop {
+            match profile
+                .macros
+                .iter()
+                .find(|existing| existing.name == m.name)
+            {
+                Some(existing) if *existing == m => break true,
+                Some(_) => {
+                    m.name = format!("{}_{}", base, n);
+                    n += 1;
+                }
+                None => break false,
+            }
+        };
+        // Point the imported binding at the (possibly renamed) macro
+        let bindings = result.bindings.iter().filter(
+            |b| matches!(&b.output, BindingOutput::Macro { macro_name, .. } if *macro_name == base),
+        );
+        for b in bindings {
+            upsert_binding(
+                profile,
+                Binding {
+                    input: b.input.clone(),
+                    output: BindingOutput::Macro {
+                        macro_name: m.name.clone(),
+                        args: BTreeMap::new(),
+                    },
+                    modifiers: b.modifiers.clone(),
+                    when: b.when.clone(),
+                    cooldown_ms: b.cooldown_ms,
+                    description: b.description.clone(),
+                    tags: b.tags.clone(),
+                },
+            );
+        }
+        if !reused {
+            profile.macros.push(m);
+        }
+    }
+
+    for b in &result.bindings {
+        if matches!(b.output, BindingOutput::Key { .. }) {
+            upsert_binding(profile, b.clone());
+        }
+    }
+}
+
+fn upsert_binding(profile: &mut Profile, binding: Binding) {
+    if let Some(existing) = profile.bindings.iter_mut().find(|b| {
+        b.input == binding.input && b.modifiers == binding.modifiers && b.when == binding.when
+    }) {
+        *existing = binding;
+    } else {
+        profile.bindings.push(binding);
+    }
+}
+
+// === input-remapper ===
+
+/// Parse an input-remapper preset. Handles both the current format (a list of
+/// mappings with `input_combination`) and the legacy `{"mapping": {...}}` one.
+fn parse_input_remapper(content: &str) -> Result<ImportResult> {
+    let json: Value = serde_json::from_str(content).context("Invalid input-remapper JSON")?;
+    let mut result = ImportResult::default();
+
+    if let Some(mappings) = json.as_array() {
+        for mapping in mappings {
+            convert_remapper_mapping(mapping, &mut result);
+        }
+    } else if let Some(mappings) = json.get("mapping").and_then(|m| m.as_object()) {
+        for (input, output) in mappings {
+            convert_legacy_remapper_mapping(input, output, &mut result);
+        }
+    } else {
+        bail!("Not an input-remapper preset (expected a list of mappings)");
+    }
+
+    Ok(result)
+}
+
+fn convert_remapper_mapping(mapping: &Value, result: &mut ImportResult) {
+    let Some(combination) = mapping.get("input_combination").and_then(|c| c.as_array()) else {
+        result
+            .warnings
+            .push("Skipped mapping without input_combination".to_string());
+        return;
+    };
+
+    if combination.len() != 1 {
+        result.warnings.push(format!(
+            "Skipped {}-key combination (only single buttons are supported)",
+            combination.len()
+        ));
+        return;
+    }
+
+    let event_type = combination[0].get("type").and_then(|t| t.as_u64());
+    let code = combination[0].get("code").and_then(|c| c.as_u64());
+    let (Some(1), Some(code)) = (event_type, code) else {
+        result
+            .warnings
+            .push("Skipped non-button input (only EV_KEY inputs are supported)".to_string());
+        return;
+    };
+    let input = code_to_name(code as u16);
+
+    let output = if let Some(symbol) = mapping.get("output_symbol").and_then(|s| s.as_str()) {
+        symbol.to_string()
+    } else if let (Some(1), Some(out_code)) = (
+        mapping.get("output_type").and_then(|t| t.as_u64()),
+        mapping.get("output_code").and_then(|c| c.as_u64()),
+    ) {
+        code_to_name(out_code as u16)
+    } else {
+        result
+            .warnings
+            .push(format!("{}: skipped mapping without a key output", input));
+        return;
+    };
+
+    convert_remapper_output(&input, &output, result);
+}
+
+fn convert_legacy_remapper_mapping(input: &str, output: &Value, result: &mut ImportResult) {
+    // Key is "type,code,value", e.g. "1,275,1"
+    let parts: Vec<&str> = input.split(',').collect();
+    if parts.len() != 3 || parts[0] != "1" {
+        result
+            .warnings
+            .push(format!("Skipped unsupported input '{}'", input));
+        return;
+    }
+    let Ok(code) = parts[1].parse::<u16>() else {
+        result
+            .warnings
+            .push(format!("Skipped invalid input '{}'", input));
+        return;
+    };
+    let input = code_to_name(code);
+
+    // Value is either "symbol" or ["symbol", "target"]
+    let symbol = output
+        .as_str()
+        .or_else(|| output.get(0).and_then(|s| s.as_str()));
+    match symbol {
+        Some(symbol) => convert_remapper_output(&input, symbol, result),
+        None => result
+            .warnings
+            .push(format!("{}: skipped mapping without a key output", input)),
+    }
+}
+
+fn convert_remapper_output(input: &str, symbol: &str, result: &mut ImportResult) {
+    if symbol.contains('(') {
+        result.warnings.push(format!(
+            "{}: input-remapper macro '{}' can't be converted, recreate it as a macro",
+            input, symbol
+        ));
+        return;
+    }
+
+    // "Control_L + c" style combos become a sequence macro
+    let keys: Vec<&str> = symbol.split('+').map(str::trim).collect();
+    match convert_key_list(&keys) {
+        Some(keys) => push_key_output(input, &[], keys, result),
+        None => result
+            .warnings
+            .push(format!("{}: unknown output key '{}'", input, symbol)),
+    }
+}
+
+// === xbindkeys ===
+
+/// Parse an xbindkeysrc: a quoted command line followed by its trigger line.
+/// Only mouse-button triggers whose command is `xdotool key/click` or
+/// `xte 'key ...'` can be converted.
+fn parse_xbindkeys(content: &str) -> ImportResult {
+    let mut result = ImportResult::default();
+    let mut pending_command: Option<String> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(command) = line.strip_prefix('"') {
+            pending_command = Some(command.trim_end_matches('"').to_string());
+            continue;
+        }
+
+        let Some(command) = pending_command.take() else {
+            continue;
+        };
+        convert_xbindkeys_entry(&command, line, &mut result);
+    }
+
+    result
+}
+
+fn convert_xbindkeys_entry(command: &str, trigger: &str, result: &mut ImportResult) {
+    // Trigger looks like "b:8", "m:0x0 + b:9" or "Control + b:2"
+    let parts: Vec<&str> = trigger.split('+').map(str::trim).collect();
+    let Some(button) = parts
+        .iter()
+        .find_map(|p| p.strip_prefix("b:"))
+        .and_then(|b| b.parse::<u8>().ok())
+    else {
+        result.warnings.push(format!(
+            "Skipped keyboard trigger '{}' (only mouse buttons are supported)",
+            trigger
+        ));
+        return;
+    };
+
+    let mut modifiers = Vec::new();
+    for part in &parts {
+        if part.starts_with("b:") || part.eq_ignore_ascii_case("release") {
+            continue;
+        }
+        if let Some(mask) = part.strip_prefix("m:") {
+            let Some(masked) = x_mask_modifiers(mask) else {
+                result.warnings.push(format!(
+                    "Skipped '{}': modifier mask '{}' is not supported",
+                    trigger, part
+                ));
+                return;
+            };
+            for m in masked {
+                if !modifiers.contains(&m) {
+                    modifiers.push(m);
+                }
+            }
+            continue;
+        }
+        match x_modifier(part) {
+            Some(m) if !modifiers.contains(&m) => modifiers.push(m),
+            Some(_) => {}
+            None => {
+                result.warnings.push(format!(
+                    "Skipped '{}': modifier '{}' is not supported",
+                    trigger, part
+                ));
+                return;
+            }
+        }
+    }
+
+    let Some(input) = x_button_to_evdev(button) else {
+        result.warnings.push(format!(
+            "Skipped button {} (wheel or unknown button)",
+            button
+        ));
+        return;
+    };
+
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let keys = match words.as_slice() {
+        ["xdotool", "key", combo, ..] => {
+            let keys: Vec<&str> = combo.split('+').collect();
+            convert_key_list(&keys)
+        }
+        ["xdotool", "click", n, ..] => n
+            .parse::<u8>()
+            .ok()
+            .and_then(x_button_to_evdev)
+            .map(|b| vec![b.to_string()]),
+        ["xte", rest @ ..] => {
+            let rest = rest.join(" ");
+            let rest = rest.trim_matches(|c| c == '\'' || c == '"');
+            rest.strip_prefix("key ")
+                .and_then(|k| convert_key_list(&[k.trim()]))
+        }
+        _ => None,
+    };
+
+    match keys {
+        Some(keys) => push_key_output(input, &modifiers, keys, result),
+        None => result.warnings.push(format!(
+            "{}: command '{}' can't be converted (only xdotool key/click and xte key)",
+            input, command
+        )),
+    }
+}
+
+/// xbindkeys modifier name -> KeyModifier (Mod1 is Alt and Mod4 Super on
+/// practically every keymap)
+fn x_modifier(name: &str) -> Option<KeyModifier> {
+    match name.to_lowercase().as_str() {
+        "control" | "ctrl" => Some(KeyModifier::Ctrl),
+        "shift" => Some(KeyModifier::Shift),
+        "alt" | "mod1" => Some(KeyModifier::Alt),
+        "mod4" | "super" => Some(KeyModifier::Meta),
+        _ => None,
+    }
+}
+
+/// xbindkeys modifier mask ("m:0x4") -> KeyModifiers, or None if it has a
+/// bit that can't be mapped. Lock (0x2) and Mod2 (0x10, NumLock) are ignored
+/// like xbindkeys itself does.
+fn x_mask_modifiers(mask: &str) -> Option<Vec<KeyModifier>> {
+    const IGNORED: u32 = 0x2 | 0x10;
+    const MODIFIERS: [(u32, KeyModifier); 4] = [
+        (0x1, KeyModifier::Shift),
+        (0x4, KeyModifier::Ctrl),
+        (0x8, KeyModifier::Alt),
+        (0x40, KeyModifier::Meta),
+    ];
+    let mask = u32::from_str_radix(mask.strip_prefix("0x")?, 16).ok()?;
+    let known = MODIFIERS
+        .iter()
+        .fold(IGNORED, |known, (bit, _)| known | bit);
+    if mask & !known != 0 {
+        return None;
+    }
+    Some(
+        MODIFIERS
+            .iter()
+            .filter(|(bit, _)| mask & bit != 0)
+            .map(|(_, m)| *m)
+            .collect(),
+    )
+}
+
+/// X11 button number -> evdev button name (as mapped by xf86-input-evdev/libinput)
+fn x_button_to_evdev(button: u8) -> Option<&'static str> {
+    match button {
+        1 => Some("BTN_LEFT"),
+        2 => Some("BTN_MIDDLE"),
+        3 => Some("BTN_RIGHT"),
+        8 => Some("BTN_SIDE"),
+        9 => Some("BTN_EXTRA"),
+        10 => Some("BTN_FORWARD"),
+        11 => Some("BTN_BACK"),
+        12 => Some("BTN_TASK"),
+        _ => None,
+    }
+}
+
+// === shared helpers ===
+
+/// A single key becomes a remap; a combo becomes a Sequence macro that holds
+/// the modifiers around a click of the last key.
+fn push_key_output(
+    input: &str,
+    held: &[KeyModifier],
+    keys: Vec<String>,
+    result: &mut ImportResult,
+) {
+    if keys.len() == 1 {
+        result.bindings.push(Binding {
+            input: input.to_string(),
+            output: BindingOutput::Key {
+                key: keys[0].clone(),
+            },
+            modifiers: held.to_vec(),
+            when: None,
+            cooldown_ms: 0,
+            description: None,
+            tags: Vec::new(),
+        });
+        return;
+    }
+
+    let (last, modifiers) = keys.split_last().expect("combo has at least two keys");
+    let mut actions: Vec<MacroAction> = modifiers
+        .iter()
+        .map(|k| MacroAction::Press(k.clone()))
+        .collect();
+    actions.push(MacroAction::Click(ClickStep::new(last.clone())));
+    actions.extend(
+        modifiers
+            .iter()
+            .rev()
+            .map(|k| MacroAction::Release(k.clone())),
+    );
+
+    // e.g. KEY_LEFTCTRL + KEY_W -> "import_leftctrl_w"
+    let name = format!(
+        "import_{}",
+        keys.iter()
+            .map(|k| k.trim_start_matches("KEY_").to_lowercase())
+            .collect::<Vec<_>>()
+            .join("_")
+    );
+
+    // The name comes from the keys, so another input with the same combo
+    // shares the macro
+    if !result.macros.iter().any(|m| m.name == name) {
+        result.macros.push(MacroDef {
+            name: name.clone(),
+            macro_type: MacroType::Sequence,
+            actions,
+            ..MacroDef::default()
+        });
+    }
+    result.bindings.push(Binding {
+        input: input.to_string(),
+        output: BindingOutput::Macro {
+            macro_name: name,
+            args: BTreeMap::new(),
+        },
+        modifiers: held.to_vec(),
+        when: None,
+        cooldown_ms: 0,
+        description: None,
+        tags: Vec::new(),
+    });
+}
+
+/// Convert every key of a combo, or None if any of them is unknown
+fn convert_key_list(keys: &[&str]) -> Option<Vec<String>> {
+    keys.iter().map(|k| keysym_to_evdev(k)).collect()
+}
+
+/// Name for an evdev key code, falling back to the raw number for codes
+/// `parse_key_name` doesn't know
+fn code_to_name(code: u16) -> String {
+    let name = format!("{:?}", KeyCode::new(code));
+    if parse_key_name(&name).is_some() {
+        name
+    } else {
+        code.to_string()
+    }
+}
+
+/// Convert an X keysym / xdotool key name ("ctrl", "Control_L", "a", "Return",
+/// "KEY_A", "BTN_LEFT") to an evdev key name understood by the mapper
+fn keysym_to_evdev(name: &str) -> Option<String> {
+    let lower = name.to_lowercase();
+    let mapped = match lower.as_str() {
+        "ctrl" | "control" | "control_l" => "KEY_LEFTCTRL",
+        "control_r" => "KEY_RIGHTCTRL",
+        "shift" | "shift_l" => "KEY_LEFTSHIFT",
+        "shift_r" => "KEY_RIGHTSHIFT",
+        "alt" | "alt_l" | "meta_l" => "KEY_LEFTALT",
+        "alt_r" | "iso_level3_shift" => "KEY_RIGHTALT",
+        "return" | "enter" => "KEY_ENTER",
+        "escape" | "esc" => "KEY_ESC",
+        "space" => "KEY_SPACE",
+        "tab" => "KEY_TAB",
+        "backspace" => "KEY_BACKSPACE",
+        "delete" => "KEY_DELETE",
+        "insert" => "KEY_INSERT",
+        "home" => "KEY_HOME",
+        "end" => "KEY_END",
+        "prior" | "page_up" => "KEY_PAGEUP",
+        "next" | "page_down" => "KEY_PAGEDOWN",
+        "up" => "KEY_UP",
+        "down" => "KEY_DOWN",
+        "left" => "KEY_LEFT",
+        "right" => "KEY_RIGHT",
+        "minus" => "KEY_MINUS",
+        "equal" => "KEY_EQUAL",
+        "comma" => "KEY_COMMA",
+        "period" => "KEY_DOT",
+        "slash" => "KEY_SLASH",
+        "backslash" => "KEY_BACKSLASH",
+        "semicolon" => "KEY_SEMICOLON",
+        "apostrophe" => "KEY_APOSTROPHE",
+        "grave" => "KEY_GRAVE",
+        "bracketleft" => "KEY_LEFTBRACE",
+        "bracketright" => "KEY_RIGHTBRACE",
+        "caps_lock" => "KEY_CAPSLOCK",
+        _ => "",
+    };
+
+    let candidate = if !mapped.is_empty() {
+        mapped.to_string()
+    } else if lower.starts_with("key_") || lower.starts_with("btn_") {
+        name.to_uppercase()
+    } else {
+        // Single letters/digits and F-keys: "a" -> KEY_A, "F5" -> KEY_F5
+        format!("KEY_{}", name.to_uppercase())
+    };
+
+    parse_key_name(&candidate).map(|_| candidate)
+}
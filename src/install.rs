@@ -0,0 +1,476 @@
+use crate::config::Config;
+use crate::device::scanner;
+use crate::logging;
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Gives the input group the uinput device and the event nodes, so the
+/// engine can run without root
+const UDEV_RULE_PATH: &str = "/etc/udev/rules.d/70-mouse-mapper.rules";
+const UDEV_RULE: &str = "\
+# Installed by mouse-mapper install
+KERNEL==\"uinput\", SUBSYSTEM==\"misc\", GROUP=\"input\", MODE=\"0660\", OPTIONS+=\"static_node=uinput\"
+SUBSYSTEM==\"input\", KERNEL==\"event*\", GROUP=\"input\", MODE=\"0660\"
+";
+/// Loads uinput at boot, before anything needs it
+const MODULES_LOAD_PATH: &str = "/etc/modules-load.d/mouse-mapper.conf";
+const GROUP: &str = "input";
+const UNIT_NAME: &str = "mouse-mapper.service";
+/// User units here are available to every user
+const USER_UNIT_DIR: &str = "/etc/systemd/user";
+const SYSTEM_UNIT_DIR: &str = "/etc/systemd/system";
+
+pub struct InstallOptions {
+    /// Install a unit that runs as root at boot instead of a user unit
+    pub system: bool,
+    /// Only print the steps
+    pub dry_run: bool,
+    /// User to add to the input group (user unit only)
+    pub user: Option<String>,
+}
+
+pub struct UninstallOptions {
+    /// Also delete the config, profiles, logs and other state
+    pub purge: bool,
+    /// Only print the steps
+    pub dry_run: bool,
+}
+
+/// One change to the system
+enum Step {
+    Write {
+        path: PathBuf,
+        contents: String,
+    },
+    /// Delete a file, or a directory with everything in it
+    Remove {
+        path: PathBuf,
+    },
+    Run {
+        program: &'static str,
+        args: Vec<String>,
+        /// A failure only warns instead of stopping
+        may_fail: bool,
+    },
+}
+
+impl Step {
+    fn run(program: &'static str, args: &[&str]) -> Step {
+        Step::Run {
+            program,
+            args: args.iter().map(|a| a.to_string()).collect(),
+            may_fail: false,
+        }
+    }
+
+    fn try_run(program: &'static str, args: &[&str]) -> Step {
+        Step::Run {
+            program,
+            args: args.iter().map(|a| a.to_string()).collect(),
+            may_fail: true,
+        }
+    }
+
+    fn command_line(program: &str, args: &[String]) -> String {
+        std::iter::once(program)
+            .chain(args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Install the udev rule, the input group and a systemd unit that runs the
+/// engine on the config's active profile, or with `dry_run` print what
+/// that would do
+pub fn run(options: InstallOptions) -> Result<()> {
+    let is_root = unsafe { libc::geteuid() } == 0;
+    if !is_root && !options.dry_run {
+        bail!("install changes system files: run it with sudo, or pass --dry-run");
+    }
+    let exe = std::env::current_exe().context("Failed to find the mouse-mapper executable")?;
+    execute(&plan(&options, &exe)?, options.dry_run)?;
+
+    if exe.components().any(|c| c.as_os_str() == "target") {
+        println!(
+            "Note: the unit runs {}, a build directory; install the binary first \
+             (e.g. cargo install --path .) to keep it working after a rebuild or clean",
+            exe.display()
+        );
+    }
+    if options.dry_run {
+        return Ok(());
+    }
+    if options.system {
+        println!("Done: mouse-mapper now runs as root at boot (journalctl -u mouse-mapper)");
+    } else {
+        println!(
+            "Done. Log out and back in for the {} group, then run",
+            GROUP
+        );
+        println!("    systemctl --user enable --now mouse-mapper");
+        println!("as your user to start it at every login.");
+    }
+    Ok(())
+}
+
+fn plan(options: &InstallOptions, exe: &Path) -> Result<Vec<Step>> {
+    let mut steps = vec![
+        Step::Write {
+            path: UDEV_RULE_PATH.into(),
+            contents: UDEV_RULE.to_string(),
+        },
+        Step::Write {
+            path: MODULES_LOAD_PATH.into(),
+            contents: "uinput\n".to_string(),
+        },
+    ];
+
+    // udev needs the group to exist before the rule can use it
+    if !group_exists(GROUP) {
+        steps.push(Step::run("groupadd", &["--system", GROUP]));
+    }
+    if !options.system {
+        // Under sudo, the user who ran it
+        let user = options
+            .user
+            .clone()
+            .or_else(|| std::env::var("SUDO_USER").ok())
+            .filter(|u| !u.is_empty() && u != "root");
+        match user {
+            Some(user) if user_in_group(&user, GROUP) => {
+                println!("{} is already in the {} group", user, GROUP);
+            }
+            Some(user) => steps.push(Step::run("usermod", &["-aG", GROUP, &user])),
+            None => println!(
+                "No user to add to the {} group: pass --user NAME (or run through sudo)",
+                GROUP
+            ),
+        }
+    }
+
+    steps.extend([
+        Step::run("modprobe", &["uinput"]),
+        Step::run("udevadm", &["control", "--reload-rules"]),
+        Step::run(
+            "udevadm",
+            &[
+                "trigger",
+                "--subsystem-match=misc",
+                "--sysname-match=uinput",
+            ],
+        ),
+        Step::run("udevadm", &["trigger", "--subsystem-match=input"]),
+    ]);
+
+    if options.system {
+        // Root's engine reads the config this command would have used
+        let config = Config::config_path()?;
+        steps.push(Step::Write {
+            path: Path::new(SYSTEM_UNIT_DIR).join(UNIT_NAME),
+            contents: unit(
+                &format!("{} --config {} run", exe.display(), config.display()),
+                "multi-user.target",
+            ),
+        });
+        steps.push(Step::run("systemctl", &["daemon-reload"]));
+        steps.push(Step::run("systemctl", &["enable", "--now", UNIT_NAME]));
+    } else {
+        steps.push(Step::Write {
+            path: Path::new(USER_UNIT_DIR).join(UNIT_NAME),
+            contents: unit(&format!("{} run", exe.display()), "default.target"),
+        });
+    }
+    Ok(steps)
+}
+
+/// Undo `install`: disable and remove the units, remove the udev rule and
+/// the uinput entry (the input group stays, other software uses it too),
+/// with `purge` delete the config and state, then check nothing of
+/// mouse-mapper is left on the input devices
+pub fn uninstall(options: UninstallOptions) -> Result<()> {
+    let is_root = unsafe { libc::geteuid() } == 0;
+    if !is_root && !options.dry_run {
+        bail!("uninstall changes system files: run it with sudo, or pass --dry-run");
+    }
+    execute(&uninstall_plan(&options)?, options.dry_run)?;
+    if options.dry_run {
+        return Ok(());
+    }
+
+    let leftovers = leftovers()?;
+    for leftover in &leftovers {
+        eprintln!("  warning: {}", leftover);
+    }
+    if !leftovers.is_empty() {
+        bail!("{} leftover(s) found", leftovers.len());
+    }
+    println!("Done: no mouse-mapper device, grab or process is left");
+    Ok(())
+}
+
+fn uninstall_plan(options: &UninstallOptions) -> Result<Vec<Step>> {
+    let mut steps = Vec::new();
+    let system_unit = Path::new(SYSTEM_UNIT_DIR).join(UNIT_NAME);
+    if system_unit.exists() {
+        steps.push(Step::try_run("systemctl", &["disable", "--now", UNIT_NAME]));
+        steps.push(Step::Remove { path: system_unit });
+        steps.push(Step::run("systemctl", &["daemon-reload"]));
+    }
+    let user_unit = Path::new(USER_UNIT_DIR).join(UNIT_NAME);
+    if user_unit.exists() {
+        // Links from `systemctl --user enable` need the unit file to undo
+        if let Some(user) = std::env::var("SUDO_USER").ok().filter(|u| !u.is_empty()) {
+            let machine = format!("--machine={}@", user);
+            steps.push(Step::try_run(
+                "systemctl",
+                &["--user", &machine, "disable", "--now", UNIT_NAME],
+            ));
+        }
+        steps.push(Step::try_run(
+            "systemctl",
+            &["--global", "disable", UNIT_NAME],
+        ));
+        steps.push(Step::Remove { path: user_unit });
+    }
+    for path in [UDEV_RULE_PATH, MODULES_LOAD_PATH] {
+        if Path::new(path).exists() {
+            steps.push(Step::Remove { path: path.into() });
+        }
+    }
+    steps.push(Step::run("udevadm", &["control", "--reload-rules"]));
+
+    if options.purge {
+        let owner = purge_owner()?;
+        println!(
+            "Purging the config and state of user {}: {} and {}",
+            owner.user,
+            owner.config_path.display(),
+            owner.state_dir.display()
+        );
+        // The whole directory when it's the default one, which holds the
+        // profiles and exports too
+        let config_dir = owner
+            .config_path
+            .parent()
+            .filter(|dir| dir.file_name().is_some_and(|n| n == "mouse-mapper"));
+        let mut paths = vec![
+            config_dir.map_or(owner.config_path.clone(), Path::to_path_buf),
+            owner.state_dir,
+        ];
+        // Its own log file, if the config still loads
+        paths.extend(
+            Config::load_from(&owner.config_path)
+                .ok()
+                .and_then(|config| config.logging.file),
+        );
+        for path in paths {
+            if path.exists() {
+                steps.push(Step::Remove { path });
+            }
+        }
+    }
+    Ok(steps)
+}
+
+/// Whose files `--purge` deletes, and where they are
+struct PurgeOwner {
+    user: String,
+    config_path: PathBuf,
+    state_dir: PathBuf,
+}
+
+/// The user who ran sudo, with the default paths under their home (sudo
+/// usually leaves HOME at /root); otherwise the current user, with the
+/// paths of this environment
+fn purge_owner() -> Result<PurgeOwner> {
+    let is_root = unsafe { libc::geteuid() } == 0;
+    let sudo_user = std::env::var("SUDO_USER")
+        .ok()
+        .filter(|u| is_root && !u.is_empty() && u != "root");
+    let owner = match sudo_user {
+        Some(user) => {
+            let home = home_of(&user)
+                .with_context(|| format!("Can't find the home directory of {}", user))?;
+            PurgeOwner {
+                config_path: Config::explicit_config_path()
+                    .unwrap_or_else(|| home.join(".config/mouse-mapper/config.toml")),
+                state_dir: home.join(".local/state/mouse-mapper"),
+                user,
+            }
+        }
+        None => PurgeOwner {
+            user: current_user().unwrap_or_else(|| "?".to_string()),
+            config_path: Config::config_path()?,
+            state_dir: logging::state_dir(),
+        },
+    };
+    for path in [&owner.config_path, &owner.state_dir] {
+        if !path.is_absolute() {
+            bail!(
+                "Can't tell where {} is (is HOME set?), not purging",
+                path.display()
+            );
+        }
+    }
+    Ok(owner)
+}
+
+/// Home directory of `user` from the password database
+fn home_of(user: &str) -> Option<PathBuf> {
+    let name = std::ffi::CString::new(user).ok()?;
+    // SAFETY: getpwnam returns null or a valid entry, read before any other
+    // getpw* call
+    let entry = unsafe { libc::getpwnam(name.as_ptr()) };
+    if entry.is_null() {
+        return None;
+    }
+    let dir = unsafe { std::ffi::CStr::from_ptr((*entry).pw_dir) };
+    Some(PathBuf::from(dir.to_string_lossy().into_owned()))
+}
+
+fn current_user() -> Option<String> {
+    // SAFETY: as in home_of
+    let entry = unsafe { libc::getpwuid(libc::geteuid()) };
+    if entry.is_null() {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr((*entry).pw_name) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// What is still around after uninstalling: our virtual devices, grabs on
+/// input devices and running mouse-mapper processes
+fn leftovers() -> Result<Vec<String>> {
+    let mut found = Vec::new();
+    for device in scanner::scan_devices()? {
+        if device.name.starts_with("MouseMapper ") {
+            found.push(format!(
+                "virtual device '{}' still exists at {}",
+                device.name,
+                device.path.display()
+            ));
+        } else if device.grabbed {
+            found.push(format!(
+                "{} ({}) is still grabbed by a process",
+                device.name,
+                device.path.display()
+            ));
+        }
+    }
+    let own_pid = std::process::id().to_string();
+    for entry in std::fs::read_dir("/proc").context("Failed to read /proc")? {
+        let entry = entry?;
+        let pid = entry.file_name().to_string_lossy().to_string();
+        if pid == own_pid || !pid.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        if std::fs::read_to_string(entry.path().join("comm"))
+            .is_ok_and(|comm| comm.trim_end() == "mouse-mapper")
+        {
+            found.push(format!("mouse-mapper is still running (pid {})", pid));
+        }
+    }
+    Ok(found)
+}
+
+/// Carry out the steps, or print them for a dry run
+fn execute(steps: &[Step], dry_run: bool) -> Result<()> {
+    for step in steps {
+        match step {
+            Step::Write { path, contents } if dry_run => {
+                println!("Would write {}:", path.display());
+                for line in contents.lines() {
+                    println!("{}", format!("    {}", line).trim_end());
+                }
+            }
+            Step::Write { path, contents } => write_file(path, contents)?,
+            Step::Remove { path } if dry_run => println!("Would remove {}", path.display()),
+            Step::Remove { path } => {
+                if path.is_dir() {
+                    std::fs::remove_dir_all(path)
+                } else {
+                    std::fs::remove_file(path)
+                }
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+                println!("Removed {}", path.display());
+            }
+            Step::Run { program, args, .. } if dry_run => {
+                println!("Would run: {}", Step::command_line(program, args));
+            }
+            Step::Run {
+                program,
+                args,
+                may_fail,
+            } => {
+                let line = Step::command_line(program, args);
+                println!("Running: {}", line);
+                let status = Command::new(program)
+                    .args(args)
+                    .status()
+                    .with_context(|| format!("Failed to run {}", program));
+                match status {
+                    Ok(status) if status.success() => {}
+                    Ok(status) if *may_fail => eprintln!("  warning: {} failed ({})", line, status),
+                    Err(e) if *may_fail => eprintln!("  warning: {:#}", e),
+                    Ok(status) => bail!("{} failed ({})", line, status),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A service running `exec_start`, restarted while the mouse is missing
+fn unit(exec_start: &str, wanted_by: &str) -> String {
+    format!(
+        "\
+[Unit]
+Description=Mouse Mapper button remapper
+After=systemd-udevd.service
+
+[Service]
+ExecStart={}
+Restart=on-failure
+RestartSec=3
+
+[Install]
+WantedBy={}
+",
+        exec_start, wanted_by
+    )
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    if std::fs::read_to_string(path).is_ok_and(|old| old == contents) {
+        println!("Unchanged: {}", path.display());
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(path, contents)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+fn group_exists(group: &str) -> bool {
+    Command::new("getent")
+        .args(["group", group])
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+fn user_in_group(user: &str, group: &str) -> bool {
+    Command::new("id")
+        .args(["-nG", user])
+        .output()
+        .is_ok_and(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .split_whitespace()
+                .any(|g| g == group)
+        })
+}
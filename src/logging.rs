@@ -0,0 +1,319 @@
+use crate::config::LoggingConfig;
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracing_chrome::ChromeLayerBuilder;
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Directory for logs and other state that isn't configuration
+/// ($XDG_STATE_HOME/mouse-mapper, usually ~/.local/state/mouse-mapper)
+pub fn state_dir() -> PathBuf {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("mouse-mapper")
+}
+
+/// Path of the log file (`logging.file`, else mouse-mapper.log in the state dir)
+pub fn log_file_path(config: &LoggingConfig) -> PathBuf {
+    config
+        .file
+        .clone()
+        .unwrap_or_else(|| state_dir().join("mouse-mapper.log"))
+}
+
+/// Global level, adjustable at runtime (stored as a `LevelFilter` discriminant)
+static LEVEL: AtomicUsize = AtomicUsize::new(log::LevelFilter::Info as usize);
+/// When set, `mouse_mapper::engine` logs at debug level regardless of `LEVEL`
+static ENGINE_DEBUG: AtomicBool = AtomicBool::new(false);
+
+const ENGINE_TARGET: &str = "mouse_mapper::engine";
+
+/// Initialize the logger to write to a file instead of stderr.
+/// This prevents log output from corrupting the TUI which owns the terminal.
+pub fn init_file_logger(config: &LoggingConfig) {
+    use std::fs;
+
+    let log_file_path = log_file_path(config);
+    if let Some(parent) = log_file_path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    let log_file =
+        match RotatingFile::open(log_file_path, config.max_size_kb * 1024, config.keep_files) {
+            Ok(f) => f,
+            Err(_) => {
+                // If we can't create a log file, just disable logging entirely
+                // rather than corrupting the TUI
+                log::set_max_level(log::LevelFilter::Off);
+                return;
+            }
+        };
+    let log_file = Mutex::new(log_file);
+
+    // env_logger does the formatting (and still honours RUST_LOG); the level
+    // checks that can change at runtime live in DynamicLogger.
+    let inner =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("trace"))
+            .format_timestamp_millis()
+            .target(env_logger::Target::Pipe(Box::new(LogWriter(log_file))))
+            .build();
+
+    set_level(parse_level_filter(&config.level));
+    set_engine_debug(config.engine_debug);
+
+    if log::set_boxed_logger(Box::new(DynamicLogger { inner })).is_err() {
+        return;
+    }
+    update_max_level();
+}
+
+/// Record tracing spans to a Chrome trace file at `path`. The file is only
+/// complete once the returned guard is dropped.
+pub fn init_trace(path: &Path) -> Result<tracing_chrome::FlushGuard> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create trace file {}", path.display()))?;
+    let (layer, guard) = ChromeLayerBuilder::new()
+        .writer(file)
+        .include_args(true)
+        .build();
+    // Not try_init(): that would also claim the `log` facade, which the file
+    // logger already has
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer))
+        .context("Failed to set up tracing")?;
+    Ok(guard)
+}
+
+/// Change the global log level at runtime
+pub fn set_level(level: log::LevelFilter) {
+    LEVEL.store(level as usize, Ordering::Relaxed);
+    update_max_level();
+}
+
+/// Force debug logging for the engine modules at runtime
+pub fn set_engine_debug(enabled: bool) {
+    ENGINE_DEBUG.store(enabled, Ordering::Relaxed);
+    update_max_level();
+}
+
+/// Parse a level name from config ("off", "error", ..., "trace"), defaulting to info
+pub fn parse_level_filter(name: &str) -> log::LevelFilter {
+    name.parse().unwrap_or(log::LevelFilter::Info)
+}
+
+fn current_level() -> log::LevelFilter {
+    match LEVEL.load(Ordering::Relaxed) {
+        0 => log::LevelFilter::Off,
+        1 => log::LevelFilter::Error,
+        2 => log::LevelFilter::Warn,
+        3 => log::LevelFilter::Info,
+        4 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    }
+}
+
+/// Keep the `log` crate's fast-path filter in sync with our settings
+fn update_max_level() {
+    let mut max = current_level();
+    if ENGINE_DEBUG.load(Ordering::Relaxed) {
+        max = max.max(log::LevelFilter::Debug);
+    }
+    log::set_max_level(max);
+}
+
+/// Wraps env_logger with level checks that can be changed while running
+struct DynamicLogger {
+    inner: env_logger::Logger,
+}
+
+impl log::Log for DynamicLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        let level = if ENGINE_DEBUG.load(Ordering::Relaxed)
+            && metadata.target().starts_with(ENGINE_TARGET)
+        {
+            current_level().max(log::LevelFilter::Debug)
+        } else {
+            current_level()
+        };
+        metadata.level() <= level && self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Log file that rolls over to `<name>.1`, `<name>.2`, ... once it grows past
+/// `max_size` bytes, keeping at most `keep` old files.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    keep: usize,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, keep: usize) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_size,
+            keep,
+        })
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.keep == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated_path(self.keep));
+            for n in (1..self.keep).rev() {
+                let _ = std::fs::rename(self.rotated_path(n), self.rotated_path(n + 1));
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = File::create(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.max_size > 0 && self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// A simple Write adapter that forwards to a Mutex<RotatingFile>.
+struct LogWriter(Mutex<RotatingFile>);
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Ok(mut f) = self.0.lock() {
+            f.write(buf)
+        } else {
+            Ok(buf.len()) // Silently discard if lock is poisoned
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if let Ok(mut f) = self.0.lock() {
+            f.flush()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// One line of the log file, with the level parsed from the env_logger prefix
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub level: log::Level,
+    pub text: String,
+}
+
+/// Incrementally reads new lines appended to the log file (like `tail -f`)
+pub struct LogTail {
+    path: PathBuf,
+    pos: u64,
+    pub lines: Vec<LogLine>,
+    max_lines: usize,
+}
+
+impl LogTail {
+    pub fn new(path: PathBuf, max_lines: usize) -> Self {
+        Self {
+            path,
+            pos: 0,
+            lines: Vec::new(),
+            max_lines,
+        }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Read anything appended since the last poll. Returns the number of new lines.
+    pub fn poll(&mut self) -> usize {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let Ok(mut file) = std::fs::File::open(&self.path) else {
+            return 0;
+        };
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        if len < self.pos {
+            // File was truncated or rotated — start over
+            self.pos = 0;
+            self.lines.clear();
+        }
+        if len == self.pos {
+            return 0;
+        }
+
+        let mut buf = Vec::new();
+        if file.seek(SeekFrom::Start(self.pos)).is_err() || file.read_to_end(&mut buf).is_err() {
+            return 0;
+        }
+
+        // Only consume complete lines; a partial last line is picked up next time
+        let Some(last_newline) = buf.iter().rposition(|&b| b == b'\n') else {
+            return 0;
+        };
+        self.pos += last_newline as u64 + 1;
+
+        let before = self.lines.len();
+        for raw in String::from_utf8_lossy(&buf[..last_newline]).lines() {
+            // Continuation lines (e.g. multi-line errors) inherit the previous level
+            let level = parse_level(raw)
+                .or_else(|| self.lines.last().map(|l| l.level))
+                .unwrap_or(log::Level::Info);
+            self.lines.push(LogLine {
+                level,
+                text: raw.to_string(),
+            });
+        }
+
+        let added = self.lines.len() - before;
+
+        if self.lines.len() > self.max_lines {
+            let drain_count = self.lines.len() - self.max_lines;
+            self.lines.drain(..drain_count);
+        }
+        added
+    }
+}
+
+/// Parse the level out of an env_logger line: "[<timestamp> LEVEL target] message"
+fn parse_level(line: &str) -> Option<log::Level> {
+    let rest = line.strip_prefix('[')?;
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
@@ -1,39 +1,86 @@
+mod api;
+mod backup;
+mod check;
+mod cli;
 mod config;
 mod device;
 mod engine;
+mod export;
+mod import;
+mod install;
+mod logging;
+mod self_test;
+mod stress;
 mod tui;
 
+use crate::cli::Cli;
 use crate::config::Config;
-use crate::device::reader::DeviceReader;
-use crate::device::writer::DeviceWriter;
+use crate::device::hidraw::HidrawReader;
+use crate::device::keyboard::KeyboardMonitor;
+use crate::device::leds::Leds;
+use crate::device::reader::{self, DeviceReader};
+use crate::device::scanner;
+use crate::device::writer::{self, DeviceWriter};
+use crate::engine::blocklist;
+use crate::engine::fullscreen;
 use crate::engine::mapper::EventMapper;
-use crate::tui::app::{App, EngineCommand, EngineMessage};
+use crate::engine::sounds::{self, Sounds};
+use crate::engine::{macros, metrics, realtime};
+use crate::tui::app::{App, EngineCommand, EngineMessage, EngineState};
 use anyhow::{Context, Result};
-use evdev::{EventType, InputEvent};
+use clap::Parser;
+use evdev::{EventType, InputEvent, RelativeAxisCode};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 
 fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if let Some(path) = cli.config.clone() {
+        Config::set_path_override(path);
+    }
+
+    // Load config first so the logger can pick up its [logging] settings
+    let config_result = Config::load();
+    let logging_config = config_result
+        .as_ref()
+        .map(|c| c.logging.clone())
+        .unwrap_or_default();
+
     // Initialize logging to a file (NOT stderr) so it doesn't corrupt the TUI.
-    // Logs go to ~/.config/mouse-mapper/mouse-mapper.log
-    init_file_logger();
+    // Logs go to ~/.local/state/mouse-mapper/mouse-mapper.log
+    logging::init_file_logger(&logging_config);
+
+    // Kept until main returns: the trace file is finished when it's dropped
+    let _trace_guard = match cli.trace.as_deref() {
+        Some(path) => Some(logging::init_trace(path)?),
+        None => None,
+    };
 
     // Check for root access — record as a log warning, not eprintln (which corrupts TUI)
     if unsafe { libc::geteuid() } != 0 {
         log::warn!("mouse-mapper should be run as root (sudo) for /dev/input access");
     }
 
-    // Load config
-    let config = Config::load().unwrap_or_else(|e| {
+    if let Some(command) = cli.command {
+        if let Ok(ref config) = config_result {
+            macros::set_speed(config.macro_speed);
+        }
+        return cli::run_command(command, config_result);
+    }
+
+    let config = config_result.unwrap_or_else(|e| {
         eprintln!("Warning: Failed to load config: {}. Using defaults.", e);
         Config::default()
     });
+    macros::set_speed(config.macro_speed);
 
     // Create communication channels
     let (cmd_tx, cmd_rx) = mpsc::unbounded_channel::<EngineCommand>();
     let (msg_tx, msg_rx) = mpsc::unbounded_channel::<EngineMessage>();
 
+    let api_config = config.api.clone();
+
     // Build the app
     let mut app = App::new(config);
     app.engine_cmd_tx = Some(cmd_tx);
@@ -45,67 +92,67 @@ fn main() -> Result<()> {
 
     // Spawn the engine command handler
     let engine_msg_tx = msg_tx.clone();
-    runtime.spawn(async move {
+    let engine_handle = runtime.spawn(async move {
         engine_task(cmd_rx, engine_msg_tx).await;
     });
 
-    // Run the TUI (blocks until quit)
-    tui::run(app)?;
+    // A kill quits the TUI the same way as pressing q, so the terminal is
+    // restored and the engine shut down
+    let signal_msg_tx = msg_tx.clone();
+    runtime.spawn(async move {
+        match termination_signal().await {
+            Ok(signal) => {
+                let _ = signal_msg_tx.send(EngineMessage::Terminate(signal));
+            }
+            Err(e) => log::error!("{:#}", e),
+        }
+    });
 
-    // Cleanup: shutdown the runtime (will cancel all tasks including macros)
-    runtime.shutdown_timeout(std::time::Duration::from_secs(2));
+    if let Some(api_config) = api_config {
+        let (api_tx, api_rx) = mpsc::unbounded_channel();
+        app.api_rx = Some(api_rx);
+        let api_msg_tx = msg_tx.clone();
+        runtime.spawn(async move {
+            if let Err(e) = api::serve(api_config, api_tx).await {
+                log::error!("HTTP API: {:#}", e);
+                let _ = api_msg_tx.send(EngineMessage::StatusUpdate(format!(
+                    "HTTP API off: {:#}",
+                    e
+                )));
+            }
+        });
+    }
 
-    Ok(())
-}
+    // Run the TUI (blocks until quit); it tells the engine to shut down. A
+    // panic drops the App, and with it the command channel, which shuts the
+    // engine down too; it's resumed once the engine let go of the devices.
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| tui::run(app)));
 
-/// Initialize the logger to write to a file instead of stderr.
-/// This prevents log output from corrupting the TUI which owns the terminal.
-fn init_file_logger() {
-    use std::fs;
-
-    let log_path = dirs::config_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join("mouse-mapper");
-    let _ = fs::create_dir_all(&log_path);
-    let log_file_path = log_path.join("mouse-mapper.log");
-
-    // Open log file (truncate on each run to avoid unbounded growth)
-    let log_file = match fs::File::create(&log_file_path) {
-        Ok(f) => f,
-        Err(_) => {
-            // If we can't create a log file, just disable logging entirely
-            // rather than corrupting the TUI
-            log::set_max_level(log::LevelFilter::Off);
-            return;
-        }
-    };
-    let log_file = std::sync::Mutex::new(log_file);
+    // Cleanup: wait for the engine to stop, then shutdown the runtime (will
+    // cancel all remaining tasks including macros)
+    let _ =
+        runtime.block_on(async { tokio::time::timeout(SHUTDOWN_TIMEOUT * 2, engine_handle).await });
+    runtime.shutdown_timeout(std::time::Duration::from_secs(2));
 
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .format_timestamp_millis()
-        .target(env_logger::Target::Pipe(Box::new(LogWriter(log_file))))
-        .init();
+    result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
 }
 
-/// A simple Write adapter that forwards to a Mutex<File>.
-struct LogWriter(std::sync::Mutex<std::fs::File>);
+/// Longest a stopping engine may take to release its keys and grab
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
 
-impl std::io::Write for LogWriter {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        if let Ok(mut f) = self.0.lock() {
-            f.write(buf)
-        } else {
-            Ok(buf.len()) // Silently discard if lock is poisoned
-        }
-    }
-
-    fn flush(&mut self) -> std::io::Result<()> {
-        if let Ok(mut f) = self.0.lock() {
-            f.flush()
-        } else {
-            Ok(())
-        }
-    }
+/// Wait for SIGTERM, SIGINT or SIGHUP and return its name
+async fn termination_signal() -> Result<&'static str> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate =
+        signal(SignalKind::terminate()).context("Failed to install a SIGTERM handler")?;
+    let mut interrupt =
+        signal(SignalKind::interrupt()).context("Failed to install a SIGINT handler")?;
+    let mut hangup = signal(SignalKind::hangup()).context("Failed to install a SIGHUP handler")?;
+    Ok(tokio::select! {
+        _ = terminate.recv() => "SIGTERM",
+        _ = interrupt.recv() => "SIGINT",
+        _ = hangup.recv() => "SIGHUP",
+    })
 }
 
 /// Background task that handles engine commands and runs the event processing loop
@@ -113,54 +160,227 @@ async fn engine_task(
     mut cmd_rx: mpsc::UnboundedReceiver<EngineCommand>,
     msg_tx: mpsc::UnboundedSender<EngineMessage>,
 ) {
-    let mut active_engine: Option<tokio::task::JoinHandle<()>> = None;
+    let mut active_engines: Vec<tokio::task::JoinHandle<()>> = Vec::new();
     let mut cancel_tx: Option<tokio::sync::watch::Sender<bool>> = None;
+    let mut capture_cancel_tx: Option<tokio::sync::watch::Sender<bool>> = None;
+    let mut hid_cancel_tx: Option<tokio::sync::watch::Sender<bool>> = None;
+    // Devices of the running engine and whether it only observes, to restart
+    // it on a profile switch
+    let mut running: Option<(Vec<String>, bool)> = None;
+    // Profile picked with SwitchProfile, used instead of the config's
+    let mut profile: Option<String> = None;
+    // Per device: where to send names of macros to run, see TriggerMacro
+    let mut trigger_txs: Vec<mpsc::UnboundedSender<String>> = Vec::new();
+    // The config's fullscreen_profile, read when the engine starts
+    let mut fullscreen_profile: Option<String> = None;
+    // Whether the focused window was fullscreen at the last check
+    let mut fullscreen = false;
+    // Profile to go back to when the fullscreen window goes
+    let mut before_fullscreen: Option<String> = None;
+    // Only the first failure to check is reported, not every one after it
+    let mut fullscreen_failed = false;
+    let mut fullscreen_check = tokio::time::interval(fullscreen::CHECK_INTERVAL);
 
     loop {
-        match cmd_rx.recv().await {
-            Some(EngineCommand::Start(device_path)) => {
-                // Stop any existing engine
-                if let Some(tx) = cancel_tx.take() {
-                    let _ = tx.send(true);
+        let command = tokio::select! {
+            command = cmd_rx.recv() => command,
+            _ = fullscreen_check.tick(),
+                if fullscreen_profile.is_some() && matches!(running, Some((_, false))) =>
+            {
+                let checked = tokio::task::spawn_blocking(fullscreen::focused_is_fullscreen).await;
+                let now = match checked {
+                    Ok(Ok(now)) => now,
+                    Ok(Err(e)) => {
+                        if !fullscreen_failed {
+                            log::warn!("Can't tell whether a window is fullscreen: {:#}", e);
+                            let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                                "Can't tell whether a window is fullscreen: {:#}",
+                                e
+                            )));
+                        }
+                        fullscreen_failed = true;
+                        continue;
+                    }
+                    Err(_) => continue,
+                };
+                fullscreen_failed = false;
+                if now == fullscreen {
+                    continue;
+                }
+                fullscreen = now;
+
+                let config = Config::load().unwrap_or_default();
+                let current = profile.clone().or(config.active_profile.clone());
+                let target = if now {
+                    let Some(target) = fullscreen_profile.clone() else {
+                        continue;
+                    };
+                    if current.as_ref() == Some(&target) {
+                        continue;
+                    }
+                    before_fullscreen = current;
+                    target
+                } else {
+                    // Not switched, or switched away from by hand since
+                    let Some(target) = before_fullscreen.take() else {
+                        continue;
+                    };
+                    target
+                };
+                if !config.profiles.iter().any(|p| p.name == target) {
+                    let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                        "Can't switch to profile {}: it doesn't exist",
+                        target
+                    )));
+                    continue;
+                }
+
+                profile = Some(target.clone());
+                if let Some((device_paths, observe)) = &running {
+                    trigger_txs = start_engines(
+                        &mut active_engines,
+                        &mut cancel_tx,
+                        device_paths,
+                        *observe,
+                        &profile,
+                        &msg_tx,
+                    ).await;
                 }
-                if let Some(handle) = active_engine.take() {
-                    handle.abort();
+                let _ = msg_tx.send(EngineMessage::ProfileSwitched(target));
+                if let Some(ref sounds) = config.sounds
+                    && let Some(ref sound) = sounds.profile_switch
+                {
+                    sounds::play(sound, &sounds.player);
                 }
+                continue;
+            }
+        };
+        match command {
+            Some(EngineCommand::Start(device_paths)) => {
+                fullscreen_profile = Config::load().ok().and_then(|c| c.fullscreen_profile);
+                fullscreen = false;
+                before_fullscreen = None;
+                trigger_txs = start_engines(
+                    &mut active_engines,
+                    &mut cancel_tx,
+                    &device_paths,
+                    false,
+                    &profile,
+                    &msg_tx,
+                )
+                .await;
+                running = Some((device_paths, false));
+            }
+
+            Some(EngineCommand::Observe(device_paths)) => {
+                trigger_txs = start_engines(
+                    &mut active_engines,
+                    &mut cancel_tx,
+                    &device_paths,
+                    true,
+                    &profile,
+                    &msg_tx,
+                )
+                .await;
+                running = Some((device_paths, true));
+            }
+
+            Some(EngineCommand::Stop) => {
+                stop_engines(&mut active_engines, &mut cancel_tx).await;
+                running = None;
+                trigger_txs.clear();
+            }
 
+            Some(EngineCommand::SwitchProfile(name)) => {
+                profile = Some(name);
+                // Picked by hand: it stays when the fullscreen window goes
+                before_fullscreen = None;
+                if let Some((device_paths, observe)) = &running {
+                    trigger_txs = start_engines(
+                        &mut active_engines,
+                        &mut cancel_tx,
+                        device_paths,
+                        *observe,
+                        &profile,
+                        &msg_tx,
+                    )
+                    .await;
+                }
+            }
+
+            Some(EngineCommand::TriggerMacro(name)) => {
+                // One device runs it, every device running it would repeat its output
+                // (the first whose engine is still up)
+                let mut name = name;
+                let sent = trigger_txs.iter().any(|tx| {
+                    tx.send(std::mem::take(&mut name))
+                        .map_err(|e| name = e.0)
+                        .is_ok()
+                });
+                if !sent {
+                    let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                        "Can't run macro {}: the engine isn't running",
+                        name
+                    )));
+                }
+            }
+
+            Some(EngineCommand::StartCapture(device_path)) => {
+                if let Some(tx) = capture_cancel_tx.take() {
+                    let _ = tx.send(true);
+                }
                 let (new_cancel_tx, new_cancel_rx) = tokio::sync::watch::channel(false);
-                cancel_tx = Some(new_cancel_tx);
+                capture_cancel_tx = Some(new_cancel_tx);
 
                 let msg_tx_clone = msg_tx.clone();
-                let path = device_path.clone();
-
-                active_engine = Some(tokio::spawn(async move {
-                    match run_engine(&path, msg_tx_clone.clone(), new_cancel_rx).await {
-                        Ok(()) => {
-                            // Engine exited cleanly (e.g. device disconnected, channel closed)
-                            let _ = msg_tx_clone
-                                .send(EngineMessage::Error("Engine stopped unexpectedly".into()));
-                        }
-                        Err(e) => {
-                            let _ = msg_tx_clone
-                                .send(EngineMessage::Error(format!("{:#}", e)));
-                        }
+                tokio::spawn(async move {
+                    if let Err(e) = run_capture(&device_path, &msg_tx_clone, new_cancel_rx).await {
+                        log::error!("Capture failed: {:#}", e);
+                        let _ = msg_tx_clone.send(EngineMessage::StatusUpdate(format!(
+                            "Capture failed: {:#}",
+                            e
+                        )));
                     }
-                }));
+                });
+            }
 
-                let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
-                    "Engine started on {}",
-                    device_path
-                )));
+            Some(EngineCommand::StopCapture) => {
+                if let Some(tx) = capture_cancel_tx.take() {
+                    let _ = tx.send(true);
+                }
             }
 
-            Some(EngineCommand::Stop) => {
-                if let Some(tx) = cancel_tx.take() {
+            Some(EngineCommand::StartHidMonitor(device_path)) => {
+                if let Some(tx) = hid_cancel_tx.take() {
                     let _ = tx.send(true);
                 }
-                if let Some(handle) = active_engine.take() {
-                    handle.abort();
+                let (new_cancel_tx, new_cancel_rx) = tokio::sync::watch::channel(false);
+                hid_cancel_tx = Some(new_cancel_tx);
+
+                let msg_tx_clone = msg_tx.clone();
+                tokio::spawn(async move {
+                    if let Err(e) =
+                        run_hid_monitor(&device_path, &msg_tx_clone, new_cancel_rx).await
+                    {
+                        log::error!("HID monitor failed: {:#}", e);
+                        let _ = msg_tx_clone.send(EngineMessage::StatusUpdate(format!(
+                            "HID monitor failed: {:#}",
+                            e
+                        )));
+                    }
+                });
+            }
+
+            Some(EngineCommand::StopHidMonitor) => {
+                if let Some(tx) = hid_cancel_tx.take() {
+                    let _ = tx.send(true);
                 }
-                let _ = msg_tx.send(EngineMessage::StatusUpdate("Engine stopped".into()));
+            }
+
+            Some(EngineCommand::SetMacroSpeed(speed)) => macros::set_speed(speed),
+
+            Some(EngineCommand::PauseMacros(switch)) => {
+                macros::switch_paused(switch);
             }
 
             Some(EngineCommand::ReloadConfig) => {
@@ -170,106 +390,882 @@ async fn engine_task(
             }
 
             Some(EngineCommand::Shutdown) | None => {
-                if let Some(tx) = cancel_tx.take() {
+                if let Some(tx) = hid_cancel_tx.take() {
                     let _ = tx.send(true);
                 }
-                if let Some(handle) = active_engine.take() {
-                    handle.abort();
-                }
+                // Let the engines release their keys and grabs before the
+                // process goes
+                stop_engines(&mut active_engines, &mut cancel_tx).await;
                 break;
             }
         }
     }
 }
 
-/// Run the actual event processing engine
+/// Cancel the running engines and wait for them to wind down: let go of
+/// keys and grabs and park their virtual devices, which the next engines
+/// pick up instead of creating new ones. One stuck past SHUTDOWN_TIMEOUT is
+/// aborted.
+async fn stop_engines(
+    active_engines: &mut Vec<tokio::task::JoinHandle<()>>,
+    cancel_tx: &mut Option<tokio::sync::watch::Sender<bool>>,
+) {
+    if let Some(tx) = cancel_tx.take() {
+        let _ = tx.send(true);
+    }
+    let deadline = tokio::time::Instant::now() + SHUTDOWN_TIMEOUT;
+    for mut handle in active_engines.drain(..) {
+        if tokio::time::timeout_at(deadline, &mut handle)
+            .await
+            .is_err()
+        {
+            handle.abort();
+        }
+    }
+}
+
+/// Stop any running engine and start a new one on each device, mapping them
+/// with `profile` (None = the config's active one) or, with `observe`, only
+/// reading them for the monitor. Returns the senders for TriggerMacro.
+async fn start_engines(
+    active_engines: &mut Vec<tokio::task::JoinHandle<()>>,
+    cancel_tx: &mut Option<tokio::sync::watch::Sender<bool>>,
+    device_paths: &[String],
+    observe: bool,
+    profile: &Option<String>,
+    msg_tx: &mpsc::UnboundedSender<EngineMessage>,
+) -> Vec<mpsc::UnboundedSender<String>> {
+    stop_engines(active_engines, cancel_tx).await;
+
+    let (new_cancel_tx, new_cancel_rx) = tokio::sync::watch::channel(false);
+    *cancel_tx = Some(new_cancel_tx);
+
+    // Every device runs its own pipeline; they share only the cancel signal
+    let mut trigger_txs = Vec::new();
+    for path in device_paths {
+        let (trigger_tx, trigger_rx) = mpsc::unbounded_channel();
+        trigger_txs.push(trigger_tx);
+        active_engines.push(tokio::spawn(supervise_engine(
+            path.clone(),
+            observe,
+            profile.clone(),
+            msg_tx.clone(),
+            new_cancel_rx.clone(),
+            trigger_rx,
+        )));
+    }
+    trigger_txs
+}
+
+/// Restarts of a failing engine run before giving up
+const MAX_ENGINE_RESTARTS: u32 = 5;
+/// A run that lasted this long counts as healthy and resets the restart count
+const STABLE_ENGINE_RUN: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Run the engine on one device, restarting it with exponential backoff when
+/// it fails, until it is cancelled or fails MAX_ENGINE_RESTARTS times in a row
+async fn supervise_engine(
+    path: String,
+    observe: bool,
+    profile: Option<String>,
+    msg_tx: mpsc::UnboundedSender<EngineMessage>,
+    mut cancel_rx: tokio::sync::watch::Receiver<bool>,
+    mut trigger_rx: mpsc::UnboundedReceiver<String>,
+) {
+    let mut attempt = 0;
+    loop {
+        let started = std::time::Instant::now();
+        // Reloaded on every run so a restart picks up config changes
+        let mut config = Config::load().unwrap_or_default();
+        if profile.is_some() {
+            config.active_profile = profile.clone();
+        }
+        let result = if observe {
+            run_observer(&path, msg_tx.clone(), cancel_rx.clone()).await
+        } else if config.realtime || !config.cpu_affinity.is_empty() {
+            let (result, rx) = run_engine_on_thread(
+                path.clone(),
+                config,
+                msg_tx.clone(),
+                cancel_rx.clone(),
+                trigger_rx,
+            )
+            .await;
+            trigger_rx = rx;
+            result
+        } else {
+            run_engine(
+                &path,
+                &config,
+                msg_tx.clone(),
+                cancel_rx.clone(),
+                &mut trigger_rx,
+            )
+            .await
+        };
+        let error = match result {
+            // Engine exited cleanly (e.g. channel closed)
+            Ok(()) => "Engine stopped unexpectedly".to_string(),
+            Err(e) => format!("{:#}", e),
+        };
+        if *cancel_rx.borrow() {
+            break;
+        }
+        log::error!("Engine on {} failed: {}", path, error);
+
+        if started.elapsed() >= STABLE_ENGINE_RUN {
+            attempt = 0;
+        }
+        attempt += 1;
+        if attempt > MAX_ENGINE_RESTARTS {
+            let _ = msg_tx.send(EngineMessage::DeviceError {
+                path,
+                error: format!("{} (gave up after {} restarts)", error, MAX_ENGINE_RESTARTS),
+            });
+            break;
+        }
+
+        let delay = std::time::Duration::from_secs(1 << (attempt - 1));
+        let _ = msg_tx.send(EngineMessage::EngineRestart {
+            path: path.clone(),
+            attempt,
+            max_attempts: MAX_ENGINE_RESTARTS,
+            delay_secs: delay.as_secs(),
+            error,
+        });
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = cancel_rx.changed() => break,
+        }
+    }
+}
+
+/// Run `run_engine` on a thread of its own, pinned to `cpu_affinity` and/or
+/// under SCHED_FIFO with `realtime`, with a single-threaded runtime for its
+/// mapping, macros and output whose blocking threads (the device reader) get
+/// the same settings. What can't be applied is reported and the engine runs
+/// without it. Hands `trigger_rx` back for the next run.
+async fn run_engine_on_thread(
+    device_path: String,
+    config: Config,
+    msg_tx: mpsc::UnboundedSender<EngineMessage>,
+    cancel_rx: tokio::sync::watch::Receiver<bool>,
+    mut trigger_rx: mpsc::UnboundedReceiver<String>,
+) -> (Result<()>, mpsc::UnboundedReceiver<String>) {
+    let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+    let spawned = std::thread::Builder::new()
+        .name("engine".to_string())
+        .spawn(move || {
+            let cpus = if config.cpu_affinity.is_empty() {
+                None
+            } else {
+                match realtime::pin_current_thread(&config.cpu_affinity) {
+                    Ok(()) => {
+                        log::info!(
+                            "Engine on {} pinned to CPU(s) {:?}",
+                            device_path,
+                            config.cpu_affinity
+                        );
+                        Some(config.cpu_affinity.clone())
+                    }
+                    Err(e) => {
+                        log::warn!("{:#}", e);
+                        let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                            "{:#}; the engine runs on any CPU",
+                            e
+                        )));
+                        None
+                    }
+                }
+            };
+            let priority = if !config.realtime {
+                None
+            } else {
+                match realtime::set_current_thread(config.realtime_priority) {
+                    Ok(priority) => {
+                        log::info!(
+                            "Engine on {} runs with SCHED_FIFO priority {}",
+                            device_path,
+                            priority
+                        );
+                        Some(priority)
+                    }
+                    Err(e) => {
+                        log::warn!("{:#}", e);
+                        let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                            "{:#}; the engine runs with normal scheduling",
+                            e
+                        )));
+                        None
+                    }
+                }
+            };
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .on_thread_start(move || {
+                    if let Some(cpus) = &cpus {
+                        let _ = realtime::pin_current_thread(cpus);
+                    }
+                    if let Some(priority) = priority {
+                        let _ = realtime::set_current_thread(priority);
+                    }
+                })
+                .build()
+                .context("Failed to create the engine runtime");
+            let result = runtime.and_then(|runtime| {
+                let result = runtime.block_on(run_engine(
+                    &device_path,
+                    &config,
+                    msg_tx,
+                    cancel_rx,
+                    &mut trigger_rx,
+                ));
+                // The reader thread only notices it's done with its next event
+                runtime.shutdown_background();
+                result
+            });
+            let _ = done_tx.send((result, trigger_rx));
+        });
+    if let Err(e) = spawned {
+        let result = Err(e).context("Failed to start the engine thread");
+        return (result, mpsc::unbounded_channel().1);
+    }
+    match done_rx.await {
+        Ok(done) => done,
+        Err(_) => (
+            Err(anyhow::anyhow!("Engine thread panicked")),
+            mpsc::unbounded_channel().1,
+        ),
+    }
+}
+
+/// Keeps the engine's virtual device for the next run when the run ends,
+/// however it ends
+struct ParkWriterOnDrop(Arc<Mutex<DeviceWriter>>);
+
+impl Drop for ParkWriterOnDrop {
+    fn drop(&mut self) {
+        writer::park(self.0.clone());
+    }
+}
+
+/// Run the actual event processing engine. Macro names arriving on
+/// `trigger_rx` are run as if a button bound to them was pressed.
 async fn run_engine(
     device_path: &str,
+    config: &Config,
     msg_tx: mpsc::UnboundedSender<EngineMessage>,
     mut cancel_rx: tokio::sync::watch::Receiver<bool>,
+    trigger_rx: &mut mpsc::UnboundedReceiver<String>,
 ) -> Result<()> {
     // Open and grab the device
     let mut reader = DeviceReader::open(Path::new(device_path))?;
 
-    // Create virtual device mirroring the source capabilities
-    let writer = DeviceWriter::from_source(reader.device())?;
-    let writer = Arc::new(Mutex::new(writer));
+    // Virtual device mirroring the source capabilities: the one of the last
+    // run, unless the capabilities changed or the user wants a fresh one
+    let writer = if config.fresh_virtual_device {
+        writer::discard_parked();
+        Arc::new(Mutex::new(DeviceWriter::from_source(reader.device())?))
+    } else {
+        writer::acquire(reader.device())?
+    };
+    let _parked = (!config.fresh_virtual_device).then(|| ParkWriterOnDrop(writer.clone()));
 
-    // Load config for the mapper
-    let config = Config::load().unwrap_or_default();
     let mut mapper = EventMapper::new(writer.clone());
-    mapper.load_config(&config);
-
-    // Grab the device (exclusive access)
-    reader.grab()?;
-
-    let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
-        "Grabbed device: {}",
-        reader.name()
-    )));
+    let warnings = mapper.load_config(config);
+    if !warnings.is_empty() {
+        let _ = msg_tx.send(EngineMessage::ConfigWarnings {
+            device: device_path.to_string(),
+            warnings,
+        });
+    }
+    let (macro_tx, mut macro_rx) = mpsc::unbounded_channel();
+    mapper.set_macro_events(macro_tx);
+    if mapper.needs_keyboard_monitor() {
+        match KeyboardMonitor::open(config.device.keyboard.as_deref(), Path::new(device_path)) {
+            Ok(keyboard) => mapper.set_keyboard_monitor(keyboard),
+            Err(e) => {
+                log::warn!("{:#}", e);
+                let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                    "{:#}; modifier bindings only see modifiers on the mouse itself",
+                    e
+                )));
+            }
+        }
+    }
 
-    // Create channel for events from the reader
-    let (event_tx, mut event_rx) = mpsc::unbounded_channel::<InputEvent>();
+    // The device's name and IDs, to find it again after a reconnect (its
+    // event node may change)
+    let identity = (
+        reader.name().to_string(),
+        reader.device().input_id().vendor(),
+        reader.device().input_id().product(),
+    );
+    let started = std::time::Instant::now();
+    let state = |grabbed: bool, mapper: &EventMapper| {
+        let (bindings_loaded, macros_loaded) = mapper.loaded_counts();
+        EngineMessage::State(EngineState {
+            device: device_path.to_string(),
+            device_name: identity.0.clone(),
+            grabbed,
+            observing: false,
+            profile: mapper.profile().map(str::to_string),
+            uptime: started.elapsed(),
+            bindings_loaded,
+            macros_loaded,
+        })
+    };
+    let mut dpi_stage = mapper.dpi_stage();
+    let _ = msg_tx.send(EngineMessage::DpiStage(dpi_stage));
+    let mut modes = mapper.active_modes();
+    let mut running_macros = mapper.running_macros();
 
-    // Spawn the blocking reader in a dedicated thread
-    let reader_handle = tokio::task::spawn_blocking(move || {
-        if let Err(e) = reader.read_loop(event_tx) {
-            log::error!("Reader error: {}", e);
+    let mut leds = (!config.leds.is_empty()).then(|| {
+        let (leds, warnings) = Leds::open(
+            &config.leds,
+            config.device.keyboard.as_deref(),
+            Path::new(device_path),
+        );
+        if !warnings.is_empty() {
+            let _ = msg_tx.send(EngineMessage::ConfigWarnings {
+                device: device_path.to_string(),
+                warnings,
+            });
         }
-        // reader is dropped here, releasing the grab
+        leds
     });
+    if let Some(ref mut leds) = leds {
+        leds.show(mapper.profile(), &modes, &running_macros);
+    }
+    let mut sounds = config.sounds.clone().map(Sounds::new);
+
+    // Idle auto-stop of toggle macros doesn't need to be precise
+    let mut idle_check = tokio::time::interval(std::time::Duration::from_secs(5));
+    // The blocked process that's running, while the device is let go of
+    let mut blocked: Option<String> = None;
+    let mut blocklist_rx = blocklist::watch(&config.blocked_processes);
 
-    // Process events
+    // One pass per connection of the device
     loop {
-        tokio::select! {
-            event = event_rx.recv() => {
-                match event {
-                    Some(input_event) => {
-                        // Send to monitor (skip EV_SYN and EV_MSC noise)
-                        if input_event.event_type() != EventType::SYNCHRONIZATION
-                            && input_event.event_type() != EventType::MISC
-                        {
-                            let _ = msg_tx.send(event_to_message(&input_event));
-                        }
+        // Grab the device (exclusive access). Another remapper may still be
+        // letting go of it, so retry a few times with backoff.
+        let mut backoff = std::time::Duration::from_millis(250);
+        loop {
+            match reader.grab() {
+                Ok(()) => break,
+                Err(e) if reader::is_busy(&e) && backoff <= std::time::Duration::from_secs(2) => {
+                    log::warn!("{:#}", e);
+                    let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                        "{:#}; retrying in {}ms",
+                        e,
+                        backoff.as_millis()
+                    )));
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let grab = reader.grab_release()?;
+        if blocked.is_some() {
+            grab.release();
+        }
+        let _ = msg_tx.send(state(blocked.is_none(), &mapper));
+
+        // Create channel for events from the reader
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel::<InputEvent>();
 
-                        // Process through mapper
-                        match mapper.process_event(input_event) {
-                            Ok(output_events) => {
-                                if !output_events.is_empty() {
-                                    if let Ok(mut w) = writer.lock() {
-                                        if let Err(e) = w.emit(&output_events) {
-                                            log::error!("Failed to emit events: {}", e);
+        // Spawn the blocking reader in a dedicated thread. The reader is dropped
+        // when it returns, releasing the grab.
+        let reader_handle = tokio::task::spawn_blocking(move || reader.read_loop(event_tx));
+
+        // Process events until the reader stops
+        loop {
+            tokio::select! {
+                event = event_rx.recv() => {
+                    match event {
+                        Some(input_event) => {
+                            // Send to monitor (skip EV_SYN and EV_MSC noise)
+                            if input_event.event_type() != EventType::SYNCHRONIZATION
+                                && input_event.event_type() != EventType::MISC
+                            {
+                                let _ = msg_tx.send(event_to_message(device_path, &input_event));
+                            }
+                            // Let go of: the system has the events already
+                            if blocked.is_some() {
+                                continue;
+                            }
+
+                            // Process through mapper
+                            metrics::event_processed();
+                            let timestamp = input_event.timestamp();
+                            let _event_span = tracing::trace_span!(
+                                "event",
+                                kind = input_event.event_type().0,
+                                code = input_event.code(),
+                                value = input_event.value(),
+                                queued_us = timestamp.elapsed().map_or(0, |d| d.as_micros() as u64),
+                            )
+                            .entered();
+                            let mapped = tracing::trace_span!("map")
+                                .in_scope(|| mapper.process_event(input_event));
+                            match mapped {
+                                Ok(output_events) => {
+                                    if !output_events.is_empty()
+                                        && let Ok(mut w) = writer.lock()
+                                    {
+                                        let events = output_events.len();
+                                        let span = tracing::trace_span!("emit", events);
+                                        let emitted = span.in_scope(|| w.emit(&output_events));
+                                        match emitted {
+                                            Ok(()) => {
+                                                if let Ok(latency) = timestamp.elapsed() {
+                                                    metrics::observe_latency(latency);
+                                                }
+                                            }
+                                            Err(e) => log::error!("Failed to emit events: {}", e),
                                         }
                                     }
                                 }
+                                Err(e) => {
+                                    log::error!("Mapper error: {}", e);
+                                }
+                            }
+
+                            // A toggle_profile binding: reload the mapper with
+                            // the other profile, without letting go of the device
+                            if let Some(name) = mapper.take_profile_switch() {
+                                if config.profiles.iter().any(|p| p.name == name) {
+                                    let mut switched = config.clone();
+                                    switched.active_profile = Some(name.clone());
+                                    let warnings = mapper.switch_profile(&switched);
+                                    if !warnings.is_empty() {
+                                        let _ = msg_tx.send(EngineMessage::ConfigWarnings {
+                                            device: device_path.to_string(),
+                                            warnings,
+                                        });
+                                    }
+                                    let _ = msg_tx.send(state(true, &mapper));
+                                    let _ = msg_tx.send(EngineMessage::ProfileSwitched(name));
+                                    if let Some(ref sounds) = sounds {
+                                        sounds.profile_switched();
+                                    }
+                                } else {
+                                    let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                                        "Can't switch to profile {}: it doesn't exist",
+                                        name
+                                    )));
+                                }
+                            }
+
+                            if mapper.dpi_stage() != dpi_stage {
+                                dpi_stage = mapper.dpi_stage();
+                                let _ = msg_tx.send(EngineMessage::DpiStage(dpi_stage));
+                            }
+                            if mapper.active_modes() != modes {
+                                modes = mapper.active_modes();
+                                let _ = msg_tx.send(EngineMessage::Modes(modes.clone()));
+                            }
+                            if mapper.running_macros() != running_macros {
+                                running_macros = mapper.running_macros();
+                                let _ = msg_tx
+                                    .send(EngineMessage::RunningMacros(running_macros.clone()));
+                            }
+                        }
+                        None => {
+                            // Reader channel closed
+                            break;
+                        }
+                    }
+                }
+                _ = idle_check.tick() => {
+                    for name in mapper.stop_idle_macros() {
+                        let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                            "Stopped toggle macro {} (no input)",
+                            name
+                        )));
+                    }
+                    if mapper.running_macros() != running_macros {
+                        running_macros = mapper.running_macros();
+                        let _ = msg_tx.send(EngineMessage::RunningMacros(running_macros.clone()));
+                    }
+                }
+                Some(event) = macro_rx.recv() => {
+                    let _ = msg_tx.send(EngineMessage::Macro(event));
+                }
+                Some(now) = blocklist_rx.recv() => {
+                    match (&blocked, &now) {
+                        (None, Some(name)) => {
+                            mapper.device_lost();
+                            grab.release();
+                            let _ = msg_tx.send(state(false, &mapper));
+                            let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                                "{} is running: {} let go of and unmapped until it exits",
+                                name, identity.0
+                            )));
+                            if mapper.running_macros() != running_macros {
+                                running_macros = mapper.running_macros();
+                                let _ = msg_tx
+                                    .send(EngineMessage::RunningMacros(running_macros.clone()));
                             }
-                            Err(e) => {
-                                log::error!("Mapper error: {}", e);
+                        }
+                        (Some(name), None) => {
+                            // Retried on the next check if someone else has it now
+                            if let Err(e) = grab.regrab() {
+                                log::warn!("{:#}", e);
+                                continue;
                             }
+                            let _ = msg_tx.send(state(true, &mapper));
+                            let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                                "{} exited: mapping {} again",
+                                name, identity.0
+                            )));
                         }
+                        _ => {}
+                    }
+                    blocked = now;
+                }
+                Some(name) = trigger_rx.recv() => {
+                    if let Some(ref process) = blocked {
+                        let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+                            "Can't run macro {}: mapping is off while {} runs",
+                            name, process
+                        )));
+                    } else if let Err(e) = mapper.trigger_macro(&name) {
+                        let _ = msg_tx.send(EngineMessage::StatusUpdate(format!("{:#}", e)));
                     }
-                    None => {
-                        // Reader channel closed
-                        break;
+                    if mapper.running_macros() != running_macros {
+                        running_macros = mapper.running_macros();
+                        let _ = msg_tx.send(EngineMessage::RunningMacros(running_macros.clone()));
                     }
                 }
+                _ = cancel_rx.changed() => {
+                    // Cancellation requested. Nothing may stay pressed, and the
+                    // device is handed back right away: the reader thread only
+                    // stops with the next event, when it finds event_rx dropped.
+                    mapper.device_lost();
+                    grab.release();
+                    reader_handle.abort();
+                    return Ok(());
+                }
             }
-            _ = cancel_rx.changed() => {
-                // Cancellation requested
-                mapper.stop_all();
-                break;
+
+            if let Some(ref mut leds) = leds {
+                leds.show(mapper.profile(), &modes, &running_macros);
+            }
+            if let Some(ref mut sounds) = sounds {
+                let toggles = running_macros
+                    .iter()
+                    .filter(|name| mapper.is_toggle_macro(name))
+                    .cloned()
+                    .collect();
+                sounds.toggles_running(toggles);
             }
         }
+
+        // Only a disconnect is worth waiting out; any other reader error ends the engine
+        match reader_handle.await {
+            Ok(Err(e)) if reader::is_disconnect(&e) => log::warn!("{:#}", e),
+            Ok(Err(e)) => return Err(e),
+            _ => return Ok(()),
+        }
+
+        // Nothing must stay pressed while the device is gone
+        mapper.device_lost();
+        if mapper.running_macros() != running_macros {
+            running_macros = mapper.running_macros();
+            let _ = msg_tx.send(EngineMessage::RunningMacros(running_macros.clone()));
+        }
+        let _ = msg_tx.send(state(false, &mapper));
+        let _ = msg_tx.send(EngineMessage::DeviceWaiting(device_path.to_string()));
+        let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+            "{} disconnected, waiting for it to come back",
+            identity.0
+        )));
+
+        let Some(path) = wait_for_device(&identity, Path::new(device_path), &mut cancel_rx).await
+        else {
+            return Ok(());
+        };
+        reader = DeviceReader::open(&path)?;
     }
+}
 
-    // The reader task will stop when event_rx is dropped (it detects send failure)
-    reader_handle.abort();
+/// Read a device without grabbing it and only forward its events to the
+/// monitor: no mapper, no virtual device, so the rest of the system keeps
+/// getting the device's input untouched
+async fn run_observer(
+    device_path: &str,
+    msg_tx: mpsc::UnboundedSender<EngineMessage>,
+    mut cancel_rx: tokio::sync::watch::Receiver<bool>,
+) -> Result<()> {
+    let mut reader = DeviceReader::open(Path::new(device_path))?;
+    let identity = (
+        reader.name().to_string(),
+        reader.device().input_id().vendor(),
+        reader.device().input_id().product(),
+    );
+    let started = std::time::Instant::now();
+    let state = |observing: bool| {
+        EngineMessage::State(EngineState {
+            device: device_path.to_string(),
+            device_name: identity.0.clone(),
+            grabbed: false,
+            observing,
+            profile: None,
+            uptime: started.elapsed(),
+            bindings_loaded: 0,
+            macros_loaded: 0,
+        })
+    };
 
+    // One pass per connection of the device
+    loop {
+        let _ = msg_tx.send(state(true));
+
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel::<InputEvent>();
+        let reader_handle = tokio::task::spawn_blocking(move || reader.read_loop(event_tx));
+
+        loop {
+            tokio::select! {
+                event = event_rx.recv() => match event {
+                    // Skip EV_SYN and EV_MSC noise, as the engine does
+                    Some(event)
+                        if event.event_type() != EventType::SYNCHRONIZATION
+                            && event.event_type() != EventType::MISC =>
+                    {
+                        let _ = msg_tx.send(event_to_message(device_path, &event));
+                    }
+                    Some(_) => {}
+                    None => break,
+                },
+                _ = cancel_rx.changed() => {
+                    reader_handle.abort();
+                    return Ok(());
+                }
+            }
+        }
+
+        match reader_handle.await {
+            Ok(Err(e)) if reader::is_disconnect(&e) => log::warn!("{:#}", e),
+            Ok(Err(e)) => return Err(e),
+            _ => return Ok(()),
+        }
+
+        let _ = msg_tx.send(state(false));
+        let _ = msg_tx.send(EngineMessage::DeviceWaiting(device_path.to_string()));
+        let _ = msg_tx.send(EngineMessage::StatusUpdate(format!(
+            "{} disconnected, waiting for it to come back",
+            identity.0
+        )));
+
+        let Some(path) = wait_for_device(&identity, Path::new(device_path), &mut cancel_rx).await
+        else {
+            return Ok(());
+        };
+        reader = DeviceReader::open(&path)?;
+    }
+}
+
+/// Run the engine on one device without the TUI until Ctrl+C (or SIGTERM or
+/// SIGHUP), printing its status to stderr (used by `mouse-mapper run`)
+fn run_headless(mut config: Config, device_path: String) -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new().context("Failed to create tokio runtime")?;
+    runtime.block_on(async move {
+        let (msg_tx, mut msg_rx) = mpsc::unbounded_channel::<EngineMessage>();
+
+        tokio::spawn(async move {
+            while let Some(msg) = msg_rx.recv().await {
+                match msg {
+                    EngineMessage::StatusUpdate(s) => eprintln!("{}", s),
+                    EngineMessage::ConfigWarnings { warnings, .. } => {
+                        for warning in warnings {
+                            eprintln!("  warning: {}", warning);
+                        }
+                    }
+                    EngineMessage::State(state) if state.grabbed => eprintln!(
+                        "Grabbed {} ({}): profile {}, {} binding(s), {} macro(s)",
+                        state.device_name,
+                        state.device,
+                        state.profile.as_deref().unwrap_or("(none)"),
+                        state.bindings_loaded,
+                        state.macros_loaded
+                    ),
+                    EngineMessage::DeviceWaiting(path) => {
+                        eprintln!("{} unplugged, waiting for it", path)
+                    }
+                    EngineMessage::ProfileSwitched(name) => {
+                        eprintln!("Switched to profile {}", name)
+                    }
+                    EngineMessage::Macro(event) => log::debug!("{:?}", event),
+                    _ => {}
+                }
+            }
+        });
+
+        // Not with --map, whose profile is the only one
+        let mut fullscreen_rx = match config.fullscreen_profile.clone() {
+            Some(name) if config.profiles.iter().any(|p| p.name == name) => {
+                let active = config.active_profile().map(|p| p.name.clone());
+                fullscreen::watch(name, active)
+            }
+            _ => mpsc::unbounded_channel().1,
+        };
+
+        // The engine is restarted with the other profile on a fullscreen switch
+        loop {
+            let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
+            // Nothing triggers macros by name without the TUI
+            let (_trigger_tx, mut trigger_rx) = mpsc::unbounded_channel();
+            let engine_config = config.clone();
+            let engine_msg_tx = msg_tx.clone();
+            let engine_device_path = device_path.clone();
+            let engine = async move {
+                if engine_config.realtime || !engine_config.cpu_affinity.is_empty() {
+                    run_engine_on_thread(
+                        engine_device_path,
+                        engine_config,
+                        engine_msg_tx,
+                        cancel_rx,
+                        trigger_rx,
+                    )
+                    .await
+                    .0
+                } else {
+                    run_engine(
+                        &engine_device_path,
+                        &engine_config,
+                        engine_msg_tx,
+                        cancel_rx,
+                        &mut trigger_rx,
+                    )
+                    .await
+                }
+            };
+            tokio::pin!(engine);
+            let target = loop {
+                tokio::select! {
+                    result = &mut engine => return result,
+                    signal = termination_signal() => {
+                        eprintln!("Stopping ({})", signal?);
+                        let _ = cancel_tx.send(true);
+                        return engine.await;
+                    }
+                    Some(switched) = fullscreen_rx.recv() => match switched {
+                        Ok(target) => break target,
+                        Err(e) => eprintln!("Can't tell whether a window is fullscreen: {:#}", e),
+                    },
+                }
+            };
+            let _ = cancel_tx.send(true);
+            engine.await?;
+            config.active_profile = Some(target.clone());
+            let _ = msg_tx.send(EngineMessage::ProfileSwitched(target));
+            if let Some(ref sounds) = config.sounds
+                && let Some(ref sound) = sounds.profile_switch
+            {
+                sounds::play(sound, &sounds.player);
+            }
+        }
+    })
+}
+
+/// Poll until a device with the given name and vendor/product IDs is plugged
+/// in again, returning its event node (None if the engine was stopped first)
+async fn wait_for_device(
+    identity: &(String, u16, u16),
+    previous_path: &Path,
+    cancel_rx: &mut tokio::sync::watch::Receiver<bool>,
+) -> Option<std::path::PathBuf> {
+    let mut poll = tokio::time::interval(std::time::Duration::from_secs(1));
+    loop {
+        tokio::select! {
+            _ = poll.tick() => {
+                let (name, vendor_id, product_id) = identity;
+                let found = scanner::find_node(name, *vendor_id, *product_id, previous_path);
+                if found.is_some() {
+                    return found;
+                }
+            }
+            _ = cancel_rx.changed() => return None,
+        }
+    }
+}
+
+/// Read the device without grabbing it and forward its button presses, so
+/// bindings can be captured before the engine is started
+async fn run_capture(
+    device_path: &str,
+    msg_tx: &mpsc::UnboundedSender<EngineMessage>,
+    mut cancel_rx: tokio::sync::watch::Receiver<bool>,
+) -> Result<()> {
+    let reader = DeviceReader::open(Path::new(device_path))?;
+    let (event_tx, mut event_rx) = mpsc::unbounded_channel::<InputEvent>();
+
+    // The reader thread notices the closed channel on the next event and exits
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = reader.read_loop(event_tx) {
+            log::error!("Capture reader error: {}", e);
+        }
+    });
+
+    loop {
+        tokio::select! {
+            event = event_rx.recv() => match event {
+                // Buttons, and wheel tilt for binding WHEEL_LEFT/WHEEL_RIGHT
+                Some(event)
+                    if event.event_type() == EventType::KEY
+                        || (event.event_type() == EventType::RELATIVE
+                            && event.code() == RelativeAxisCode::REL_HWHEEL.0) =>
+                {
+                    let _ = msg_tx.send(event_to_message(device_path, &event));
+                }
+                Some(_) => {}
+                None => break,
+            },
+            _ = cancel_rx.changed() => break,
+        }
+    }
     Ok(())
 }
 
-/// Convert an InputEvent to an EngineMessage for the monitor
-fn event_to_message(event: &InputEvent) -> EngineMessage {
+/// Forward the raw HID reports of a device's hidraw node to the monitor
+async fn run_hid_monitor(
+    device_path: &str,
+    msg_tx: &mpsc::UnboundedSender<EngineMessage>,
+    cancel_rx: tokio::sync::watch::Receiver<bool>,
+) -> Result<()> {
+    let reader = HidrawReader::open(Path::new(device_path))?;
+    let hidraw = reader.path().display().to_string();
+    let (report_tx, mut report_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+
+    let reader_handle = tokio::task::spawn_blocking(move || reader.read_loop(report_tx, cancel_rx));
+
+    while let Some(report) = report_rx.recv().await {
+        let _ = msg_tx.send(EngineMessage::HidReport {
+            hidraw: hidraw.clone(),
+            report,
+            timestamp: format_timestamp(std::time::SystemTime::now()),
+        });
+    }
+    reader_handle.await?
+}
+
+/// Seconds within the current 1000s with microseconds, as shown in the monitor
+fn format_timestamp(time: std::time::SystemTime) -> String {
+    let duration = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    format!(
+        "{}.{:06}",
+        duration.as_secs() % 1000,
+        duration.subsec_micros()
+    )
+}
+
+/// Convert an InputEvent read from `device_path` to an EngineMessage for the monitor
+fn event_to_message(device_path: &str, event: &InputEvent) -> EngineMessage {
     let event_type = match event.event_type() {
         EventType::SYNCHRONIZATION => "EV_SYN".to_string(),
         EventType::KEY => "EV_KEY".to_string(),
@@ -286,16 +1282,11 @@ fn event_to_message(event: &InputEvent) -> EngineMessage {
         _ => format!("{}", event.code()),
     };
 
-    let timestamp = {
-        let ts = event.timestamp();
-        let duration = ts.duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
-        format!("{}.{:06}", duration.as_secs() % 1000, duration.subsec_micros())
-    };
-
     EngineMessage::RawEvent {
+        device: device_path.to_string(),
         event_type,
         code,
         value: event.value(),
-        timestamp,
+        timestamp: format_timestamp(event.timestamp()),
     }
 }
@@ -0,0 +1,440 @@
+use crate::config::{Binding, BindingOutput, Config, MacroType, Profile, ScrollAxis};
+use crate::device::reader::DeviceReader;
+use crate::device::synthetic;
+use crate::device::writer::VIRTUAL_DEVICE_NAME;
+use crate::engine::mapper::{key_name, parse_key_name, WHEEL_LEFT, WHEEL_RIGHT};
+use crate::tui::app::{binding_output_summary, EngineMessage};
+use anyhow::{bail, Context, Result};
+use evdev::uinput::VirtualDevice;
+use evdev::{EventType, InputEvent, KeyCode, RelativeAxisCode};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// Name of the virtual mouse the test presses the bindings' buttons on
+const SOURCE_NAME: &str = "MouseMapper Self-Test Source";
+/// Longest to wait for the engine to grab the source and create its output
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest to wait for the output of a remap, toggle or scroll
+const OUTPUT_TIMEOUT: Duration = Duration::from_millis(500);
+/// Longest to wait for a macro's first output (it may start with a delay)
+const MACRO_TIMEOUT: Duration = Duration::from_secs(2);
+/// Between bindings, output has died down once nothing came for this long
+const QUIET_TIME: Duration = Duration::from_millis(200);
+/// ... or at the latest after this long (a runaway macro)
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Result of testing one binding
+enum Outcome {
+    Pass(String),
+    Fail(String),
+    Skip(String),
+}
+
+/// What a binding should make the output device do, for the outputs the
+/// test can see
+enum Expect {
+    /// The key is pressed and released along with the input
+    Key(KeyCode),
+    /// The key is pressed by one click of the input and released by the next
+    ToggleKey(KeyCode),
+    /// A click of the input scrolls this many notches
+    Scroll(RelativeAxisCode, i32),
+    /// Holding the input produces some output; toggle macros are clicked
+    /// again to stop them
+    Macro { toggle: bool },
+}
+
+/// Run the engine on a virtual source mouse with the active profile, press
+/// the input of every binding on it and check what comes out of the engine's
+/// output device. Prints a line per binding; fails if any binding failed.
+pub fn run(mut config: Config) -> Result<()> {
+    // All output is read from the one virtual device
+    config.virtual_keyboard = false;
+    let profile = config
+        .active_profile()
+        .cloned()
+        .context("No profile to test")?;
+    let inputs: Vec<KeyCode> = profile
+        .enabled_bindings()
+        .flat_map(|b| b.input_keys())
+        .filter_map(parse_key_name)
+        .filter(|key| *key != WHEEL_LEFT && *key != WHEEL_RIGHT)
+        .collect();
+    let existing_nodes = event_nodes();
+    let mut source = synthetic::create_source(SOURCE_NAME, &inputs)?;
+    let source_path = synthetic::event_node(&mut source)?;
+
+    let runtime = tokio::runtime::Runtime::new().context("Failed to create tokio runtime")?;
+    let outcomes = runtime.block_on(async move {
+        let (msg_tx, mut msg_rx) = mpsc::unbounded_channel::<EngineMessage>();
+        let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
+        let (_trigger_tx, mut trigger_rx) = mpsc::unbounded_channel();
+
+        let engine_path = source_path.to_string_lossy().to_string();
+        let engine = tokio::spawn(async move {
+            crate::run_engine(&engine_path, &config, msg_tx, cancel_rx, &mut trigger_rx).await
+        });
+
+        let grabbed = tokio::time::timeout(START_TIMEOUT, async {
+            while let Some(msg) = msg_rx.recv().await {
+                match msg {
+                    EngineMessage::ConfigWarnings { warnings, .. } => {
+                        for warning in warnings {
+                            eprintln!("  warning: {}", warning);
+                        }
+                    }
+                    EngineMessage::State(state) if state.grabbed => return true,
+                    _ => {}
+                }
+            }
+            false
+        })
+        .await;
+        if !matches!(grabbed, Ok(true)) {
+            let _ = cancel_tx.send(true);
+            return match engine.await {
+                Ok(Err(e)) => Err(e.context("Engine failed to start on the test source")),
+                _ => bail!("Engine didn't grab the test source"),
+            };
+        }
+        tokio::spawn(async move { while msg_rx.recv().await.is_some() {} });
+
+        let result = async {
+            let mut output = Output::open(&existing_nodes).await?;
+            println!("Self-test of profile '{}':", profile.name);
+            let mut seen = HashSet::new();
+            let mut outcomes = Vec::new();
+            for binding in profile.enabled_bindings() {
+                let outcome = match plan(binding, &profile, &mut seen) {
+                    Ok(expect) => test_binding(binding, &expect, &mut source, &mut output).await,
+                    Err(outcome) => outcome,
+                };
+                let (action, target) = binding_output_summary(&binding.output);
+                let (status, detail) = match &outcome {
+                    Outcome::Pass(detail) => ("pass", detail),
+                    Outcome::Fail(detail) => ("FAIL", detail),
+                    Outcome::Skip(detail) => ("skip", detail),
+                };
+                println!(
+                    "  {}  {:<28} {:<12} {:<20} {}",
+                    status,
+                    binding.input_label(),
+                    action,
+                    target,
+                    detail
+                );
+                outcomes.push(outcome);
+            }
+            Ok(outcomes)
+        }
+        .await;
+
+        let _ = cancel_tx.send(true);
+        match engine.await {
+            Ok(engine_result) => engine_result.context("Engine failed during the self-test")?,
+            Err(e) => bail!("Engine task panicked: {}", e),
+        }
+        result
+    })?;
+
+    let count = |f: fn(&Outcome) -> bool| outcomes.iter().filter(|o| f(o)).count();
+    let passed = count(|o| matches!(o, Outcome::Pass(_)));
+    let failed = count(|o| matches!(o, Outcome::Fail(_)));
+    let skipped = count(|o| matches!(o, Outcome::Skip(_)));
+    println!("{} passed, {} failed, {} skipped", passed, failed, skipped);
+    if failed > 0 {
+        bail!("{} binding(s) failed", failed);
+    }
+    Ok(())
+}
+
+/// What to check for a binding, or why it can't be tested
+fn plan(
+    binding: &Binding,
+    profile: &Profile,
+    seen: &mut HashSet<String>,
+) -> Result<Expect, Outcome> {
+    for key in binding.input_keys() {
+        if parse_key_name(key).is_none() {
+            return Err(Outcome::Fail(format!("unknown input key '{}'", key)));
+        }
+    }
+    if !seen.insert(binding.input_label()) {
+        return Err(Outcome::Skip("shadowed by an earlier binding".to_string()));
+    }
+    if !binding.modifiers.is_empty() {
+        return Err(Outcome::Skip("needs keyboard modifiers held".to_string()));
+    }
+    if let Some(when) = &binding.when {
+        return Err(Outcome::Skip(format!("only applies with mode '{}'", when)));
+    }
+    if profile.swap_buttons
+        && binding.input_keys().iter().any(|key| {
+            matches!(
+                parse_key_name(key),
+                Some(KeyCode::BTN_LEFT | KeyCode::BTN_RIGHT)
+            )
+        })
+    {
+        return Err(Outcome::Skip("left/right are swapped".to_string()));
+    }
+
+    let output_key = |name: &str| {
+        parse_key_name(name).ok_or_else(|| Outcome::Fail(format!("unknown output key '{}'", name)))
+    };
+    match &binding.output {
+        BindingOutput::Key { key } => Ok(Expect::Key(output_key(key)?)),
+        BindingOutput::ToggleKey { toggle_key } => Ok(Expect::ToggleKey(output_key(toggle_key)?)),
+        BindingOutput::Scroll { scroll } => {
+            let axis = match scroll.axis {
+                ScrollAxis::Wheel => RelativeAxisCode::REL_WHEEL,
+                ScrollAxis::HWheel => RelativeAxisCode::REL_HWHEEL,
+            };
+            Ok(Expect::Scroll(axis, scroll.amount))
+        }
+        BindingOutput::Macro { macro_name, .. } => {
+            match profile.macros.iter().find(|m| &m.name == macro_name) {
+                Some(def) => Ok(Expect::Macro {
+                    toggle: def.macro_type == MacroType::Toggle,
+                }),
+                None => Err(Outcome::Fail(format!(
+                    "macro '{}' isn't defined",
+                    macro_name
+                ))),
+            }
+        }
+        _ => Err(Outcome::Skip(
+            "switches engine state, nothing to see on the output".to_string(),
+        )),
+    }
+}
+
+async fn test_binding(
+    binding: &Binding,
+    expect: &Expect,
+    source: &mut VirtualDevice,
+    output: &mut Output,
+) -> Outcome {
+    output.settle().await;
+    let inputs: Vec<KeyCode> = binding
+        .input_keys()
+        .iter()
+        .filter_map(|key| parse_key_name(key))
+        .collect();
+    let mut press = |value: i32| -> Result<()> {
+        let order: Vec<&KeyCode> = match value {
+            1 => inputs.iter().collect(),
+            _ => inputs.iter().rev().collect(),
+        };
+        for key in order {
+            if let Some(event) = input_event(*key, value) {
+                source
+                    .emit(&[event])
+                    .context("Failed to write to the test source")?;
+            }
+        }
+        Ok(())
+    };
+
+    let outcome = async {
+        Ok(match *expect {
+            Expect::Key(key) => {
+                press(1)?;
+                if !output
+                    .wait_for(OUTPUT_TIMEOUT, |events| has_key(events, key, 1))
+                    .await
+                {
+                    return Ok(Outcome::Fail(format!("no {} press", key_name(key))));
+                }
+                press(0)?;
+                if !output
+                    .wait_for(OUTPUT_TIMEOUT, |events| {
+                        events
+                            .iter()
+                            .position(|e| is_key(e, key, 1))
+                            .is_some_and(|i| has_key(&events[i..], key, 0))
+                    })
+                    .await
+                {
+                    return Ok(Outcome::Fail(format!("{} isn't released", key_name(key))));
+                }
+                Outcome::Pass(format!("{} pressed and released", key_name(key)))
+            }
+            Expect::ToggleKey(key) => {
+                press(1)?;
+                press(0)?;
+                if !output
+                    .wait_for(OUTPUT_TIMEOUT, |events| has_key(events, key, 1))
+                    .await
+                {
+                    return Ok(Outcome::Fail(format!("no {} press", key_name(key))));
+                }
+                output.settle().await;
+                press(1)?;
+                press(0)?;
+                if !output
+                    .wait_for(OUTPUT_TIMEOUT, |events| has_key(events, key, 0))
+                    .await
+                {
+                    return Ok(Outcome::Fail(format!(
+                        "{} isn't released by the second click",
+                        key_name(key)
+                    )));
+                }
+                Outcome::Pass(format!("{} latched and released", key_name(key)))
+            }
+            Expect::Scroll(axis, amount) => {
+                press(1)?;
+                press(0)?;
+                let scrolled = |events: &[InputEvent]| -> i32 {
+                    events
+                        .iter()
+                        .filter(|e| e.event_type() == EventType::RELATIVE && e.code() == axis.0)
+                        .map(|e| e.value())
+                        .sum()
+                };
+                if !output
+                    .wait_for(OUTPUT_TIMEOUT, |events| scrolled(events) == amount)
+                    .await
+                {
+                    return Ok(Outcome::Fail(format!(
+                        "scrolled {} instead of {} notch(es)",
+                        scrolled(&output.events),
+                        amount
+                    )));
+                }
+                Outcome::Pass(format!("scrolled {} notch(es)", amount))
+            }
+            Expect::Macro { toggle } => {
+                press(1)?;
+                let produced = output
+                    .wait_for(MACRO_TIMEOUT, |events| !events.is_empty())
+                    .await;
+                press(0)?;
+                if toggle {
+                    press(1)?;
+                    press(0)?;
+                }
+                if !produced {
+                    return Ok(Outcome::Fail(format!(
+                        "no output within {}s",
+                        MACRO_TIMEOUT.as_secs()
+                    )));
+                }
+                Outcome::Pass("produced output".to_string())
+            }
+        })
+    }
+    .await;
+    outcome.unwrap_or_else(|e: anyhow::Error| Outcome::Fail(format!("{:#}", e)))
+}
+
+/// The source event for pressing (1) or releasing (0) an input key; a wheel
+/// tilt is a single notch, there's nothing to release
+fn input_event(key: KeyCode, value: i32) -> Option<InputEvent> {
+    let tilt = match key {
+        WHEEL_LEFT => -1,
+        WHEEL_RIGHT => 1,
+        _ => return Some(InputEvent::new(EventType::KEY.0, key.code(), value)),
+    };
+    (value == 1)
+        .then(|| InputEvent::new(EventType::RELATIVE.0, RelativeAxisCode::REL_HWHEEL.0, tilt))
+}
+
+fn is_key(event: &InputEvent, key: KeyCode, value: i32) -> bool {
+    event.event_type() == EventType::KEY && event.code() == key.code() && event.value() == value
+}
+
+fn has_key(events: &[InputEvent], key: KeyCode, value: i32) -> bool {
+    events.iter().any(|e| is_key(e, key, value))
+}
+
+/// The engine's output device, grabbed so nothing the test triggers reaches
+/// the desktop
+struct Output {
+    rx: mpsc::UnboundedReceiver<InputEvent>,
+    /// Events read since the last `settle`, SYN_REPORT and MSC left out
+    events: Vec<InputEvent>,
+}
+
+impl Output {
+    /// Find the output device the engine created (one that wasn't among
+    /// `existing` nodes) and start reading it
+    async fn open(existing: &HashSet<PathBuf>) -> Result<Self> {
+        let started = Instant::now();
+        let path = loop {
+            let created = event_nodes()
+                .into_iter()
+                .filter(|path| !existing.contains(path))
+                .find(|path| {
+                    evdev::Device::open(path).is_ok_and(|d| d.name() == Some(VIRTUAL_DEVICE_NAME))
+                });
+            match created {
+                Some(path) => break path,
+                None if started.elapsed() > START_TIMEOUT => {
+                    bail!("The engine's output device didn't show up")
+                }
+                None => tokio::time::sleep(Duration::from_millis(50)).await,
+            }
+        };
+
+        let mut reader = DeviceReader::open(&path)?;
+        reader.grab()?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        // Ends with an error once the engine stops and the device goes away
+        tokio::task::spawn_blocking(move || reader.read_loop(tx));
+        Ok(Self { rx, events: vec![] })
+    }
+
+    /// Read until `done` holds for the events so far; false if it didn't
+    /// within `timeout`
+    async fn wait_for(&mut self, timeout: Duration, done: impl Fn(&[InputEvent]) -> bool) -> bool {
+        let deadline = Instant::now() + timeout;
+        while !done(&self.events) {
+            match tokio::time::timeout_at(deadline, self.rx.recv()).await {
+                Ok(Some(event)) => {
+                    if event.event_type() != EventType::SYNCHRONIZATION
+                        && event.event_type() != EventType::MISC
+                    {
+                        self.events.push(event);
+                    }
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Let the output of the previous binding die down and forget it
+    async fn settle(&mut self) {
+        let deadline = Instant::now() + SETTLE_TIMEOUT;
+        while Instant::now() < deadline {
+            match tokio::time::timeout(QUIET_TIME, self.rx.recv()).await {
+                Ok(Some(_)) => continue,
+                _ => break,
+            }
+        }
+        self.events.clear();
+    }
+}
+
+/// The /dev/input/eventN nodes there are now
+fn event_nodes() -> HashSet<PathBuf> {
+    std::fs::read_dir("/dev/input")
+        .map(|dir| {
+            dir.flatten()
+                .map(|entry| entry.path())
+                .filter(|path| is_event_node(path))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn is_event_node(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("event"))
+}
@@ -0,0 +1,265 @@
+use crate::config::Config;
+use crate::device::synthetic;
+use crate::engine::metrics::{self, LATENCY_BUCKETS};
+use crate::tui::app::EngineMessage;
+use anyhow::{bail, Context, Result};
+use evdev::uinput::VirtualDevice;
+use evdev::{EventType, InputEvent, KeyCode, RelativeAxisCode};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Name of the virtual mouse the generator writes to
+const SOURCE_NAME: &str = "MouseMapper Stress Source";
+/// Buttons stormed unless --button is given: ones desktops rarely act on
+pub const DEFAULT_BUTTONS: [KeyCode; 3] = [KeyCode::BTN_TASK, KeyCode::BTN_0, KeyCode::BTN_1];
+/// In the mixed pattern, every this many motion reports also carries a button
+const MIXED_BUTTON_EVERY: u64 = 16;
+/// Longest to wait for the engine to grab the source device
+const GRAB_TIMEOUT: Duration = Duration::from_secs(5);
+/// After generating, the engine is done once nothing was processed for this long
+const SETTLE_TIME: Duration = Duration::from_millis(300);
+/// ... or at the latest after this long
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Event pattern of the stress generator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StressPattern {
+    /// Pointer motion: REL_X and REL_Y jittering by one pixel
+    Rel,
+    /// Button press/release storm
+    Buttons,
+    /// Motion with a button press or release in every 16th report
+    Mixed,
+}
+
+pub struct StressOptions {
+    /// Reports (batches ending in SYN_REPORT) per second
+    pub rate: u32,
+    pub duration: Duration,
+    pub pattern: StressPattern,
+    pub buttons: Vec<KeyCode>,
+}
+
+/// What the generator thread wrote
+struct Generated {
+    reports: u64,
+    /// SYN_REPORTs included, as the engine reads those too
+    events: u64,
+    elapsed: Duration,
+}
+
+/// Create a virtual source mouse, run the engine on it with the active
+/// profile and write `options.pattern` to it, then print how much of it the
+/// engine got through and how fast
+pub fn run(config: Config, options: StressOptions) -> Result<()> {
+    if options.rate == 0 {
+        bail!("--rate must be at least 1");
+    }
+    let mut source = synthetic::create_source(SOURCE_NAME, &options.buttons)?;
+    let path = synthetic::event_node(&mut source)?;
+
+    let runtime = tokio::runtime::Runtime::new().context("Failed to create tokio runtime")?;
+    runtime.block_on(async move {
+        let (msg_tx, mut msg_rx) = mpsc::unbounded_channel::<EngineMessage>();
+        let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
+        let (_trigger_tx, mut trigger_rx) = mpsc::unbounded_channel();
+
+        let engine_path = path.to_string_lossy().to_string();
+        let engine = tokio::spawn(async move {
+            crate::run_engine(&engine_path, &config, msg_tx, cancel_rx, &mut trigger_rx).await
+        });
+
+        // Only write once the source is grabbed, or the desktop gets it raw
+        let grabbed = tokio::time::timeout(GRAB_TIMEOUT, async {
+            while let Some(msg) = msg_rx.recv().await {
+                match msg {
+                    EngineMessage::ConfigWarnings { warnings, .. } => {
+                        for warning in warnings {
+                            eprintln!("  warning: {}", warning);
+                        }
+                    }
+                    EngineMessage::State(state) if state.grabbed => {
+                        eprintln!(
+                            "Grabbed {} ({}): profile {}, {} binding(s), {} macro(s)",
+                            state.device_name,
+                            state.device,
+                            state.profile.as_deref().unwrap_or("(none)"),
+                            state.bindings_loaded,
+                            state.macros_loaded
+                        );
+                        return true;
+                    }
+                    _ => {}
+                }
+            }
+            false
+        })
+        .await;
+        if !matches!(grabbed, Ok(true)) {
+            let _ = cancel_tx.send(true);
+            return match engine.await {
+                Ok(Err(e)) => Err(e.context("Engine failed to start on the stress source")),
+                _ => bail!("Engine didn't grab the stress source"),
+            };
+        }
+        // The engine's monitor messages aren't looked at, but must not pile up
+        tokio::spawn(async move { while msg_rx.recv().await.is_some() {} });
+
+        eprintln!(
+            "Writing the {:?} pattern at {} report(s)/s for {:.1}s; the mapped output reaches the desktop",
+            options.pattern,
+            options.rate,
+            options.duration.as_secs_f64()
+        );
+        let before = metrics::snapshot();
+        let generator = tokio::task::spawn_blocking(move || generate(&mut source, &options));
+        let generated = generator.await.context("Generator thread panicked")??;
+        let drain_started = Instant::now();
+        wait_until_settled(generated.events, before).await;
+        let drain_time = drain_started.elapsed();
+
+        let _ = cancel_tx.send(true);
+        match engine.await {
+            Ok(result) => result.context("Engine failed during the stress run")?,
+            Err(e) => bail!("Engine task panicked: {}", e),
+        }
+
+        print_report(&generated, &metrics::snapshot().since(&before), drain_time);
+        Ok(())
+    })
+}
+
+/// Write reports at `options.rate` for `options.duration`; when writing falls
+/// behind, reports are written back to back until it catches up
+fn generate(source: &mut VirtualDevice, options: &StressOptions) -> Result<Generated> {
+    let mut reports = 0u64;
+    let mut events = 0u64;
+    let mut batch = Vec::with_capacity(3);
+    let started = Instant::now();
+    loop {
+        let elapsed = started.elapsed();
+        if elapsed >= options.duration {
+            return Ok(Generated {
+                reports,
+                events,
+                elapsed,
+            });
+        }
+        let due = (elapsed.as_secs_f64() * f64::from(options.rate)) as u64;
+        while reports < due {
+            batch.clear();
+            pattern_report(options, reports, &mut batch);
+            source
+                .emit(&batch)
+                .context("Failed to write to the stress source")?;
+            // emit() ends every batch with a SYN_REPORT
+            events += batch.len() as u64 + 1;
+            reports += 1;
+        }
+        std::thread::sleep(Duration::from_micros(100));
+    }
+}
+
+/// The events of report number `n` (without its SYN_REPORT)
+fn pattern_report(options: &StressOptions, n: u64, batch: &mut Vec<InputEvent>) {
+    let motion = |batch: &mut Vec<InputEvent>| {
+        // Back and forth, so the pointer stays where it is
+        let delta = if n.is_multiple_of(2) { 1 } else { -1 };
+        batch.push(InputEvent::new(
+            EventType::RELATIVE.0,
+            RelativeAxisCode::REL_X.0,
+            delta,
+        ));
+        batch.push(InputEvent::new(
+            EventType::RELATIVE.0,
+            RelativeAxisCode::REL_Y.0,
+            delta,
+        ));
+    };
+    // The k-th button event: press then release of one button, then the next,
+    // so no button is left down and none repeats a state (the kernel would
+    // drop that)
+    let button = |batch: &mut Vec<InputEvent>, k: u64| {
+        let code = options.buttons[(k / 2 % options.buttons.len() as u64) as usize];
+        let value = if k.is_multiple_of(2) { 1 } else { 0 };
+        batch.push(InputEvent::new(EventType::KEY.0, code.0, value));
+    };
+
+    match options.pattern {
+        StressPattern::Rel => motion(batch),
+        StressPattern::Buttons => button(batch, n),
+        StressPattern::Mixed => {
+            motion(batch);
+            if n.is_multiple_of(MIXED_BUTTON_EVERY) {
+                button(batch, n / MIXED_BUTTON_EVERY);
+            }
+        }
+    }
+}
+
+/// Wait for the engine to work through what's still queued: until it has
+/// processed `sent` events since `before`, or stops making progress
+async fn wait_until_settled(sent: u64, before: metrics::Snapshot) {
+    let started = Instant::now();
+    let mut last = metrics::snapshot().events_processed;
+    let mut last_change = Instant::now();
+    while started.elapsed() < DRAIN_TIMEOUT {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let processed = metrics::snapshot().events_processed;
+        if processed - before.events_processed >= sent {
+            return;
+        }
+        if processed != last {
+            last = processed;
+            last_change = Instant::now();
+        } else if last_change.elapsed() >= SETTLE_TIME {
+            return;
+        }
+    }
+}
+
+fn print_report(generated: &Generated, run: &metrics::Snapshot, drain_time: Duration) {
+    let seconds = generated.elapsed.as_secs_f64();
+    let processing_seconds = (generated.elapsed + drain_time).as_secs_f64();
+    let dropped = generated.events.saturating_sub(run.events_processed);
+
+    println!(
+        "Sent       {} report(s), {} event(s) in {:.2}s ({:.0} reports/s, {:.0} events/s)",
+        generated.reports,
+        generated.events,
+        seconds,
+        generated.reports as f64 / seconds,
+        generated.events as f64 / seconds
+    );
+    println!(
+        "Processed  {} event(s) ({:.0} events/s)",
+        run.events_processed,
+        run.events_processed as f64 / processing_seconds
+    );
+    println!(
+        "Dropped    {} event(s) ({:.2}%)",
+        dropped,
+        dropped as f64 * 100.0 / generated.events.max(1) as f64
+    );
+    println!("Emitted    {} event(s)", run.events_emitted);
+
+    let count = run.latency_count();
+    if count == 0 {
+        println!("Latency    no output was written");
+        return;
+    }
+    let bound = |q: f64| match run.latency_quantile(q) {
+        Some(le) => format!("<= {}ms", le * 1000.0),
+        None => format!(
+            "> {}ms",
+            LATENCY_BUCKETS[LATENCY_BUCKETS.len() - 1] * 1000.0
+        ),
+    };
+    println!(
+        "Latency    mean {:.3}ms, p50 {}, p99 {} ({} sample(s))",
+        run.latency_sum.as_secs_f64() * 1000.0 / count as f64,
+        bound(0.5),
+        bound(0.99),
+        count
+    );
+}
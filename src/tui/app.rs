@@ -1,8 +1,70 @@
-use crate::config::{Binding, BindingOutput, Config, MacroAction, MacroDef, MacroType};
+use crate::api::{ApiRequest, ApiResponse};
+use crate::backup;
+use crate::config::{
+    format_macro_params, parse_macro_params, Binding, BindingOutput, ClickStep, Config, DpiStep,
+    KeyModifier, MacroAction, MacroDef, MacroType, ModeSwitch, Profile, ScrollOutput, WarpConfig,
+};
+use crate::device::battery::{self, BatteryInfo};
+use crate::device::ratbag::{ButtonAction, Ratbag, RatbagDevice, RatbagProfile};
 use crate::device::scanner::{self, DeviceInfo};
-use std::time::Instant;
+use crate::device::sensor::{self, SensorInfo};
+use crate::device::watch;
+use crate::engine::macros::{self, MacroEvent};
+use crate::engine::metrics;
+use crate::engine::sounds;
+use crate::export::{self, ExportFormat};
+use crate::import;
+use crate::logging::{self, LogLine, LogTail};
+use crate::tui::keymap::{Action, Keymap};
+use crate::tui::palette::{CommandPalette, PaletteAction};
+use crate::tui::templates;
+use crate::tui::theme::Theme;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc as std_mpsc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+/// How often battery levels are re-read
+const BATTERY_POLL_INTERVAL: Duration = Duration::from_secs(300);
+
+/// How often the selected mouse's DPI and report rate are re-read, so
+/// changes made with its onboard DPI button show up
+const SENSOR_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long /dev/input must be quiet after a change before the device list
+/// is rescanned: a plugged in mouse brings several nodes at once
+const DEVICE_SETTLE: Duration = Duration::from_millis(300);
+
+/// Macro playback speeds that < and > step through
+const MACRO_SPEEDS: [f64; 9] = [0.1, 0.25, 0.5, 0.75, 1.0, 1.5, 2.0, 4.0, 10.0];
+
+/// Result of one background battery read: device path -> level
+type BatteryResults = Vec<(PathBuf, Option<BatteryInfo>)>;
+
+/// What a background ratbagd call was for, see `App::poll_ratbag`
+enum RatbagJob {
+    /// Connect if needed and read the devices; `report` says how many
+    Refresh { report: bool },
+    /// A setter followed by a re-read, with the status to show if it worked
+    Apply(String),
+    /// Writing pending changes to the device, with the status to show
+    Commit(String),
+}
+
+/// Result of one background ratbagd call
+struct RatbagReply {
+    job: RatbagJob,
+    /// The connection used, or why connecting failed
+    ratbag: anyhow::Result<Ratbag>,
+    /// Result of the setter or commit (Ok for a refresh)
+    result: anyhow::Result<()>,
+    /// Devices re-read after a refresh or a setter that worked
+    devices: Option<anyhow::Result<Vec<RatbagDevice>>>,
+}
+
 /// Which tab is currently active
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Tab {
@@ -10,11 +72,20 @@ pub enum Tab {
     Bindings,
     Macros,
     Monitor,
+    Logs,
+    Hardware,
 }
 
 impl Tab {
     pub fn all() -> &'static [Tab] {
-        &[Tab::Devices, Tab::Bindings, Tab::Macros, Tab::Monitor]
+        &[
+            Tab::Devices,
+            Tab::Bindings,
+            Tab::Macros,
+            Tab::Monitor,
+            Tab::Logs,
+            Tab::Hardware,
+        ]
     }
 
     pub fn title(&self) -> &str {
@@ -23,6 +94,8 @@ impl Tab {
             Tab::Bindings => "Bindings",
             Tab::Macros => "Macros",
             Tab::Monitor => "Monitor",
+            Tab::Logs => "Logs",
+            Tab::Hardware => "Hardware",
         }
     }
 
@@ -31,16 +104,20 @@ impl Tab {
             Tab::Devices => Tab::Bindings,
             Tab::Bindings => Tab::Macros,
             Tab::Macros => Tab::Monitor,
-            Tab::Monitor => Tab::Devices,
+            Tab::Monitor => Tab::Logs,
+            Tab::Logs => Tab::Hardware,
+            Tab::Hardware => Tab::Devices,
         }
     }
 
     pub fn prev(&self) -> Tab {
         match self {
-            Tab::Devices => Tab::Monitor,
+            Tab::Devices => Tab::Hardware,
             Tab::Bindings => Tab::Devices,
             Tab::Macros => Tab::Bindings,
             Tab::Monitor => Tab::Macros,
+            Tab::Logs => Tab::Monitor,
+            Tab::Hardware => Tab::Logs,
         }
     }
 }
@@ -56,79 +133,370 @@ pub enum InputMode {
     Capturing { field: CaptureField },
     /// Confirming an action
     Confirming(String),
+    /// Asking whether to save unsaved changes before quitting
+    ConfirmQuit,
+}
+
+/// Which list has focus on the Hardware tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardwareFocus {
+    Profiles,
+    Resolutions,
+    Buttons,
+}
+
+impl HardwareFocus {
+    pub fn next(&self) -> HardwareFocus {
+        match self {
+            HardwareFocus::Profiles => HardwareFocus::Resolutions,
+            HardwareFocus::Resolutions => HardwareFocus::Buttons,
+            HardwareFocus::Buttons => HardwareFocus::Profiles,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CaptureField {
     BindingInput,
     BindingOutput,
+    /// Key of the selected step in the macro action editor
+    MacroAction,
+    /// New step inserted after the selected one: a Press if `hold`, else a Click
+    MacroInsert {
+        hold: bool,
+    },
+    /// Button of the selected bulk editor row
+    GridInput,
+    /// Key the selected bulk editor row is remapped to
+    GridOutput,
 }
 
 /// Messages from the engine to the TUI
 #[derive(Debug, Clone)]
 pub enum EngineMessage {
-    /// A raw input event was received (for the monitor tab)
+    /// A raw input event was received from the device at path `device` (for
+    /// the monitor tab)
     RawEvent {
+        device: String,
         event_type: String,
         code: String,
         value: i32,
         timestamp: String,
     },
+    /// A raw HID report was read from a hidraw node (for the monitor tab)
+    HidReport {
+        hidraw: String,
+        report: Vec<u8>,
+        timestamp: String,
+    },
     /// Engine status changed
     StatusUpdate(String),
-    /// Engine encountered an error
-    Error(String),
+    /// State of the engine on one device, sent whenever it changes
+    State(EngineState),
+    /// Bindings/macros of the profile that didn't load or won't work, found
+    /// when the engine started on `device`
+    ConfigWarnings {
+        device: String,
+        warnings: Vec<String>,
+    },
+    /// The device at this path was unplugged; the engine resumes when it's back
+    DeviceWaiting(String),
+    /// The engine on a device failed and is restarted after `delay_secs`
+    EngineRestart {
+        path: String,
+        attempt: u32,
+        max_attempts: u32,
+        delay_secs: u64,
+        error: String,
+    },
+    /// The engine stopped handling a device because of an error
+    DeviceError { path: String, error: String },
+    /// Software DPI stage changed: (index, number of stages, multiplier)
+    DpiStage(Option<(usize, usize, f64)>),
+    /// Named modes that are on changed
+    Modes(Vec<String>),
+    /// Repeat/toggle macros that are running changed
+    RunningMacros(Vec<String>),
+    /// Macro lifecycle event (started, iteration, stopped, error)
+    Macro(MacroEvent),
+    /// The process got this termination signal: quit now, without asking
+    /// about unsaved changes
+    Terminate(&'static str),
+    /// A toggle_profile binding switched the engine on a device to this
+    /// profile
+    ProfileSwitched(String),
 }
 
 /// Commands from the TUI to the engine
 #[derive(Debug, Clone)]
 pub enum EngineCommand {
-    /// Start the engine with the given device path
-    Start(String),
-    /// Stop the engine
+    /// Start the engine on the given device paths (each gets its own mapper
+    /// and virtual device)
+    Start(Vec<String>),
+    /// Read the given device paths without grabbing them, only forwarding
+    /// their events to the monitor: no mapping and no virtual device
+    Observe(Vec<String>),
+    /// Stop the engine (mapping or observing)
     Stop,
+    /// Map with this profile instead of the config's active one, restarting
+    /// a running engine
+    SwitchProfile(String),
+    /// Run the named macro of the profile on the first device, as if a
+    /// button bound to it was pressed
+    TriggerMacro(String),
+    /// Read the given device without grabbing it, forwarding button presses
+    /// for capture while the engine isn't running
+    StartCapture(String),
+    /// Stop a capture-only read started with StartCapture
+    StopCapture,
+    /// Read the hidraw node behind the given evdev device, forwarding its raw
+    /// HID reports to the monitor
+    StartHidMonitor(String),
+    /// Stop a hidraw read started with StartHidMonitor
+    StopHidMonitor,
+    /// Change the playback speed of all macros, running ones included
+    SetMacroSpeed(f64),
+    /// Pause or resume all repeat and toggle macros
+    PauseMacros(ModeSwitch),
     /// Reload config
     ReloadConfig,
     /// Shutdown everything
     Shutdown,
 }
 
+/// Machine-readable state of the engine on one device
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EngineState {
+    /// Device path the engine was started on
+    pub device: String,
+    pub device_name: String,
+    pub grabbed: bool,
+    /// Reading the device without a grab (observe-only engine)
+    pub observing: bool,
+    /// Profile whose bindings are loaded
+    pub profile: Option<String>,
+    /// Time since the engine started on the device (as of sending)
+    pub uptime: Duration,
+    pub bindings_loaded: usize,
+    pub macros_loaded: usize,
+}
+
+/// Engine state of one device it was asked to grab
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineDeviceState {
+    Starting,
+    Grabbed,
+    /// Read without a grab by the observe-only engine
+    Observing,
+    /// Unplugged, waiting for it to come back
+    Waiting,
+    /// Failed, restart attempt (n, max) pending
+    Restarting(u32, u32),
+    Failed(String),
+}
+
 /// Application state
 pub struct App {
     pub config: Config,
+    /// Snapshot of the config as last loaded from / saved to disk
+    pub saved_config: Config,
+    pub theme: Theme,
+    /// Main screen keys, `[keybindings]` applied to the defaults
+    pub keymap: Keymap,
     pub current_tab: Tab,
     pub input_mode: InputMode,
+    /// Mode to go back to when quitting is cancelled (Ctrl+C can come from
+    /// any mode)
+    pub mode_before_quit: InputMode,
     pub should_quit: bool,
+    /// Command palette opened with Ctrl+P
+    pub command_palette: Option<CommandPalette>,
 
     // Device tab state
     pub devices: Vec<DeviceInfo>,
     pub device_list_index: usize,
     pub selected_device: Option<DeviceInfo>,
+    /// Devices tagged with Space, grabbed together with Enter
+    pub tagged_devices: HashSet<PathBuf>,
     pub engine_running: bool,
+    /// The running engine only observes its devices (no grab, no mapping)
+    pub engine_observing: bool,
+    /// Per-device state of the running engine
+    pub engine_devices: HashMap<PathBuf, EngineDeviceState>,
+    /// Config problems the engine reported when it started (shown in the
+    /// Devices tab's engine panel)
+    pub engine_warnings: Vec<String>,
+    /// Latest reported engine state per device, with when it arrived
+    pub engine_states: HashMap<PathBuf, (EngineState, Instant)>,
+    /// Recent automatic engine restarts, oldest first
+    pub engine_restarts: Vec<EngineRestart>,
+    /// Software DPI stage reported by the running engine
+    pub dpi_stage: Option<(usize, usize, f64)>,
+    /// Named modes that are on in the running engine
+    pub active_modes: Vec<String>,
+    /// Repeat/toggle macros running in the engine
+    pub running_macros: Vec<String>,
+    /// Latest run of each macro by name, from the engine's macro events
+    pub macro_activity: HashMap<String, MacroActivity>,
+    /// Last known battery level per device path (wireless mice only)
+    pub battery_levels: HashMap<PathBuf, BatteryInfo>,
+    /// Devices we already warned about, so the warning isn't repeated every poll
+    pub battery_warned: HashSet<PathBuf>,
+    pub battery_polled: Option<Instant>,
+    battery_rx: Option<std_mpsc::Receiver<BatteryResults>>,
+    /// Hardware DPI and report rate per device path (supported mice only)
+    pub sensor_info: HashMap<PathBuf, SensorInfo>,
+    /// Device and time of the last sensor read
+    sensor_polled: Option<(PathBuf, Instant)>,
+    sensor_rx: Option<std_mpsc::Receiver<(PathBuf, Option<SensorInfo>)>>,
+    /// Changes of /dev/input (None if it can't be watched)
+    device_watch_rx: Option<std_mpsc::Receiver<()>>,
+    /// Time of the last change not rescanned yet
+    devices_changed: Option<Instant>,
 
     // Bindings tab state
     pub binding_list_index: usize,
     pub editing_binding: Option<EditingBinding>,
+    /// Set while `editing_binding` is being filled by the guided wizard
+    pub wizard_step: Option<WizardStep>,
+    /// Presses of the new binding's button seen on the wizard's test step
+    pub wizard_test_presses: u32,
+    /// Path being typed for "import bindings from file"
+    pub import_path: Option<String>,
+    /// Path being typed for "restore the config from a backup"
+    pub restore_path: Option<String>,
+    /// Diff shown for confirmation before saving
+    pub save_preview: Option<SavePreview>,
+    /// First visible row of the Bindings/Macros tables, kept so the selected
+    /// row stays in view
+    pub binding_list_offset: usize,
+    pub macro_list_offset: usize,
+    /// Rows the last drawn Bindings/Macros table had room for (PgUp/PgDn step)
+    pub list_page_rows: usize,
+    /// Bindings list filter (`/`)
+    pub binding_filter: String,
+    /// Only list the bindings with this tag (`t` cycles through them)
+    pub binding_tag_filter: Option<String>,
+    /// Macros list filter (`/`)
+    pub macro_filter: String,
+    /// True while typing the filter of the current tab's list
+    pub list_filter_editing: bool,
+    /// Bulk binding editor for button grids, while open
+    pub grid_editor: Option<GridEditor>,
 
     // Macros tab state
     pub macro_list_index: usize,
     pub editing_macro: Option<EditingMacro>,
+    /// Selected row while the "new macro" template picker is open
+    pub template_picker: Option<usize>,
+    /// Values typed for the chosen template's parameters, while asking for them
+    pub template_values: Option<Vec<String>>,
+    /// Parameter being typed in `template_values`
+    pub template_field: usize,
 
     // Monitor tab state
     pub monitor_events: Vec<EngineMessage>,
     pub monitor_paused: bool,
+    /// Device whose hidraw reports are shown in the monitor
+    pub monitor_hid: Option<PathBuf>,
+    /// Show a pane per device instead of one interleaved stream
+    pub monitor_split: bool,
     pub monitor_max_events: usize,
 
+    // Logs tab state
+    pub log_tail: LogTail,
+    /// Most verbose level shown
+    pub log_min_level: log::Level,
+    /// Case-insensitive substring filter
+    pub log_filter: String,
+    pub log_filter_editing: bool,
+    /// How many lines the view is scrolled up from the bottom (0 = follow)
+    pub log_scroll: usize,
+
+    // Hardware tab state (onboard settings via ratbagd)
+    pub ratbag: Option<Ratbag>,
+    pub ratbag_devices: Vec<RatbagDevice>,
+    /// Set once the tab has tried to connect, so it doesn't retry every frame
+    pub ratbag_loaded: bool,
+    pub ratbag_error: Option<String>,
+    pub ratbag_device_index: usize,
+    pub ratbag_profile_index: usize,
+    pub ratbag_focus: HardwareFocus,
+    /// Selected row in the Resolutions or Buttons list
+    pub ratbag_row: usize,
+    /// Changes made through ratbagd that haven't been committed to the device
+    pub ratbag_uncommitted: bool,
+    /// Reply of the ratbagd call running in the background, if one is
+    ratbag_rx: Option<std_mpsc::Receiver<RatbagReply>>,
+
     // Communication channels
     pub engine_cmd_tx: Option<mpsc::UnboundedSender<EngineCommand>>,
     pub engine_msg_rx: Option<mpsc::UnboundedReceiver<EngineMessage>>,
+    /// Requests of the HTTP API (None unless it's configured)
+    pub api_rx: Option<mpsc::UnboundedReceiver<ApiRequest>>,
 
     /// True while waiting for a mouse button press to capture via the engine event stream
     pub capturing: bool,
+    /// True while the TUI reads the device on its own (engine not running),
+    /// for a capture or the wizard's test step
+    pub capture_only: bool,
+    /// Buttons held so far while capturing a binding input, in press order
+    pub capture_chord: Vec<String>,
 
     // Status bar
     pub status_message: String,
     pub status_time: Instant,
+    /// Past status messages, oldest first (shown with N)
+    pub notifications: VecDeque<Notification>,
+}
+
+/// How a status message is colored in the notification history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A past status message
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub at: Instant,
+    pub severity: Severity,
+    pub text: String,
+}
+
+/// Messages kept in `App::notifications`
+const MAX_NOTIFICATIONS: usize = 200;
+
+/// What saving would change on disk, as unified diff lines
+#[derive(Debug, Clone)]
+pub struct SavePreview {
+    pub lines: Vec<String>,
+    /// First line shown
+    pub scroll: usize,
+}
+
+/// A restart of a failed engine run, for the Devices tab history
+#[derive(Debug, Clone)]
+pub struct EngineRestart {
+    pub at: Instant,
+    pub path: String,
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub error: String,
+}
+
+/// Restarts kept in `App::engine_restarts`
+const MAX_ENGINE_RESTART_HISTORY: usize = 20;
+
+/// Latest run of a macro as reported by the engine
+#[derive(Debug, Clone, Default)]
+pub struct MacroActivity {
+    pub running: bool,
+    /// Passes over the actions in the current or last run
+    pub iterations: u64,
+    pub error: Option<String>,
 }
 
 /// State for editing a binding
@@ -136,16 +504,254 @@ pub struct App {
 pub struct EditingBinding {
     pub index: Option<usize>, // None = new binding
     pub input: String,
+    /// Keyboard modifiers the binding requires
+    pub modifiers: Vec<KeyModifier>,
     pub output_type: BindingOutputType,
     pub output_value: String,
-    pub field_index: usize,        // 0=input, 1=output_type, 2=output_value
+    /// How a SetMode output switches its mode (output_value holds the name)
+    pub mode_switch: ModeSwitch,
+    /// Mode condition, "" for none
+    pub when: String,
+    pub cooldown_ms: String,
+    /// Macro arguments as "name=value, ...", for Macro outputs
+    pub args: String,
+    /// Note on the binding, "" for none
+    pub description: String,
+    /// Tags as "work, fps"
+    pub tags: String,
+    pub field_index: usize, // 0=input, 1=output_type, 2=output_value, 3=when, 4=cooldown, 5=args, 6=description, 7=tags
     pub macro_select_index: usize, // index in the macro list when output_type is Macro
 }
 
+impl EditingBinding {
+    /// Axis and amount text of a Scroll output being edited ("wheel", "-3")
+    fn scroll_parts(&self) -> (String, String) {
+        match self.output_value.split_once(' ') {
+            Some((axis, amount)) => (axis.to_string(), amount.to_string()),
+            None => ("wheel".to_string(), self.output_value.clone()),
+        }
+    }
+
+    /// Switch a Scroll output between the vertical and horizontal wheel
+    pub fn flip_scroll_axis(&mut self) {
+        let (axis, amount) = self.scroll_parts();
+        let axis = if axis == "hwheel" { "wheel" } else { "hwheel" };
+        self.output_value = format!("{} {}", axis, amount);
+    }
+
+    /// Type into a Scroll output's amount: a digit, or '-' to flip the direction
+    pub fn type_scroll_amount(&mut self, c: char) {
+        let (axis, mut amount) = self.scroll_parts();
+        if c == '-' {
+            amount = match amount.strip_prefix('-') {
+                Some(rest) => rest.to_string(),
+                None => format!("-{}", amount),
+            };
+        } else if c.is_ascii_digit() {
+            amount.push(c);
+        } else {
+            return;
+        }
+        self.output_value = format!("{} {}", axis, amount);
+    }
+
+    pub fn erase_scroll_amount(&mut self) {
+        let (axis, mut amount) = self.scroll_parts();
+        amount.pop();
+        self.output_value = format!("{} {}", axis, amount);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum BindingOutputType {
     Key,
+    ToggleKey,
     Macro,
+    Scroll,
+    DpiStage,
+    PointerKeys,
+    Autoscroll,
+    Mode,
+    SwapButtons,
+    PauseMacros,
+    Sticky,
+    Warp,
+    Profile,
+}
+
+impl BindingOutputType {
+    pub fn all() -> &'static [BindingOutputType] {
+        &[
+            BindingOutputType::Key,
+            BindingOutputType::ToggleKey,
+            BindingOutputType::Macro,
+            BindingOutputType::Scroll,
+            BindingOutputType::DpiStage,
+            BindingOutputType::PointerKeys,
+            BindingOutputType::Autoscroll,
+            BindingOutputType::Mode,
+            BindingOutputType::SwapButtons,
+            BindingOutputType::PauseMacros,
+            BindingOutputType::Sticky,
+            BindingOutputType::Warp,
+            BindingOutputType::Profile,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BindingOutputType::Key => "Key Remap",
+            BindingOutputType::ToggleKey => "Toggle Key",
+            BindingOutputType::Macro => "Macro",
+            BindingOutputType::Scroll => "Scroll",
+            BindingOutputType::DpiStage => "DPI Stage",
+            BindingOutputType::PointerKeys => "Pointer Keys",
+            BindingOutputType::Autoscroll => "Autoscroll",
+            BindingOutputType::Mode => "Set Mode",
+            BindingOutputType::SwapButtons => "Swap Left/Right",
+            BindingOutputType::PauseMacros => "Pause Macros",
+            BindingOutputType::Sticky => "Sticky Modifier",
+            BindingOutputType::Warp => "Warp Cursor",
+            BindingOutputType::Profile => "Toggle Profile",
+        }
+    }
+
+    pub fn next(&self) -> BindingOutputType {
+        match self {
+            BindingOutputType::Key => BindingOutputType::ToggleKey,
+            BindingOutputType::ToggleKey => BindingOutputType::Macro,
+            BindingOutputType::Macro => BindingOutputType::Scroll,
+            BindingOutputType::Scroll => BindingOutputType::DpiStage,
+            BindingOutputType::DpiStage => BindingOutputType::PointerKeys,
+            BindingOutputType::PointerKeys => BindingOutputType::Autoscroll,
+            BindingOutputType::Autoscroll => BindingOutputType::Mode,
+            BindingOutputType::Mode => BindingOutputType::SwapButtons,
+            BindingOutputType::SwapButtons => BindingOutputType::PauseMacros,
+            BindingOutputType::PauseMacros => BindingOutputType::Sticky,
+            BindingOutputType::Sticky => BindingOutputType::Warp,
+            BindingOutputType::Warp => BindingOutputType::Profile,
+            BindingOutputType::Profile => BindingOutputType::Key,
+        }
+    }
+
+    /// Fixed values the output can take (cycled with Tab); empty for key/macro outputs
+    pub fn choices(&self) -> &'static [&'static str] {
+        match self {
+            BindingOutputType::DpiStage => &["next", "prev"],
+            BindingOutputType::PointerKeys
+            | BindingOutputType::Autoscroll
+            | BindingOutputType::SwapButtons
+            | BindingOutputType::PauseMacros => &["toggle", "on", "off"],
+            BindingOutputType::Sticky => &["shift", "ctrl", "alt", "meta"],
+            BindingOutputType::Key
+            | BindingOutputType::ToggleKey
+            | BindingOutputType::Macro
+            | BindingOutputType::Scroll
+            | BindingOutputType::Mode
+            | BindingOutputType::Warp
+            | BindingOutputType::Profile => &[],
+        }
+    }
+
+    /// Output value a freshly chosen type starts with
+    pub fn initial_value(&self) -> String {
+        match self {
+            BindingOutputType::Scroll => "wheel -3".to_string(),
+            _ => self
+                .choices()
+                .first()
+                .map(|c| c.to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Whether the output is a key, set by capturing it
+    pub fn captures_key(&self) -> bool {
+        matches!(self, BindingOutputType::Key | BindingOutputType::ToggleKey)
+    }
+}
+
+/// Steps of the guided new-binding wizard (it fills `editing_binding`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WizardStep {
+    /// Press the button to bind
+    Input,
+    /// Choose what the button does
+    Action,
+    /// Capture or pick what it sends / switches
+    Target,
+    /// Press the button to check it's detected, then save
+    Test,
+}
+
+impl WizardStep {
+    pub fn number(&self) -> usize {
+        match self {
+            WizardStep::Input => 1,
+            WizardStep::Action => 2,
+            WizardStep::Target => 3,
+            WizardStep::Test => 4,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            WizardStep::Input => "Press the button",
+            WizardStep::Action => "Choose an action",
+            WizardStep::Target => "Choose the target",
+            WizardStep::Test => "Test and save",
+        }
+    }
+}
+
+/// Number of rows the bulk editor starts with (a typical MMO side grid)
+const GRID_ROWS: usize = 12;
+
+/// One physical button of the bulk binding editor
+#[derive(Debug, Clone, Default)]
+pub struct GridRow {
+    /// Captured button name, "" until captured
+    pub input: String,
+    /// Key remap or macro to assign, None to leave the button alone
+    pub output: Option<BindingOutput>,
+}
+
+/// Bulk binding editor: captures each button of a button grid once and
+/// assigns outputs to all of them on one screen
+#[derive(Debug, Clone)]
+pub struct GridEditor {
+    pub rows: Vec<GridRow>,
+    pub selected: usize,
+    /// Capturing the buttons row after row until Esc
+    pub learning: bool,
+}
+
+impl GridEditor {
+    fn new() -> Self {
+        Self {
+            rows: vec![GridRow::default(); GRID_ROWS],
+            selected: 0,
+            learning: false,
+        }
+    }
+
+    /// Assign the next macro to the selected row (after the last one: none)
+    pub fn cycle_macro(&mut self, macro_names: &[String]) {
+        let Some(row) = self.rows.get_mut(self.selected) else {
+            return;
+        };
+        let next = match &row.output {
+            Some(BindingOutput::Macro { macro_name, .. }) => macro_names
+                .iter()
+                .position(|n| n == macro_name)
+                .and_then(|i| macro_names.get(i + 1)),
+            _ => macro_names.first(),
+        };
+        row.output = next.map(|name| BindingOutput::Macro {
+            macro_name: name.clone(),
+            args: BTreeMap::new(),
+        });
+    }
 }
 
 /// State for editing a macro
@@ -156,135 +762,1518 @@ pub struct EditingMacro {
     pub macro_type: MacroType,
     pub actions: Vec<MacroAction>,
     pub interval_ms: String,
+    /// Gaps of `MacroDef::interval_pattern_ms` as "45, 60, 52"
+    pub interval_pattern: String,
+    pub shuffle_pattern: bool,
+    pub ramp_from_ms: String,
+    pub ramp_ms: String,
+    pub initial_delay_ms: String,
     pub jitter_ms: String,
-    pub field_index: usize, // which field is focused
+    pub press_ms: String,
+    pub release_ms: String,
+    pub abort_on_release: bool,
+    pub idle_stop_min: String,
+    /// Parameters as "name=default, ...", see `MacroDef::params`
+    pub params: String,
+    /// Note on the macro, "" for none
+    pub description: String,
+    pub field_index: usize,    // which field is focused
+    pub action_index: usize,   // selected step in the action list
+    pub actions_focused: bool, // true while the step editor has focus
+}
+
+impl EditingMacro {
+    /// Editor state for an existing (`index` = Some) or new macro definition
+    pub fn from_def(index: Option<usize>, macro_def: &MacroDef) -> Self {
+        Self {
+            index,
+            name: macro_def.name.clone(),
+            macro_type: macro_def.macro_type.clone(),
+            actions: macro_def.actions.clone(),
+            interval_ms: macro_def.interval_ms.to_string(),
+            interval_pattern: macro_def
+                .interval_pattern_ms
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            shuffle_pattern: macro_def.shuffle_pattern,
+            ramp_from_ms: macro_def.ramp_from_ms.to_string(),
+            ramp_ms: macro_def.ramp_ms.to_string(),
+            initial_delay_ms: macro_def.initial_delay_ms.to_string(),
+            jitter_ms: macro_def.jitter_ms.to_string(),
+            press_ms: macro_def.press_ms.to_string(),
+            release_ms: macro_def.release_ms.to_string(),
+            abort_on_release: macro_def.abort_on_release,
+            idle_stop_min: macro_def.idle_stop_min.to_string(),
+            params: format_macro_params(&macro_def.params),
+            description: macro_def.description.clone().unwrap_or_default(),
+            field_index: 0,
+            action_index: 0,
+            actions_focused: false,
+        }
+    }
+
+    /// Index of the free-text parameters field
+    pub const PARAMS_FIELD: usize = 14;
+    /// Index of the free-text interval pattern field
+    pub const PATTERN_FIELD: usize = 4;
+    /// Index of the free-text description field
+    pub const DESCRIPTION_FIELD: usize = 15;
+    /// Index of the last field in the dialog
+    pub const LAST_FIELD: usize = Self::DESCRIPTION_FIELD;
+
+    /// The text of the focused field when it holds a number
+    pub fn number_field_mut(&mut self) -> Option<&mut String> {
+        match self.field_index {
+            3 => Some(&mut self.interval_ms),
+            6 => Some(&mut self.ramp_from_ms),
+            7 => Some(&mut self.ramp_ms),
+            8 => Some(&mut self.initial_delay_ms),
+            9 => Some(&mut self.jitter_ms),
+            10 => Some(&mut self.press_ms),
+            11 => Some(&mut self.release_ms),
+            13 => Some(&mut self.idle_stop_min),
+            _ => None,
+        }
+    }
+
+    /// Build the macro definition, or say which field is invalid
+    pub fn to_def(&self) -> Result<MacroDef, String> {
+        let number = |label: &str, value: &str| -> Result<u64, String> {
+            if value.is_empty() {
+                return Ok(0);
+            }
+            value
+                .parse()
+                .map_err(|_| format!("{} must be a whole number (got '{}')", label, value))
+        };
+
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Macro name can't be empty".to_string());
+        }
+        let interval_ms = number("Interval", &self.interval_ms)?;
+        if interval_ms == 0
+            && matches!(self.macro_type, MacroType::RepeatOnHold | MacroType::Toggle)
+        {
+            return Err(format!(
+                "{} macros need an interval above 0ms",
+                macro_type_name(&self.macro_type)
+            ));
+        }
+        let interval_pattern_ms = self
+            .interval_pattern
+            .split([',', ' '])
+            .filter(|gap| !gap.is_empty())
+            .map(|gap| match gap.parse::<u64>() {
+                Ok(ms) if ms > 0 => Ok(ms),
+                _ => Err(format!("Pattern gaps must be above 0ms (got '{}')", gap)),
+            })
+            .collect::<Result<Vec<u64>, String>>()?;
+        let ramp_from_ms = number("Ramp from", &self.ramp_from_ms)?;
+        let ramp_ms = number("Ramp time", &self.ramp_ms)?;
+        if ramp_from_ms > 0 && ramp_from_ms <= interval_ms {
+            return Err("Ramp from must be longer than the interval".to_string());
+        }
+        if ramp_from_ms > 0 && ramp_ms == 0 {
+            return Err("Set a ramp time for the ramp".to_string());
+        }
+        let jitter_ms = number("Jitter", &self.jitter_ms)?;
+        let description = self.description.trim();
+        if jitter_ms >= interval_ms && jitter_ms > 0 {
+            return Err("Jitter must be smaller than the interval".to_string());
+        }
+
+        Ok(MacroDef {
+            name: name.to_string(),
+            macro_type: self.macro_type.clone(),
+            actions: self.actions.clone(),
+            interval_ms,
+            interval_pattern_ms,
+            shuffle_pattern: self.shuffle_pattern,
+            ramp_from_ms,
+            ramp_ms,
+            initial_delay_ms: number("Initial delay", &self.initial_delay_ms)?,
+            jitter_ms,
+            press_ms: number("Press", &self.press_ms)?,
+            release_ms: number("Release", &self.release_ms)?,
+            abort_on_release: self.abort_on_release,
+            idle_stop_min: number("Idle stop", &self.idle_stop_min)?,
+            params: parse_macro_params(&self.params)?,
+            description: (!description.is_empty()).then(|| description.to_string()),
+        })
+    }
+
+    /// Insert a new Click step after the selected one and select it
+    pub fn add_action(&mut self) {
+        self.insert_action(MacroAction::Click(ClickStep::new("BTN_LEFT")));
+    }
+
+    /// Insert a step after the selected one and select it
+    pub fn insert_action(&mut self, action: MacroAction) {
+        let at = if self.actions.is_empty() {
+            0
+        } else {
+            self.action_index + 1
+        };
+        self.actions.insert(at, action);
+        self.action_index = at;
+    }
+
+    /// Remove the selected step
+    pub fn delete_action(&mut self) {
+        if self.action_index < self.actions.len() {
+            self.actions.remove(self.action_index);
+            if self.action_index > 0 && self.action_index >= self.actions.len() {
+                self.action_index = self.actions.len().saturating_sub(1);
+            }
+        }
+    }
+
+    /// Swap the selected step with its neighbour above
+    pub fn move_action_up(&mut self) {
+        if self.action_index > 0 && self.action_index < self.actions.len() {
+            self.actions.swap(self.action_index, self.action_index - 1);
+            self.action_index -= 1;
+        }
+    }
+
+    /// Swap the selected step with its neighbour below
+    pub fn move_action_down(&mut self) {
+        if self.action_index + 1 < self.actions.len() {
+            self.actions.swap(self.action_index, self.action_index + 1);
+            self.action_index += 1;
+        }
+    }
+
+    /// Cycle the selected step through Click -> Press -> Release -> Delay ->
+    /// Choice, keeping the key name when switching between key steps.
+    pub fn cycle_action_kind(&mut self) {
+        if let Some(action) = self.actions.get_mut(self.action_index) {
+            *action = match action {
+                MacroAction::Click(step) => MacroAction::Press(step.key.clone()),
+                MacroAction::Press(k) => MacroAction::Release(k.clone()),
+                MacroAction::Release(_) => MacroAction::Delay(50),
+                MacroAction::Delay(_) => MacroAction::Choice(1),
+                MacroAction::Choice(_)
+                | MacroAction::SetMode { .. }
+                | MacroAction::RunMacro(_)
+                | MacroAction::Obs(_)
+                | MacroAction::Notify(_)
+                | MacroAction::Warp(_) => MacroAction::Click(ClickStep::new("BTN_LEFT")),
+            };
+        }
+    }
+
+    /// Point the selected key step at the next declared parameter, so its
+    /// key comes from the binding ("{key}")
+    pub fn cycle_action_param(&mut self) {
+        let placeholders: Vec<String> = parse_macro_params(&self.params)
+            .unwrap_or_default()
+            .into_keys()
+            .map(|name| format!("{{{}}}", name))
+            .collect();
+        let current = match self.actions.get(self.action_index) {
+            Some(MacroAction::Click(ClickStep { key, .. }))
+            | Some(MacroAction::Press(key))
+            | Some(MacroAction::Release(key)) => key,
+            _ => return,
+        };
+        let next = match placeholders.iter().position(|p| p == current) {
+            Some(i) => placeholders.get((i + 1) % placeholders.len()),
+            None => placeholders.first(),
+        };
+        if let Some(next) = next.cloned() {
+            self.set_action_key(next);
+        }
+    }
+
+    /// Make the selected step run the next of `macro_names` (other than the
+    /// macro being edited), or insert such a step if it runs none
+    pub fn cycle_run_macro(&mut self, macro_names: &[String]) {
+        let names: Vec<&String> = macro_names.iter().filter(|n| **n != self.name).collect();
+        let Some(first) = names.first() else {
+            return;
+        };
+        match self.actions.get_mut(self.action_index) {
+            Some(MacroAction::RunMacro(current)) => {
+                let next = names
+                    .iter()
+                    .position(|n| *n == current)
+                    .map_or(*first, |i| names[(i + 1) % names.len()]);
+                *current = next.clone();
+            }
+            _ => self.insert_action(MacroAction::RunMacro((*first).clone())),
+        }
+    }
+
+    /// Set the key of the selected step (ignored for Delay steps)
+    pub fn set_action_key(&mut self, name: String) {
+        match self.actions.get_mut(self.action_index) {
+            Some(MacroAction::Click(ClickStep { key, .. }))
+            | Some(MacroAction::Press(key))
+            | Some(MacroAction::Release(key)) => *key = name,
+            _ => {}
+        }
+    }
 }
 
 impl App {
     pub fn new(config: Config) -> Self {
+        let log_path = logging::log_file_path(&config.logging);
+        let keymap = Keymap::from_config(&config.keybindings);
+        let status_message = format!("Press {} for help", keymap.key(Action::Help));
         Self {
+            saved_config: config.clone(),
+            theme: Theme::from_config(&config.theme),
+            keymap,
             config,
             current_tab: Tab::Devices,
             input_mode: InputMode::Normal,
+            mode_before_quit: InputMode::Normal,
             should_quit: false,
+            command_palette: None,
 
             devices: Vec::new(),
             device_list_index: 0,
             selected_device: None,
+            tagged_devices: HashSet::new(),
             engine_running: false,
+            engine_observing: false,
+            engine_devices: HashMap::new(),
+            engine_warnings: Vec::new(),
+            engine_states: HashMap::new(),
+            engine_restarts: Vec::new(),
+            dpi_stage: None,
+            active_modes: Vec::new(),
+            running_macros: Vec::new(),
+            macro_activity: HashMap::new(),
+            battery_levels: HashMap::new(),
+            battery_warned: HashSet::new(),
+            battery_polled: None,
+            battery_rx: None,
+            sensor_info: HashMap::new(),
+            sensor_polled: None,
+            sensor_rx: None,
+            device_watch_rx: None,
+            devices_changed: None,
 
             binding_list_index: 0,
             editing_binding: None,
+            wizard_step: None,
+            wizard_test_presses: 0,
+            import_path: None,
+            restore_path: None,
+            save_preview: None,
+            binding_list_offset: 0,
+            macro_list_offset: 0,
+            list_page_rows: 10,
+            binding_filter: String::new(),
+            binding_tag_filter: None,
+            macro_filter: String::new(),
+            list_filter_editing: false,
+            grid_editor: None,
 
             macro_list_index: 0,
             editing_macro: None,
+            template_picker: None,
+            template_values: None,
+            template_field: 0,
 
             monitor_events: Vec::new(),
             monitor_paused: false,
+            monitor_hid: None,
+            monitor_split: false,
             monitor_max_events: 500,
 
+            log_tail: LogTail::new(log_path, 2000),
+            log_min_level: log::Level::Trace,
+            log_filter: String::new(),
+            log_filter_editing: false,
+            log_scroll: 0,
+
+            ratbag: None,
+            ratbag_devices: Vec::new(),
+            ratbag_loaded: false,
+            ratbag_error: None,
+            ratbag_device_index: 0,
+            ratbag_profile_index: 0,
+            ratbag_focus: HardwareFocus::Profiles,
+            ratbag_row: 0,
+            ratbag_uncommitted: false,
+            ratbag_rx: None,
+
             engine_cmd_tx: None,
             engine_msg_rx: None,
+            api_rx: None,
 
             capturing: false,
+            capture_only: false,
+            capture_chord: Vec::new(),
 
-            status_message: String::from("Press ? for help"),
+            status_message,
             status_time: Instant::now(),
+            notifications: VecDeque::new(),
         }
     }
 
     pub fn set_status(&mut self, msg: impl Into<String>) {
-        self.status_message = msg.into();
+        self.notify(Severity::Info, msg.into());
+    }
+
+    pub fn set_warning(&mut self, msg: impl Into<String>) {
+        self.notify(Severity::Warning, msg.into());
+    }
+
+    pub fn set_error(&mut self, msg: impl Into<String>) {
+        self.notify(Severity::Error, msg.into());
+    }
+
+    /// Show a message in the status bar and keep it in the notification history
+    fn notify(&mut self, severity: Severity, text: String) {
+        // Repeats (e.g. capture prompts) would crowd out everything else
+        let repeat = self
+            .notifications
+            .back()
+            .is_some_and(|n| n.text == text && n.severity == severity);
+        if !repeat {
+            if self.notifications.len() >= MAX_NOTIFICATIONS {
+                self.notifications.pop_front();
+            }
+            self.notifications.push_back(Notification {
+                at: Instant::now(),
+                severity,
+                text: text.clone(),
+            });
+        }
+        self.status_message = text;
         self.status_time = Instant::now();
     }
 
+    /// True if the in-memory config differs from what is on disk
+    pub fn is_dirty(&self) -> bool {
+        self.config != self.saved_config
+    }
+
+    /// Quit, or ask to save first if there are unsaved changes
+    pub fn request_quit(&mut self) {
+        if self.is_dirty() {
+            self.mode_before_quit = std::mem::replace(&mut self.input_mode, InputMode::ConfirmQuit);
+        } else {
+            self.should_quit = true;
+        }
+    }
+
     /// Refresh the device list
     pub fn refresh_devices(&mut self) {
-        match scanner::scan_devices() {
-            Ok(devices) => {
-                self.devices = devices;
-                self.set_status(format!("Found {} devices", self.devices.len()));
+        match self.rescan_devices() {
+            Ok(found) => {
+                if found == self.devices.len() {
+                    self.set_status(format!("Found {} devices", found));
+                } else {
+                    self.set_status(format!(
+                        "Found {} devices ({} ignored)",
+                        found,
+                        found - self.devices.len()
+                    ));
+                }
             }
             Err(e) => {
-                self.set_status(format!("Error scanning devices: {}", e));
+                self.set_error(format!("Error scanning devices: {}", e));
             }
         }
     }
 
-    /// Get bindings for the active profile
-    pub fn current_bindings(&self) -> &[Binding] {
-        self.config
-            .active_profile()
-            .map(|p| p.bindings.as_slice())
-            .unwrap_or(&[])
+    /// Scan the devices again and forget what belonged to nodes that are
+    /// gone. Returns how many were found, ignored ones included.
+    fn rescan_devices(&mut self) -> anyhow::Result<usize> {
+        let mut devices = scanner::scan_devices()?;
+        let found = devices.len();
+        let ignore = &self.config.device;
+        devices.retain(|d| !ignore.is_ignored(&d.path.to_string_lossy(), &d.name));
+        self.devices = devices;
+        self.device_list_index = self
+            .device_list_index
+            .min(self.devices.len().saturating_sub(1));
+
+        let present: HashSet<PathBuf> = self.devices.iter().map(|d| d.path.clone()).collect();
+        self.tagged_devices.retain(|path| present.contains(path));
+        self.battery_levels.retain(|path, _| present.contains(path));
+        self.battery_warned.retain(|path| present.contains(path));
+        self.sensor_info.retain(|path, _| present.contains(path));
+        // The selected device may have come back on another node
+        if let Some(selected) = self.selected_device.take() {
+            self.selected_device = self
+                .devices
+                .iter()
+                .find(|d| d.path == selected.path)
+                .or_else(|| {
+                    self.devices.iter().find(|d| {
+                        d.name == selected.name
+                            && d.vendor_id == selected.vendor_id
+                            && d.product_id == selected.product_id
+                    })
+                })
+                .cloned();
+        }
+        self.battery_polled = None;
+        self.sensor_polled = None;
+        Ok(found)
     }
 
-    /// Get macros for the active profile
-    pub fn current_macros(&self) -> &[MacroDef] {
-        self.config
-            .active_profile()
-            .map(|p| p.macros.as_slice())
-            .unwrap_or(&[])
+    /// Follow devices being plugged in and unplugged (see poll_device_changes)
+    pub fn watch_devices(&mut self) {
+        match watch::watch_event_nodes(Path::new("/dev/input")) {
+            Ok(rx) => self.device_watch_rx = Some(rx),
+            Err(e) => log::warn!("{:#}; the device list only updates on rescan", e),
+        }
     }
 
-    /// Select the device at the current index and update config
-    pub fn select_current_device(&mut self) {
-        if let Some(device) = self.devices.get(self.device_list_index) {
-            self.selected_device = Some(device.clone());
-            self.config.device.name = Some(device.name.clone());
-            self.config.device.path = Some(device.path.to_string_lossy().to_string());
-            self.config.device.vendor_id = Some(device.vendor_id);
-            self.config.device.product_id = Some(device.product_id);
-            self.set_status(format!("Selected: {}", device.name));
+    /// Rescan the device list once /dev/input settled after a change, and
+    /// say which devices came and went (apart from our own virtual ones)
+    pub fn poll_device_changes(&mut self) {
+        if let Some(ref rx) = self.device_watch_rx {
+            while rx.try_recv().is_ok() {
+                self.devices_changed = Some(Instant::now());
+            }
+        }
+        if self
+            .devices_changed
+            .is_none_or(|t| t.elapsed() < DEVICE_SETTLE)
+        {
+            return;
+        }
+        self.devices_changed = None;
+
+        let before: HashMap<PathBuf, String> = self
+            .devices
+            .iter()
+            .map(|d| (d.path.clone(), d.name.clone()))
+            .collect();
+        if let Err(e) = self.rescan_devices() {
+            self.set_error(format!("Error scanning devices: {}", e));
+            return;
         }
+        let ours = |name: &str| name.starts_with("MouseMapper ");
+        let mut connected: Vec<&str> = self
+            .devices
+            .iter()
+            .filter(|d| !before.contains_key(&d.path) && !ours(&d.name))
+            .map(|d| d.name.as_str())
+            .collect();
+        connected.sort();
+        connected.dedup();
+        let mut disconnected: Vec<&str> = before
+            .iter()
+            .filter(|(path, name)| !ours(name) && !self.devices.iter().any(|d| &d.path == *path))
+            .map(|(_, name)| name.as_str())
+            .collect();
+        disconnected.sort();
+        disconnected.dedup();
+        let message = match (connected.is_empty(), disconnected.is_empty()) {
+            (true, true) => return,
+            (false, true) => format!("Connected: {}", connected.join(", ")),
+            (true, false) => format!("Disconnected: {}", disconnected.join(", ")),
+            (false, false) => format!(
+                "Connected: {}; disconnected: {}",
+                connected.join(", "),
+                disconnected.join(", ")
+            ),
+        };
+        log::info!("{}", message);
+        self.set_status(message);
     }
 
-    /// Toggle the engine (start/stop)
-    pub fn toggle_engine(&mut self) {
-        if self.engine_running {
-            self.send_engine_command(EngineCommand::Stop);
-            self.engine_running = false;
-            self.set_status("Engine stopped");
-        } else if let Some(ref device) = self.selected_device {
-            let path = device.path.to_string_lossy().to_string();
-            self.send_engine_command(EngineCommand::Start(path));
-            self.engine_running = true;
-            self.set_status("Engine started");
-        } else {
-            self.set_status("No device selected! Select a device first.");
+    /// Answer the HTTP API requests that came in since the last frame
+    pub fn poll_api(&mut self) {
+        let Some(mut rx) = self.api_rx.take() else {
+            return;
+        };
+        while let Ok(request) = rx.try_recv() {
+            let path: Vec<&str> = request.path.iter().map(String::as_str).collect();
+            let response = self.handle_api_request(&request.method, &path);
+            let _ = request.reply.send(response);
         }
+        self.api_rx = Some(rx);
     }
 
-    fn send_engine_command(&self, cmd: EngineCommand) {
-        if let Some(ref tx) = self.engine_cmd_tx {
-            let _ = tx.send(cmd);
+    /// Route one API call:
+    ///   GET  /state                      engine state of every device
+    ///   GET  /profiles                   profile names and the active one
+    ///   POST /profiles/{name}/activate   switch profile (restarts the engine)
+    ///   GET  /macros                     macros of the active profile
+    ///   POST /macros/{name}/toggle       run/stop a macro like its button would
+    /// GET /metrics never gets here, the server answers it from the engine's
+    /// counters
+    fn handle_api_request(&mut self, method: &str, path: &[&str]) -> ApiResponse {
+        match (method, path) {
+            ("GET", ["state"]) => ApiResponse::ok(self.api_state()),
+            ("GET", ["profiles"]) => ApiResponse::ok(json!({
+                "active": self.config.active_profile().map(|p| &p.name),
+                "profiles": self.config.profiles.iter().map(|p| &p.name).collect::<Vec<_>>(),
+            })),
+            ("POST", ["profiles", name, "activate"]) => self.api_activate_profile(name),
+            ("GET", ["macros"]) => {
+                let macros: Vec<_> = self
+                    .config
+                    .active_profile()
+                    .map(|p| p.macros.as_slice())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|m| {
+                        json!({
+                            "name": m.name,
+                            "type": m.macro_type,
+                            "running": self.running_macros.contains(&m.name),
+                        })
+                    })
+                    .collect();
+                ApiResponse::ok(json!({ "macros": macros }))
+            }
+            ("POST", ["macros", name, "toggle"]) => self.api_toggle_macro(name),
+            (_, ["state"] | ["profiles"] | ["profiles", _, "activate"])
+            | (_, ["macros"] | ["macros", _, "toggle"]) => {
+                ApiResponse::error(405, format!("{} isn't supported here", method))
+            }
+            _ => ApiResponse::error(404, format!("No endpoint /{}", path.join("/"))),
         }
     }
 
-    /// Process incoming engine messages.
-    /// Caps the number of messages processed per tick to prevent the UI from freezing
-    /// when the engine produces a burst of events (e.g. rapid mouse movement).
-    /// Also intercepts EV_KEY press events for button capture when in capture mode.
-    pub fn poll_engine_messages(&mut self) {
-        let mut rx = match self.engine_msg_rx.take() {
-            Some(rx) => rx,
-            None => return,
+    fn api_state(&self) -> serde_json::Value {
+        let engine = if !self.engine_running {
+            "stopped"
+        } else if self.engine_observing {
+            "observing"
+        } else {
+            "running"
         };
+        let mut devices: Vec<&EngineState> = self.engine_states.values().map(|(s, _)| s).collect();
+        devices.sort_by(|a, b| a.device.cmp(&b.device));
+        json!({
+            "engine": engine,
+            "profile": self.config.active_profile().map(|p| &p.name),
+            "devices": devices,
+            "dpi_stage": self.dpi_stage.map(|(index, count, multiplier)| json!({
+                "index": index,
+                "count": count,
+                "multiplier": multiplier,
+            })),
+            "modes": self.active_modes,
+            "running_macros": self.running_macros,
+        })
+    }
 
-        const MAX_MESSAGES_PER_TICK: usize = 200;
-        let mut processed = 0;
+    fn api_activate_profile(&mut self, name: &str) -> ApiResponse {
+        if !self.config.profiles.iter().any(|p| p.name == name) {
+            return ApiResponse::error(404, format!("No profile named {}", name));
+        }
+        // An open editor would save into the profile it was opened on
+        if self.input_mode != InputMode::Normal {
+            return ApiResponse::error(409, "Busy editing in mouse-mapper, try again later");
+        }
+        self.activate_profile(name);
+        self.set_status(format!("Switched to profile {} (HTTP API)", name));
+        ApiResponse::ok(json!({ "profile": name }))
+    }
 
-        while processed < MAX_MESSAGES_PER_TICK {
-            match rx.try_recv() {
-                Ok(msg) => {
-                    processed += 1;
-                    match &msg {
-                        EngineMessage::StatusUpdate(s) => {
-                            self.set_status(s.clone());
+    /// Make `name` the active profile, restarting a running engine with it
+    fn activate_profile(&mut self, name: &str) {
+        self.config.active_profile = Some(name.to_string());
+        self.binding_list_index = 0;
+        self.macro_list_index = 0;
+        self.send_engine_command(EngineCommand::SwitchProfile(name.to_string()));
+        if self.engine_running
+            && let Some(ref sounds) = self.config.sounds
+            && let Some(ref sound) = sounds.profile_switch
+        {
+            sounds::play(sound, &sounds.player);
+        }
+    }
+
+    fn api_toggle_macro(&mut self, name: &str) -> ApiResponse {
+        let exists = self
+            .config
+            .active_profile()
+            .is_some_and(|p| p.macros.iter().any(|m| m.name == name));
+        if !exists {
+            return ApiResponse::error(404, format!("No macro named {}", name));
+        }
+        if !self.engine_running || self.engine_observing {
+            return ApiResponse::error(409, "The engine isn't running");
+        }
+        self.send_engine_command(EngineCommand::TriggerMacro(name.to_string()));
+        ApiResponse::accepted(json!({ "macro": name }))
+    }
+
+    /// Start a background battery read of all mice when due, and collect the
+    /// results of the previous one. HID++ queries can take a while to time
+    /// out, so they run off the UI thread.
+    pub fn poll_battery(&mut self) {
+        if let Some(ref rx) = self.battery_rx {
+            match rx.try_recv() {
+                Ok(results) => {
+                    self.battery_rx = None;
+                    for (path, info) in results {
+                        match info {
+                            Some(info) => self.update_battery(path, info),
+                            None => {
+                                self.battery_levels.remove(&path);
+                            }
+                        }
+                    }
+                }
+                Err(std_mpsc::TryRecvError::Empty) => return,
+                Err(std_mpsc::TryRecvError::Disconnected) => self.battery_rx = None,
+            }
+        }
+
+        if self
+            .battery_polled
+            .is_some_and(|t| t.elapsed() < BATTERY_POLL_INTERVAL)
+        {
+            return;
+        }
+        self.battery_polled = Some(Instant::now());
+
+        let mice: Vec<(PathBuf, u16)> = self
+            .devices
+            .iter()
+            .filter(|d| d.is_mouse)
+            .map(|d| (d.path.clone(), d.vendor_id))
+            .collect();
+        let (tx, rx) = std_mpsc::channel();
+        std::thread::spawn(move || {
+            let results = mice
+                .into_iter()
+                .map(|(path, vendor_id)| {
+                    let info = battery::read_battery(&path, vendor_id);
+                    (path, info)
+                })
+                .collect();
+            let _ = tx.send(results);
+        });
+        self.battery_rx = Some(rx);
+    }
+
+    fn update_battery(&mut self, path: PathBuf, info: BatteryInfo) {
+        if info.is_low() {
+            if self.battery_warned.insert(path.clone()) {
+                let name = self
+                    .devices
+                    .iter()
+                    .find(|d| d.path == path)
+                    .map_or_else(|| path.display().to_string(), |d| d.name.clone());
+                log::warn!("Low battery: {} at {}%", name, info.percent);
+                self.set_warning(format!("Low battery: {} at {}%", name, info.percent));
+            }
+        } else {
+            self.battery_warned.remove(&path);
+        }
+        self.battery_levels.insert(path, info);
+    }
+
+    /// Read the DPI and report rate of the selected device in the background
+    /// when it changed or the last read is stale, and collect the result
+    pub fn poll_sensor(&mut self) {
+        if let Some(ref rx) = self.sensor_rx {
+            match rx.try_recv() {
+                Ok((path, info)) => {
+                    self.sensor_rx = None;
+                    match info {
+                        Some(info) => {
+                            self.sensor_info.insert(path, info);
+                        }
+                        None => {
+                            self.sensor_info.remove(&path);
+                        }
+                    }
+                }
+                Err(std_mpsc::TryRecvError::Empty) => return,
+                Err(std_mpsc::TryRecvError::Disconnected) => self.sensor_rx = None,
+            }
+        }
+
+        let Some(device) = self.selected_device.as_ref().filter(|d| d.is_mouse) else {
+            return;
+        };
+        if self
+            .sensor_polled
+            .as_ref()
+            .is_some_and(|(path, t)| path == &device.path && t.elapsed() < SENSOR_POLL_INTERVAL)
+        {
+            return;
+        }
+        let (path, vendor_id) = (device.path.clone(), device.vendor_id);
+        self.sensor_polled = Some((path.clone(), Instant::now()));
+
+        let (tx, rx) = std_mpsc::channel();
+        std::thread::spawn(move || {
+            let info = sensor::read_sensor(&path, vendor_id);
+            let _ = tx.send((path, info));
+        });
+        self.sensor_rx = Some(rx);
+    }
+
+    /// Hardware DPI and report rate of the selected device, if it reports them
+    pub fn selected_sensor(&self) -> Option<SensorInfo> {
+        let device = self.selected_device.as_ref()?;
+        self.sensor_info.get(&device.path).copied()
+    }
+
+    /// Start or stop showing the selected device's raw HID reports in the
+    /// monitor, next to its input events
+    pub fn toggle_hid_monitor(&mut self) {
+        if self.monitor_hid.take().is_some() {
+            self.send_engine_command(EngineCommand::StopHidMonitor);
+            self.set_status("HID report view off");
+            return;
+        }
+        let Some(device) = self.selected_device.as_ref() else {
+            self.set_error("Select a device first (Enter on Devices tab)");
+            return;
+        };
+        let path = device.path.clone();
+        self.send_engine_command(EngineCommand::StartHidMonitor(
+            path.to_string_lossy().to_string(),
+        ));
+        self.set_status(format!("Showing HID reports of {}", device.name));
+        self.monitor_hid = Some(path);
+    }
+
+    /// Battery of the selected device, if it reports one
+    pub fn selected_battery(&self) -> Option<BatteryInfo> {
+        let device = self.selected_device.as_ref()?;
+        self.battery_levels.get(&device.path).copied()
+    }
+
+    /// Pick up new lines from the log file
+    pub fn poll_logs(&mut self) {
+        let added = self.log_tail.poll();
+        if self.log_scroll > 0 {
+            // Keep the view anchored while scrolled back instead of following new lines
+            self.log_scroll = (self.log_scroll + added).min(self.log_tail.lines.len());
+        }
+    }
+
+    /// Indices of the active profile's bindings matching the Bindings filter
+    /// (input, action, output, description or "#tag") and the tag filter
+    pub fn visible_bindings(&self) -> Vec<usize> {
+        let needle = self.binding_filter.to_lowercase();
+        self.current_bindings()
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| {
+                self.binding_tag_filter
+                    .as_ref()
+                    .is_none_or(|tag| b.tags.contains(tag))
+            })
+            .filter(|(_, b)| {
+                let (action, output) = binding_output_summary(&b.output);
+                needle.is_empty()
+                    || format!(
+                        "{} {} {} {} #{}",
+                        b.input_label(),
+                        action,
+                        output,
+                        b.description.as_deref().unwrap_or_default(),
+                        b.tags.join(" #")
+                    )
+                    .to_lowercase()
+                    .contains(&needle)
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Indices of the active profile's macros matching the Macros filter
+    /// (name, type or description)
+    pub fn visible_macros(&self) -> Vec<usize> {
+        let needle = self.macro_filter.to_lowercase();
+        self.current_macros()
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| {
+                needle.is_empty()
+                    || format!(
+                        "{} {} {}",
+                        m.name,
+                        macro_type_name(&m.macro_type),
+                        m.description.as_deref().unwrap_or_default()
+                    )
+                    .to_lowercase()
+                    .contains(&needle)
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Move the Bindings/Macros selection by `delta` visible rows (clamped to
+    /// the list), or onto the first visible row if the filter hid the selected one
+    pub fn move_list_selection(&mut self, delta: isize) {
+        let (visible, index) = match self.current_tab {
+            Tab::Bindings => (self.visible_bindings(), &mut self.binding_list_index),
+            Tab::Macros => (self.visible_macros(), &mut self.macro_list_index),
+            _ => return,
+        };
+        let next = match visible.iter().position(|i| i == index) {
+            Some(pos) => {
+                let last = visible.len() as isize - 1;
+                visible.get((pos as isize).saturating_add(delta).clamp(0, last) as usize)
+            }
+            None => visible.first(),
+        };
+        if let Some(next) = next {
+            *index = *next;
+        }
+    }
+
+    /// Keep the Bindings/Macros selection on a row the filter shows
+    pub fn snap_list_selection(&mut self) {
+        let (visible, index) = match self.current_tab {
+            Tab::Bindings => (self.visible_bindings(), &mut self.binding_list_index),
+            Tab::Macros => (self.visible_macros(), &mut self.macro_list_index),
+            _ => return,
+        };
+        if !visible.contains(index)
+            && let Some(first) = visible.first()
+        {
+            *index = *first;
+        }
+    }
+
+    /// Log lines passing the level and text filters
+    pub fn filtered_log_lines(&self) -> Vec<&LogLine> {
+        let needle = self.log_filter.to_lowercase();
+        self.log_tail
+            .lines
+            .iter()
+            .filter(|l| l.level <= self.log_min_level)
+            .filter(|l| needle.is_empty() || l.text.to_lowercase().contains(&needle))
+            .collect()
+    }
+
+    /// Cycle the level filter: TRACE -> DEBUG -> INFO -> WARN -> ERROR -> TRACE
+    pub fn cycle_log_level(&mut self) {
+        self.log_min_level = match self.log_min_level {
+            log::Level::Trace => log::Level::Debug,
+            log::Level::Debug => log::Level::Info,
+            log::Level::Info => log::Level::Warn,
+            log::Level::Warn => log::Level::Error,
+            log::Level::Error => log::Level::Trace,
+        };
+        self.log_scroll = 0;
+        self.set_status(format!("Showing {} and above", self.log_min_level));
+    }
+
+    /// Cycle the log file verbosity (applied immediately and stored in config)
+    pub fn cycle_log_verbosity(&mut self) {
+        let next = match logging::parse_level_filter(&self.config.logging.level) {
+            log::LevelFilter::Off => log::LevelFilter::Error,
+            log::LevelFilter::Error => log::LevelFilter::Warn,
+            log::LevelFilter::Warn => log::LevelFilter::Info,
+            log::LevelFilter::Info => log::LevelFilter::Debug,
+            log::LevelFilter::Debug => log::LevelFilter::Trace,
+            log::LevelFilter::Trace => log::LevelFilter::Off,
+        };
+        self.config.logging.level = next.to_string().to_lowercase();
+        logging::set_level(next);
+        self.set_status(format!("Log level: {}", self.config.logging.level));
+    }
+
+    /// Toggle debug logging for the engine modules only
+    pub fn toggle_engine_debug(&mut self) {
+        self.config.logging.engine_debug = !self.config.logging.engine_debug;
+        logging::set_engine_debug(self.config.logging.engine_debug);
+        self.set_status(if self.config.logging.engine_debug {
+            "Engine debug logging on"
+        } else {
+            "Engine debug logging off"
+        });
+    }
+
+    // === Hardware (ratbagd) ===
+
+    /// Connect to ratbagd if needed and re-read all devices, in the
+    /// background; `report` shows how many were found
+    pub fn refresh_ratbag(&mut self, report: bool) {
+        self.ratbag_loaded = true;
+        self.spawn_ratbag(RatbagJob::Refresh { report }, |_| Ok(()));
+    }
+
+    /// Whether a ratbagd call is still running
+    pub fn ratbag_busy(&self) -> bool {
+        self.ratbag_rx.is_some()
+    }
+
+    /// Run a ratbagd call off the UI thread: D-Bus calls block, and ratbagd
+    /// can take seconds talking to the mouse. One runs at a time.
+    fn spawn_ratbag(
+        &mut self,
+        job: RatbagJob,
+        f: impl FnOnce(&Ratbag) -> anyhow::Result<()> + Send + 'static,
+    ) {
+        if self.ratbag_busy() {
+            self.set_status("Waiting for ratbagd...");
+            return;
+        }
+        let ratbag = self.ratbag.clone();
+        let (tx, rx) = std_mpsc::channel();
+        std::thread::spawn(move || {
+            let ratbag = ratbag.map_or_else(Ratbag::connect, Ok);
+            let (result, devices) = match &ratbag {
+                Ok(ratbag) => {
+                    let result = f(ratbag);
+                    let reread = result.is_ok() && !matches!(job, RatbagJob::Commit(_));
+                    (result, reread.then(|| ratbag.devices()))
+                }
+                Err(_) => (Ok(()), None),
+            };
+            let _ = tx.send(RatbagReply {
+                job,
+                ratbag,
+                result,
+                devices,
+            });
+        });
+        self.ratbag_rx = Some(rx);
+    }
+
+    /// Collect the reply of the background ratbagd call, if it's done
+    pub fn poll_ratbag(&mut self) {
+        let Some(ref rx) = self.ratbag_rx else {
+            return;
+        };
+        let reply = match rx.try_recv() {
+            Ok(reply) => reply,
+            Err(std_mpsc::TryRecvError::Empty) => return,
+            Err(std_mpsc::TryRecvError::Disconnected) => {
+                self.ratbag_rx = None;
+                return;
+            }
+        };
+        self.ratbag_rx = None;
+
+        match reply.ratbag {
+            Ok(ratbag) => self.ratbag = Some(ratbag),
+            Err(e) => {
+                self.ratbag = None;
+                self.ratbag_error = Some(format!("{:#}", e));
+                return;
+            }
+        }
+        match (&reply.job, reply.result) {
+            (_, Err(e)) => self.set_error(format!("{:#}", e)),
+            (RatbagJob::Refresh { .. }, Ok(())) => {}
+            (RatbagJob::Apply(done), Ok(())) => {
+                self.ratbag_uncommitted = true;
+                self.set_status(format!(
+                    "{} (press '{}' to write to the mouse)",
+                    done,
+                    self.keymap.key(Action::Commit)
+                ));
+            }
+            (RatbagJob::Commit(done), Ok(())) => {
+                self.ratbag_uncommitted = false;
+                self.set_status(done.clone());
+            }
+        }
+
+        match reply.devices {
+            Some(Ok(devices)) => {
+                let first_load = self.ratbag_devices.is_empty();
+                self.ratbag_devices = devices;
+                self.ratbag_error = None;
+                self.ratbag_device_index = self
+                    .ratbag_device_index
+                    .min(self.ratbag_devices.len().saturating_sub(1));
+                if first_load && let Some(device) = self.current_ratbag_device() {
+                    self.ratbag_profile_index = device.active_profile_index();
+                }
+                if matches!(reply.job, RatbagJob::Refresh { report: true }) {
+                    self.set_status(format!(
+                        "Found {} ratbagd device(s)",
+                        self.ratbag_devices.len()
+                    ));
+                }
+            }
+            Some(Err(e)) => {
+                // The connection may have gone stale (ratbagd restarted); reconnect next time
+                self.ratbag = None;
+                self.ratbag_error = Some(format!("{:#}", e));
+            }
+            None => {}
+        }
+    }
+
+    pub fn current_ratbag_device(&self) -> Option<&RatbagDevice> {
+        self.ratbag_devices.get(self.ratbag_device_index)
+    }
+
+    /// The profile shown on the Hardware tab (not necessarily the active one)
+    pub fn current_ratbag_profile(&self) -> Option<&RatbagProfile> {
+        self.current_ratbag_device()?
+            .profiles
+            .get(self.ratbag_profile_index)
+    }
+
+    /// Number of rows in the focused Hardware list
+    pub fn ratbag_focus_len(&self) -> usize {
+        match self.ratbag_focus {
+            HardwareFocus::Profiles => self.current_ratbag_device().map_or(0, |d| d.profiles.len()),
+            HardwareFocus::Resolutions => self
+                .current_ratbag_profile()
+                .map_or(0, |p| p.resolutions.len()),
+            HardwareFocus::Buttons => self.current_ratbag_profile().map_or(0, |p| p.buttons.len()),
+        }
+    }
+
+    pub fn next_ratbag_device(&mut self) {
+        if self.ratbag_devices.len() > 1 {
+            self.ratbag_device_index = (self.ratbag_device_index + 1) % self.ratbag_devices.len();
+            self.ratbag_profile_index = self
+                .current_ratbag_device()
+                .map_or(0, |d| d.active_profile_index());
+            self.ratbag_row = 0;
+        }
+    }
+
+    /// Run a ratbagd setter, then re-read the device so the tab shows the result
+    fn ratbag_apply(
+        &mut self,
+        done: &str,
+        f: impl FnOnce(&Ratbag) -> anyhow::Result<()> + Send + 'static,
+    ) {
+        if self.ratbag.is_none() {
+            return;
+        }
+        self.spawn_ratbag(RatbagJob::Apply(done.to_string()), f);
+    }
+
+    /// Enter on the Hardware tab: activate the selected profile or DPI stage
+    pub fn activate_ratbag_selection(&mut self) {
+        match self.ratbag_focus {
+            HardwareFocus::Profiles => {
+                let Some(profile) = self.current_ratbag_profile().cloned() else {
+                    return;
+                };
+                self.ratbag_apply(&format!("Profile {} activated", profile.index), move |r| {
+                    r.set_active_profile(&profile)
+                });
+            }
+            HardwareFocus::Resolutions => {
+                let Some(res) = self
+                    .current_ratbag_profile()
+                    .and_then(|p| p.resolutions.get(self.ratbag_row))
+                    .cloned()
+                else {
+                    return;
+                };
+                self.ratbag_apply(&format!("DPI stage {} activated", res.index), move |r| {
+                    r.set_active_resolution(&res)
+                });
+            }
+            HardwareFocus::Buttons => {}
+        }
+    }
+
+    /// Make the selected DPI stage the one used at power-on
+    pub fn set_default_ratbag_resolution(&mut self) {
+        if self.ratbag_focus != HardwareFocus::Resolutions {
+            return;
+        }
+        let Some(res) = self
+            .current_ratbag_profile()
+            .and_then(|p| p.resolutions.get(self.ratbag_row))
+            .cloned()
+        else {
+            return;
+        };
+        self.ratbag_apply(
+            &format!("DPI stage {} is now the default", res.index),
+            move |r| r.set_default_resolution(&res),
+        );
+    }
+
+    /// +/- on the Hardware tab: step report rate, DPI or button number
+    pub fn adjust_ratbag_selection(&mut self, up: bool) {
+        let Some(profile) = self.current_ratbag_profile().cloned() else {
+            return;
+        };
+        match self.ratbag_focus {
+            HardwareFocus::Profiles => {
+                let Some(rate) = step_value(&profile.report_rates, profile.report_rate, up) else {
+                    return;
+                };
+                self.ratbag_apply(&format!("Report rate set to {}Hz", rate), move |r| {
+                    r.set_report_rate(&profile, rate)
+                });
+            }
+            HardwareFocus::Resolutions => {
+                let Some(res) = profile.resolutions.get(self.ratbag_row).cloned() else {
+                    return;
+                };
+                let Some(dpi) = step_value(&res.supported, res.dpi.0, up) else {
+                    return;
+                };
+                self.ratbag_apply(
+                    &format!("DPI stage {} set to {}", res.index, dpi),
+                    move |r| r.set_dpi(&res, dpi),
+                );
+            }
+            HardwareFocus::Buttons => {
+                let Some(button) = profile.buttons.get(self.ratbag_row).cloned() else {
+                    return;
+                };
+                let current = match button.action {
+                    ButtonAction::Button(n) => n,
+                    _ => 0,
+                };
+                let n = if up {
+                    current + 1
+                } else {
+                    current.saturating_sub(1)
+                };
+                let action = if n == 0 {
+                    ButtonAction::None
+                } else {
+                    ButtonAction::Button(n)
+                };
+                self.ratbag_apply(
+                    &format!("Button {} set to {}", button.index, action.describe()),
+                    move |r| r.set_button_action(&button, &action),
+                );
+            }
+        }
+    }
+
+    /// Write pending ratbagd changes to the mouse's onboard memory
+    pub fn commit_ratbag(&mut self) {
+        let (Some(_), Some(device)) = (&self.ratbag, self.current_ratbag_device()) else {
+            return;
+        };
+        let device = device.clone();
+        let done = format!("Settings written to {}", device.name);
+        self.spawn_ratbag(RatbagJob::Commit(done), move |r| r.commit(&device));
+    }
+
+    /// Get bindings for the active profile
+    pub fn current_bindings(&self) -> &[Binding] {
+        self.config
+            .active_profile()
+            .map(|p| p.bindings.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Get macros for the active profile
+    pub fn current_macros(&self) -> &[MacroDef] {
+        self.config
+            .active_profile()
+            .map(|p| p.macros.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Select the device at the current index and update config
+    pub fn select_current_device(&mut self) {
+        if let Some(device) = self.devices.get(self.device_list_index) {
+            self.selected_device = Some(device.clone());
+            self.config.device.name = Some(device.name.clone());
+            self.config.device.path = Some(device.path.to_string_lossy().to_string());
+            self.config.device.vendor_id = Some(device.vendor_id);
+            self.config.device.product_id = Some(device.product_id);
+            self.set_status(format!("Selected: {}", device.name));
+        }
+    }
+
+    /// Tag/untag the device at the current index for grabbing
+    pub fn toggle_device_tag(&mut self) {
+        if let Some(device) = self.devices.get(self.device_list_index)
+            && !self.tagged_devices.remove(&device.path)
+        {
+            self.tagged_devices.insert(device.path.clone());
+        }
+    }
+
+    /// Start the engine on every tagged device. The first one becomes the
+    /// selected device (saved to the config and used for capture).
+    pub fn grab_tagged_devices(&mut self) {
+        let tagged: Vec<DeviceInfo> = self
+            .devices
+            .iter()
+            .filter(|d| self.tagged_devices.contains(&d.path))
+            .cloned()
+            .collect();
+        let Some(first) = tagged.first() else {
+            self.set_status("No devices tagged (Space to tag)");
+            return;
+        };
+        let index = self.devices.iter().position(|d| d.path == first.path);
+        self.device_list_index = index.unwrap_or(self.device_list_index);
+        self.select_current_device();
+        if self.engine_running {
+            self.stop_engine();
+        }
+        self.start_engine(tagged.iter().map(|d| d.path.clone()).collect(), false);
+    }
+
+    /// Toggle the engine (start/stop). Starting grabs the tagged devices if
+    /// any, otherwise the selected one. An observing engine is replaced.
+    pub fn toggle_engine(&mut self) {
+        if self.engine_running && !self.engine_observing {
+            self.stop_engine();
+            self.set_status("Engine stopped");
+        } else if !self.tagged_devices.is_empty() {
+            self.grab_tagged_devices();
+        } else if let Some(ref device) = self.selected_device {
+            let path = device.path.clone();
+            if self.engine_running {
+                self.stop_engine();
+            }
+            self.start_engine(vec![path], false);
+        } else {
+            self.set_status("No device selected! Select a device first.");
+        }
+    }
+
+    /// Toggle observe-only mode: the tagged devices (or the selected one) are
+    /// read without a grab and only feed the monitor, nothing is remapped.
+    /// Replaces a running mapping engine.
+    pub fn toggle_observe(&mut self) {
+        if self.engine_running && self.engine_observing {
+            self.stop_engine();
+            self.set_status("Stopped observing");
+            return;
+        }
+        let paths: Vec<PathBuf> = if !self.tagged_devices.is_empty() {
+            self.devices
+                .iter()
+                .filter(|d| self.tagged_devices.contains(&d.path))
+                .map(|d| d.path.clone())
+                .collect()
+        } else if let Some(ref device) = self.selected_device {
+            vec![device.path.clone()]
+        } else {
+            self.set_status("No device selected! Select a device first.");
+            return;
+        };
+        if self.engine_running {
+            self.stop_engine();
+        }
+        self.start_engine(paths, true);
+    }
+
+    fn start_engine(&mut self, paths: Vec<PathBuf>, observe: bool) {
+        let device_paths = paths
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        self.send_engine_command(if observe {
+            EngineCommand::Observe(device_paths)
+        } else {
+            EngineCommand::Start(device_paths)
+        });
+        self.engine_warnings.clear();
+        self.engine_devices = paths
+            .into_iter()
+            .map(|p| (p, EngineDeviceState::Starting))
+            .collect();
+        self.engine_running = true;
+        self.engine_observing = observe;
+        self.set_status(format!(
+            "Engine {} on {} device(s)",
+            if observe { "observing" } else { "started" },
+            self.engine_devices.len()
+        ));
+    }
+
+    fn stop_engine(&mut self) {
+        self.send_engine_command(EngineCommand::Stop);
+        self.engine_running = false;
+        self.engine_observing = false;
+        self.dpi_stage = None;
+        self.active_modes.clear();
+        self.running_macros.clear();
+        self.stop_macro_activity();
+        self.engine_states.clear();
+        self.engine_warnings.clear();
+        // A rescan while running recorded our own grabs on the devices
+        for device in &mut self.devices {
+            if self.engine_devices.contains_key(&device.path) {
+                device.grabbed = false;
+            }
+        }
+        self.engine_devices.clear();
+    }
+
+    /// Step the macro playback speed up or down; the change is kept in the
+    /// config, so saving makes it the default
+    pub fn step_macro_speed(&mut self, faster: bool) {
+        let current = self.config.macro_speed;
+        let next = if faster {
+            MACRO_SPEEDS.iter().copied().find(|s| *s > current + 1e-9)
+        } else {
+            MACRO_SPEEDS
+                .iter()
+                .rev()
+                .copied()
+                .find(|s| *s < current - 1e-9)
+        };
+        let Some(speed) = next else {
+            self.set_status(format!("Macro speed already at x{}", current));
+            return;
+        };
+        self.config.macro_speed = speed;
+        self.send_engine_command(EngineCommand::SetMacroSpeed(speed));
+        self.set_status(format!("Macro speed x{}", speed));
+    }
+
+    /// Pause or resume all running repeat and toggle macros
+    pub fn toggle_macro_pause(&mut self) {
+        let pausing = !macros::paused();
+        self.send_engine_command(EngineCommand::PauseMacros(ModeSwitch::Toggle));
+        self.set_status(if pausing {
+            "Macros paused"
+        } else {
+            "Macros resumed"
+        });
+    }
+
+    fn send_engine_command(&self, cmd: EngineCommand) {
+        if let Some(ref tx) = self.engine_cmd_tx {
+            let _ = tx.send(cmd);
+        }
+    }
+
+    /// Process incoming engine messages.
+    /// Caps the number of messages processed per tick to prevent the UI from freezing
+    /// when the engine produces a burst of events (e.g. rapid mouse movement).
+    /// Also intercepts EV_KEY press events for button capture when in capture mode.
+    pub fn poll_engine_messages(&mut self) {
+        let mut rx = match self.engine_msg_rx.take() {
+            Some(rx) => rx,
+            None => return,
+        };
+
+        const MAX_MESSAGES_PER_TICK: usize = 200;
+        let mut processed = 0;
+
+        while processed < MAX_MESSAGES_PER_TICK {
+            match rx.try_recv() {
+                Ok(msg) => {
+                    processed += 1;
+                    match &msg {
+                        EngineMessage::StatusUpdate(s) => {
+                            self.set_status(s.clone());
+                        }
+                        EngineMessage::ConfigWarnings { warnings, .. } => {
+                            self.set_warning(format!(
+                                "{} binding/macro problem(s) in the profile: {}",
+                                warnings.len(),
+                                warnings.first().map(String::as_str).unwrap_or("")
+                            ));
+                            // Every device loads the same profile; keep one copy
+                            self.engine_warnings = warnings.clone();
+                        }
+                        EngineMessage::State(state) => {
+                            let path = PathBuf::from(&state.device);
+                            if state.grabbed {
+                                self.set_status(format!("Grabbed device: {}", state.device_name));
+                                if let Some(device_state) = self.engine_devices.get_mut(&path) {
+                                    *device_state = EngineDeviceState::Grabbed;
+                                }
+                            } else if state.observing {
+                                self.set_status(format!(
+                                    "Observing device: {} (not grabbed)",
+                                    state.device_name
+                                ));
+                                if let Some(device_state) = self.engine_devices.get_mut(&path) {
+                                    *device_state = EngineDeviceState::Observing;
+                                }
+                            }
+                            self.engine_states
+                                .insert(path, (state.clone(), Instant::now()));
+                        }
+                        EngineMessage::DeviceWaiting(path) => {
+                            if let Some(state) = self.engine_devices.get_mut(&PathBuf::from(path)) {
+                                *state = EngineDeviceState::Waiting;
+                            }
+                        }
+                        EngineMessage::EngineRestart {
+                            path,
+                            attempt,
+                            max_attempts,
+                            delay_secs,
+                            error,
+                        } => {
+                            self.set_warning(format!(
+                                "Engine on {} failed ({}); restart {}/{} in {}s",
+                                path, error, attempt, max_attempts, delay_secs
+                            ));
+                            if let Some(state) = self.engine_devices.get_mut(&PathBuf::from(path)) {
+                                *state = EngineDeviceState::Restarting(*attempt, *max_attempts);
+                            }
+                            self.engine_restarts.push(EngineRestart {
+                                at: Instant::now(),
+                                path: path.clone(),
+                                attempt: *attempt,
+                                max_attempts: *max_attempts,
+                                error: error.clone(),
+                            });
+                            if self.engine_restarts.len() > MAX_ENGINE_RESTART_HISTORY {
+                                self.engine_restarts.remove(0);
+                            }
+                        }
+                        EngineMessage::DeviceError { path, error } => {
+                            self.set_error(format!("ERROR: {}: {}", path, error));
+                            self.engine_states.remove(&PathBuf::from(path));
+                            if let Some(state) = self.engine_devices.get_mut(&PathBuf::from(path)) {
+                                *state = EngineDeviceState::Failed(error.clone());
+                            }
+                            // The engine is down once no device is left running
+                            let all_failed = self
+                                .engine_devices
+                                .values()
+                                .all(|s| matches!(s, EngineDeviceState::Failed(_)));
+                            if self.engine_running && all_failed {
+                                self.engine_running = false;
+                                self.engine_observing = false;
+                                self.dpi_stage = None;
+                                self.active_modes.clear();
+                                self.running_macros.clear();
+                                self.stop_macro_activity();
+                            }
+                        }
+                        EngineMessage::DpiStage(stage) => {
+                            self.dpi_stage = *stage;
+                        }
+                        EngineMessage::Modes(modes) => {
+                            self.active_modes = modes.clone();
+                        }
+                        EngineMessage::RunningMacros(names) => {
+                            self.running_macros = names.clone();
+                        }
+                        EngineMessage::Macro(event) => self.update_macro_activity(event),
+                        EngineMessage::Terminate(signal) => {
+                            log::info!("Got {}, quitting", signal);
+                            self.should_quit = true;
                         }
-                        EngineMessage::Error(e) => {
-                            self.set_status(format!("ERROR: {}", e));
-                            self.engine_running = false;
+                        EngineMessage::ProfileSwitched(name) => {
+                            self.set_status(format!("Switched to profile {}", name));
+                            // The tabs follow, unless a binding or macro of
+                            // the current profile is open
+                            if self.editing_binding.is_none()
+                                && self.editing_macro.is_none()
+                                && self.config.profiles.iter().any(|p| p.name == *name)
+                            {
+                                self.config.active_profile = Some(name.clone());
+                                self.binding_list_index = 0;
+                                self.macro_list_index = 0;
+                            }
+                        }
+                        EngineMessage::HidReport { .. } => {
+                            if !self.monitor_paused {
+                                self.monitor_events.push(msg.clone());
+                            }
                         }
                         EngineMessage::RawEvent {
                             event_type,
@@ -292,33 +2281,72 @@ impl App {
                             value,
                             ..
                         } => {
+                            // Tilting the wheel binds its WHEEL_LEFT/WHEEL_RIGHT pseudo button
+                            if self.capturing
+                                && event_type == "EV_REL"
+                                && code == "REL_HWHEEL"
+                                && *value != 0
+                                && matches!(
+                                    self.input_mode,
+                                    InputMode::Capturing {
+                                        field: CaptureField::BindingInput | CaptureField::GridInput
+                                    }
+                                )
+                            {
+                                let tilt = if *value < 0 {
+                                    "WHEEL_LEFT"
+                                } else {
+                                    "WHEEL_RIGHT"
+                                };
+                                self.apply_capture(tilt.to_string());
+                                continue;
+                            }
+                            // Binding inputs are captured as a chord of every button
+                            // pressed until the first release
+                            if self.capturing
+                                && event_type == "EV_KEY"
+                                && matches!(
+                                    self.input_mode,
+                                    InputMode::Capturing {
+                                        field: CaptureField::BindingInput
+                                    }
+                                )
+                            {
+                                match value {
+                                    1 if !self.capture_chord.contains(code) => {
+                                        self.capture_chord.push(code.clone());
+                                        self.set_status(format!(
+                                            "Holding {} (press more buttons for a chord, release to finish)",
+                                            self.capture_chord.join("+")
+                                        ));
+                                    }
+                                    0 if !self.capture_chord.is_empty() => {
+                                        let chord = self.capture_chord.join("+");
+                                        self.apply_capture(chord);
+                                    }
+                                    _ => {}
+                                }
+                                continue;
+                            }
                             // If we're in capture mode and this is a button press,
                             // intercept it for capture instead of adding to monitor
                             if self.capturing && event_type == "EV_KEY" && *value == 1 {
-                                let captured = code.clone();
-                                // Apply the captured key name to the appropriate field
-                                match &self.input_mode {
-                                    InputMode::Capturing { field } => match field {
-                                        CaptureField::BindingInput => {
-                                            if let Some(ref mut editing) = self.editing_binding {
-                                                editing.input = captured.clone();
-                                            }
-                                        }
-                                        CaptureField::BindingOutput => {
-                                            if let Some(ref mut editing) = self.editing_binding {
-                                                editing.output_value = captured.clone();
-                                            }
-                                        }
-                                    },
-                                    _ => {}
-                                }
-                                self.capturing = false;
-                                self.input_mode = InputMode::Editing(String::new());
-                                self.set_status(format!("Captured: {}", captured));
+                                self.apply_capture(code.clone());
                                 // Don't add this event to monitor — it was consumed by capture
                                 continue;
                             }
 
+                            if self.wizard_step == Some(WizardStep::Test)
+                                && event_type == "EV_KEY"
+                                && *value == 1
+                                && self.editing_binding.as_ref().is_some_and(|e| {
+                                    e.input.split('+').next_back().map(str::trim)
+                                        == Some(code.as_str())
+                                })
+                            {
+                                self.wizard_test_presses += 1;
+                            }
+
                             if !self.monitor_paused {
                                 self.monitor_events.push(msg.clone());
                             }
@@ -333,33 +2361,328 @@ impl App {
         if self.monitor_events.len() > self.monitor_max_events {
             let drain_count = self.monitor_events.len() - self.monitor_max_events;
             self.monitor_events.drain(..drain_count);
+            metrics::messages_dropped(drain_count);
         }
 
         self.engine_msg_rx = Some(rx);
     }
 
+    fn update_macro_activity(&mut self, event: &MacroEvent) {
+        match event {
+            MacroEvent::Started(name) => {
+                let activity = self.macro_activity.entry(name.clone()).or_default();
+                activity.running = true;
+                activity.iterations = 0;
+                activity.error = None;
+            }
+            MacroEvent::Iteration(name, n) => {
+                self.macro_activity
+                    .entry(name.clone())
+                    .or_default()
+                    .iterations = *n;
+            }
+            MacroEvent::Stopped { name, iterations } => {
+                let activity = self.macro_activity.entry(name.clone()).or_default();
+                activity.running = false;
+                activity.iterations = *iterations;
+            }
+            MacroEvent::Error { name, message } => {
+                self.macro_activity.entry(name.clone()).or_default().error = Some(message.clone());
+            }
+            MacroEvent::LimitReached { name, cancelled } => match cancelled {
+                Some(cancelled) => self.set_status(format!(
+                    "Stopped macro {} to make room for {}",
+                    cancelled, name
+                )),
+                None => self.set_warning(format!(
+                    "Macro {} not started: too many macros running",
+                    name
+                )),
+            },
+        }
+    }
+
+    /// The engine is gone, so nothing is running anymore
+    fn stop_macro_activity(&mut self) {
+        for activity in self.macro_activity.values_mut() {
+            activity.running = false;
+        }
+    }
+
+    // === Command palette ===
+
+    pub fn open_command_palette(&mut self) {
+        self.command_palette = Some(CommandPalette::default());
+        self.input_mode = InputMode::Editing(String::new());
+    }
+
+    pub fn close_command_palette(&mut self) {
+        self.command_palette = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Close the palette and run the chosen action as its key would. Actions
+    /// that open a tab's dialog switch to that tab first.
+    pub fn run_palette_action(&mut self, action: PaletteAction) {
+        self.close_command_palette();
+        match action {
+            PaletteAction::GoToTab(tab) => self.current_tab = tab,
+            PaletteAction::ToggleEngine => self.toggle_engine(),
+            PaletteAction::ToggleObserve => self.toggle_observe(),
+            PaletteAction::RefreshDevices => self.refresh_devices(),
+            PaletteAction::SwitchProfile(name) => {
+                self.activate_profile(&name);
+                self.set_status(format!("Switched to profile {}", name));
+            }
+            PaletteAction::ExportProfile(format) => self.export_active_profile(format),
+            PaletteAction::AddBinding => {
+                self.current_tab = Tab::Bindings;
+                self.start_new_binding();
+            }
+            PaletteAction::BindingWizard => {
+                self.current_tab = Tab::Bindings;
+                self.start_binding_wizard();
+            }
+            PaletteAction::BulkEditor => {
+                self.current_tab = Tab::Bindings;
+                self.open_grid_editor();
+            }
+            PaletteAction::ImportBindings => {
+                self.current_tab = Tab::Bindings;
+                self.import_path = Some(String::new());
+                self.input_mode = InputMode::Editing(String::new());
+            }
+            PaletteAction::SwapButtons => self.toggle_swap_buttons(),
+            PaletteAction::ToggleTag(tag) => self.toggle_tag(&tag),
+            PaletteAction::AddMacro => {
+                self.current_tab = Tab::Macros;
+                self.start_new_macro();
+            }
+            PaletteAction::ToggleMacro(name) => {
+                if !self.engine_running || self.engine_observing {
+                    self.set_error("Start the engine to run macros");
+                    return;
+                }
+                self.send_engine_command(EngineCommand::TriggerMacro(name.clone()));
+                self.set_status(format!("Toggled macro {}", name));
+            }
+            PaletteAction::PauseMacros => self.toggle_macro_pause(),
+            PaletteAction::MacroSpeed { faster } => self.step_macro_speed(faster),
+            PaletteAction::ToggleHidMonitor => self.toggle_hid_monitor(),
+            PaletteAction::ClearMonitor => {
+                self.monitor_events.clear();
+                self.set_status("Monitor cleared");
+            }
+            PaletteAction::CycleLogLevel => self.cycle_log_level(),
+            PaletteAction::ToggleEngineDebug => self.toggle_engine_debug(),
+            PaletteAction::Save => self.preview_save(),
+            PaletteAction::BackupConfig => self.backup_config(),
+            PaletteAction::RestoreConfig => {
+                self.restore_path = Some(String::new());
+                self.input_mode = InputMode::Editing(String::new());
+            }
+            PaletteAction::Quit => self.request_quit(),
+        }
+    }
+
+    /// Write the active profile in `format` to the exports directory next to
+    /// the config
+    fn export_active_profile(&mut self, format: ExportFormat) {
+        let Some(profile) = self.config.active_profile() else {
+            self.set_error("No profile to export");
+            return;
+        };
+        let result = export::export_profile(profile, &self.config.device, format);
+        let extension = match format {
+            ExportFormat::Keyd => "conf",
+            ExportFormat::Evremap => "toml",
+        };
+        let written = Config::export_path(&profile.name, extension)
+            .and_then(|path| export::write_output(&result, Some(&path)).map(|_| path));
+        match written {
+            Ok(path) if result.warnings.is_empty() => self.set_status(format!(
+                "Exported {} binding(s) to {}",
+                result.exported,
+                path.display()
+            )),
+            Ok(path) => self.set_warning(format!(
+                "Exported {} binding(s) to {}, {} skipped: {}",
+                result.exported,
+                path.display(),
+                result.warnings.len(),
+                result.warnings.join("; ")
+            )),
+            Err(e) => self.set_error(format!("Export failed: {:#}", e)),
+        }
+    }
+
     // === Binding editing ===
 
     pub fn start_new_binding(&mut self) {
         self.editing_binding = Some(EditingBinding {
             index: None,
             input: String::new(),
+            modifiers: Vec::new(),
             output_type: BindingOutputType::Key,
             output_value: String::new(),
+            mode_switch: ModeSwitch::Toggle,
+            when: String::new(),
+            cooldown_ms: String::new(),
+            args: String::new(),
+            description: String::new(),
+            tags: String::new(),
             field_index: 0,
             macro_select_index: 0,
         });
         self.input_mode = InputMode::Editing(String::new());
     }
 
+    /// Start the guided wizard for a new binding, waiting for the button first
+    pub fn start_binding_wizard(&mut self) {
+        self.start_new_binding();
+        self.wizard_step = Some(WizardStep::Input);
+        self.wizard_test_presses = 0;
+        self.start_capture(CaptureField::BindingInput);
+    }
+
+    /// Move the wizard to its next step, or save on the last one. Stays put
+    /// (with a hint in the status bar) if the current step isn't done.
+    pub fn wizard_next(&mut self) {
+        let (Some(step), Some(editing)) = (self.wizard_step, self.editing_binding.as_mut()) else {
+            return;
+        };
+        match step {
+            WizardStep::Input => {
+                if editing.input.is_empty() {
+                    self.set_status("Press Enter, then the button to bind");
+                    return;
+                }
+                self.wizard_step = Some(WizardStep::Action);
+            }
+            WizardStep::Action => {
+                editing.output_value = editing.output_type.initial_value();
+                editing.macro_select_index = 0;
+                self.wizard_step = Some(WizardStep::Target);
+                if editing.output_type.captures_key() {
+                    self.start_capture(CaptureField::BindingOutput);
+                }
+            }
+            WizardStep::Target => {
+                if editing.output_type == BindingOutputType::Macro {
+                    let macro_names = self.macro_names();
+                    let Some(editing) = self.editing_binding.as_mut() else {
+                        return;
+                    };
+                    let Some(name) = macro_names.get(editing.macro_select_index) else {
+                        self.set_status("No macros yet, create one in the Macros tab first");
+                        return;
+                    };
+                    editing.output_value = name.clone();
+                } else if editing.output_type == BindingOutputType::Scroll
+                    && ScrollOutput::parse(&editing.output_value).is_none()
+                {
+                    self.set_status("Type how many notches to scroll");
+                    return;
+                } else if editing.output_type == BindingOutputType::Warp {
+                    let target = editing.output_value.clone();
+                    if let Err(e) = self.warp_config().resolve(&target) {
+                        self.set_status(format!("Can't warp: {}", e));
+                        return;
+                    }
+                } else if editing.output_value.trim().is_empty() {
+                    let hint = if editing.output_type.captures_key() {
+                        "Press Enter, then the key or button to send"
+                    } else if editing.output_type == BindingOutputType::Profile {
+                        "Pick the profile to switch to"
+                    } else {
+                        "Type a mode name first"
+                    };
+                    self.set_status(hint);
+                    return;
+                }
+                self.wizard_step = Some(WizardStep::Test);
+                self.wizard_test_presses = 0;
+                if !self.engine_running
+                    && let Some(ref device) = self.selected_device
+                {
+                    let path = device.path.to_string_lossy().to_string();
+                    self.send_engine_command(EngineCommand::StartCapture(path));
+                    self.capture_only = true;
+                }
+            }
+            WizardStep::Test => {
+                self.stop_wizard_test();
+                self.wizard_step = None;
+                self.save_editing_binding();
+            }
+        }
+    }
+
+    /// Close the wizard without saving
+    pub fn cancel_binding_wizard(&mut self) {
+        self.stop_wizard_test();
+        self.wizard_step = None;
+        self.editing_binding = None;
+        self.input_mode = InputMode::Normal;
+        self.set_status("New binding cancelled");
+    }
+
+    fn stop_wizard_test(&mut self) {
+        if self.capture_only {
+            self.send_engine_command(EngineCommand::StopCapture);
+            self.capture_only = false;
+        }
+    }
+
     pub fn start_edit_binding(&mut self) {
         let bindings = self.current_bindings().to_vec();
         if let Some(binding) = bindings.get(self.binding_list_index) {
+            let mut mode_switch = ModeSwitch::Toggle;
+            let mut args = String::new();
             let (output_type, output_value) = match &binding.output {
                 BindingOutput::Key { key } => (BindingOutputType::Key, key.clone()),
-                BindingOutput::Macro { macro_name } => {
+                BindingOutput::ToggleKey { toggle_key } => {
+                    (BindingOutputType::ToggleKey, toggle_key.clone())
+                }
+                BindingOutput::Scroll { scroll } => (BindingOutputType::Scroll, scroll.to_string()),
+                BindingOutput::Macro {
+                    macro_name,
+                    args: macro_args,
+                } => {
+                    args = format_macro_params(macro_args);
                     (BindingOutputType::Macro, macro_name.clone())
                 }
+                BindingOutput::DpiStage { dpi_stage } => (
+                    BindingOutputType::DpiStage,
+                    dpi_step_name(*dpi_stage).to_string(),
+                ),
+                BindingOutput::PointerKeys { pointer_keys } => (
+                    BindingOutputType::PointerKeys,
+                    mode_switch_name(*pointer_keys).to_string(),
+                ),
+                BindingOutput::Autoscroll { autoscroll } => (
+                    BindingOutputType::Autoscroll,
+                    mode_switch_name(*autoscroll).to_string(),
+                ),
+                BindingOutput::SetMode { mode, set } => {
+                    mode_switch = *set;
+                    (BindingOutputType::Mode, mode.clone())
+                }
+                BindingOutput::SwapButtons { swap_buttons } => (
+                    BindingOutputType::SwapButtons,
+                    mode_switch_name(*swap_buttons).to_string(),
+                ),
+                BindingOutput::PauseMacros { pause_macros } => (
+                    BindingOutputType::PauseMacros,
+                    mode_switch_name(*pause_macros).to_string(),
+                ),
+                BindingOutput::Sticky { sticky } => {
+                    (BindingOutputType::Sticky, sticky.label().to_lowercase())
+                }
+                BindingOutput::Warp { warp } => (BindingOutputType::Warp, warp.clone()),
+                BindingOutput::ToggleProfile { toggle_profile } => {
+                    (BindingOutputType::Profile, toggle_profile.clone())
+                }
             };
             // If editing a macro binding, try to find the index of the selected macro
             let macro_select_index = if output_type == BindingOutputType::Macro {
@@ -373,8 +2696,19 @@ impl App {
             self.editing_binding = Some(EditingBinding {
                 index: Some(self.binding_list_index),
                 input: binding.input.clone(),
+                modifiers: binding.modifiers.clone(),
                 output_type,
                 output_value,
+                mode_switch,
+                when: binding.when.clone().unwrap_or_default(),
+                cooldown_ms: if binding.cooldown_ms > 0 {
+                    binding.cooldown_ms.to_string()
+                } else {
+                    String::new()
+                },
+                args,
+                description: binding.description.clone().unwrap_or_default(),
+                tags: binding.tags.join(", "),
                 field_index: 0,
                 macro_select_index,
             });
@@ -382,19 +2716,228 @@ impl App {
         }
     }
 
+    /// Desktop size and named positions Warp outputs resolve against
+    pub fn warp_config(&self) -> WarpConfig {
+        self.config.warp.clone().unwrap_or_default()
+    }
+
+    /// Flip the active profile's left-handed button swap (applied on the
+    /// next engine start)
+    pub fn toggle_swap_buttons(&mut self) {
+        if let Some(profile) = self.config.active_profile_mut() {
+            profile.swap_buttons = !profile.swap_buttons;
+            let state = if profile.swap_buttons { "on" } else { "off" };
+            self.set_status(format!(
+                "Left-handed button swap {} (save and restart the engine to apply)",
+                state
+            ));
+        }
+    }
+
+    /// Step the Bindings tag filter through the active profile's tags, then
+    /// back to showing every binding
+    pub fn cycle_binding_tag_filter(&mut self) {
+        let tags = self
+            .config
+            .active_profile()
+            .map(Profile::binding_tags)
+            .unwrap_or_default();
+        let next = match &self.binding_tag_filter {
+            None => tags.first(),
+            Some(current) => match tags.iter().position(|t| t == current) {
+                Some(pos) => tags.get(pos + 1),
+                None => tags.first(),
+            },
+        };
+        self.binding_tag_filter = next.cloned();
+        match &self.binding_tag_filter {
+            Some(tag) => self.set_status(format!("Showing bindings tagged '{}'", tag)),
+            None if tags.is_empty() => self.set_status("No binding has a tag"),
+            None => self.set_status("Showing all bindings"),
+        }
+        let visible = self.visible_bindings();
+        if !visible.contains(&self.binding_list_index)
+            && let Some(&first) = visible.first()
+        {
+            self.binding_list_index = first;
+        }
+    }
+
+    /// Switch the bindings of the filtered tag (or, without a tag filter, the
+    /// selected binding's first tag) off or back on
+    pub fn toggle_binding_tag(&mut self) {
+        let tag = self.binding_tag_filter.clone().or_else(|| {
+            self.current_bindings()
+                .get(self.binding_list_index)
+                .and_then(|b| b.tags.first().cloned())
+        });
+        match tag {
+            Some(tag) => self.toggle_tag(&tag),
+            None => self.set_status("The selected binding has no tag"),
+        }
+    }
+
+    /// Switch the bindings tagged `tag` off or back on
+    pub fn toggle_tag(&mut self, tag: &str) {
+        let Some(profile) = self.config.active_profile_mut() else {
+            return;
+        };
+        let enabled = if let Some(pos) = profile.disabled_tags.iter().position(|t| t == tag) {
+            profile.disabled_tags.remove(pos);
+            true
+        } else {
+            profile.disabled_tags.push(tag.to_string());
+            profile.disabled_tags.sort();
+            false
+        };
+        let count = profile
+            .bindings
+            .iter()
+            .filter(|b| b.tags.iter().any(|t| t == tag))
+            .count();
+        self.set_status(format!(
+            "{} {} binding(s) tagged '{}' (save and restart the engine to apply)",
+            if enabled { "Enabled" } else { "Disabled" },
+            count,
+            tag
+        ));
+    }
+
+    /// Add or remove a required modifier, keeping Ctrl/Shift/Alt/Meta order
+    pub fn toggle_binding_modifier(&mut self, modifier: KeyModifier) {
+        if let Some(ref mut editing) = self.editing_binding {
+            if editing.modifiers.contains(&modifier) {
+                editing.modifiers.retain(|m| *m != modifier);
+            } else {
+                editing.modifiers.push(modifier);
+                editing
+                    .modifiers
+                    .sort_by_key(|m| KeyModifier::all().iter().position(|a| a == m));
+            }
+        }
+    }
+
     pub fn save_editing_binding(&mut self) {
         if let Some(ref editing) = self.editing_binding.clone() {
+            if editing.output_type == BindingOutputType::Mode
+                && editing.output_value.trim().is_empty()
+            {
+                self.set_status("Enter a mode name first");
+                return;
+            }
+            if editing.output_type == BindingOutputType::Warp
+                && let Err(e) = self.warp_config().resolve(&editing.output_value)
+            {
+                self.set_error(format!("Can't warp: {}", e));
+                return;
+            }
+            if editing.output_type == BindingOutputType::Profile
+                && !self.profile_names().contains(&editing.output_value)
+            {
+                self.set_status("Pick the profile to switch to with Tab");
+                return;
+            }
+            let scroll = ScrollOutput::parse(&editing.output_value);
+            if editing.output_type == BindingOutputType::Scroll && scroll.is_none() {
+                self.set_error("Scroll amount must be a whole number of notches");
+                return;
+            }
+            let args = match parse_macro_params(&editing.args) {
+                Ok(args) => args,
+                Err(e) => {
+                    self.set_error(format!("Macro arguments: {}", e));
+                    return;
+                }
+            };
+            // Arguments the macro can't take are still saved, with a warning
+            let args_problem = self
+                .current_macros()
+                .iter()
+                .find(|m| m.name == editing.output_value)
+                .and_then(|def| def.with_args(&args).err())
+                .filter(|_| editing.output_type == BindingOutputType::Macro);
             let output = match editing.output_type {
                 BindingOutputType::Key => BindingOutput::Key {
                     key: editing.output_value.clone(),
                 },
+                BindingOutputType::ToggleKey => BindingOutput::ToggleKey {
+                    toggle_key: editing.output_value.clone(),
+                },
+                BindingOutputType::Scroll => BindingOutput::Scroll {
+                    scroll: scroll.unwrap_or_default(),
+                },
                 BindingOutputType::Macro => BindingOutput::Macro {
                     macro_name: editing.output_value.clone(),
+                    args,
+                },
+                BindingOutputType::DpiStage => BindingOutput::DpiStage {
+                    dpi_stage: match editing.output_value.as_str() {
+                        "prev" => DpiStep::Prev,
+                        _ => DpiStep::Next,
+                    },
+                },
+                BindingOutputType::PointerKeys => BindingOutput::PointerKeys {
+                    pointer_keys: match editing.output_value.as_str() {
+                        "on" => ModeSwitch::On,
+                        "off" => ModeSwitch::Off,
+                        _ => ModeSwitch::Toggle,
+                    },
+                },
+                BindingOutputType::Autoscroll => BindingOutput::Autoscroll {
+                    autoscroll: match editing.output_value.as_str() {
+                        "on" => ModeSwitch::On,
+                        "off" => ModeSwitch::Off,
+                        _ => ModeSwitch::Toggle,
+                    },
+                },
+                BindingOutputType::Mode => BindingOutput::SetMode {
+                    mode: editing.output_value.trim().to_string(),
+                    set: editing.mode_switch,
+                },
+                BindingOutputType::SwapButtons => BindingOutput::SwapButtons {
+                    swap_buttons: match editing.output_value.as_str() {
+                        "on" => ModeSwitch::On,
+                        "off" => ModeSwitch::Off,
+                        _ => ModeSwitch::Toggle,
+                    },
+                },
+                BindingOutputType::PauseMacros => BindingOutput::PauseMacros {
+                    pause_macros: match editing.output_value.as_str() {
+                        "on" => ModeSwitch::On,
+                        "off" => ModeSwitch::Off,
+                        _ => ModeSwitch::Toggle,
+                    },
+                },
+                BindingOutputType::Sticky => BindingOutput::Sticky {
+                    sticky: match editing.output_value.as_str() {
+                        "ctrl" => KeyModifier::Ctrl,
+                        "alt" => KeyModifier::Alt,
+                        "meta" => KeyModifier::Meta,
+                        _ => KeyModifier::Shift,
+                    },
+                },
+                BindingOutputType::Warp => BindingOutput::Warp {
+                    warp: editing.output_value.trim().to_string(),
+                },
+                BindingOutputType::Profile => BindingOutput::ToggleProfile {
+                    toggle_profile: editing.output_value.clone(),
                 },
             };
+            let when = editing.when.trim();
+            let description = editing.description.trim();
             let binding = Binding {
                 input: editing.input.clone(),
                 output,
+                modifiers: editing.modifiers.clone(),
+                when: (!when.is_empty()).then(|| when.to_string()),
+                cooldown_ms: editing.cooldown_ms.parse().unwrap_or(0),
+                description: (!description.is_empty()).then(|| description.to_string()),
+                tags: editing
+                    .tags
+                    .split([',', ' '])
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect(),
             };
 
             if let Some(profile) = self.config.active_profile_mut() {
@@ -409,66 +2952,133 @@ impl App {
 
             self.editing_binding = None;
             self.input_mode = InputMode::Normal;
-            self.set_status("Binding saved");
+            match args_problem {
+                Some(problem) => self.set_warning(format!("Binding saved, but {}", problem)),
+                None => self.set_status("Binding saved"),
+            }
         }
     }
 
     pub fn delete_current_binding(&mut self) {
         let idx = self.binding_list_index;
-        if let Some(profile) = self.config.active_profile_mut() {
-            if idx < profile.bindings.len() {
-                profile.bindings.remove(idx);
-                if self.binding_list_index > 0 && self.binding_list_index >= profile.bindings.len()
-                {
-                    self.binding_list_index = profile.bindings.len().saturating_sub(1);
-                }
+        if let Some(profile) = self.config.active_profile_mut()
+            && idx < profile.bindings.len()
+        {
+            profile.bindings.remove(idx);
+            if self.binding_list_index > 0 && self.binding_list_index >= profile.bindings.len() {
+                self.binding_list_index = profile.bindings.len().saturating_sub(1);
             }
         }
         self.set_status("Binding deleted");
     }
 
+    /// Import bindings from an input-remapper preset or xbindkeysrc into the active profile
+    pub fn import_bindings(&mut self, path: &str) {
+        let path = match path.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+            None => std::path::PathBuf::from(path),
+        };
+        match import::import_file(&path, None) {
+            Ok(result) => {
+                if let Some(profile) = self.config.active_profile_mut() {
+                    import::apply(profile, &result);
+                }
+                for warning in &result.warnings {
+                    log::warn!("Import: {}", warning);
+                }
+                self.set_status(format!(
+                    "Imported {} binding(s), {} macro(s){}",
+                    result.bindings.len(),
+                    result.macros.len(),
+                    if result.warnings.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", {} skipped (see Logs)", result.warnings.len())
+                    }
+                ));
+            }
+            Err(e) => self.set_error(format!("Import failed: {:#}", e)),
+        }
+    }
+
     // === Macro editing ===
 
+    /// Open the template picker; the chosen template pre-fills the macro editor
     pub fn start_new_macro(&mut self) {
-        self.editing_macro = Some(EditingMacro {
-            index: None,
-            name: String::new(),
-            macro_type: MacroType::RepeatOnHold,
-            actions: vec![MacroAction::Click("BTN_LEFT".to_string())],
-            interval_ms: "50".to_string(),
-            jitter_ms: "10".to_string(),
-            field_index: 0,
-        });
+        self.template_picker = Some(0);
         self.input_mode = InputMode::Editing(String::new());
     }
 
+    /// Use the template at `index`: ask for its parameters, or build it
+    /// right away if it has none
+    pub fn choose_template(&mut self, index: usize) {
+        match templates::all().get(index) {
+            Some(template) if !template.params.is_empty() => {
+                self.template_values = Some(
+                    template
+                        .params
+                        .iter()
+                        .map(|p| p.default.to_string())
+                        .collect(),
+                );
+                self.template_field = 0;
+            }
+            _ => self.start_macro_from_template(index, &[]),
+        }
+    }
+
+    /// Open the macro editor pre-filled from the template at `index`, built
+    /// with `values` for its parameters. A bad value keeps the form open.
+    pub fn start_macro_from_template(&mut self, index: usize, values: &[String]) {
+        if let Some(template) = templates::all().get(index) {
+            let def = match template.build(values) {
+                Ok(def) => def,
+                Err(e) => {
+                    self.set_error(e);
+                    return;
+                }
+            };
+            self.template_picker = None;
+            self.template_values = None;
+            let mut editing = EditingMacro::from_def(None, &def);
+            // Make the default name unique within the profile
+            if !editing.name.is_empty() {
+                let base = editing.name.clone();
+                let names = self.macro_names();
+                let mut n = 2;
+                while names.contains(&editing.name) {
+                    editing.name = format!("{}_{}", base, n);
+                    n += 1;
+                }
+            }
+            self.editing_macro = Some(editing);
+            self.input_mode = InputMode::Editing(String::new());
+        } else {
+            self.template_picker = None;
+            self.template_values = None;
+            self.input_mode = InputMode::Normal;
+        }
+    }
+
     pub fn start_edit_macro(&mut self) {
         let macros = self.current_macros().to_vec();
         if let Some(macro_def) = macros.get(self.macro_list_index) {
-            self.editing_macro = Some(EditingMacro {
-                index: Some(self.macro_list_index),
-                name: macro_def.name.clone(),
-                macro_type: macro_def.macro_type.clone(),
-                actions: macro_def.actions.clone(),
-                interval_ms: macro_def.interval_ms.to_string(),
-                jitter_ms: macro_def.jitter_ms.to_string(),
-                field_index: 0,
-            });
+            self.editing_macro = Some(EditingMacro::from_def(
+                Some(self.macro_list_index),
+                macro_def,
+            ));
             self.input_mode = InputMode::Editing(String::new());
         }
     }
 
     pub fn save_editing_macro(&mut self) {
         if let Some(ref editing) = self.editing_macro.clone() {
-            let interval_ms = editing.interval_ms.parse().unwrap_or(50);
-            let jitter_ms = editing.jitter_ms.parse().unwrap_or(0);
-            let macro_def = MacroDef {
-                name: editing.name.clone(),
-                macro_type: editing.macro_type.clone(),
-                actions: editing.actions.clone(),
-                interval_ms,
-                initial_delay_ms: 0,
-                jitter_ms,
+            let macro_def = match editing.to_def() {
+                Ok(def) => def,
+                Err(e) => {
+                    self.set_error(e);
+                    return;
+                }
             };
 
             if let Some(profile) = self.config.active_profile_mut() {
@@ -489,12 +3099,12 @@ impl App {
 
     pub fn delete_current_macro(&mut self) {
         let idx = self.macro_list_index;
-        if let Some(profile) = self.config.active_profile_mut() {
-            if idx < profile.macros.len() {
-                profile.macros.remove(idx);
-                if self.macro_list_index > 0 && self.macro_list_index >= profile.macros.len() {
-                    self.macro_list_index = profile.macros.len().saturating_sub(1);
-                }
+        if let Some(profile) = self.config.active_profile_mut()
+            && idx < profile.macros.len()
+        {
+            profile.macros.remove(idx);
+            if self.macro_list_index > 0 && self.macro_list_index >= profile.macros.len() {
+                self.macro_list_index = profile.macros.len().saturating_sub(1);
             }
         }
         self.set_status("Macro deleted");
@@ -503,27 +3113,140 @@ impl App {
     /// Save config to disk
     pub fn save_config(&mut self) {
         match self.config.save() {
-            Ok(()) => self.set_status("Config saved"),
-            Err(e) => self.set_status(format!("Failed to save config: {}", e)),
+            Ok(()) => {
+                self.saved_config = self.config.clone();
+                self.set_status("Config saved");
+            }
+            Err(e) => self.set_error(format!("Failed to save config: {}", e)),
         }
 
         // Also tell the engine to reload
         self.send_engine_command(EngineCommand::ReloadConfig);
     }
 
+    /// Show what saving would write, diffed against the files on disk, and
+    /// wait for confirmation; saves right away when nothing would change
+    pub fn preview_save(&mut self) {
+        let files = match self.config.planned_files() {
+            Ok(files) => files,
+            Err(e) => {
+                self.set_error(format!("Failed to save config: {}", e));
+                return;
+            }
+        };
+        let mut lines = Vec::new();
+        for (path, content) in &files {
+            let on_disk = std::fs::read_to_string(path).ok();
+            let name = path.display().to_string();
+            lines.extend(crate::tui::diff::unified(
+                on_disk.as_deref().unwrap_or(""),
+                content.as_deref().unwrap_or(""),
+                if on_disk.is_some() {
+                    &name
+                } else {
+                    "/dev/null"
+                },
+                if content.is_some() {
+                    &name
+                } else {
+                    "/dev/null"
+                },
+            ));
+        }
+        if lines.is_empty() {
+            self.save_config();
+            return;
+        }
+        self.save_preview = Some(SavePreview { lines, scroll: 0 });
+        self.input_mode = InputMode::Editing(String::new());
+    }
+
+    /// Back the saved config up to the backups/ directory of the state dir
+    pub fn backup_config(&mut self) {
+        let path = backup::default_path("mouse-mapper");
+        match backup::backup(&path, &self.saved_config) {
+            Ok(summary) => {
+                for include in &summary.skipped {
+                    log::warn!("Backup: {} is outside the config directory", include);
+                }
+                self.set_status(format!(
+                    "Backed up {} file(s) to {}{}",
+                    summary.files,
+                    path.display(),
+                    if self.is_dirty() {
+                        " (unsaved changes aren't in it)"
+                    } else {
+                        ""
+                    }
+                ));
+            }
+            Err(e) => self.set_error(format!("Backup failed: {:#}", e)),
+        }
+    }
+
+    /// Replace the config on disk with a backup and load it
+    pub fn restore_config(&mut self, path: &str) {
+        if self.is_dirty() {
+            self.set_warning("Save or undo your changes first, restoring replaces the config");
+            return;
+        }
+        let path = match path.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+            None => std::path::PathBuf::from(path),
+        };
+        let summary = match backup::restore(&path) {
+            Ok(summary) => summary,
+            Err(e) => {
+                self.set_error(format!("Restore failed: {:#}", e));
+                return;
+            }
+        };
+        match Config::load() {
+            Ok(config) => {
+                self.config = config;
+                self.saved_config = self.config.clone();
+                self.binding_list_index = 0;
+                self.macro_list_index = 0;
+                self.binding_tag_filter = None;
+                self.set_status(format!(
+                    "Restored {} file(s){}",
+                    summary.files,
+                    summary
+                        .previous
+                        .map(|p| format!(", the old config is in {}", p.display()))
+                        .unwrap_or_default()
+                ));
+                self.send_engine_command(EngineCommand::ReloadConfig);
+            }
+            Err(e) => self.set_error(format!("Restored, but loading failed: {:#}", e)),
+        }
+    }
+
     /// Start capturing a mouse button press via the engine's event stream.
-    /// The engine must be running — it reads events from the grabbed device and
-    /// forwards them as `EngineMessage::RawEvent`. `poll_engine_messages()` will
-    /// intercept the first EV_KEY press while `self.capturing` is true.
+    /// If the engine is running it forwards events from the grabbed device as
+    /// `EngineMessage::RawEvent`; otherwise the selected device is read without
+    /// grabbing it for the duration of the capture. `poll_engine_messages()`
+    /// will intercept the first EV_KEY press while `self.capturing` is true.
     pub fn start_capture(&mut self, field: CaptureField) {
         if !self.engine_running {
-            self.set_status("Start the engine first to capture buttons!");
-            return;
+            let Some(ref device) = self.selected_device else {
+                self.set_status("Select a device first to capture buttons!");
+                return;
+            };
+            let path = device.path.to_string_lossy().to_string();
+            self.send_engine_command(EngineCommand::StartCapture(path));
+            self.capture_only = true;
         }
 
         let msg = match &field {
-            CaptureField::BindingInput => "Press a mouse button to capture... (Esc to cancel)",
-            CaptureField::BindingOutput => {
+            CaptureField::BindingInput => {
+                "Press a mouse button (or hold several for a chord)... (Esc to cancel)"
+            }
+            CaptureField::GridInput => "Press the grid button for this row... (Esc to stop)",
+            CaptureField::BindingOutput
+            | CaptureField::MacroAction
+            | CaptureField::MacroInsert { .. }
+            | CaptureField::GridOutput => {
                 "Press a key or mouse button to capture... (Esc to cancel)"
             }
         };
@@ -533,7 +3256,158 @@ impl App {
         self.set_status(msg);
     }
 
+    /// Put a captured key name into the field being captured and leave capture mode
+    pub fn apply_capture(&mut self, captured: String) {
+        if let InputMode::Capturing { field } = &self.input_mode {
+            match field {
+                CaptureField::BindingInput => {
+                    if let Some(ref mut editing) = self.editing_binding {
+                        editing.input = captured.clone();
+                    }
+                }
+                CaptureField::BindingOutput => {
+                    if let Some(ref mut editing) = self.editing_binding {
+                        editing.output_value = captured.clone();
+                    }
+                }
+                CaptureField::MacroAction => {
+                    if let Some(ref mut editing) = self.editing_macro {
+                        editing.set_action_key(captured.clone());
+                    }
+                }
+                CaptureField::MacroInsert { hold } => {
+                    if let Some(ref mut editing) = self.editing_macro {
+                        editing.insert_action(if *hold {
+                            MacroAction::Press(captured.clone())
+                        } else {
+                            MacroAction::Click(ClickStep::new(captured.clone()))
+                        });
+                    }
+                }
+                CaptureField::GridInput => self.set_grid_input(captured.clone()),
+                CaptureField::GridOutput => {
+                    if let Some(ref mut grid) = self.grid_editor
+                        && let Some(row) = grid.rows.get_mut(grid.selected)
+                    {
+                        row.output = Some(BindingOutput::Key {
+                            key: captured.clone(),
+                        });
+                        grid.selected = (grid.selected + 1).min(grid.rows.len() - 1);
+                    }
+                }
+            }
+        }
+        self.finish_capture();
+        self.set_status(format!("Captured: {}", captured));
+        if self.wizard_step.is_some() {
+            self.wizard_next();
+        }
+        if let Some(ref mut grid) = self.grid_editor
+            && grid.learning
+        {
+            if grid.selected + 1 < grid.rows.len() {
+                grid.selected += 1;
+                self.start_capture(CaptureField::GridInput);
+            } else {
+                grid.learning = false;
+            }
+        }
+    }
+
+    /// Put a captured button into the selected bulk editor row, taking over
+    /// the output of its existing plain binding if it has one
+    fn set_grid_input(&mut self, input: String) {
+        let existing = self
+            .current_bindings()
+            .iter()
+            .find(|b| b.input == input && b.modifiers.is_empty() && b.when.is_none())
+            .map(|b| b.output.clone());
+        if let Some(ref mut grid) = self.grid_editor
+            && let Some(row) = grid.rows.get_mut(grid.selected)
+        {
+            row.input = input;
+            if existing.is_some() {
+                row.output = existing;
+            }
+        }
+    }
+
+    // === Bulk binding editor ===
+
+    pub fn open_grid_editor(&mut self) {
+        self.grid_editor = Some(GridEditor::new());
+        self.input_mode = InputMode::Editing(String::new());
+        self.set_status("Press l to capture the buttons one after another");
+    }
+
+    pub fn close_grid_editor(&mut self) {
+        self.grid_editor = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Capture the grid's buttons in order, starting at the selected row
+    pub fn start_grid_learning(&mut self) {
+        if let Some(ref mut grid) = self.grid_editor {
+            grid.learning = true;
+        }
+        self.start_capture(CaptureField::GridInput);
+    }
+
+    /// Write the rows that have a button and an output as bindings, replacing
+    /// the plain binding of each button if there is one
+    pub fn apply_grid_editor(&mut self) {
+        let Some(grid) = self.grid_editor.take() else {
+            return;
+        };
+        let mut count = 0;
+        if let Some(profile) = self.config.active_profile_mut() {
+            for row in grid.rows {
+                let (false, Some(output)) = (row.input.is_empty(), row.output) else {
+                    continue;
+                };
+                let binding = Binding {
+                    input: row.input,
+                    output,
+                    modifiers: Vec::new(),
+                    when: None,
+                    cooldown_ms: 0,
+                    description: None,
+                    tags: Vec::new(),
+                };
+                match profile.bindings.iter_mut().find(|b| {
+                    b.input == binding.input && b.modifiers.is_empty() && b.when.is_none()
+                }) {
+                    Some(existing) => *existing = binding,
+                    None => profile.bindings.push(binding),
+                }
+                count += 1;
+            }
+        }
+        self.input_mode = InputMode::Normal;
+        self.set_status(format!("Applied {} bindings from the bulk editor", count));
+    }
+
+    /// Leave capture mode (captured or cancelled), stopping a capture-only read
+    pub fn finish_capture(&mut self) {
+        if self.capture_only {
+            self.send_engine_command(EngineCommand::StopCapture);
+            self.capture_only = false;
+        }
+        self.capturing = false;
+        self.capture_chord.clear();
+        self.input_mode = InputMode::Editing(String::new());
+    }
+
     /// Get the list of macro names from the active profile
+    /// Names of all profiles, in config order
+    pub fn profile_names(&self) -> Vec<String> {
+        self.config
+            .profiles
+            .iter()
+            .map(|p| p.name.clone())
+            .collect()
+    }
+
     pub fn macro_names(&self) -> Vec<String> {
         self.current_macros()
             .iter()
@@ -541,3 +3415,83 @@ impl App {
             .collect()
     }
 }
+
+/// Next/previous entry of a sorted list of supported values, relative to `current`
+fn step_value(values: &[u32], current: u32, up: bool) -> Option<u32> {
+    if up {
+        values.iter().copied().filter(|&v| v > current).min()
+    } else {
+        values.iter().copied().filter(|&v| v < current).max()
+    }
+}
+
+/// Config/display name of a mode switch
+pub fn mode_switch_name(switch: ModeSwitch) -> &'static str {
+    match switch {
+        ModeSwitch::Toggle => "toggle",
+        ModeSwitch::On => "on",
+        ModeSwitch::Off => "off",
+    }
+}
+
+/// Scroll offset that keeps row `selected` inside a viewport of `rows` rows,
+/// moving `offset` as little as possible
+pub fn scroll_offset(offset: usize, selected: usize, rows: usize) -> usize {
+    offset
+        .min(selected)
+        .max((selected + 1).saturating_sub(rows.max(1)))
+}
+
+/// Action column and output text of a binding in the Bindings list
+pub fn binding_output_summary(output: &BindingOutput) -> (&'static str, String) {
+    match output {
+        BindingOutput::Key { key } => ("Key Remap", key.clone()),
+        BindingOutput::ToggleKey { toggle_key } => ("Toggle Key", toggle_key.clone()),
+        BindingOutput::Scroll { scroll } => ("Scroll", scroll.to_string()),
+        BindingOutput::Macro { macro_name, args } if args.is_empty() => {
+            ("Macro", macro_name.clone())
+        }
+        BindingOutput::Macro { macro_name, args } => (
+            "Macro",
+            format!("{}({})", macro_name, format_macro_params(args)),
+        ),
+        BindingOutput::DpiStage { dpi_stage } => {
+            ("DPI Stage", dpi_step_name(*dpi_stage).to_string())
+        }
+        BindingOutput::PointerKeys { pointer_keys } => {
+            ("Pointer Keys", mode_switch_name(*pointer_keys).to_string())
+        }
+        BindingOutput::Autoscroll { autoscroll } => {
+            ("Autoscroll", mode_switch_name(*autoscroll).to_string())
+        }
+        BindingOutput::SetMode { mode, set } => {
+            ("Set Mode", format!("{}: {}", mode, mode_switch_name(*set)))
+        }
+        BindingOutput::SwapButtons { swap_buttons } => {
+            ("Swap L/R", mode_switch_name(*swap_buttons).to_string())
+        }
+        BindingOutput::PauseMacros { pause_macros } => {
+            ("Pause Macros", mode_switch_name(*pause_macros).to_string())
+        }
+        BindingOutput::Sticky { sticky } => ("Sticky Mod", sticky.label().to_string()),
+        BindingOutput::Warp { warp } => ("Warp Cursor", warp.clone()),
+        BindingOutput::ToggleProfile { toggle_profile } => ("A/B Profile", toggle_profile.clone()),
+    }
+}
+
+pub fn macro_type_name(macro_type: &MacroType) -> &'static str {
+    match macro_type {
+        MacroType::RepeatOnHold => "Repeat on Hold",
+        MacroType::Sequence => "Sequence",
+        MacroType::Toggle => "Toggle",
+        MacroType::Random => "Random",
+    }
+}
+
+/// Config/display name of a DPI stage step
+pub fn dpi_step_name(step: DpiStep) -> &'static str {
+    match step {
+        DpiStep::Next => "next",
+        DpiStep::Prev => "prev",
+    }
+}
@@ -0,0 +1,109 @@
+/// Lines of unchanged text kept around each change
+const CONTEXT: usize = 3;
+/// Above this many cells the LCS table isn't built and the changed middle
+/// is shown as removed then added
+const MAX_TABLE: usize = 4_000_000;
+
+/// One line of a diff: ' ' unchanged, '-' removed, '+' added
+type Op<'a> = (char, &'a str);
+
+/// Unified diff of `old` against `new`, with `---`/`+++` headers naming
+/// the file. Empty when the texts are the same.
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> Vec<String> {
+    if old == new {
+        return Vec::new();
+    }
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let ops = line_ops(&old, &new);
+
+    let mut out = vec![format!("--- {}", old_name), format!("+++ {}", new_name)];
+    let changes: Vec<usize> = (0..ops.len()).filter(|&i| ops[i].0 != ' ').collect();
+    let mut i = 0;
+    while i < changes.len() {
+        // Changes closer than twice the context share a hunk
+        let start = changes[i].saturating_sub(CONTEXT);
+        let mut last = changes[i];
+        while i + 1 < changes.len() && changes[i + 1] - last <= 2 * CONTEXT {
+            i += 1;
+            last = changes[i];
+        }
+        let end = (last + CONTEXT + 1).min(ops.len());
+        i += 1;
+
+        let before = &ops[..start];
+        let old_start = before.iter().filter(|(k, _)| *k != '+').count();
+        let new_start = before.iter().filter(|(k, _)| *k != '-').count();
+        let hunk = &ops[start..end];
+        let old_len = hunk.iter().filter(|(k, _)| *k != '+').count();
+        let new_len = hunk.iter().filter(|(k, _)| *k != '-').count();
+        out.push(format!(
+            "@@ -{} +{} @@",
+            range(old_start, old_len),
+            range(new_start, new_len)
+        ));
+        out.extend(hunk.iter().map(|(kind, line)| format!("{}{}", kind, line)));
+    }
+    out
+}
+
+/// "start,len" as diff prints it: 1-based, and the line before for an
+/// empty range
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+/// Edit script turning `old` into `new`, from the longest common
+/// subsequence of the lines between their common prefix and suffix
+fn line_ops<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Op<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<Op> = old[..prefix].iter().map(|l| (' ', *l)).collect();
+    if (a.len() + 1) * (b.len() + 1) > MAX_TABLE {
+        ops.extend(a.iter().map(|l| ('-', *l)));
+        ops.extend(b.iter().map(|l| ('+', *l)));
+    } else {
+        // lcs[i][j]: length of the LCS of a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lcs = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * width + j] = if a[i] == b[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                ops.push((' ', a[i]));
+                i += 1;
+                j += 1;
+            } else if j == b.len()
+                || (i < a.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                ops.push(('-', a[i]));
+                i += 1;
+            } else {
+                ops.push(('+', b[j]));
+                j += 1;
+            }
+        }
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|l| (' ', *l)));
+    ops
+}
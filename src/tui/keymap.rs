@@ -0,0 +1,461 @@
+use crate::config::KeyList;
+use crate::tui::app::Tab;
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::collections::{BTreeMap, HashMap};
+
+/// A command of the main screen (no dialog open) that the `[keybindings]`
+/// config section can put on other keys. Dialogs keep their fixed keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    // Everywhere
+    Quit,
+    NextTab,
+    PrevTab,
+    Save,
+    Help,
+    Notifications,
+    CommandPalette,
+    MacroSlower,
+    MacroFaster,
+    PauseMacros,
+    // Lists and scrolling
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    // Devices
+    Select,
+    Tag,
+    Engine,
+    Observe,
+    Refresh,
+    // Bindings and macros
+    Add,
+    Edit,
+    Delete,
+    Filter,
+    Wizard,
+    BulkEditor,
+    Import,
+    SwapButtons,
+    TagFilter,
+    ToggleTag,
+    // Monitor
+    PauseMonitor,
+    Clear,
+    HidReports,
+    SplitMonitor,
+    // Logs
+    LogLevel,
+    LogVerbosity,
+    EngineDebug,
+    // Hardware
+    NextSection,
+    Increase,
+    Decrease,
+    DefaultDpi,
+    Commit,
+    NextDevice,
+}
+
+const LISTS: &[Tab] = &[
+    Tab::Devices,
+    Tab::Bindings,
+    Tab::Macros,
+    Tab::Logs,
+    Tab::Hardware,
+];
+
+impl Action {
+    pub const ALL: &[Action] = &[
+        Action::Quit,
+        Action::NextTab,
+        Action::PrevTab,
+        Action::Save,
+        Action::Help,
+        Action::Notifications,
+        Action::CommandPalette,
+        Action::MacroSlower,
+        Action::MacroFaster,
+        Action::PauseMacros,
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::Top,
+        Action::Bottom,
+        Action::Select,
+        Action::Tag,
+        Action::Engine,
+        Action::Observe,
+        Action::Refresh,
+        Action::Add,
+        Action::Edit,
+        Action::Delete,
+        Action::Filter,
+        Action::Wizard,
+        Action::BulkEditor,
+        Action::Import,
+        Action::SwapButtons,
+        Action::TagFilter,
+        Action::ToggleTag,
+        Action::PauseMonitor,
+        Action::Clear,
+        Action::HidReports,
+        Action::SplitMonitor,
+        Action::LogLevel,
+        Action::LogVerbosity,
+        Action::EngineDebug,
+        Action::NextSection,
+        Action::Increase,
+        Action::Decrease,
+        Action::DefaultDpi,
+        Action::Commit,
+        Action::NextDevice,
+    ];
+
+    /// Name in the `[keybindings]` section
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::NextTab => "next_tab",
+            Action::PrevTab => "prev_tab",
+            Action::Save => "save",
+            Action::Help => "help",
+            Action::Notifications => "notifications",
+            Action::CommandPalette => "command_palette",
+            Action::MacroSlower => "macro_slower",
+            Action::MacroFaster => "macro_faster",
+            Action::PauseMacros => "pause_macros",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::Top => "top",
+            Action::Bottom => "bottom",
+            Action::Select => "select",
+            Action::Tag => "tag",
+            Action::Engine => "engine",
+            Action::Observe => "observe",
+            Action::Refresh => "refresh",
+            Action::Add => "add",
+            Action::Edit => "edit",
+            Action::Delete => "delete",
+            Action::Filter => "filter",
+            Action::Wizard => "wizard",
+            Action::BulkEditor => "bulk_editor",
+            Action::Import => "import",
+            Action::SwapButtons => "swap_buttons",
+            Action::TagFilter => "tag_filter",
+            Action::ToggleTag => "toggle_tag",
+            Action::PauseMonitor => "pause_monitor",
+            Action::Clear => "clear",
+            Action::HidReports => "hid_reports",
+            Action::SplitMonitor => "split_monitor",
+            Action::LogLevel => "log_level",
+            Action::LogVerbosity => "log_verbosity",
+            Action::EngineDebug => "engine_debug",
+            Action::NextSection => "next_section",
+            Action::Increase => "increase",
+            Action::Decrease => "decrease",
+            Action::DefaultDpi => "default_dpi",
+            Action::Commit => "commit",
+            Action::NextDevice => "next_device",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::NextTab => &["Right", "l"],
+            Action::PrevTab => &["Left", "h"],
+            Action::Save => &["s"],
+            Action::Help => &["?"],
+            Action::Notifications => &["N"],
+            Action::CommandPalette => &["Ctrl+p"],
+            Action::MacroSlower => &["<"],
+            Action::MacroFaster => &[">"],
+            Action::PauseMacros => &["P"],
+            Action::Up => &["Up", "k"],
+            Action::Down => &["Down", "j"],
+            Action::PageUp => &["PageUp"],
+            Action::PageDown => &["PageDown"],
+            Action::Top => &["Home"],
+            Action::Bottom => &["End", "G"],
+            Action::Select => &["Enter"],
+            Action::Tag => &["Space"],
+            Action::Engine => &["g"],
+            Action::Observe => &["o"],
+            Action::Refresh => &["r"],
+            Action::Add => &["a"],
+            Action::Edit => &["e"],
+            Action::Delete => &["d"],
+            Action::Filter => &["/"],
+            Action::Wizard => &["g"],
+            Action::BulkEditor => &["b"],
+            Action::Import => &["i"],
+            Action::SwapButtons => &["w"],
+            Action::TagFilter => &["t"],
+            Action::ToggleTag => &["x"],
+            Action::PauseMonitor => &["p"],
+            Action::Clear => &["c"],
+            Action::HidReports => &["h"],
+            Action::SplitMonitor => &["v"],
+            Action::LogLevel => &["f"],
+            Action::LogVerbosity => &["v"],
+            Action::EngineDebug => &["e"],
+            Action::NextSection => &["Tab"],
+            Action::Increase => &["+", "="],
+            Action::Decrease => &["-"],
+            Action::DefaultDpi => &["d"],
+            Action::Commit => &["c"],
+            Action::NextDevice => &["n"],
+        }
+    }
+
+    /// Tabs the action works on, None if it works on all of them
+    pub fn tabs(self) -> Option<&'static [Tab]> {
+        Some(match self {
+            Action::Quit
+            | Action::NextTab
+            | Action::PrevTab
+            | Action::Save
+            | Action::Help
+            | Action::Notifications
+            | Action::CommandPalette
+            | Action::MacroSlower
+            | Action::MacroFaster
+            | Action::PauseMacros => return None,
+            Action::Up
+            | Action::Down
+            | Action::PageUp
+            | Action::PageDown
+            | Action::Top
+            | Action::Bottom => LISTS,
+            Action::Select => &[Tab::Devices, Tab::Hardware],
+            Action::Tag | Action::Engine | Action::Observe => &[Tab::Devices],
+            Action::Refresh => &[Tab::Devices, Tab::Hardware],
+            Action::Add | Action::Edit | Action::Delete => &[Tab::Bindings, Tab::Macros],
+            Action::Filter => &[Tab::Bindings, Tab::Macros, Tab::Logs],
+            Action::Wizard
+            | Action::BulkEditor
+            | Action::Import
+            | Action::SwapButtons
+            | Action::TagFilter
+            | Action::ToggleTag => &[Tab::Bindings],
+            Action::PauseMonitor | Action::HidReports | Action::SplitMonitor => &[Tab::Monitor],
+            Action::Clear => &[Tab::Monitor, Tab::Logs],
+            Action::LogLevel | Action::LogVerbosity | Action::EngineDebug => &[Tab::Logs],
+            Action::NextSection
+            | Action::Increase
+            | Action::Decrease
+            | Action::DefaultDpi
+            | Action::Commit
+            | Action::NextDevice => &[Tab::Hardware],
+        })
+    }
+
+    fn works_on(self, tab: Tab) -> bool {
+        self.tabs().is_none_or(|tabs| tabs.contains(&tab))
+    }
+
+    /// Whether the two can be reached from the same tab
+    fn overlaps(self, other: Action) -> bool {
+        match (self.tabs(), other.tabs()) {
+            (Some(a), Some(b)) => a.iter().any(|tab| b.contains(tab)),
+            _ => true,
+        }
+    }
+}
+
+/// A key as written in `[keybindings]`: a character ("a", "?", "N"), a
+/// key name ("Enter", "PageUp", "F5"), either with a "Ctrl+" prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPress {
+    code: KeyCode,
+    ctrl: bool,
+}
+
+impl KeyPress {
+    pub fn parse(s: &str) -> Option<KeyPress> {
+        let (ctrl, rest) = match s.get(..5) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("ctrl+") && s.len() > 5 => (true, &s[5..]),
+            _ => (false, s),
+        };
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(if ctrl { c.to_ascii_lowercase() } else { c }),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+            },
+        };
+        Some(KeyPress { code, ctrl })
+    }
+
+    fn matches(&self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+        let code = match code {
+            KeyCode::Char(c) if ctrl => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        self.ctrl == ctrl && self.code == code
+    }
+
+    /// Short form for hints and the help overlay
+    pub fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            code => format!("{:?}", code),
+        };
+        if self.ctrl {
+            format!("Ctrl+{}", key)
+        } else {
+            key
+        }
+    }
+}
+
+/// Keys of every action: the defaults, with the actions named in
+/// `[keybindings]` moved to the keys given there
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    keys: HashMap<Action, Vec<KeyPress>>,
+}
+
+impl Keymap {
+    /// Build the keymap from the `[keybindings]` config section. Unknown
+    /// actions and keys are logged and skipped, as are keys that another
+    /// action reachable from the same tab already has.
+    pub fn from_config(config: &BTreeMap<String, KeyList>) -> Self {
+        let mut keys: HashMap<Action, Vec<KeyPress>> = Action::ALL
+            .iter()
+            .map(|a| {
+                let defaults = a.default_keys().iter().filter_map(|k| KeyPress::parse(k));
+                (*a, defaults.collect())
+            })
+            .collect();
+
+        let mut configured = Vec::new();
+        for (name, list) in config {
+            let Some(action) = Action::ALL.iter().copied().find(|a| a.name() == name) else {
+                log::warn!("Unknown action '{}' in [keybindings], ignoring", name);
+                continue;
+            };
+            let mut parsed = Vec::new();
+            for key in list.keys() {
+                match KeyPress::parse(key) {
+                    Some(press) => parsed.push(press),
+                    None => log::warn!("Invalid key '{}' for {} in [keybindings]", key, name),
+                }
+            }
+            keys.insert(action, parsed);
+            configured.push(action);
+        }
+
+        // A configured key is taken from the defaults of every other action
+        // reachable from the same tab; two configured actions on one key
+        // keep it on the first (by name)
+        for (i, action) in configured.iter().enumerate() {
+            let own = keys[action].clone();
+            for other in Action::ALL {
+                if other == action || !action.overlaps(*other) || configured[..i].contains(other) {
+                    continue;
+                }
+                let taken = keys.get_mut(other).expect("every action has keys");
+                let before = taken.len();
+                taken.retain(|k| !own.contains(k));
+                if taken.len() == before {
+                    continue;
+                }
+                if configured.contains(other) {
+                    log::warn!(
+                        "[keybindings] {} and {} share a key, keeping it on {}",
+                        action.name(),
+                        other.name(),
+                        action.name()
+                    );
+                } else {
+                    log::info!(
+                        "[keybindings] {} takes a key of {}",
+                        action.name(),
+                        other.name()
+                    );
+                }
+            }
+        }
+
+        Keymap { keys }
+    }
+
+    /// The action a key press runs on `tab`; actions available everywhere
+    /// come first
+    pub fn action(&self, code: KeyCode, modifiers: KeyModifiers, tab: Tab) -> Option<Action> {
+        let bound = |a: &&Action| self.keys[*a].iter().any(|k| k.matches(code, modifiers));
+        Action::ALL
+            .iter()
+            .filter(|a| a.tabs().is_none())
+            .find(bound)
+            .or_else(|| {
+                Action::ALL
+                    .iter()
+                    .filter(|a| a.tabs().is_some() && a.works_on(tab))
+                    .find(bound)
+            })
+            .copied()
+    }
+
+    /// The first key of the action, for hints ("-" if it has none)
+    pub fn key(&self, action: Action) -> String {
+        self.keys[&action]
+            .first()
+            .map(KeyPress::label)
+            .unwrap_or_else(|| "-".to_string())
+    }
+
+    /// All keys of the action, "Up/k"
+    pub fn keys(&self, action: Action) -> String {
+        let labels: Vec<String> = self.keys[&action].iter().map(KeyPress::label).collect();
+        if labels.is_empty() {
+            "-".to_string()
+        } else {
+            labels.join("/")
+        }
+    }
+
+    /// "a=add, e=edit" style hint from the actions' first keys
+    pub fn hint(&self, entries: &[(Action, &str)]) -> String {
+        entries
+            .iter()
+            .map(|(action, what)| format!("{}={}", self.key(*action), what))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_config(&BTreeMap::new())
+    }
+}
@@ -1,11 +1,20 @@
 pub mod app;
+pub mod diff;
+pub mod keymap;
+pub mod palette;
 pub mod tabs;
+pub mod templates;
+pub mod theme;
 pub mod widgets;
 
-use crate::config::MacroType;
-use crate::tui::app::{App, BindingOutputType, EngineCommand, InputMode, Tab};
+use crate::config::{KeyModifier, MacroAction, MacroType, ModeSwitch};
+use crate::tui::app::{
+    App, BindingOutputType, EditingMacro, EngineCommand, HardwareFocus, InputMode, Tab, WizardStep,
+};
+use crate::tui::keymap::Action;
 use anyhow::Result;
 use crossterm::{
+    cursor::Show,
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -16,45 +25,107 @@ use ratatui::{
     Terminal,
 };
 use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
+/// Whether the TUI has the terminal in raw mode on the alternate screen
+static TERMINAL_TAKEN: AtomicBool = AtomicBool::new(false);
+
 /// Run the TUI event loop
 pub fn run(mut app: App) -> Result<()> {
+    install_panic_hook();
+
     // Setup terminal
     enable_raw_mode()?;
+    TERMINAL_TAKEN.store(true, Ordering::SeqCst);
+    let _guard = TerminalGuard;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Initial device scan
+    // Initial device scan, then follow plugging and unplugging
     app.refresh_devices();
+    app.watch_devices();
 
     let result = run_loop(&mut terminal, &mut app);
 
-    // Restore terminal
+    // Tell engine to shut down first: after a SIGHUP the terminal may be gone
+    // and restoring it fail
+    if let Some(ref tx) = app.engine_cmd_tx {
+        let _ = tx.send(EngineCommand::Shutdown);
+    }
+
+    restore_terminal()?;
+
+    result
+}
+
+/// Leave raw mode and the alternate screen. Does nothing the second time, so
+/// the panic hook and [`TerminalGuard`] can both call it.
+fn restore_terminal() -> Result<()> {
+    if !TERMINAL_TAKEN.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
     disable_raw_mode()?;
     execute!(
-        terminal.backend_mut(),
+        io::stdout(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        Show
     )?;
-    terminal.show_cursor()?;
+    Ok(())
+}
 
-    // Tell engine to shut down
-    if let Some(ref tx) = app.engine_cmd_tx {
-        let _ = tx.send(EngineCommand::Shutdown);
+/// Restores the terminal however `run` ends: returning an error early or
+/// unwinding from a panic
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if let Err(e) = restore_terminal() {
+            log::error!("Failed to restore the terminal: {:#}", e);
+        }
     }
+}
 
-    result
+/// Log every panic. One on the TUI thread restores the terminal before the
+/// message is printed, or the message would vanish with the alternate screen
+/// and leave the shell in raw mode; one elsewhere (an engine task) is only
+/// logged, as printing would garble the TUI, which keeps running.
+fn install_panic_hook() {
+    let tui_thread = std::thread::current().id();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        log::error!("{}", info);
+        if TERMINAL_TAKEN.load(Ordering::SeqCst) && std::thread::current().id() != tui_thread {
+            return;
+        }
+        let _ = restore_terminal();
+        previous(info);
+    }));
 }
 
 fn run_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> Result<()> {
     let mut show_help = false;
+    let mut show_notifications = false;
 
     loop {
         // Poll engine messages
         app.poll_engine_messages();
+        app.poll_api();
+        app.poll_battery();
+        app.poll_device_changes();
+        if app.current_tab == Tab::Devices {
+            app.poll_sensor();
+        }
+        if app.current_tab == Tab::Logs {
+            app.poll_logs();
+        }
+        app.poll_ratbag();
+        if app.current_tab == Tab::Hardware && !app.ratbag_loaded {
+            app.refresh_ratbag(false);
+        }
 
         // Draw
         terminal.draw(|f| {
@@ -73,12 +144,64 @@ fn run_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App
                 Tab::Bindings => tabs::bindings::render(f, app, chunks[1]),
                 Tab::Macros => tabs::macros::render(f, app, chunks[1]),
                 Tab::Monitor => tabs::monitor::render(f, app, chunks[1]),
+                Tab::Logs => tabs::logs::render(f, app, chunks[1]),
+                Tab::Hardware => tabs::hardware::render(f, app, chunks[1]),
             }
 
             widgets::render_status_bar(f, app, chunks[2]);
 
+            if let Some(ref path) = app.import_path {
+                widgets::render_prompt(
+                    f,
+                    &app.theme,
+                    " Import bindings ",
+                    "input-remapper preset (.json) or xbindkeysrc path:",
+                    path,
+                    f.area(),
+                );
+            } else if let Some(ref path) = app.restore_path {
+                widgets::render_prompt(
+                    f,
+                    &app.theme,
+                    " Restore config ",
+                    "Backup (.tar) path:",
+                    path,
+                    f.area(),
+                );
+            } else if let Some(ref preview) = app.save_preview {
+                widgets::render_save_preview(f, &app.theme, preview, f.area());
+            }
+
+            match &app.input_mode {
+                InputMode::Confirming(msg) => {
+                    widgets::render_confirm(
+                        f,
+                        &app.theme,
+                        msg,
+                        "y=yes, any other key=cancel",
+                        f.area(),
+                    );
+                }
+                InputMode::ConfirmQuit => {
+                    widgets::render_confirm(
+                        f,
+                        &app.theme,
+                        "Save changes before quitting?",
+                        "y=save and quit, n/Ctrl+C=discard, Esc=cancel",
+                        f.area(),
+                    );
+                }
+                _ => {}
+            }
+
+            if app.command_palette.is_some() {
+                widgets::render_command_palette(f, app, f.area());
+            }
+
             if show_help {
-                widgets::render_help(f, f.area());
+                widgets::render_help(f, app, f.area());
+            } else if show_notifications {
+                widgets::render_notifications(f, app, f.area());
             }
         })?;
 
@@ -87,159 +210,255 @@ fn run_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App
         }
 
         // Handle input with a small timeout so we can poll engine messages
-        if event::poll(Duration::from_millis(50))? {
-            if let Event::Key(key) = event::read()? {
-                // Global: Ctrl+C always quits
-                if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        if event::poll(Duration::from_millis(50))?
+            && let Event::Key(key) = event::read()?
+        {
+            // Global: Ctrl+C quits like q, asking about unsaved changes;
+            // again at that question it quits without saving
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                if app.input_mode == InputMode::ConfirmQuit {
                     app.should_quit = true;
-                    continue;
+                } else {
+                    app.request_quit();
                 }
+                continue;
+            }
 
-                // Help toggle
-                if key.code == KeyCode::Char('?') && app.input_mode == InputMode::Normal {
-                    show_help = !show_help;
-                    continue;
-                }
+            let action = if app.input_mode == InputMode::Normal {
+                app.keymap.action(key.code, key.modifiers, app.current_tab)
+            } else {
+                None
+            };
 
-                if show_help {
-                    // Any key closes help
-                    show_help = false;
-                    continue;
-                }
+            // Help toggle: F1 works in dialogs too, where '?' may be typed
+            // into a field (but not while capturing, where F1 is a key to bind)
+            if action == Some(Action::Help)
+                || (key.code == KeyCode::F(1)
+                    && matches!(app.input_mode, InputMode::Normal | InputMode::Editing(_)))
+            {
+                show_help = !show_help;
+                continue;
+            }
 
-                // Handle based on input mode
-                match &app.input_mode {
-                    InputMode::Normal => {
-                        handle_normal_input(app, key.code)?;
-                    }
-                    InputMode::Editing(_) => {
-                        handle_editing_input(app, key.code, key.modifiers);
-                    }
-                    InputMode::Capturing { .. } => {
-                        // In capture mode, any key is recorded
-                        handle_capture_input(app, key.code);
-                    }
-                    InputMode::Confirming(_) => {
-                        handle_confirm_input(app, key.code);
+            if show_help {
+                // Any key closes help
+                show_help = false;
+                continue;
+            }
+
+            // Notification history toggle (any key closes it)
+            if show_notifications {
+                show_notifications = false;
+                continue;
+            }
+            if action == Some(Action::Notifications) {
+                show_notifications = true;
+                continue;
+            }
+            if action == Some(Action::CommandPalette) {
+                app.open_command_palette();
+                continue;
+            }
+
+            // Handle based on input mode
+            match &app.input_mode {
+                InputMode::Normal => {
+                    if let Some(action) = action {
+                        handle_normal_input(app, action)?;
                     }
                 }
+                InputMode::Editing(_) => {
+                    handle_editing_input(app, key.code, key.modifiers);
+                }
+                InputMode::Capturing { .. } => {
+                    // In capture mode, any key is recorded
+                    handle_capture_input(app, key.code);
+                }
+                InputMode::Confirming(_) => {
+                    handle_confirm_input(app, key.code);
+                }
+                InputMode::ConfirmQuit => {
+                    handle_confirm_quit_input(app, key.code);
+                }
             }
         }
     }
 }
 
-fn handle_normal_input(app: &mut App, key: KeyCode) -> Result<()> {
-    match key {
-        // Quit
-        KeyCode::Char('q') => {
-            app.should_quit = true;
+fn handle_normal_input(app: &mut App, action: Action) -> Result<()> {
+    match action {
+        Action::Quit => {
+            app.request_quit();
         }
 
+        // Macro playback speed
+        Action::MacroSlower => app.step_macro_speed(false),
+        Action::MacroFaster => app.step_macro_speed(true),
+        Action::PauseMacros => app.toggle_macro_pause(),
+
         // Tab navigation
-        KeyCode::Right | KeyCode::Char('l') => {
+        Action::NextTab => {
             app.current_tab = app.current_tab.next();
         }
-        KeyCode::Left | KeyCode::Char('h') => {
+        Action::PrevTab => {
             app.current_tab = app.current_tab.prev();
         }
 
-        // Save config
-        KeyCode::Char('s') => {
-            app.save_config();
+        Action::Save => {
+            app.preview_save();
         }
 
-        // Tab-specific keys
+        // Tab-specific actions
         _ => match app.current_tab {
-            Tab::Devices => handle_devices_input(app, key),
-            Tab::Bindings => handle_bindings_input(app, key),
-            Tab::Macros => handle_macros_input(app, key),
-            Tab::Monitor => handle_monitor_input(app, key),
+            Tab::Devices => handle_devices_input(app, action),
+            Tab::Bindings => handle_bindings_input(app, action),
+            Tab::Macros => handle_macros_input(app, action),
+            Tab::Monitor => handle_monitor_input(app, action),
+            Tab::Logs => handle_logs_input(app, action),
+            Tab::Hardware => handle_hardware_input(app, action),
         },
     }
 
     Ok(())
 }
 
-fn handle_devices_input(app: &mut App, key: KeyCode) {
-    match key {
-        KeyCode::Up | KeyCode::Char('k') => {
-            if app.device_list_index > 0 {
-                app.device_list_index -= 1;
-            }
+fn handle_devices_input(app: &mut App, action: Action) {
+    match action {
+        Action::Up if app.device_list_index > 0 => {
+            app.device_list_index -= 1;
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if app.device_list_index + 1 < app.devices.len() {
-                app.device_list_index += 1;
-            }
+        Action::Down if app.device_list_index + 1 < app.devices.len() => {
+            app.device_list_index += 1;
         }
-        KeyCode::Enter => {
+        Action::Select if !app.tagged_devices.is_empty() => {
+            app.grab_tagged_devices();
+        }
+        Action::Select => {
             app.select_current_device();
         }
-        KeyCode::Char(' ') => {
+        Action::Tag => {
+            app.toggle_device_tag();
+        }
+        Action::Engine => {
             app.toggle_engine();
         }
-        KeyCode::Char('r') => {
+        Action::Observe => {
+            app.toggle_observe();
+        }
+        Action::Refresh => {
             app.refresh_devices();
         }
         _ => {}
     }
 }
 
-fn handle_bindings_input(app: &mut App, key: KeyCode) {
-    match key {
-        KeyCode::Up | KeyCode::Char('k') => {
-            if app.binding_list_index > 0 {
-                app.binding_list_index -= 1;
+fn handle_hardware_input(app: &mut App, action: Action) {
+    match action {
+        Action::Up => match app.ratbag_focus {
+            HardwareFocus::Profiles => {
+                app.ratbag_profile_index = app.ratbag_profile_index.saturating_sub(1);
+                app.ratbag_row = 0;
             }
-        }
-        KeyCode::Down | KeyCode::Char('j') => {
-            let len = app.current_bindings().len();
-            if app.binding_list_index + 1 < len {
-                app.binding_list_index += 1;
+            _ => app.ratbag_row = app.ratbag_row.saturating_sub(1),
+        },
+        Action::Down => {
+            let len = app.ratbag_focus_len();
+            match app.ratbag_focus {
+                HardwareFocus::Profiles if app.ratbag_profile_index + 1 < len => {
+                    app.ratbag_profile_index += 1;
+                    app.ratbag_row = 0;
+                }
+                HardwareFocus::Resolutions | HardwareFocus::Buttons if app.ratbag_row + 1 < len => {
+                    app.ratbag_row += 1;
+                }
+                _ => {}
             }
         }
-        KeyCode::Char('a') => {
+        Action::NextSection => {
+            app.ratbag_focus = app.ratbag_focus.next();
+            app.ratbag_row = 0;
+        }
+        Action::Select => app.activate_ratbag_selection(),
+        Action::Increase => app.adjust_ratbag_selection(true),
+        Action::Decrease => app.adjust_ratbag_selection(false),
+        Action::DefaultDpi => app.set_default_ratbag_resolution(),
+        Action::Commit => app.commit_ratbag(),
+        Action::NextDevice => app.next_ratbag_device(),
+        Action::Refresh => app.refresh_ratbag(true),
+        _ => {}
+    }
+}
+
+fn handle_bindings_input(app: &mut App, action: Action) {
+    match action {
+        Action::Up => app.move_list_selection(-1),
+        Action::Down => app.move_list_selection(1),
+        Action::PageUp => app.move_list_selection(-(app.list_page_rows as isize)),
+        Action::PageDown => app.move_list_selection(app.list_page_rows as isize),
+        Action::Top => app.move_list_selection(isize::MIN),
+        Action::Bottom => app.move_list_selection(isize::MAX),
+        Action::Filter => {
+            app.list_filter_editing = true;
+            app.input_mode = InputMode::Editing(String::new());
+        }
+        Action::Add => {
             app.start_new_binding();
         }
-        KeyCode::Char('e') => {
+        Action::Edit | Action::Delete
+            if !app.visible_bindings().contains(&app.binding_list_index) =>
+        {
+            app.set_status("No binding matches the filter");
+        }
+        Action::Edit => {
             app.start_edit_binding();
         }
-        KeyCode::Char('d') => {
+        Action::Delete => {
             app.input_mode = InputMode::Confirming("Delete this binding?".to_string());
         }
+        Action::SwapButtons => app.toggle_swap_buttons(),
+        Action::TagFilter => app.cycle_binding_tag_filter(),
+        Action::ToggleTag => app.toggle_binding_tag(),
+        Action::Wizard => app.start_binding_wizard(),
+        Action::BulkEditor => app.open_grid_editor(),
+        Action::Import => {
+            app.import_path = Some(String::new());
+            app.input_mode = InputMode::Editing(String::new());
+        }
         _ => {}
     }
 }
 
-fn handle_macros_input(app: &mut App, key: KeyCode) {
-    match key {
-        KeyCode::Up | KeyCode::Char('k') => {
-            if app.macro_list_index > 0 {
-                app.macro_list_index -= 1;
-            }
-        }
-        KeyCode::Down | KeyCode::Char('j') => {
-            let len = app.current_macros().len();
-            if app.macro_list_index + 1 < len {
-                app.macro_list_index += 1;
-            }
+fn handle_macros_input(app: &mut App, action: Action) {
+    match action {
+        Action::Up => app.move_list_selection(-1),
+        Action::Down => app.move_list_selection(1),
+        Action::PageUp => app.move_list_selection(-(app.list_page_rows as isize)),
+        Action::PageDown => app.move_list_selection(app.list_page_rows as isize),
+        Action::Top => app.move_list_selection(isize::MIN),
+        Action::Bottom => app.move_list_selection(isize::MAX),
+        Action::Filter => {
+            app.list_filter_editing = true;
+            app.input_mode = InputMode::Editing(String::new());
         }
-        KeyCode::Char('a') => {
+        Action::Add => {
             app.start_new_macro();
         }
-        KeyCode::Char('e') => {
+        Action::Edit | Action::Delete if !app.visible_macros().contains(&app.macro_list_index) => {
+            app.set_status("No macro matches the filter");
+        }
+        Action::Edit => {
             app.start_edit_macro();
         }
-        KeyCode::Char('d') => {
+        Action::Delete => {
             app.input_mode = InputMode::Confirming("Delete this macro?".to_string());
         }
         _ => {}
     }
 }
 
-fn handle_monitor_input(app: &mut App, key: KeyCode) {
-    match key {
-        KeyCode::Char('p') => {
+fn handle_monitor_input(app: &mut App, action: Action) {
+    match action {
+        Action::PauseMonitor => {
             app.monitor_paused = !app.monitor_paused;
             if app.monitor_paused {
                 app.set_status("Monitor paused");
@@ -247,15 +466,239 @@ fn handle_monitor_input(app: &mut App, key: KeyCode) {
                 app.set_status("Monitor resumed");
             }
         }
-        KeyCode::Char('c') => {
+        Action::Clear => {
             app.monitor_events.clear();
             app.set_status("Monitor cleared");
         }
+        Action::HidReports => app.toggle_hid_monitor(),
+        Action::SplitMonitor => {
+            app.monitor_split = !app.monitor_split;
+            if app.monitor_split {
+                app.set_status("Monitor split per device");
+            } else {
+                app.set_status("Monitor showing all devices together");
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_logs_input(app: &mut App, action: Action) {
+    match action {
+        Action::Up => {
+            app.log_scroll = (app.log_scroll + 1).min(app.log_tail.lines.len());
+        }
+        Action::Down => {
+            app.log_scroll = app.log_scroll.saturating_sub(1);
+        }
+        Action::PageUp => {
+            app.log_scroll = (app.log_scroll + 20).min(app.log_tail.lines.len());
+        }
+        Action::PageDown => {
+            app.log_scroll = app.log_scroll.saturating_sub(20);
+        }
+        Action::Top => {
+            app.log_scroll = app.log_tail.lines.len();
+        }
+        Action::Bottom => {
+            app.log_scroll = 0;
+        }
+        Action::LogLevel => {
+            app.cycle_log_level();
+        }
+        Action::LogVerbosity => {
+            app.cycle_log_verbosity();
+        }
+        Action::EngineDebug => {
+            app.toggle_engine_debug();
+        }
+        Action::Filter => {
+            app.log_filter_editing = true;
+            app.input_mode = InputMode::Editing(String::new());
+        }
+        Action::Clear => {
+            app.log_filter.clear();
+            app.log_scroll = 0;
+            app.set_status("Log filter cleared");
+        }
+        _ => {}
+    }
+}
+
+/// Keys for the command palette: typing filters, Enter runs the selection
+fn handle_palette_input(app: &mut App, key: KeyCode) {
+    let Some(query) = app.command_palette.as_ref().map(|p| p.query.clone()) else {
+        return;
+    };
+    let matches = palette::matches(app, &query);
+    let Some(palette) = app.command_palette.as_mut() else {
+        return;
+    };
+    match key {
+        KeyCode::Esc => app.close_command_palette(),
+        KeyCode::Up if palette.selected > 0 => palette.selected -= 1,
+        KeyCode::Down if palette.selected + 1 < matches.len() => palette.selected += 1,
+        KeyCode::Enter => match matches.into_iter().nth(palette.selected) {
+            Some(entry) => app.run_palette_action(entry.action),
+            None => app.set_status("No command matches"),
+        },
+        KeyCode::Backspace => {
+            palette.query.pop();
+            palette.selected = 0;
+        }
+        KeyCode::Char(c) => {
+            palette.query.push(c);
+            palette.selected = 0;
+        }
+        _ => {}
+    }
+}
+
+fn handle_import_path_input(app: &mut App, key: KeyCode) {
+    let Some(path) = app.import_path.as_mut() else {
+        return;
+    };
+    match key {
+        KeyCode::Esc => {
+            app.import_path = None;
+            app.input_mode = InputMode::Normal;
+        }
+        KeyCode::Enter => {
+            let path = path.clone();
+            app.import_path = None;
+            app.input_mode = InputMode::Normal;
+            if !path.is_empty() {
+                app.import_bindings(&path);
+            }
+        }
+        KeyCode::Backspace => {
+            path.pop();
+        }
+        KeyCode::Char(c) => {
+            path.push(c);
+        }
+        _ => {}
+    }
+}
+
+fn handle_restore_path_input(app: &mut App, key: KeyCode) {
+    let Some(path) = app.restore_path.as_mut() else {
+        return;
+    };
+    match key {
+        KeyCode::Esc => {
+            app.restore_path = None;
+            app.input_mode = InputMode::Normal;
+        }
+        KeyCode::Enter => {
+            let path = path.clone();
+            app.restore_path = None;
+            app.input_mode = InputMode::Normal;
+            if !path.is_empty() {
+                app.restore_config(&path);
+            }
+        }
+        KeyCode::Backspace => {
+            path.pop();
+        }
+        KeyCode::Char(c) => {
+            path.push(c);
+        }
+        _ => {}
+    }
+}
+
+/// Scrolling the save diff; y or Enter saves, Esc or n goes back
+fn handle_save_preview_input(app: &mut App, key: KeyCode) {
+    let Some(preview) = app.save_preview.as_mut() else {
+        return;
+    };
+    let last = preview.lines.len().saturating_sub(1);
+    match key {
+        KeyCode::Char('y') | KeyCode::Enter => {
+            app.save_preview = None;
+            app.input_mode = InputMode::Normal;
+            app.save_config();
+        }
+        KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('q') => {
+            app.save_preview = None;
+            app.input_mode = InputMode::Normal;
+            app.set_status("Save cancelled");
+        }
+        KeyCode::Up | KeyCode::Char('k') => preview.scroll = preview.scroll.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => preview.scroll = (preview.scroll + 1).min(last),
+        KeyCode::PageUp => preview.scroll = preview.scroll.saturating_sub(20),
+        KeyCode::PageDown => preview.scroll = (preview.scroll + 20).min(last),
+        KeyCode::Home => preview.scroll = 0,
+        KeyCode::End => preview.scroll = last,
+        _ => {}
+    }
+}
+
+/// Typing the Bindings/Macros filter; the selection follows the matches
+fn handle_list_filter_input(app: &mut App, key: KeyCode) {
+    let filter = if app.current_tab == Tab::Macros {
+        &mut app.macro_filter
+    } else {
+        &mut app.binding_filter
+    };
+    match key {
+        KeyCode::Esc => {
+            filter.clear();
+            app.list_filter_editing = false;
+            app.input_mode = InputMode::Normal;
+        }
+        KeyCode::Enter => {
+            app.list_filter_editing = false;
+            app.input_mode = InputMode::Normal;
+        }
+        KeyCode::Backspace => {
+            filter.pop();
+        }
+        KeyCode::Char(c) => {
+            filter.push(c);
+        }
         _ => {}
     }
+    app.snap_list_selection();
+}
+
+fn handle_log_filter_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc => {
+            app.log_filter.clear();
+            app.log_filter_editing = false;
+            app.input_mode = InputMode::Normal;
+        }
+        KeyCode::Enter => {
+            app.log_filter_editing = false;
+            app.input_mode = InputMode::Normal;
+        }
+        KeyCode::Backspace => {
+            app.log_filter.pop();
+        }
+        KeyCode::Char(c) => {
+            app.log_filter.push(c);
+        }
+        _ => {}
+    }
+    app.log_scroll = 0;
 }
 
 fn handle_editing_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
+    if app.command_palette.is_some() {
+        handle_palette_input(app, key);
+        return;
+    }
+    if app.wizard_step.is_some() {
+        handle_wizard_input(app, key);
+        return;
+    }
+    if app.grid_editor.is_some() {
+        handle_grid_editor_input(app, key, modifiers);
+        return;
+    }
+
     // Ctrl+S always saves (binding or macro)
     if modifiers.contains(KeyModifiers::CONTROL) && key == KeyCode::Char('s') {
         if app.editing_binding.is_some() {
@@ -271,17 +714,192 @@ fn handle_editing_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
         handle_editing_binding_input(app, key);
     } else if app.editing_macro.is_some() {
         handle_editing_macro_input(app, key);
+    } else if app.import_path.is_some() {
+        handle_import_path_input(app, key);
+    } else if app.restore_path.is_some() {
+        handle_restore_path_input(app, key);
+    } else if app.save_preview.is_some() {
+        handle_save_preview_input(app, key);
+    } else if app.template_picker.is_some() {
+        handle_template_picker_input(app, key);
+    } else if app.log_filter_editing {
+        handle_log_filter_input(app, key);
+    } else if app.list_filter_editing {
+        handle_list_filter_input(app, key);
+    }
+}
+
+/// Keys for the bulk binding editor
+fn handle_grid_editor_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
+    let macro_names = app.macro_names();
+    let Some(grid) = app.grid_editor.as_mut() else {
+        return;
+    };
+
+    match key {
+        KeyCode::Esc => {
+            app.close_grid_editor();
+            app.set_status("Bulk editor closed without changes");
+        }
+        KeyCode::Char('s') if modifiers.contains(KeyModifiers::CONTROL) => {
+            app.apply_grid_editor();
+        }
+        KeyCode::Up | KeyCode::Char('k') if grid.selected > 0 => grid.selected -= 1,
+        KeyCode::Down | KeyCode::Char('j') if grid.selected + 1 < grid.rows.len() => {
+            grid.selected += 1;
+        }
+        KeyCode::Char('+') => grid.rows.push(Default::default()),
+        KeyCode::Char('l') => app.start_grid_learning(),
+        KeyCode::Char('i') => app.start_capture(app::CaptureField::GridInput),
+        KeyCode::Enter => app.start_capture(app::CaptureField::GridOutput),
+        KeyCode::Tab => grid.cycle_macro(&macro_names),
+        KeyCode::Char('d') | KeyCode::Delete => {
+            if let Some(row) = grid.rows.get_mut(grid.selected) {
+                row.output = None;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Keys for the guided new-binding wizard
+fn handle_wizard_input(app: &mut App, key: KeyCode) {
+    let (Some(step), Some(output_type)) = (
+        app.wizard_step,
+        app.editing_binding.as_ref().map(|e| e.output_type.clone()),
+    ) else {
+        return;
+    };
+    let macro_count = app.macro_names().len();
+    let profile_names = app.profile_names();
+    let Some(editing) = app.editing_binding.as_mut() else {
+        return;
+    };
+    let choices = output_type.choices();
+    let choice_index = choices
+        .iter()
+        .position(|c| *c == editing.output_value)
+        .unwrap_or(0);
+
+    match (step, key) {
+        (_, KeyCode::Esc) => app.cancel_binding_wizard(),
+        (WizardStep::Input, KeyCode::Enter) if editing.input.is_empty() => {
+            app.start_capture(app::CaptureField::BindingInput);
+        }
+        (WizardStep::Input, KeyCode::Char('c')) => {
+            app.start_capture(app::CaptureField::BindingInput);
+        }
+        (WizardStep::Action, KeyCode::Up | KeyCode::Down) => {
+            let all = BindingOutputType::all();
+            let current = all.iter().position(|t| *t == output_type).unwrap_or(0);
+            let next = if key == KeyCode::Up {
+                (current + all.len() - 1) % all.len()
+            } else {
+                (current + 1) % all.len()
+            };
+            editing.output_type = all[next].clone();
+        }
+        (WizardStep::Target, KeyCode::Enter)
+            if output_type.captures_key() && editing.output_value.is_empty() =>
+        {
+            app.start_capture(app::CaptureField::BindingOutput);
+        }
+        (WizardStep::Target, KeyCode::Char('c')) if output_type.captures_key() => {
+            app.start_capture(app::CaptureField::BindingOutput);
+        }
+        (WizardStep::Target, KeyCode::Up) if output_type == BindingOutputType::Macro => {
+            editing.macro_select_index = editing.macro_select_index.saturating_sub(1);
+        }
+        (WizardStep::Target, KeyCode::Down) if output_type == BindingOutputType::Macro => {
+            editing.macro_select_index =
+                (editing.macro_select_index + 1).min(macro_count.saturating_sub(1));
+        }
+        (WizardStep::Target, KeyCode::Up | KeyCode::Down | KeyCode::Tab)
+            if output_type == BindingOutputType::Profile =>
+        {
+            editing.output_value =
+                cycle_name(&profile_names, &editing.output_value, key == KeyCode::Up);
+        }
+        (WizardStep::Target, KeyCode::Up | KeyCode::Down) if !choices.is_empty() => {
+            let next = if key == KeyCode::Up {
+                (choice_index + choices.len() - 1) % choices.len()
+            } else {
+                (choice_index + 1) % choices.len()
+            };
+            editing.output_value = choices[next].to_string();
+        }
+        (WizardStep::Target, KeyCode::Tab) if output_type == BindingOutputType::Scroll => {
+            editing.flip_scroll_axis();
+        }
+        (WizardStep::Target, KeyCode::Char(c)) if output_type == BindingOutputType::Scroll => {
+            editing.type_scroll_amount(c);
+        }
+        (WizardStep::Target, KeyCode::Backspace) if output_type == BindingOutputType::Scroll => {
+            editing.erase_scroll_amount();
+        }
+        (WizardStep::Target, KeyCode::Tab) if output_type == BindingOutputType::Mode => {
+            editing.mode_switch = match editing.mode_switch {
+                ModeSwitch::Toggle => ModeSwitch::On,
+                ModeSwitch::On => ModeSwitch::Off,
+                ModeSwitch::Off => ModeSwitch::Toggle,
+            };
+        }
+        (WizardStep::Target, KeyCode::Char(c))
+            if output_type == BindingOutputType::Warp && is_warp_char(c) =>
+        {
+            editing.output_value.push(c);
+        }
+        (WizardStep::Target, KeyCode::Backspace) if output_type == BindingOutputType::Warp => {
+            editing.output_value.pop();
+        }
+        (WizardStep::Target, KeyCode::Char(c)) if output_type == BindingOutputType::Mode => {
+            editing.output_value.push(c);
+        }
+        (WizardStep::Target, KeyCode::Backspace) if output_type == BindingOutputType::Mode => {
+            editing.output_value.pop();
+        }
+        (_, KeyCode::Enter) => app.wizard_next(),
+        _ => {}
     }
 }
 
+/// The name after `current` in `names` (before it with `back`), wrapping
+/// around; the first one when `current` isn't there
+fn cycle_name(names: &[String], current: &str, back: bool) -> String {
+    let Some(index) = names.iter().position(|n| n == current) else {
+        return names.first().cloned().unwrap_or_default();
+    };
+    let next = if back {
+        (index + names.len() - 1) % names.len()
+    } else {
+        (index + 1) % names.len()
+    };
+    names[next].clone()
+}
+
+/// Characters of a warp target: coordinates or a position name
+fn is_warp_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | ',')
+}
+
 fn handle_editing_binding_input(app: &mut App, key: KeyCode) {
     // Determine current field_index and output_type before borrow
-    let (field_index, is_macro_output, is_key_output) = {
+    let (
+        field_index,
+        is_macro_output,
+        is_key_output,
+        is_mode_output,
+        is_scroll_output,
+        is_warp_output,
+    ) = {
         let editing = app.editing_binding.as_ref().unwrap();
         (
             editing.field_index,
             editing.output_type == BindingOutputType::Macro,
-            editing.output_type == BindingOutputType::Key,
+            editing.output_type.captures_key(),
+            editing.output_type == BindingOutputType::Mode,
+            editing.output_type == BindingOutputType::Scroll,
+            editing.output_type == BindingOutputType::Warp,
         )
     };
 
@@ -306,58 +924,83 @@ fn handle_editing_binding_input(app: &mut App, key: KeyCode) {
                     } else if is_macro_output {
                         // Select the currently highlighted macro
                         let macro_names = app.macro_names();
-                        if let Some(editing) = app.editing_binding.as_mut() {
-                            if let Some(name) = macro_names.get(editing.macro_select_index) {
-                                editing.output_value = name.clone();
-                                app.set_status(format!("Selected macro: {}", name));
-                            }
+                        if let Some(editing) = app.editing_binding.as_mut()
+                            && let Some(name) = macro_names.get(editing.macro_select_index)
+                        {
+                            editing.output_value = name.clone();
+                            app.set_status(format!("Selected macro: {}", name));
                         }
                         // Save the binding after selecting a macro
                         app.save_editing_binding();
+                    } else {
+                        app.save_editing_binding();
                     }
                 }
+                // Fields 3-7: mode condition, cooldown, macro args,
+                // description and tags — typed, Enter saves
+                3..=7 => app.save_editing_binding(),
                 _ => {}
             }
         }
         KeyCode::Up => {
             // On field 2 with Macro output: navigate macro list
             if field_index == 2 && is_macro_output {
-                if let Some(ref mut editing) = app.editing_binding {
-                    if editing.macro_select_index > 0 {
-                        editing.macro_select_index -= 1;
-                    }
-                }
-            } else if let Some(ref mut editing) = app.editing_binding {
-                if editing.field_index > 0 {
-                    editing.field_index -= 1;
+                if let Some(ref mut editing) = app.editing_binding
+                    && editing.macro_select_index > 0
+                {
+                    editing.macro_select_index -= 1;
                 }
+            } else if let Some(ref mut editing) = app.editing_binding
+                && editing.field_index > 0
+            {
+                editing.field_index -= 1;
             }
         }
         KeyCode::Down => {
             // On field 2 with Macro output: navigate macro list
+            // (past the last macro, move on to the mode condition)
             if field_index == 2 && is_macro_output {
                 let macro_count = app.macro_names().len();
                 if let Some(ref mut editing) = app.editing_binding {
                     if editing.macro_select_index + 1 < macro_count {
                         editing.macro_select_index += 1;
+                    } else {
+                        editing.field_index = 3;
                     }
                 }
-            } else if let Some(ref mut editing) = app.editing_binding {
-                if editing.field_index < 2 {
-                    editing.field_index += 1;
-                }
+            } else if let Some(ref mut editing) = app.editing_binding
+                && editing.field_index < 7
+            {
+                editing.field_index += 1;
             }
         }
         KeyCode::Tab => {
+            let profile_names = app.profile_names();
             if let Some(ref mut editing) = app.editing_binding {
+                let choices = editing.output_type.choices();
                 if editing.field_index == 1 {
-                    editing.output_type = match editing.output_type {
-                        BindingOutputType::Key => BindingOutputType::Macro,
-                        BindingOutputType::Macro => BindingOutputType::Key,
-                    };
+                    editing.output_type = editing.output_type.next();
                     // Reset output_value when switching types
-                    editing.output_value.clear();
+                    editing.output_value = editing.output_type.initial_value();
                     editing.macro_select_index = 0;
+                } else if editing.field_index == 2 && is_scroll_output {
+                    editing.flip_scroll_axis();
+                } else if editing.field_index == 2
+                    && editing.output_type == BindingOutputType::Profile
+                {
+                    editing.output_value = cycle_name(&profile_names, &editing.output_value, false);
+                } else if editing.field_index == 2 && is_mode_output {
+                    editing.mode_switch = match editing.mode_switch {
+                        ModeSwitch::Toggle => ModeSwitch::On,
+                        ModeSwitch::On => ModeSwitch::Off,
+                        ModeSwitch::Off => ModeSwitch::Toggle,
+                    };
+                } else if editing.field_index == 2 && !choices.is_empty() {
+                    let current = choices
+                        .iter()
+                        .position(|c| *c == editing.output_value)
+                        .unwrap_or(0);
+                    editing.output_value = choices[(current + 1) % choices.len()].to_string();
                 }
             }
         }
@@ -372,10 +1015,92 @@ fn handle_editing_binding_input(app: &mut App, key: KeyCode) {
                     2 if is_key_output => {
                         editing.output_value.clear();
                     }
+                    2 if is_mode_output || is_warp_output => {
+                        editing.output_value.pop();
+                    }
+                    2 if is_scroll_output => {
+                        editing.erase_scroll_amount();
+                    }
+                    3 => {
+                        editing.when.pop();
+                    }
+                    4 => {
+                        editing.cooldown_ms.pop();
+                    }
+                    5 => {
+                        editing.args.pop();
+                    }
+                    6 => {
+                        editing.description.pop();
+                    }
+                    7 => {
+                        editing.tags.pop();
+                    }
                     _ => {}
                 }
             }
         }
+        KeyCode::Char(c) if field_index == 0 => {
+            // On the input field, c/s/a/m toggle the required keyboard modifiers
+            let modifier = match c {
+                'c' => Some(KeyModifier::Ctrl),
+                's' => Some(KeyModifier::Shift),
+                'a' => Some(KeyModifier::Alt),
+                'm' => Some(KeyModifier::Meta),
+                _ => None,
+            };
+            if let Some(modifier) = modifier {
+                app.toggle_binding_modifier(modifier);
+            }
+        }
+        // Mode names are typed: the SetMode output's name and the condition
+        // ("!name" = while the mode is off)
+        KeyCode::Char(c)
+            if (field_index == 2 && is_mode_output || field_index == 3)
+                && (c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '!') =>
+        {
+            if let Some(ref mut editing) = app.editing_binding {
+                if field_index == 2 {
+                    editing.output_value.push(c);
+                } else {
+                    editing.when.push(c);
+                }
+            }
+        }
+        // Warp targets are typed too: "x,y" or a position name
+        KeyCode::Char(c) if field_index == 2 && is_warp_output && is_warp_char(c) => {
+            if let Some(ref mut editing) = app.editing_binding {
+                editing.output_value.push(c);
+            }
+        }
+        KeyCode::Char(c) if field_index == 2 && is_scroll_output => {
+            if let Some(ref mut editing) = app.editing_binding {
+                editing.type_scroll_amount(c);
+            }
+        }
+        KeyCode::Char(c) if field_index == 4 && c.is_ascii_digit() => {
+            if let Some(ref mut editing) = app.editing_binding {
+                editing.cooldown_ms.push(c);
+            }
+        }
+        KeyCode::Char(c) if field_index == 5 => {
+            if let Some(ref mut editing) = app.editing_binding {
+                editing.args.push(c);
+            }
+        }
+        KeyCode::Char(c) if field_index == 6 => {
+            if let Some(ref mut editing) = app.editing_binding {
+                editing.description.push(c);
+            }
+        }
+        KeyCode::Char(c)
+            if field_index == 7
+                && (c.is_alphanumeric() || c == '_' || c == '-' || c == ',' || c == ' ') =>
+        {
+            if let Some(ref mut editing) = app.editing_binding {
+                editing.tags.push(c);
+            }
+        }
         KeyCode::Char(_) => {
             // No manual typing for binding fields — use capture for input/key output,
             // use list selection for macro output. This prevents mistyped key names.
@@ -385,93 +1110,223 @@ fn handle_editing_binding_input(app: &mut App, key: KeyCode) {
 }
 
 fn handle_editing_macro_input(app: &mut App, key: KeyCode) {
+    if app
+        .editing_macro
+        .as_ref()
+        .is_some_and(|e| e.actions_focused)
+    {
+        handle_macro_actions_input(app, key);
+        return;
+    }
+
     match key {
         KeyCode::Esc => {
             app.editing_macro = None;
             app.input_mode = InputMode::Normal;
         }
         KeyCode::Enter => {
-            app.save_editing_macro();
+            // Enter on the actions field opens the step editor; elsewhere it saves
+            if let Some(ref mut editing) = app.editing_macro
+                && editing.field_index == 2
+            {
+                editing.actions_focused = true;
+                app.set_status("Editing steps (Esc to return to fields)");
+            } else {
+                app.save_editing_macro();
+            }
         }
         KeyCode::Up => {
-            if let Some(ref mut editing) = app.editing_macro {
-                if editing.field_index > 0 {
-                    editing.field_index -= 1;
-                }
+            if let Some(ref mut editing) = app.editing_macro
+                && editing.field_index > 0
+            {
+                editing.field_index -= 1;
             }
         }
         KeyCode::Down => {
-            if let Some(ref mut editing) = app.editing_macro {
-                if editing.field_index < 4 {
-                    editing.field_index += 1;
-                }
+            if let Some(ref mut editing) = app.editing_macro
+                && editing.field_index < EditingMacro::LAST_FIELD
+            {
+                editing.field_index += 1;
             }
         }
         KeyCode::Tab => {
             if let Some(ref mut editing) = app.editing_macro {
-                if editing.field_index == 1 {
-                    editing.macro_type = match editing.macro_type {
-                        MacroType::RepeatOnHold => MacroType::Sequence,
-                        MacroType::Sequence => MacroType::Toggle,
-                        MacroType::Toggle => MacroType::RepeatOnHold,
-                    };
+                match editing.field_index {
+                    1 => {
+                        editing.macro_type = match editing.macro_type {
+                            MacroType::RepeatOnHold => MacroType::Sequence,
+                            MacroType::Sequence => MacroType::Toggle,
+                            MacroType::Toggle => MacroType::Random,
+                            MacroType::Random => MacroType::RepeatOnHold,
+                        };
+                    }
+                    5 => editing.shuffle_pattern = !editing.shuffle_pattern,
+                    12 => editing.abort_on_release = !editing.abort_on_release,
+                    _ => {}
                 }
             }
         }
         KeyCode::Backspace => {
             if let Some(ref mut editing) = app.editing_macro {
-                match editing.field_index {
-                    0 => {
-                        editing.name.pop();
-                    }
-                    3 => {
-                        editing.interval_ms.pop();
-                    }
-                    4 => {
-                        editing.jitter_ms.pop();
-                    }
-                    _ => {}
+                if editing.field_index == 0 {
+                    editing.name.pop();
+                } else if editing.field_index == EditingMacro::PARAMS_FIELD {
+                    editing.params.pop();
+                } else if editing.field_index == EditingMacro::DESCRIPTION_FIELD {
+                    editing.description.pop();
+                } else if editing.field_index == EditingMacro::PATTERN_FIELD {
+                    editing.interval_pattern.pop();
+                } else if let Some(value) = editing.number_field_mut() {
+                    value.pop();
                 }
             }
         }
         KeyCode::Char(c) => {
             if let Some(ref mut editing) = app.editing_macro {
-                match editing.field_index {
-                    0 => editing.name.push(c),
-                    2 => {
-                        if editing.actions.is_empty() {
-                            editing
-                                .actions
-                                .push(crate::config::MacroAction::Click(String::new()));
-                        }
-                        if let Some(crate::config::MacroAction::Click(s)) =
-                            editing.actions.first_mut()
-                        {
-                            s.push(c);
-                        }
-                    }
-                    3 => {
-                        if c.is_ascii_digit() {
-                            editing.interval_ms.push(c);
-                        }
+                if editing.field_index == 0 {
+                    editing.name.push(c);
+                } else if editing.field_index == EditingMacro::PARAMS_FIELD {
+                    editing.params.push(c);
+                } else if editing.field_index == EditingMacro::DESCRIPTION_FIELD {
+                    editing.description.push(c);
+                } else if editing.field_index == EditingMacro::PATTERN_FIELD {
+                    if c.is_ascii_digit() || c == ',' || c == ' ' {
+                        editing.interval_pattern.push(c);
                     }
-                    4 => {
-                        if c.is_ascii_digit() {
-                            editing.jitter_ms.push(c);
-                        }
-                    }
-                    _ => {}
+                } else if c.is_ascii_digit()
+                    && let Some(value) = editing.number_field_mut()
+                {
+                    value.push(c);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn handle_template_picker_input(app: &mut App, key: KeyCode) {
+    let Some(selected) = app.template_picker else {
+        return;
+    };
+    if app.template_values.is_some() {
+        handle_template_params_input(app, selected, key);
+        return;
+    }
+    let count = templates::all().len();
+
+    match key {
+        KeyCode::Esc => {
+            app.template_picker = None;
+            app.input_mode = InputMode::Normal;
+        }
+        KeyCode::Up | KeyCode::Char('k') if selected > 0 => {
+            app.template_picker = Some(selected - 1);
+        }
+        KeyCode::Down | KeyCode::Char('j') if selected + 1 < count => {
+            app.template_picker = Some(selected + 1);
+        }
+        KeyCode::Enter => {
+            app.choose_template(selected);
+        }
+        _ => {}
+    }
+}
+
+/// Keys while typing the parameters of the chosen template
+fn handle_template_params_input(app: &mut App, selected: usize, key: KeyCode) {
+    let field = app.template_field;
+    let Some(values) = app.template_values.as_mut() else {
+        return;
+    };
+    match key {
+        // Back to the template list
+        KeyCode::Esc => app.template_values = None,
+        KeyCode::Up | KeyCode::BackTab if field > 0 => app.template_field -= 1,
+        KeyCode::Down | KeyCode::Tab if field + 1 < values.len() => app.template_field += 1,
+        KeyCode::Enter => {
+            let values = values.clone();
+            app.start_macro_from_template(selected, &values);
+        }
+        KeyCode::Backspace => {
+            if let Some(value) = values.get_mut(field) {
+                value.pop();
+            }
+        }
+        KeyCode::Char(c) => {
+            if let Some(value) = values.get_mut(field) {
+                value.push(c);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Keys for the macro step editor (the action list inside the macro dialog)
+fn handle_macro_actions_input(app: &mut App, key: KeyCode) {
+    let macro_names = app.macro_names();
+    let Some(editing) = app.editing_macro.as_mut() else {
+        return;
+    };
+
+    match key {
+        KeyCode::Esc => {
+            editing.actions_focused = false;
+        }
+        KeyCode::Up if editing.action_index > 0 => {
+            editing.action_index -= 1;
+        }
+        KeyCode::Down if editing.action_index + 1 < editing.actions.len() => {
+            editing.action_index += 1;
+        }
+        KeyCode::Char('K') => editing.move_action_up(),
+        KeyCode::Char('J') => editing.move_action_down(),
+        KeyCode::Char('a') | KeyCode::Insert => editing.add_action(),
+        KeyCode::Char('d') | KeyCode::Delete => editing.delete_action(),
+        KeyCode::Tab => editing.cycle_action_kind(),
+        KeyCode::Char('v') => editing.cycle_action_param(),
+        KeyCode::Char('r') => editing.cycle_run_macro(&macro_names),
+        KeyCode::Enter => {
+            // Key steps are set by capture; Delay steps and hold times are typed as digits
+            if matches!(
+                editing.actions.get(editing.action_index),
+                Some(MacroAction::Click(_) | MacroAction::Press(_) | MacroAction::Release(_))
+            ) {
+                app.start_capture(app::CaptureField::MacroAction);
+            }
+        }
+        KeyCode::Char('c') => app.start_capture(app::CaptureField::MacroInsert { hold: false }),
+        KeyCode::Char('p') => app.start_capture(app::CaptureField::MacroInsert { hold: true }),
+        KeyCode::Char(c) if c.is_ascii_digit() => {
+            let digit = c.to_digit(10).unwrap_or(0);
+            match editing.actions.get_mut(editing.action_index) {
+                Some(MacroAction::Delay(ms)) => {
+                    *ms = ms.saturating_mul(10).saturating_add(digit as u64);
                 }
+                Some(MacroAction::Choice(weight)) => {
+                    *weight = weight.saturating_mul(10).saturating_add(digit);
+                }
+                Some(MacroAction::Click(step)) => {
+                    step.hold_ms = step.hold_ms.saturating_mul(10).saturating_add(digit as u64);
+                }
+                _ => {}
             }
         }
+        KeyCode::Backspace => match editing.actions.get_mut(editing.action_index) {
+            Some(MacroAction::Delay(ms)) => *ms /= 10,
+            Some(MacroAction::Choice(weight)) => *weight /= 10,
+            Some(MacroAction::Click(step)) => step.hold_ms /= 10,
+            _ => {}
+        },
         _ => {}
     }
 }
 
 fn handle_capture_input(app: &mut App, key: KeyCode) {
     if key == KeyCode::Esc {
-        app.capturing = false;
-        app.input_mode = InputMode::Editing(String::new());
+        app.finish_capture();
+        if let Some(ref mut grid) = app.grid_editor {
+            grid.learning = false;
+        }
         app.set_status("Capture cancelled");
         return;
     }
@@ -479,27 +1334,23 @@ fn handle_capture_input(app: &mut App, key: KeyCode) {
     // For BindingInput: only mouse buttons (via engine stream) are accepted.
     // Keyboard keys are ignored here — the engine's event stream handles mouse capture.
     //
-    // For BindingOutput: also accept keyboard keys from crossterm, so the user can
-    // remap a mouse button to a keyboard key (e.g. BTN_RIGHT -> KEY_A).
+    // For BindingOutput and macro steps: also accept keyboard keys from crossterm, so the
+    // user can remap a mouse button to a keyboard key (e.g. BTN_RIGHT -> KEY_A).
     // Mouse button outputs are still captured via the engine stream in poll_engine_messages().
     let is_output_capture = matches!(
         &app.input_mode,
         InputMode::Capturing {
             field: app::CaptureField::BindingOutput
+                | app::CaptureField::MacroAction
+                | app::CaptureField::MacroInsert { .. }
+                | app::CaptureField::GridOutput
         }
     );
 
-    if is_output_capture {
-        if let Some(evdev_name) = crossterm_to_evdev_name(key) {
-            if let Some(ref mut editing) = app.editing_binding {
-                editing.output_value = evdev_name.clone();
-            }
-            app.capturing = false;
-            app.input_mode = InputMode::Editing(String::new());
-            app.set_status(format!("Captured: {}", evdev_name));
-        }
-        // If crossterm_to_evdev_name returns None, ignore the key (unsupported key)
+    if is_output_capture && let Some(evdev_name) = crossterm_to_evdev_name(key) {
+        app.apply_capture(evdev_name);
     }
+    // If crossterm_to_evdev_name returns None, ignore the key (unsupported key)
     // For BindingInput: all keyboard keys are silently ignored — waiting for mouse via engine
 }
 
@@ -566,3 +1417,23 @@ fn handle_confirm_input(app: &mut App, key: KeyCode) {
         }
     }
 }
+
+fn handle_confirm_quit_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Char('y') | KeyCode::Enter => {
+            app.input_mode = std::mem::replace(&mut app.mode_before_quit, InputMode::Normal);
+            app.save_config();
+            // Only quit if the save actually went through
+            if !app.is_dirty() {
+                app.should_quit = true;
+            }
+        }
+        KeyCode::Char('n') => {
+            app.should_quit = true;
+        }
+        _ => {
+            app.input_mode = std::mem::replace(&mut app.mode_before_quit, InputMode::Normal);
+            app.set_status("Quit cancelled");
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use crate::export::ExportFormat;
+use crate::tui::app::{App, Tab};
+use crate::tui::keymap::Action;
+
+/// Something the command palette (Ctrl+P) can run
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaletteAction {
+    GoToTab(Tab),
+    ToggleEngine,
+    ToggleObserve,
+    RefreshDevices,
+    SwitchProfile(String),
+    ExportProfile(ExportFormat),
+    AddBinding,
+    BindingWizard,
+    BulkEditor,
+    ImportBindings,
+    SwapButtons,
+    /// Switch the bindings with a tag off or back on
+    ToggleTag(String),
+    AddMacro,
+    /// Run or stop a macro of the active profile, like its button would
+    ToggleMacro(String),
+    PauseMacros,
+    MacroSpeed {
+        faster: bool,
+    },
+    ToggleHidMonitor,
+    ClearMonitor,
+    CycleLogLevel,
+    ToggleEngineDebug,
+    Save,
+    BackupConfig,
+    RestoreConfig,
+    Quit,
+}
+
+/// One row of the palette
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    pub label: String,
+    /// The dedicated key for it, if it has one, shown next to the label
+    pub key: String,
+    pub action: PaletteAction,
+}
+
+/// Search state of the open palette
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    pub query: String,
+    /// Row in the filtered list
+    pub selected: usize,
+}
+
+/// Every action the palette offers, profiles and macros from the config
+pub fn entries(app: &App) -> Vec<PaletteEntry> {
+    let on = |action, tab: Tab| format!("{} ({})", app.keymap.key(action), tab.title());
+    let entry = |label: &str, key: String, action| PaletteEntry {
+        label: label.to_string(),
+        key,
+        action,
+    };
+    let mut entries = vec![
+        entry(
+            "Engine: Start / stop mapping",
+            on(Action::Engine, Tab::Devices),
+            PaletteAction::ToggleEngine,
+        ),
+        entry(
+            "Engine: Start / stop observing",
+            on(Action::Observe, Tab::Devices),
+            PaletteAction::ToggleObserve,
+        ),
+        entry(
+            "Devices: Rescan",
+            on(Action::Refresh, Tab::Devices),
+            PaletteAction::RefreshDevices,
+        ),
+    ];
+    let active = app.config.active_profile().map(|p| p.name.clone());
+    for profile in &app.config.profiles {
+        if Some(&profile.name) != active.as_ref() {
+            entries.push(entry(
+                &format!("Profile: Switch to {}", profile.label()),
+                String::new(),
+                PaletteAction::SwitchProfile(profile.name.clone()),
+            ));
+        }
+    }
+    entries.extend([
+        entry(
+            "Profile: Export to keyd",
+            String::new(),
+            PaletteAction::ExportProfile(ExportFormat::Keyd),
+        ),
+        entry(
+            "Profile: Export to evremap",
+            String::new(),
+            PaletteAction::ExportProfile(ExportFormat::Evremap),
+        ),
+        entry(
+            "Bindings: Add binding",
+            on(Action::Add, Tab::Bindings),
+            PaletteAction::AddBinding,
+        ),
+        entry(
+            "Bindings: Add with the wizard",
+            on(Action::Wizard, Tab::Bindings),
+            PaletteAction::BindingWizard,
+        ),
+        entry(
+            "Bindings: Bulk editor",
+            on(Action::BulkEditor, Tab::Bindings),
+            PaletteAction::BulkEditor,
+        ),
+        entry(
+            "Bindings: Import preset",
+            on(Action::Import, Tab::Bindings),
+            PaletteAction::ImportBindings,
+        ),
+        entry(
+            "Bindings: Swap left / right buttons",
+            on(Action::SwapButtons, Tab::Bindings),
+            PaletteAction::SwapButtons,
+        ),
+    ]);
+    if let Some(profile) = app.config.active_profile() {
+        for tag in profile.binding_tags() {
+            entries.push(entry(
+                &format!("Bindings: Enable / disable tag {}", tag),
+                String::new(),
+                PaletteAction::ToggleTag(tag),
+            ));
+        }
+    }
+    entries.push(entry(
+        "Macros: Add macro",
+        on(Action::Add, Tab::Macros),
+        PaletteAction::AddMacro,
+    ));
+    if let Some(profile) = app.config.active_profile() {
+        for m in &profile.macros {
+            entries.push(entry(
+                &format!("Macros: Run / stop {}", m.name),
+                String::new(),
+                PaletteAction::ToggleMacro(m.name.clone()),
+            ));
+        }
+    }
+    entries.extend([
+        entry(
+            "Macros: Pause / resume all",
+            app.keymap.key(Action::PauseMacros),
+            PaletteAction::PauseMacros,
+        ),
+        entry(
+            "Macros: Speed up playback",
+            app.keymap.key(Action::MacroFaster),
+            PaletteAction::MacroSpeed { faster: true },
+        ),
+        entry(
+            "Macros: Slow down playback",
+            app.keymap.key(Action::MacroSlower),
+            PaletteAction::MacroSpeed { faster: false },
+        ),
+        entry(
+            "Monitor: Show / hide HID reports",
+            on(Action::HidReports, Tab::Monitor),
+            PaletteAction::ToggleHidMonitor,
+        ),
+        entry(
+            "Monitor: Clear events",
+            on(Action::Clear, Tab::Monitor),
+            PaletteAction::ClearMonitor,
+        ),
+        entry(
+            "Logs: Cycle level filter",
+            on(Action::LogLevel, Tab::Logs),
+            PaletteAction::CycleLogLevel,
+        ),
+        entry(
+            "Logs: Toggle engine debug logging",
+            on(Action::EngineDebug, Tab::Logs),
+            PaletteAction::ToggleEngineDebug,
+        ),
+    ]);
+    for tab in Tab::all() {
+        entries.push(entry(
+            &format!("Go to: {} tab", tab.title()),
+            String::new(),
+            PaletteAction::GoToTab(*tab),
+        ));
+    }
+    entries.push(entry(
+        "Config: Save",
+        app.keymap.key(Action::Save),
+        PaletteAction::Save,
+    ));
+    entries.push(entry(
+        "Config: Back up",
+        String::new(),
+        PaletteAction::BackupConfig,
+    ));
+    entries.push(entry(
+        "Config: Restore from a backup",
+        String::new(),
+        PaletteAction::RestoreConfig,
+    ));
+    entries.push(entry(
+        "Quit",
+        app.keymap.key(Action::Quit),
+        PaletteAction::Quit,
+    ));
+    entries
+}
+
+/// The entries matching `query`, best match first (all of them, in their
+/// usual order, for an empty query)
+pub fn matches(app: &App, query: &str) -> Vec<PaletteEntry> {
+    let mut scored: Vec<(i32, PaletteEntry)> = entries(app)
+        .into_iter()
+        .filter_map(|e| fuzzy_score(query, &e.label).map(|score| (score, e)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, e)| e).collect()
+}
+
+/// Score how well `text` matches `query` when the query's characters appear
+/// in it in order (case-insensitive, spaces in the query ignored); None if
+/// they don't. Runs of adjacent characters and word starts score higher,
+/// gaps lower.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut from = 0;
+    let mut previous: Option<usize> = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = from + text.get(from..)?.iter().position(|t| *t == c)?;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        if let Some(previous) = previous {
+            let gap = found - previous - 1;
+            if gap == 0 {
+                score += 5;
+            } else {
+                score -= gap.min(5) as i32;
+            }
+        }
+        previous = Some(found);
+        from = found + 1;
+    }
+    Some(score)
+}
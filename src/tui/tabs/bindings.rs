@@ -1,55 +1,97 @@
 use crate::config::BindingOutput;
-use crate::tui::app::{App, BindingOutputType, InputMode};
+use crate::tui::app::{
+    binding_output_summary, mode_switch_name, scroll_offset, App, BindingOutputType, CaptureField,
+    InputMode, WizardStep,
+};
+use crate::tui::keymap::Action;
 use ratatui::{
     layout::{Constraint, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
     Frame,
 };
 
-pub fn render(f: &mut Frame, app: &App, area: Rect) {
+pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
+    // Scroll so the selected row stays inside the table (borders + header)
+    let page_rows = area.height.saturating_sub(3).max(1) as usize;
+    let selected = app
+        .visible_bindings()
+        .iter()
+        .position(|&i| i == app.binding_list_index);
+    if let Some(pos) = selected {
+        app.binding_list_offset = scroll_offset(app.binding_list_offset, pos, page_rows);
+    }
+    app.list_page_rows = page_rows;
+    let app = &*app;
+
+    let theme = &app.theme;
     let bindings = app.current_bindings();
 
     if bindings.is_empty() && app.editing_binding.is_none() {
         let msg = Paragraph::new(vec![
             Line::from("No bindings configured for the active profile."),
             Line::from(""),
-            Line::from("Press 'a' to add a new binding."),
+            Line::from(format!(
+                "Press '{}' to add a new binding, or '{}' for a guided setup.",
+                app.keymap.key(Action::Add),
+                app.keymap.key(Action::Wizard)
+            )),
             Line::from(""),
             Line::from("Bindings remap mouse buttons to other keys/buttons,"),
             Line::from("or trigger macros when pressed."),
         ])
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(" Bindings (a=add, e=edit, d=delete, s=save config) "),
-        );
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " Bindings ({}) ",
+            app.keymap.hint(&[
+                (Action::Add, "add"),
+                (Action::Edit, "edit"),
+                (Action::Delete, "delete"),
+                (Action::Import, "import"),
+                (Action::Save, "save config"),
+            ])
+        )));
         f.render_widget(msg, area);
     } else if app.editing_binding.is_none() {
+        let profile = app.config.active_profile();
+        let swapped = profile.is_some_and(|p| p.swap_buttons);
         // Show binding list
-        let header_cells = ["Input Button", "Action", "Output"].iter().map(|h| {
-            Cell::from(*h).style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            )
-        });
+        let header_cells = ["Input Button", "Action", "Output", "Tags", "Description"]
+            .iter()
+            .map(|h| {
+                Cell::from(*h).style(
+                    Style::default()
+                        .fg(theme.highlight)
+                        .add_modifier(Modifier::BOLD),
+                )
+            });
         let header = Row::new(header_cells).height(1);
 
-        let rows: Vec<Row> = bindings
+        let visible = app.visible_bindings();
+        let rows: Vec<Row> = visible
             .iter()
-            .map(|binding| {
-                let (action, output) = match &binding.output {
-                    BindingOutput::Key { key } => ("Key Remap", key.clone()),
-                    BindingOutput::Macro { macro_name } => ("Macro", macro_name.clone()),
-                };
+            .map(|&i| {
+                let binding = &bindings[i];
+                let (action, output) = binding_output_summary(&binding.output);
 
-                Row::new(vec![
-                    Cell::from(binding.input.clone()),
+                let row = Row::new(vec![
+                    Cell::from(binding.input_label()),
                     Cell::from(action),
                     Cell::from(output),
-                ])
+                    Cell::from(binding.tags.join(", ")).style(Style::default().fg(theme.accent)),
+                    Cell::from(binding.description.clone().unwrap_or_default())
+                        .style(Style::default().fg(theme.muted)),
+                ]);
+                // Bindings of a disabled tag stay listed, struck out
+                if profile.is_some_and(|p| !p.binding_enabled(binding)) {
+                    row.style(
+                        Style::default()
+                            .fg(theme.muted)
+                            .add_modifier(Modifier::CROSSED_OUT),
+                    )
+                } else {
+                    row
+                }
             })
             .collect();
 
@@ -57,44 +99,458 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
             Constraint::Length(20),
             Constraint::Length(15),
             Constraint::Min(20),
+            Constraint::Length(14),
+            Constraint::Min(20),
         ];
 
+        let filter_str = if app.list_filter_editing {
+            format!(" filter: {}_ ", app.binding_filter)
+        } else if !app.binding_filter.is_empty() {
+            format!(
+                " filter: {} ({}/{}) ",
+                app.binding_filter,
+                visible.len(),
+                bindings.len()
+            )
+        } else {
+            String::new()
+        };
+        let tag_str = match &app.binding_tag_filter {
+            Some(tag) => format!(" [tag: {}]", tag),
+            None => String::new(),
+        };
         let table = Table::new(rows, widths)
             .header(header)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title(" Bindings (a=add, e=edit, d=delete, s=save config) "),
-            )
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                " Bindings{}{} ({}){}",
+                if swapped { " [left-handed]" } else { "" },
+                tag_str,
+                app.keymap.hint(&[
+                    (Action::Add, "add"),
+                    (Action::Edit, "edit"),
+                    (Action::Delete, "delete"),
+                    (Action::Filter, "filter"),
+                    (Action::SwapButtons, "swap L/R"),
+                    (Action::TagFilter, "tags"),
+                    (Action::Save, "save config"),
+                ]),
+                filter_str
+            )))
             .row_highlight_style(
                 Style::default()
-                    .bg(Color::DarkGray)
+                    .bg(theme.selection)
                     .add_modifier(Modifier::BOLD),
             )
             .highlight_symbol(">> ");
 
-        let mut state = TableState::default();
-        state.select(Some(app.binding_list_index));
+        let mut state = TableState::default()
+            .with_offset(app.binding_list_offset)
+            .with_selected(selected);
 
         f.render_stateful_widget(table, area, &mut state);
     }
 
     // Render edit dialog if active
-    if app.editing_binding.is_some() {
+    if app.grid_editor.is_some() {
+        render_grid_editor(f, app, area);
+    } else if let Some(step) = app.wizard_step {
+        render_wizard(f, app, step, area);
+    } else if app.editing_binding.is_some() {
         render_edit_dialog(f, app, area);
     }
 }
 
+fn render_grid_editor(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let Some(grid) = app.grid_editor.as_ref() else {
+        return;
+    };
+    let capturing = match &app.input_mode {
+        InputMode::Capturing { field } => Some(field),
+        _ => None,
+    };
+
+    let dialog_height = (grid.rows.len() as u16 + 6).min(area.height.saturating_sub(2));
+    let dialog_width = 64.min(area.width.saturating_sub(4));
+    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    f.render_widget(Clear, dialog_area);
+
+    let header = Row::new(["#", "Button", "Output"].iter().map(|h| {
+        Cell::from(*h).style(
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD),
+        )
+    }));
+    let waiting = Style::default()
+        .fg(theme.highlight)
+        .add_modifier(Modifier::BOLD);
+
+    let rows: Vec<Row> = grid
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let selected = i == grid.selected;
+            let input = if selected && capturing == Some(&CaptureField::GridInput) {
+                Cell::from("[press button...]").style(waiting)
+            } else if row.input.is_empty() {
+                Cell::from("-").style(Style::default().fg(theme.hint))
+            } else {
+                Cell::from(row.input.clone())
+            };
+            let output = if selected && capturing == Some(&CaptureField::GridOutput) {
+                Cell::from("[press key...]").style(waiting)
+            } else {
+                match &row.output {
+                    Some(BindingOutput::Key { key }) => Cell::from(key.clone()),
+                    Some(BindingOutput::Macro { macro_name, .. }) => {
+                        Cell::from(format!("macro: {}", macro_name))
+                    }
+                    Some(_) => Cell::from("(other)"),
+                    None => Cell::from("-").style(Style::default().fg(theme.hint)),
+                }
+            };
+            Row::new(vec![Cell::from(format!("{:>2}", i + 1)), input, output])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Length(22),
+        Constraint::Min(20),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Bulk Bindings (l=learn buttons, i=button, Enter=key, Tab=macro) ")
+                .title_bottom(" d=clear +=add row Ctrl+S=apply Esc=cancel ")
+                .border_style(Style::default().fg(theme.accent)),
+        )
+        .row_highlight_style(
+            Style::default()
+                .bg(theme.selection)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    let mut state = TableState::default();
+    state.select(Some(grid.selected));
+    f.render_stateful_widget(table, dialog_area, &mut state);
+}
+
+/// What each action type does, for the wizard's action list
+fn describe_output_type(output_type: &BindingOutputType) -> &'static str {
+    match output_type {
+        BindingOutputType::Key => "send another key or button",
+        BindingOutputType::ToggleKey => "hold a key down until the next press",
+        BindingOutputType::Macro => "run a macro",
+        BindingOutputType::Scroll => "scroll the wheel a few notches",
+        BindingOutputType::DpiStage => "cycle pointer speed stages",
+        BindingOutputType::PointerKeys => "move the pointer to press arrow keys",
+        BindingOutputType::Mode => "switch a mode other bindings depend on",
+        BindingOutputType::SwapButtons => "swap left and right click",
+        BindingOutputType::PauseMacros => "pause running macros and resume them later",
+        BindingOutputType::Autoscroll => "scroll by moving the mouse, like a middle click",
+        BindingOutputType::Sticky => "hold a modifier for the next key or click",
+        BindingOutputType::Warp => "jump the cursor to a spot on the screen",
+        BindingOutputType::Profile => "flip between this profile and another one",
+    }
+}
+
+fn render_wizard(f: &mut Frame, app: &App, step: WizardStep, area: Rect) {
+    let theme = &app.theme;
+    let Some(editing) = app.editing_binding.as_ref() else {
+        return;
+    };
+    let is_capturing = matches!(app.input_mode, InputMode::Capturing { .. });
+
+    let dialog_height = 18.min(area.height.saturating_sub(4));
+    let dialog_width = 60.min(area.width.saturating_sub(4));
+    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    f.render_widget(Clear, dialog_area);
+
+    let label_style = Style::default().fg(theme.highlight);
+    let value_style = Style::default().fg(theme.text).add_modifier(Modifier::BOLD);
+    let hint_style = Style::default().fg(theme.hint);
+    let waiting_style = Style::default()
+        .fg(theme.highlight)
+        .add_modifier(Modifier::BOLD);
+    // A list row, marked when selected
+    let option = |text: String, selected: bool| {
+        if selected {
+            Line::from(Span::styled(
+                format!("  > {}", text),
+                Style::default()
+                    .fg(theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            ))
+        } else {
+            Line::from(Span::styled(
+                format!("    {}", text),
+                Style::default().fg(theme.muted),
+            ))
+        }
+    };
+
+    let mut lines = vec![Line::from("")];
+    let hint = match step {
+        WizardStep::Input => {
+            lines.push(Line::from("  Press the mouse button you want to bind."));
+            lines.push(Line::from(Span::styled(
+                "  Hold several buttons together for a chord.",
+                hint_style,
+            )));
+            lines.push(Line::from(""));
+            if is_capturing {
+                lines.push(Line::from(Span::styled(
+                    "  [Waiting for button press...]",
+                    waiting_style,
+                )));
+            } else if editing.input.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    "  [<Enter to capture>]",
+                    hint_style,
+                )));
+            } else {
+                lines.push(Line::from(vec![
+                    Span::styled("  Button: ", label_style),
+                    Span::styled(editing.input.clone(), value_style),
+                ]));
+            }
+            "  Enter=next  c=capture again  Esc=cancel"
+        }
+        WizardStep::Action => {
+            lines.push(Line::from(vec![
+                Span::styled("  What should ", Style::default()),
+                Span::styled(editing.input.clone(), value_style),
+                Span::raw(" do?"),
+            ]));
+            lines.push(Line::from(""));
+            for output_type in BindingOutputType::all() {
+                lines.push(option(
+                    format!(
+                        "{:<16} {}",
+                        output_type.label(),
+                        describe_output_type(output_type)
+                    ),
+                    *output_type == editing.output_type,
+                ));
+            }
+            "  Up/Down=choose  Enter=next  Esc=cancel"
+        }
+        WizardStep::Target => {
+            match editing.output_type {
+                BindingOutputType::Key | BindingOutputType::ToggleKey => {
+                    lines.push(Line::from("  Press the key or mouse button to send."));
+                    lines.push(Line::from(""));
+                    if is_capturing {
+                        lines.push(Line::from(Span::styled(
+                            "  [Waiting for key/button press...]",
+                            waiting_style,
+                        )));
+                    } else if editing.output_value.is_empty() {
+                        lines.push(Line::from(Span::styled(
+                            "  [<Enter to capture>]",
+                            hint_style,
+                        )));
+                    } else {
+                        lines.push(Line::from(vec![
+                            Span::styled("  Sends: ", label_style),
+                            Span::styled(editing.output_value.clone(), value_style),
+                        ]));
+                    }
+                }
+                BindingOutputType::Macro => {
+                    let macro_names = app.macro_names();
+                    lines.push(Line::from("  Which macro should it run?"));
+                    lines.push(Line::from(""));
+                    if macro_names.is_empty() {
+                        lines.push(Line::from(Span::styled(
+                            "  No macros yet -- create one in the Macros tab",
+                            hint_style,
+                        )));
+                    }
+                    for (i, name) in macro_names.iter().enumerate().take(8) {
+                        lines.push(option(name.clone(), i == editing.macro_select_index));
+                    }
+                }
+                BindingOutputType::Scroll => {
+                    lines.push(Line::from("  How far should each press scroll?"));
+                    lines.push(Line::from(Span::styled(
+                        "  Negative notches scroll down (or left).",
+                        hint_style,
+                    )));
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(vec![
+                        Span::styled("  Scroll: ", label_style),
+                        Span::styled(format!("[{}_]", editing.output_value), value_style),
+                        Span::styled("  (Tab = axis, - = direction)", hint_style),
+                    ]));
+                }
+                BindingOutputType::Warp => {
+                    lines.push(Line::from("  Where should the cursor jump to?"));
+                    lines.push(Line::from(Span::styled(
+                        "  Type x,y in pixels or a position from the [warp] section.",
+                        hint_style,
+                    )));
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(vec![
+                        Span::styled("  Warp to: ", label_style),
+                        Span::styled(format!("[{}_]", editing.output_value), value_style),
+                    ]));
+                    let names: Vec<&str> = app
+                        .config
+                        .warp
+                        .iter()
+                        .flat_map(|w| w.positions.keys())
+                        .map(String::as_str)
+                        .collect();
+                    if !names.is_empty() {
+                        lines.push(Line::from(Span::styled(
+                            format!("  Positions: {}", names.join(", ")),
+                            hint_style,
+                        )));
+                    }
+                }
+                BindingOutputType::Profile => {
+                    lines.push(Line::from("  Which profile should it flip to?"));
+                    lines.push(Line::from(Span::styled(
+                        "  Pressed again there, it goes back to the profile it came from.",
+                        hint_style,
+                    )));
+                    lines.push(Line::from(""));
+                    for name in app.profile_names() {
+                        let selected = name == editing.output_value;
+                        lines.push(option(name, selected));
+                    }
+                }
+                BindingOutputType::Mode => {
+                    lines.push(Line::from("  Name the mode this button switches."));
+                    lines.push(Line::from(Span::styled(
+                        "  Other bindings can require it with 'When mode'.",
+                        hint_style,
+                    )));
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(vec![
+                        Span::styled("  Mode:   ", label_style),
+                        Span::styled(format!("[{}_]", editing.output_value), value_style),
+                    ]));
+                    lines.push(Line::from(vec![
+                        Span::styled("  Switch: ", label_style),
+                        Span::styled(
+                            format!("[{}]", mode_switch_name(editing.mode_switch)),
+                            value_style,
+                        ),
+                        Span::styled("  (Tab to cycle)", hint_style),
+                    ]));
+                }
+                _ => {
+                    lines.push(Line::from(format!(
+                        "  {}: choose a setting.",
+                        editing.output_type.label()
+                    )));
+                    lines.push(Line::from(""));
+                    for choice in editing.output_type.choices() {
+                        lines.push(option(choice.to_string(), *choice == editing.output_value));
+                    }
+                }
+            }
+            if editing.output_type.captures_key() {
+                "  Enter=next  c=capture again  Esc=cancel"
+            } else {
+                "  Up/Down=choose  Enter=next  Esc=cancel"
+            }
+        }
+        WizardStep::Test => {
+            let target = match editing.output_type {
+                BindingOutputType::DpiStage => {
+                    format!("{} ({})", editing.output_type.label(), editing.output_value)
+                }
+                BindingOutputType::Mode => format!(
+                    "{} {} ({})",
+                    editing.output_type.label(),
+                    editing.output_value,
+                    mode_switch_name(editing.mode_switch)
+                ),
+                _ => format!("{} {}", editing.output_type.label(), editing.output_value),
+            };
+            lines.push(Line::from(vec![
+                Span::styled("  ", Style::default()),
+                Span::styled(editing.input.clone(), value_style),
+                Span::raw(" -> "),
+                Span::styled(target, value_style),
+            ]));
+            lines.push(Line::from(""));
+            if app.engine_running || app.capture_only {
+                lines.push(Line::from(format!(
+                    "  Press {} now to check it's detected.",
+                    editing.input
+                )));
+                lines.push(Line::from(""));
+                let (text, style) = if app.wizard_test_presses == 0 {
+                    ("  [Not detected yet]".to_string(), hint_style)
+                } else {
+                    (
+                        format!("  [Detected {}x]", app.wizard_test_presses),
+                        Style::default()
+                            .fg(theme.success)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                };
+                lines.push(Line::from(Span::styled(text, style)));
+            } else {
+                lines.push(Line::from(Span::styled(
+                    "  Select a device to test the button here.",
+                    hint_style,
+                )));
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "  Save, then restart the engine for it to take effect.",
+                hint_style,
+            )));
+            "  Enter=save  Esc=cancel"
+        }
+    };
+
+    // Keep the key hint on the last line of the dialog
+    let inner_height = dialog_height.saturating_sub(2) as usize;
+    while lines.len() + 1 < inner_height {
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(Span::styled(hint, hint_style)));
+
+    let title = format!(" New Binding - Step {}/4: {} ", step.number(), step.title());
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(theme.accent)),
+    );
+
+    f.render_widget(paragraph, dialog_area);
+}
+
 fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let editing = app.editing_binding.as_ref().unwrap();
     let is_capturing = matches!(app.input_mode, InputMode::Capturing { .. });
     let macro_names = app.macro_names();
     let is_macro_output = editing.output_type == BindingOutputType::Macro;
 
     // Increase dialog height when showing macro list
-    let base_height: u16 = 14;
+    let base_height: u16 = 25;
     let macro_list_extra: u16 = if is_macro_output && editing.field_index == 2 {
-        (macro_names.len() as u16).min(6).max(1) + 1 // +1 for label
+        (macro_names.len() as u16).clamp(1, 6) + 1 // +1 for label
     } else {
         0
     };
@@ -114,10 +570,7 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
         " New Binding "
     };
 
-    let output_type_str = match editing.output_type {
-        BindingOutputType::Key => "Key Remap",
-        BindingOutputType::Macro => "Macro",
-    };
+    let output_type_str = editing.output_type.label();
 
     let field_indicator = |idx: usize| -> &str {
         if editing.field_index == idx {
@@ -127,11 +580,9 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
         }
     };
 
-    let focused_style = Style::default()
-        .fg(Color::White)
-        .add_modifier(Modifier::BOLD);
-    let unfocused_style = Style::default().fg(Color::Gray);
-    let hint_style = Style::default().fg(Color::DarkGray);
+    let focused_style = Style::default().fg(theme.text).add_modifier(Modifier::BOLD);
+    let unfocused_style = Style::default().fg(theme.muted);
+    let hint_style = Style::default().fg(theme.hint);
 
     // Field 0: Input button
     let input_display = if is_capturing && editing.field_index == 0 {
@@ -139,12 +590,14 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
     } else if editing.input.is_empty() {
         "[<Enter to capture>]".to_string()
     } else {
-        format!("[{}]", editing.input)
+        let mut parts: Vec<&str> = editing.modifiers.iter().map(|m| m.label()).collect();
+        parts.push(&editing.input);
+        format!("[{}]", parts.join("+"))
     };
 
     let input_style = if is_capturing && editing.field_index == 0 {
         Style::default()
-            .fg(Color::Yellow)
+            .fg(theme.highlight)
             .add_modifier(Modifier::BOLD)
     } else if editing.field_index == 0 {
         focused_style
@@ -155,7 +608,7 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
     let mut lines = vec![
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Input button: ", Style::default().fg(Color::Yellow)),
+            Span::styled("  Input button: ", Style::default().fg(theme.highlight)),
             Span::styled(input_display, input_style),
             Span::raw(field_indicator(0)),
             if editing.field_index == 0 && !is_capturing {
@@ -164,9 +617,17 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
                 Span::raw("")
             },
         ]),
+        Line::from(if editing.field_index == 0 && !is_capturing {
+            Span::styled(
+                "    c/s/a/m = only while Ctrl/Shift/Alt/Meta is held",
+                hint_style,
+            )
+        } else {
+            Span::raw("")
+        }),
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Output type:  ", Style::default().fg(Color::Yellow)),
+            Span::styled("  Output type:  ", Style::default().fg(theme.highlight)),
             Span::styled(
                 format!("[{}]", output_type_str),
                 if editing.field_index == 1 {
@@ -176,7 +637,7 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
                 },
             ),
             Span::raw(field_indicator(1)),
-            Span::styled("  (Tab to toggle)", hint_style),
+            Span::styled("  (Tab to cycle)", hint_style),
         ]),
         Line::from(""),
     ];
@@ -184,11 +645,7 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
     // Field 2: Output value — different rendering based on output type
     if is_macro_output {
         // Macro output: show a selectable list
-        let output_label = if editing.field_index == 2 {
-            "  Output macro: "
-        } else {
-            "  Output macro: "
-        };
+        let output_label = "  Output macro: ";
         let current_value = if editing.output_value.is_empty() {
             "<none selected>"
         } else {
@@ -196,7 +653,7 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
         };
 
         lines.push(Line::from(vec![
-            Span::styled(output_label, Style::default().fg(Color::Yellow)),
+            Span::styled(output_label, Style::default().fg(theme.highlight)),
             Span::styled(
                 format!("[{}]", current_value),
                 if editing.field_index == 2 {
@@ -213,7 +670,7 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
             if macro_names.is_empty() {
                 lines.push(Line::from(Span::styled(
                     "    No macros -- create one in the Macros tab first",
-                    Style::default().fg(Color::Red),
+                    Style::default().fg(theme.error),
                 )));
             } else {
                 for (i, name) in macro_names.iter().enumerate() {
@@ -221,10 +678,10 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
                     let prefix = if is_selected { "  > " } else { "    " };
                     let style = if is_selected {
                         Style::default()
-                            .fg(Color::Cyan)
+                            .fg(theme.accent)
                             .add_modifier(Modifier::BOLD)
                     } else {
-                        Style::default().fg(Color::Gray)
+                        Style::default().fg(theme.muted)
                     };
                     lines.push(Line::from(Span::styled(
                         format!("{}{}", prefix, name),
@@ -237,6 +694,130 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
                 )));
             }
         }
+    } else if editing.output_type == BindingOutputType::Scroll {
+        lines.push(Line::from(vec![
+            Span::styled("  Scroll:       ", Style::default().fg(theme.highlight)),
+            Span::styled(
+                format!("[{}]", editing.output_value),
+                if editing.field_index == 2 {
+                    focused_style
+                } else {
+                    unfocused_style
+                },
+            ),
+            Span::raw(field_indicator(2)),
+            Span::styled("  (Tab = axis, - = direction)", hint_style),
+        ]));
+        lines.push(Line::from(Span::styled(
+            "    Wheel notches per press; negative scrolls down (or left)",
+            hint_style,
+        )));
+    } else if editing.output_type == BindingOutputType::Warp {
+        let target = if editing.output_value.is_empty() {
+            "<type x,y or a name>"
+        } else {
+            editing.output_value.as_str()
+        };
+        lines.push(Line::from(vec![
+            Span::styled("  Warp to:      ", Style::default().fg(theme.highlight)),
+            Span::styled(
+                format!("[{}]", target),
+                if editing.field_index == 2 {
+                    focused_style
+                } else {
+                    unfocused_style
+                },
+            ),
+            Span::raw(field_indicator(2)),
+        ]));
+        let warp = app.warp_config();
+        lines.push(Line::from(Span::styled(
+            format!(
+                "    Pixels on the {}x{} desktop, or a position from the [warp] section",
+                warp.width, warp.height
+            ),
+            hint_style,
+        )));
+    } else if editing.output_type == BindingOutputType::Mode {
+        let name = if editing.output_value.is_empty() {
+            "<type a name>"
+        } else {
+            editing.output_value.as_str()
+        };
+        lines.push(Line::from(vec![
+            Span::styled("  Mode:         ", Style::default().fg(theme.highlight)),
+            Span::styled(
+                format!("[{}: {}]", name, mode_switch_name(editing.mode_switch)),
+                if editing.field_index == 2 {
+                    focused_style
+                } else {
+                    unfocused_style
+                },
+            ),
+            Span::raw(field_indicator(2)),
+            Span::styled("  (Tab to cycle)", hint_style),
+        ]));
+        lines.push(Line::from(Span::styled(
+            "    Bindings with a matching 'when' apply while it's on",
+            hint_style,
+        )));
+    } else if editing.output_type == BindingOutputType::Profile {
+        let name = if editing.output_value.is_empty() {
+            "<Tab to pick>"
+        } else {
+            editing.output_value.as_str()
+        };
+        lines.push(Line::from(vec![
+            Span::styled("  Profile:      ", Style::default().fg(theme.highlight)),
+            Span::styled(
+                format!("[{}]", name),
+                if editing.field_index == 2 {
+                    focused_style
+                } else {
+                    unfocused_style
+                },
+            ),
+            Span::raw(field_indicator(2)),
+            Span::styled("  (Tab to cycle)", hint_style),
+        ]));
+        lines.push(Line::from(Span::styled(
+            "    Switches to it, or back to the last profile when already on it",
+            hint_style,
+        )));
+    } else if let Some(first) = editing.output_type.choices().first() {
+        let value = if editing.output_value.is_empty() {
+            *first
+        } else {
+            editing.output_value.as_str()
+        };
+        let explanation = match editing.output_type {
+            BindingOutputType::DpiStage => "    Cycles the profile's dpi_stages multipliers",
+            BindingOutputType::Sticky => "    Held for the next key or click only, then released",
+            BindingOutputType::SwapButtons => {
+                "    Left-handed mode: BTN_LEFT and BTN_RIGHT trade places"
+            }
+            BindingOutputType::PauseMacros => {
+                "    Repeat and toggle macros keep their state while paused"
+            }
+            BindingOutputType::Autoscroll => {
+                "    The further the mouse moves up or down, the faster it scrolls"
+            }
+            _ => "    Pointer movement presses arrow keys while on",
+        };
+        lines.push(Line::from(vec![
+            Span::styled("  Action:       ", Style::default().fg(theme.highlight)),
+            Span::styled(
+                format!("[{}]", value),
+                if editing.field_index == 2 {
+                    focused_style
+                } else {
+                    unfocused_style
+                },
+            ),
+            Span::raw(field_indicator(2)),
+            Span::styled("  (Tab to cycle)", hint_style),
+        ]));
+        lines.push(Line::from(Span::styled(explanation, hint_style)));
     } else {
         // Key output: capture-based
         let output_display = if is_capturing && editing.field_index == 2 {
@@ -249,7 +830,7 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
 
         let output_style = if is_capturing && editing.field_index == 2 {
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.highlight)
                 .add_modifier(Modifier::BOLD)
         } else if editing.field_index == 2 {
             focused_style
@@ -258,7 +839,7 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
         };
 
         lines.push(Line::from(vec![
-            Span::styled("  Output key:   ", Style::default().fg(Color::Yellow)),
+            Span::styled("  Output key:   ", Style::default().fg(theme.highlight)),
             Span::styled(output_display, output_style),
             Span::raw(field_indicator(2)),
             if editing.field_index == 2 && !is_capturing {
@@ -269,6 +850,121 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
         ]));
     }
 
+    // Field 3: mode condition
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("  When mode:    ", Style::default().fg(theme.highlight)),
+        Span::styled(
+            if editing.when.is_empty() {
+                "[always]".to_string()
+            } else {
+                format!("[{}]", editing.when)
+            },
+            if editing.field_index == 3 {
+                focused_style
+            } else {
+                unfocused_style
+            },
+        ),
+        Span::raw(field_indicator(3)),
+        if editing.field_index == 3 {
+            Span::styled("  (name, or !name while off)", hint_style)
+        } else {
+            Span::raw("")
+        },
+    ]));
+
+    // Field 4: cooldown
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("  Cooldown:     ", Style::default().fg(theme.highlight)),
+        Span::styled(
+            if editing.cooldown_ms.is_empty() {
+                "[off]".to_string()
+            } else {
+                format!("[{}ms]", editing.cooldown_ms)
+            },
+            if editing.field_index == 4 {
+                focused_style
+            } else {
+                unfocused_style
+            },
+        ),
+        Span::raw(field_indicator(4)),
+        if editing.field_index == 4 {
+            Span::styled("  (presses within it are ignored)", hint_style)
+        } else {
+            Span::raw("")
+        },
+    ]));
+
+    // Field 5: macro arguments
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("  Macro args:   ", Style::default().fg(theme.highlight)),
+        Span::styled(
+            if editing.args.is_empty() {
+                "[none]".to_string()
+            } else {
+                format!("[{}]", editing.args)
+            },
+            if editing.field_index == 5 {
+                focused_style
+            } else {
+                unfocused_style
+            },
+        ),
+        Span::raw(field_indicator(5)),
+        if editing.field_index == 5 {
+            Span::styled("  (name=value, ...)", hint_style)
+        } else {
+            Span::raw("")
+        },
+    ]));
+
+    // Field 6: description
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("  Description:  ", Style::default().fg(theme.highlight)),
+        Span::styled(
+            if editing.description.is_empty() {
+                "[none]".to_string()
+            } else {
+                format!("[{}]", editing.description)
+            },
+            if editing.field_index == 6 {
+                focused_style
+            } else {
+                unfocused_style
+            },
+        ),
+        Span::raw(field_indicator(6)),
+    ]));
+
+    // Field 7: tags
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("  Tags:         ", Style::default().fg(theme.highlight)),
+        Span::styled(
+            if editing.tags.is_empty() {
+                "[none]".to_string()
+            } else {
+                format!("[{}]", editing.tags)
+            },
+            if editing.field_index == 7 {
+                focused_style
+            } else {
+                unfocused_style
+            },
+        ),
+        Span::raw(field_indicator(7)),
+        if editing.field_index == 7 {
+            Span::styled("  (work, fps, ...)", hint_style)
+        } else {
+            Span::raw("")
+        },
+    ]));
+
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
         "  Up/Down=fields  Ctrl+S=save  Esc=cancel",
@@ -279,7 +975,7 @@ fn render_edit_dialog(f: &mut Frame, app: &App, area: Rect) {
         Block::default()
             .borders(Borders::ALL)
             .title(title)
-            .border_style(Style::default().fg(Color::Cyan)),
+            .border_style(Style::default().fg(theme.accent)),
     );
 
     f.render_widget(paragraph, dialog_area);
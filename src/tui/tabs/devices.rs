@@ -1,13 +1,16 @@
-use crate::tui::app::App;
+use crate::device::scanner;
+use crate::tui::app::{App, EngineDeviceState};
+use crate::tui::keymap::Action;
 use ratatui::{
-    layout::{Constraint, Rect},
-    style::{Color, Modifier, Style},
-    text::Line,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
     widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
     Frame,
 };
 
 pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let devices = &app.devices;
 
     if devices.is_empty() {
@@ -15,53 +18,120 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
             Line::from("No input devices found."),
             Line::from(""),
             Line::from("Make sure you're running as root (sudo)."),
-            Line::from("Press 'r' to refresh the device list."),
+            Line::from(format!(
+                "Press '{}' to refresh the device list.",
+                app.keymap.key(Action::Refresh)
+            )),
         ])
         .block(Block::default().borders(Borders::ALL).title(" Devices "));
         f.render_widget(msg, area);
         return;
     }
 
-    let header_cells = ["Path", "Name", "VID:PID", "Type", "Capabilities"]
-        .iter()
-        .map(|h| {
-            Cell::from(*h).style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            )
-        });
+    // Profiles apply to the device the [device] config section points at
+    let configured = scanner::configured_device(devices, &app.config.device).map(|d| &d.path);
+    let profile_name = app
+        .config
+        .active_profile()
+        .map_or("(none)", |p| p.name.as_str());
+
+    let header_cells = [
+        "Path",
+        "Name",
+        "VID:PID",
+        "Type",
+        "Profile",
+        "Grabbed",
+        "Battery",
+        "Capabilities",
+    ]
+    .iter()
+    .map(|h| {
+        Cell::from(*h).style(
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD),
+        )
+    });
     let header = Row::new(header_cells).height(1);
 
     let rows: Vec<Row> = devices
         .iter()
-        .enumerate()
-        .map(|(_i, device)| {
+        .map(|device| {
             let selected = app
                 .selected_device
                 .as_ref()
                 .is_some_and(|d| d.path == device.path);
 
-            let type_str = if device.is_mouse { "Mouse" } else { "Other" };
+            let type_str = if device.is_mouse {
+                "Mouse"
+            } else if device.is_tablet {
+                "Tablet"
+            } else {
+                "Other"
+            };
             let vid_pid = format!("{:04x}:{:04x}", device.vendor_id, device.product_id);
 
             let style = if selected {
                 Style::default()
-                    .fg(Color::Green)
+                    .fg(theme.success)
                     .add_modifier(Modifier::BOLD)
-            } else if device.is_mouse {
-                Style::default().fg(Color::White)
+            } else if device.is_mouse || device.is_tablet {
+                Style::default().fg(theme.text)
             } else {
-                Style::default().fg(Color::DarkGray)
+                Style::default().fg(theme.hint)
             };
 
-            let prefix = if selected { "* " } else { "  " };
+            let prefix = match (selected, app.tagged_devices.contains(&device.path)) {
+                (true, true) => "*+",
+                (true, false) => "* ",
+                (false, true) => " +",
+                (false, false) => "  ",
+            };
+
+            let profile = if configured == Some(&device.path) {
+                profile_name
+            } else {
+                ""
+            };
+            // The engine's own grab shows up as busy too, so check it first
+            let (grab, grab_style) = match app.engine_devices.get(&device.path) {
+                Some(EngineDeviceState::Grabbed) => ("mouse-mapper".to_string(), style),
+                Some(EngineDeviceState::Observing) => ("observing".to_string(), style),
+                Some(EngineDeviceState::Starting) => ("starting".to_string(), style),
+                Some(EngineDeviceState::Restarting(attempt, max)) => (
+                    format!("restarting {}/{}", attempt, max),
+                    Style::default().fg(theme.error),
+                ),
+                Some(EngineDeviceState::Waiting) => (
+                    "waiting (unplugged)".to_string(),
+                    Style::default().fg(theme.hint),
+                ),
+                Some(EngineDeviceState::Failed(e)) => {
+                    (format!("failed: {}", e), Style::default().fg(theme.error))
+                }
+                None if device.grabbed => (
+                    "other process".to_string(),
+                    Style::default().fg(theme.error),
+                ),
+                None => (String::new(), style),
+            };
+
+            let battery = match app.battery_levels.get(&device.path) {
+                Some(info) if info.charging => format!("{}% +", info.percent),
+                Some(info) if info.is_low() => format!("{}% !", info.percent),
+                Some(info) => format!("{}%", info.percent),
+                None => String::new(),
+            };
 
             Row::new(vec![
                 Cell::from(format!("{}{}", prefix, device.path.display())),
                 Cell::from(device.name.clone()),
                 Cell::from(vid_pid),
                 Cell::from(type_str),
+                Cell::from(profile),
+                Cell::from(grab).style(grab_style),
+                Cell::from(battery),
                 Cell::from(device.capabilities.clone()),
             ])
             .style(style)
@@ -73,25 +143,185 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
         Constraint::Min(30),
         Constraint::Length(10),
         Constraint::Length(8),
+        Constraint::Length(12),
+        Constraint::Length(14),
+        Constraint::Length(8),
         Constraint::Min(20),
     ];
 
     let table = Table::new(rows, widths)
         .header(header)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(" Devices (Enter=select, r=refresh, Space=start/stop engine) "),
-        )
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " Devices ({}){} ",
+            app.keymap.hint(&[
+                (Action::Select, "select/grab tagged"),
+                (Action::Tag, "tag"),
+                (Action::Engine, "start/stop engine"),
+                (Action::Refresh, "refresh"),
+            ]),
+            if app.tagged_devices.is_empty() {
+                String::new()
+            } else {
+                format!(" [{} tagged]", app.tagged_devices.len())
+            }
+        )))
         .row_highlight_style(
             Style::default()
-                .bg(Color::DarkGray)
+                .bg(theme.selection)
                 .add_modifier(Modifier::BOLD),
         )
         .highlight_symbol(">> ");
 
+    // Engine panel and recent automatic restarts below the table, while
+    // there's something to show
+    let engine_height = match app.engine_states.len() {
+        0 => 0,
+        n => (n + app.engine_warnings.len()).min(8) as u16 + 2,
+    };
+    let history_height = if app.engine_restarts.is_empty() { 0 } else { 7 };
+    let sensor_height = if app.selected_sensor().is_some() {
+        4
+    } else {
+        0
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(5),
+            Constraint::Length(sensor_height),
+            Constraint::Length(engine_height),
+            Constraint::Length(history_height),
+        ])
+        .split(area);
+    let table_area = chunks[0];
+    let history_area = (history_height > 0).then_some(chunks[3]);
+
     let mut state = TableState::default();
     state.select(Some(app.device_list_index));
 
-    f.render_stateful_widget(table, area, &mut state);
+    f.render_stateful_widget(table, table_area, &mut state);
+
+    if sensor_height > 0 {
+        render_sensor_panel(f, app, chunks[1]);
+    }
+    if engine_height > 0 {
+        render_engine_panel(f, app, chunks[2]);
+    }
+
+    if let Some(history_area) = history_area {
+        let lines: Vec<Line> = app
+            .engine_restarts
+            .iter()
+            .rev()
+            .take(history_area.height.saturating_sub(2) as usize)
+            .map(|r| {
+                Line::styled(
+                    format!(
+                        "{:>4}s ago  {}  restart {}/{}: {}",
+                        r.at.elapsed().as_secs(),
+                        r.path,
+                        r.attempt,
+                        r.max_attempts,
+                        r.error
+                    ),
+                    Style::default().fg(theme.muted),
+                )
+            })
+            .collect();
+        let history = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Engine restarts (newest first) "),
+        );
+        f.render_widget(history, history_area);
+    }
+}
+
+/// Hardware DPI and report rate of the selected mouse, next to what the
+/// software DPI stage turns that into
+fn render_sensor_panel(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let Some(info) = app.selected_sensor() else {
+        return;
+    };
+    let or_unknown = |value: Option<u32>| value.map_or("?".to_string(), |v| v.to_string());
+
+    let mut hardware = format!("Hardware: {} DPI", or_unknown(info.dpi));
+    if let Some(default_dpi) = info.default_dpi {
+        hardware.push_str(&format!(" (default {})", default_dpi));
+    }
+    hardware.push_str(&format!(
+        "  report rate: {} Hz",
+        or_unknown(info.report_rate_hz)
+    ));
+
+    let software = match (app.dpi_stage, info.dpi) {
+        (Some((index, count, multiplier)), Some(dpi)) => format!(
+            "Software: stage {}/{} (x{}) = {:.0} effective DPI",
+            index + 1,
+            count,
+            multiplier,
+            dpi as f64 * multiplier
+        ),
+        (Some((index, count, multiplier)), None) => {
+            format!("Software: stage {}/{} (x{})", index + 1, count, multiplier)
+        }
+        (None, _) => "Software: no DPI stages active".to_string(),
+    };
+
+    let panel = Paragraph::new(vec![
+        Line::styled(hardware, Style::default().fg(theme.text)),
+        Line::styled(software, Style::default().fg(theme.muted)),
+    ])
+    .block(Block::default().borders(Borders::ALL).title(" Sensor "));
+    f.render_widget(panel, area);
+}
+
+/// One line per device the engine runs on, from its latest EngineState
+fn render_engine_panel(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let mut states: Vec<_> = app.engine_states.values().collect();
+    states.sort_by(|a, b| a.0.device.cmp(&b.0.device));
+
+    let mut lines: Vec<Line> = states
+        .into_iter()
+        .map(|(state, received)| {
+            let (status, style) = if state.grabbed {
+                ("grabbed", Style::default().fg(theme.success))
+            } else if state.observing {
+                ("observing", Style::default().fg(theme.highlight))
+            } else {
+                ("not grabbed", Style::default().fg(theme.error))
+            };
+            let uptime = (state.uptime + received.elapsed()).as_secs();
+            Line::from(vec![
+                Span::styled(format!("{:<12}", status), style),
+                Span::styled(
+                    format!(
+                        "{} ({})  profile: {}  {} binding(s), {} macro(s)  up {}m{:02}s",
+                        state.device_name,
+                        state.device,
+                        state.profile.as_deref().unwrap_or("(none)"),
+                        state.bindings_loaded,
+                        state.macros_loaded,
+                        uptime / 60,
+                        uptime % 60
+                    ),
+                    Style::default().fg(theme.text),
+                ),
+            ])
+        })
+        .collect();
+    for warning in &app.engine_warnings {
+        lines.push(Line::styled(
+            format!("warning     {}", warning),
+            Style::default().fg(theme.error),
+        ));
+    }
+    let title = match app.engine_warnings.len() {
+        0 => " Engine ".to_string(),
+        n => format!(" Engine ({} binding/macro problem(s)) ", n),
+    };
+    let panel = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(panel, area);
 }
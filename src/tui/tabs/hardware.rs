@@ -0,0 +1,244 @@
+use crate::tui::app::{App, HardwareFocus};
+use crate::tui::keymap::Action;
+use crate::tui::theme::Theme;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+
+    let Some(device) = app.current_ratbag_device() else {
+        if app.ratbag_busy() {
+            let msg = Paragraph::new("Asking ratbagd for mice...")
+                .block(Block::default().borders(Borders::ALL).title(" Hardware "));
+            f.render_widget(msg, area);
+            return;
+        }
+        let mut lines = vec![
+            Line::from("No mice with onboard settings found."),
+            Line::from(""),
+        ];
+        if let Some(ref err) = app.ratbag_error {
+            lines.push(Line::from(format!("ratbagd: {}", err)));
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(
+            "This tab reads DPI stages, button functions and profiles",
+        ));
+        lines.push(Line::from(format!(
+            "through ratbagd (libratbag). Press '{}' to try again.",
+            app.keymap.key(Action::Refresh)
+        )));
+        let msg =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(format!(
+                " Hardware ({}) ",
+                app.keymap.hint(&[(Action::Refresh, "refresh")])
+            )));
+        f.render_widget(msg, area);
+        return;
+    };
+
+    let title = format!(
+        " {} ({}) [{}/{}]{} ({}) ",
+        device.name,
+        device.model,
+        app.ratbag_device_index + 1,
+        app.ratbag_devices.len(),
+        if app.ratbag_uncommitted {
+            " [UNCOMMITTED]"
+        } else {
+            ""
+        },
+        app.keymap.hint(&[
+            (Action::NextSection, "focus"),
+            (Action::Select, "activate"),
+            (Action::Increase, "more"),
+            (Action::Decrease, "less"),
+            (Action::Commit, "commit"),
+            (Action::NextDevice, "next device"),
+        ]),
+    );
+    let outer = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(theme.accent));
+    let inner = outer.inner(area);
+    f.render_widget(outer, area);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(28),
+            Constraint::Length(34),
+            Constraint::Min(30),
+        ])
+        .split(inner);
+
+    // Profiles
+    let rows: Vec<Row> = device
+        .profiles
+        .iter()
+        .map(|p| {
+            let name = if p.name.is_empty() {
+                format!("Profile {}", p.index)
+            } else {
+                p.name.clone()
+            };
+            let style = if p.is_active {
+                Style::default()
+                    .fg(theme.success)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            Row::new(vec![
+                Cell::from(format!("{}{}", if p.is_active { "* " } else { "  " }, name)),
+                Cell::from(if p.report_rate > 0 {
+                    format!("{}Hz", p.report_rate)
+                } else {
+                    String::new()
+                }),
+            ])
+            .style(style)
+        })
+        .collect();
+    render_list(
+        f,
+        theme,
+        " Profiles ",
+        ["Name", "Rate"],
+        [Constraint::Min(14), Constraint::Length(7)],
+        rows,
+        (app.ratbag_focus == HardwareFocus::Profiles).then_some(app.ratbag_profile_index),
+        columns[0],
+    );
+
+    let Some(profile) = app.current_ratbag_profile() else {
+        return;
+    };
+    let selected_row = |focus: HardwareFocus| (app.ratbag_focus == focus).then_some(app.ratbag_row);
+
+    // DPI stages
+    let rows: Vec<Row> = profile
+        .resolutions
+        .iter()
+        .map(|r| {
+            let dpi = if r.separate_xy && r.dpi.0 != r.dpi.1 {
+                format!("{}x{}", r.dpi.0, r.dpi.1)
+            } else {
+                r.dpi.0.to_string()
+            };
+            let flags = match (r.is_active, r.is_default) {
+                (true, true) => "active, default",
+                (true, false) => "active",
+                (false, true) => "default",
+                (false, false) => "",
+            };
+            let style = if r.is_active {
+                Style::default()
+                    .fg(theme.success)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            Row::new(vec![
+                Cell::from(r.index.to_string()),
+                Cell::from(dpi),
+                Cell::from(flags),
+            ])
+            .style(style)
+        })
+        .collect();
+    render_list(
+        f,
+        theme,
+        &format!(
+            " DPI Stages ({}) ",
+            app.keymap.hint(&[(Action::DefaultDpi, "default")])
+        ),
+        ["#", "DPI", ""],
+        [
+            Constraint::Length(3),
+            Constraint::Length(10),
+            Constraint::Min(8),
+        ],
+        rows,
+        selected_row(HardwareFocus::Resolutions),
+        columns[1],
+    );
+
+    // Buttons
+    let rows: Vec<Row> = profile
+        .buttons
+        .iter()
+        .map(|b| {
+            Row::new(vec![
+                Cell::from(b.index.to_string()),
+                Cell::from(b.action.describe()),
+            ])
+            .style(Style::default().fg(theme.text))
+        })
+        .collect();
+    render_list(
+        f,
+        theme,
+        " Buttons ",
+        ["#", "Function"],
+        [Constraint::Length(3), Constraint::Min(20)],
+        rows,
+        selected_row(HardwareFocus::Buttons),
+        columns[2],
+    );
+}
+
+/// One of the three columns; `selected` is Some only for the focused column
+#[allow(clippy::too_many_arguments)]
+fn render_list<const N: usize>(
+    f: &mut Frame,
+    theme: &Theme,
+    title: &str,
+    headers: [&str; N],
+    widths: [Constraint; N],
+    rows: Vec<Row>,
+    selected: Option<usize>,
+    area: Rect,
+) {
+    let header = Row::new(headers.iter().map(|h| {
+        Cell::from(*h).style(
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD),
+        )
+    }));
+
+    let border_style = if selected.is_some() {
+        Style::default().fg(theme.accent)
+    } else {
+        Style::default().fg(theme.muted)
+    };
+
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title.to_string())
+                .border_style(border_style),
+        )
+        .row_highlight_style(
+            Style::default()
+                .bg(theme.selection)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ");
+
+    let mut state = TableState::default();
+    state.select(selected);
+
+    f.render_stateful_widget(table, area, &mut state);
+}
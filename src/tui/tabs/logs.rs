@@ -0,0 +1,88 @@
+use crate::tui::app::App;
+use crate::tui::keymap::Action;
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+pub fn render(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let lines = app.filtered_log_lines();
+
+    let filter_str = if app.log_filter_editing {
+        format!(" filter: {}_ ", app.log_filter)
+    } else if !app.log_filter.is_empty() {
+        format!(" filter: {} ", app.log_filter)
+    } else {
+        String::new()
+    };
+    let title = format!(
+        " Logs [show {}+]{}{} [file: {}{}] ({}) ",
+        app.log_min_level,
+        filter_str,
+        if app.log_scroll > 0 {
+            " [SCROLLED]"
+        } else {
+            ""
+        },
+        app.config.logging.level,
+        if app.config.logging.engine_debug {
+            ", engine=debug"
+        } else {
+            ""
+        },
+        app.keymap.hint(&[
+            (Action::LogLevel, "show"),
+            (Action::Filter, "filter"),
+            (Action::LogVerbosity, "verbosity"),
+            (Action::EngineDebug, "engine debug"),
+        ])
+    );
+
+    if lines.is_empty() {
+        let msg = Paragraph::new(vec![
+            Line::from("No log lines to show."),
+            Line::from(""),
+            Line::from(format!("Reading {}", app.log_tail.path().display())),
+            Line::from("Set RUST_LOG=debug for more detail."),
+        ])
+        .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(msg, area);
+        return;
+    }
+
+    // Show the window ending `log_scroll` lines above the newest line
+    let visible_height = area.height.saturating_sub(2) as usize; // account for borders
+    let scroll = app
+        .log_scroll
+        .min(lines.len().saturating_sub(visible_height));
+    let end = lines.len() - scroll;
+    let start = end.saturating_sub(visible_height);
+
+    let rendered: Vec<Line> = lines[start..end]
+        .iter()
+        .map(|l| {
+            let style = match l.level {
+                log::Level::Error => Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+                log::Level::Warn => Style::default().fg(theme.highlight),
+                log::Level::Info => Style::default().fg(theme.text),
+                log::Level::Debug | log::Level::Trace => Style::default().fg(theme.hint),
+            };
+            Line::from(Span::styled(l.text.clone(), style))
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(rendered).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(theme.accent)),
+    );
+
+    f.render_widget(paragraph, area);
+}
@@ -1,72 +1,148 @@
-use crate::config::MacroType;
-use crate::tui::app::App;
+use crate::config::{ClickStep, MacroAction};
+use crate::tui::app::{
+    macro_type_name, mode_switch_name, scroll_offset, App, CaptureField, EditingMacro, InputMode,
+};
+use crate::tui::keymap::Action;
+use crate::tui::templates;
 use ratatui::{
     layout::{Constraint, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
     Frame,
 };
 
-pub fn render(f: &mut Frame, app: &App, area: Rect) {
+pub fn render(f: &mut Frame, app: &mut App, area: Rect) {
+    // Scroll so the selected row stays inside the table (borders + header)
+    let page_rows = area.height.saturating_sub(3).max(1) as usize;
+    let selected = app
+        .visible_macros()
+        .iter()
+        .position(|&i| i == app.macro_list_index);
+    if let Some(pos) = selected {
+        app.macro_list_offset = scroll_offset(app.macro_list_offset, pos, page_rows);
+    }
+    app.list_page_rows = page_rows;
+    let app = &*app;
+
+    let theme = &app.theme;
     let macros = app.current_macros();
 
     if macros.is_empty() && app.editing_macro.is_none() {
         let msg = Paragraph::new(vec![
             Line::from("No macros configured for the active profile."),
             Line::from(""),
-            Line::from("Press 'a' to add a new macro."),
+            Line::from(format!(
+                "Press '{}' to add a new macro.",
+                app.keymap.key(Action::Add)
+            )),
             Line::from(""),
             Line::from("Macros can repeat clicks while a button is held,"),
             Line::from("play a sequence of key presses, or toggle repeating."),
         ])
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(" Macros (a=add, e=edit, d=delete, s=save config) "),
-        );
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " Macros ({}) ",
+            app.keymap.hint(&[
+                (Action::Add, "add"),
+                (Action::Edit, "edit"),
+                (Action::Delete, "delete"),
+                (Action::Save, "save config"),
+            ])
+        )));
         f.render_widget(msg, area);
     } else if app.editing_macro.is_none() {
-        let header_cells = ["Name", "Type", "Actions", "Interval", "Jitter"]
-            .iter()
-            .map(|h| {
-                Cell::from(*h).style(
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD),
-                )
-            });
+        let header_cells = [
+            "Name",
+            "Type",
+            "Status",
+            "Actions",
+            "Interval",
+            "Jitter",
+            "Description",
+        ]
+        .iter()
+        .map(|h| {
+            Cell::from(*h).style(
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            )
+        });
         let header = Row::new(header_cells).height(1);
 
-        let rows: Vec<Row> = macros
+        let visible = app.visible_macros();
+        let rows: Vec<Row> = visible
             .iter()
-            .map(|m| {
-                let type_str = match m.macro_type {
-                    MacroType::RepeatOnHold => "Repeat on Hold",
-                    MacroType::Sequence => "Sequence",
-                    MacroType::Toggle => "Toggle",
-                };
+            .map(|&i| {
+                let m = &macros[i];
+                let type_str = macro_type_name(&m.macro_type);
 
                 let actions_str = m
                     .actions
                     .iter()
-                    .map(|a| format!("{:?}", a))
+                    .map(describe_action)
                     .collect::<Vec<_>>()
                     .join(", ");
 
-                let interval = format!("{}ms", m.interval_ms);
+                let interval = if m.press_ms > 0 {
+                    // Turbo duty cycle: down/up
+                    let up = if m.release_ms > 0 {
+                        m.release_ms
+                    } else {
+                        m.interval_ms
+                    };
+                    format!("{}/{}ms", m.press_ms, up)
+                } else if !m.interval_pattern_ms.is_empty() {
+                    let gaps: Vec<String> =
+                        m.interval_pattern_ms.iter().map(u64::to_string).collect();
+                    format!("{}ms", gaps.join("/"))
+                } else {
+                    format!("{}ms", m.interval_ms)
+                };
                 let jitter = if m.jitter_ms > 0 {
                     format!("\u{00b1}{}ms", m.jitter_ms)
                 } else {
                     "off".to_string()
                 };
 
+                let activity = app.macro_activity.get(&m.name);
+                let status = match activity {
+                    Some(a) if a.error.is_some() => {
+                        Cell::from(format!("error: {}", a.error.as_deref().unwrap_or_default()))
+                            .style(Style::default().fg(theme.error))
+                    }
+                    Some(a) if a.running => Cell::from(format!("running #{}", a.iterations))
+                        .style(Style::default().fg(theme.success)),
+                    Some(a) => Cell::from(format!("ran {}x", a.iterations))
+                        .style(Style::default().fg(theme.muted)),
+                    None => Cell::from(""),
+                };
+
+                let running =
+                    app.running_macros.contains(&m.name) || activity.is_some_and(|a| a.running);
+                let name = if running {
+                    Cell::from(Line::from(vec![
+                        Span::styled(
+                            "\u{25cf} ",
+                            Style::default()
+                                .fg(theme.success)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(m.name.clone()),
+                    ]))
+                } else {
+                    Cell::from(format!("  {}", m.name))
+                };
+
                 Row::new(vec![
-                    Cell::from(m.name.clone()),
+                    name,
                     Cell::from(type_str),
+                    status,
                     Cell::from(actions_str),
                     Cell::from(interval),
                     Cell::from(jitter),
+                    Cell::from(m.description.clone().unwrap_or_default())
+                        .style(Style::default().fg(theme.muted)),
                 ])
             })
             .collect();
@@ -74,40 +150,201 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
         let widths = [
             Constraint::Length(20),
             Constraint::Length(16),
+            Constraint::Length(14),
             Constraint::Min(20),
             Constraint::Length(10),
             Constraint::Length(10),
+            Constraint::Min(16),
         ];
 
+        let filter_str = if app.list_filter_editing {
+            format!(" filter: {}_ ", app.macro_filter)
+        } else if !app.macro_filter.is_empty() {
+            format!(
+                " filter: {} ({}/{}) ",
+                app.macro_filter,
+                visible.len(),
+                macros.len()
+            )
+        } else {
+            String::new()
+        };
         let table = Table::new(rows, widths)
             .header(header)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title(" Macros (a=add, e=edit, d=delete, s=save config) "),
-            )
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                " Macros ({}){}",
+                app.keymap.hint(&[
+                    (Action::Add, "add"),
+                    (Action::Edit, "edit"),
+                    (Action::Delete, "delete"),
+                    (Action::Filter, "filter"),
+                    (Action::Save, "save config"),
+                ]),
+                filter_str
+            )))
             .row_highlight_style(
                 Style::default()
-                    .bg(Color::DarkGray)
+                    .bg(theme.selection)
                     .add_modifier(Modifier::BOLD),
             )
             .highlight_symbol(">> ");
 
-        let mut state = TableState::default();
-        state.select(Some(app.macro_list_index));
+        let mut state = TableState::default()
+            .with_offset(app.macro_list_offset)
+            .with_selected(selected);
 
         f.render_stateful_widget(table, area, &mut state);
     }
 
     // Render edit dialog if active
     if let Some(ref editing) = app.editing_macro {
-        render_edit_dialog(f, editing, area);
+        render_edit_dialog(f, app, editing, area);
+    }
+
+    if let Some(selected) = app.template_picker {
+        match app.template_values {
+            Some(ref values) => render_template_params(f, app, selected, values, area),
+            None => render_template_picker(f, app, selected, area),
+        }
     }
 }
 
-fn render_edit_dialog(f: &mut Frame, editing: &crate::tui::app::EditingMacro, area: Rect) {
+/// The form asking for the chosen template's parameters
+fn render_template_params(
+    f: &mut Frame,
+    app: &App,
+    selected: usize,
+    values: &[String],
+    area: Rect,
+) {
+    let theme = &app.theme;
+    let Some(template) = templates::all().get(selected) else {
+        return;
+    };
+
+    let mut lines = vec![Line::from("")];
+    for (i, (param, value)) in template.params.iter().zip(values).enumerate() {
+        let is_selected = i == app.template_field;
+        let value_style = if is_selected {
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.muted)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:<26}", format!("{}:", param.label)),
+                Style::default().fg(theme.highlight),
+            ),
+            Span::styled(format!("[{}]", value), value_style),
+            Span::raw(if is_selected { " <<" } else { "" }),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Keys and buttons by name, e.g. BTN_SIDE or v",
+        Style::default().fg(theme.hint),
+    )));
+    lines.push(Line::from(Span::styled(
+        "  Up/Down=field  Enter=create  Esc=back",
+        Style::default().fg(theme.hint),
+    )));
+
+    let dialog_width = 60.min(area.width.saturating_sub(4));
+    let dialog_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    f.render_widget(Clear, dialog_area);
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" New Macro: {} ", template.name))
+            .border_style(Style::default().fg(theme.accent)),
+    );
+
+    f.render_widget(paragraph, dialog_area);
+}
+
+fn render_template_picker(f: &mut Frame, app: &App, selected: usize, area: Rect) {
+    let theme = &app.theme;
+    let templates = templates::all();
+
+    let mut lines = vec![Line::from("")];
+    for (i, template) in templates.iter().enumerate() {
+        let is_selected = i == selected;
+        let prefix = if is_selected { "  > " } else { "    " };
+        let name_style = if is_selected {
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.muted)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}{:<14}", prefix, template.name), name_style),
+            Span::styled(template.description, Style::default().fg(theme.hint)),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Up/Down=select  Enter=use template  Esc=cancel",
+        Style::default().fg(theme.hint),
+    )));
+
+    let dialog_width = 70.min(area.width.saturating_sub(4));
+    let dialog_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    f.render_widget(Clear, dialog_area);
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" New Macro: choose a template ")
+            .border_style(Style::default().fg(theme.accent)),
+    );
+
+    f.render_widget(paragraph, dialog_area);
+}
+
+/// Number of steps visible at once in the step editor
+const VISIBLE_STEPS: usize = 8;
+
+/// Short human-readable description of a macro step
+pub fn describe_action(action: &MacroAction) -> String {
+    match action {
+        MacroAction::Click(ClickStep { key, hold_ms: 0 }) => format!("Click {}", key),
+        MacroAction::Click(ClickStep { key, hold_ms }) => {
+            format!("Click {}, hold {}ms", key, hold_ms)
+        }
+        MacroAction::Press(k) => format!("Press {}", k),
+        MacroAction::Release(k) => format!("Release {}", k),
+        MacroAction::Delay(ms) => format!("Delay {}ms", ms),
+        MacroAction::RunMacro(name) => format!("Run macro {}", name),
+        MacroAction::Choice(weight) => format!("-- Choice, weight {} --", weight),
+        MacroAction::SetMode { mode, set } => {
+            format!("Mode {} {}", mode, mode_switch_name(*set))
+        }
+        MacroAction::Obs(request) => format!("OBS: {}", request),
+        MacroAction::Notify(message) => format!("Notify \"{}\"", message),
+        MacroAction::Warp(target) => format!("Warp cursor to {}", target),
+    }
+}
+
+fn render_edit_dialog(f: &mut Frame, app: &App, editing: &EditingMacro, area: Rect) {
+    let theme = &app.theme;
+    let show_steps = editing.field_index == 2;
+    let steps_height = if show_steps {
+        editing.actions.len().clamp(1, VISIBLE_STEPS) as u16 + 3 // +3 for hint lines
+    } else {
+        0
+    };
     let dialog_width = 65.min(area.width.saturating_sub(4));
-    let dialog_height = 19.min(area.height.saturating_sub(4));
+    let dialog_height = (41 + steps_height).min(area.height.saturating_sub(4));
     let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
     let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
     let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
@@ -120,18 +357,13 @@ fn render_edit_dialog(f: &mut Frame, editing: &crate::tui::app::EditingMacro, ar
         " New Macro "
     };
 
-    let type_str = match editing.macro_type {
-        MacroType::RepeatOnHold => "Repeat on Hold",
-        MacroType::Sequence => "Sequence",
-        MacroType::Toggle => "Toggle",
-    };
+    let type_str = macro_type_name(&editing.macro_type);
 
-    let actions_str = editing
-        .actions
-        .iter()
-        .map(|a| format!("{:?}", a))
-        .collect::<Vec<_>>()
-        .join(", ");
+    let actions_str = format!(
+        "{} step{}",
+        editing.actions.len(),
+        if editing.actions.len() == 1 { "" } else { "s" }
+    );
 
     let field_indicator = |idx: usize| -> &str {
         if editing.field_index == idx {
@@ -141,10 +373,10 @@ fn render_edit_dialog(f: &mut Frame, editing: &crate::tui::app::EditingMacro, ar
         }
     };
 
-    let lines = vec![
+    let mut lines = vec![
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Name:     ", Style::default().fg(Color::Yellow)),
+            Span::styled("  Name:     ", Style::default().fg(theme.highlight)),
             Span::styled(
                 format!(
                     "[{}]",
@@ -155,71 +387,148 @@ fn render_edit_dialog(f: &mut Frame, editing: &crate::tui::app::EditingMacro, ar
                     }
                 ),
                 if editing.field_index == 0 {
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD)
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(Color::Gray)
+                    Style::default().fg(theme.muted)
                 },
             ),
             Span::raw(field_indicator(0)),
         ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Type:     ", Style::default().fg(Color::Yellow)),
+            Span::styled("  Type:     ", Style::default().fg(theme.highlight)),
             Span::styled(
                 format!("[{}]", type_str),
                 if editing.field_index == 1 {
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD)
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(Color::Gray)
+                    Style::default().fg(theme.muted)
                 },
             ),
             Span::raw(field_indicator(1)),
-            Span::styled("  (Tab to cycle)", Style::default().fg(Color::DarkGray)),
+            Span::styled("  (Tab to cycle)", Style::default().fg(theme.hint)),
         ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Actions:  ", Style::default().fg(Color::Yellow)),
+            Span::styled("  Actions:  ", Style::default().fg(theme.highlight)),
             Span::styled(
-                format!(
-                    "[{}]",
-                    if actions_str.is_empty() {
-                        "<add actions>"
-                    } else {
-                        &actions_str
-                    }
-                ),
+                format!("[{}]", actions_str),
                 if editing.field_index == 2 {
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD)
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(Color::Gray)
+                    Style::default().fg(theme.muted)
                 },
             ),
             Span::raw(field_indicator(2)),
+            if show_steps && !editing.actions_focused {
+                Span::styled("  (Enter to edit steps)", Style::default().fg(theme.hint))
+            } else {
+                Span::raw("")
+            },
         ]),
+    ];
+
+    if show_steps {
+        lines.extend(render_steps(app, editing));
+    }
+
+    lines.extend(vec![
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Interval: ", Style::default().fg(Color::Yellow)),
+            Span::styled("  Interval: ", Style::default().fg(theme.highlight)),
             Span::styled(
                 format!("[{}ms]", editing.interval_ms),
                 if editing.field_index == 3 {
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD)
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(Color::Gray)
+                    Style::default().fg(theme.muted)
                 },
             ),
             Span::raw(field_indicator(3)),
         ]),
         Line::from(""),
         Line::from(vec![
-            Span::styled("  Jitter:   ", Style::default().fg(Color::Yellow)),
+            Span::styled("  Pattern:  ", Style::default().fg(theme.highlight)),
+            Span::styled(
+                if editing.interval_pattern.is_empty() {
+                    "[off]".to_string()
+                } else {
+                    format!("[{}]ms", editing.interval_pattern)
+                },
+                if editing.field_index == EditingMacro::PATTERN_FIELD {
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme.muted)
+                },
+            ),
+            Span::raw(field_indicator(EditingMacro::PATTERN_FIELD)),
+            if editing.field_index == EditingMacro::PATTERN_FIELD {
+                Span::styled(
+                    "  (gaps used in turn instead of the interval)",
+                    Style::default().fg(theme.hint),
+                )
+            } else {
+                Span::raw("")
+            },
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Order:    ", Style::default().fg(theme.highlight)),
+            Span::styled(
+                if editing.shuffle_pattern {
+                    "[shuffled]"
+                } else {
+                    "[in turn]"
+                },
+                if editing.field_index == 5 {
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme.muted)
+                },
+            ),
+            Span::raw(field_indicator(5)),
+            if editing.field_index == 5 {
+                Span::styled(
+                    "  (Tab; shuffled = new random order each round)",
+                    Style::default().fg(theme.hint),
+                )
+            } else {
+                Span::raw("")
+            },
+        ]),
+        Line::from(""),
+        number_field(
+            app,
+            editing,
+            "  Ramp from:",
+            &editing.ramp_from_ms,
+            "ms",
+            6,
+            "  (first gap, shrinking to the interval; 0 = no ramp)",
+        ),
+        Line::from(""),
+        number_field(
+            app,
+            editing,
+            "  Ramp time:",
+            &editing.ramp_ms,
+            "ms",
+            7,
+            "  (how long the button is held to reach the interval)",
+        ),
+        Line::from(""),
+        number_field(
+            app,
+            editing,
+            "  Delay:    ",
+            &editing.initial_delay_ms,
+            "ms",
+            8,
+            "  (wait before the first repeat, 0 = none)",
+        ),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Jitter:   ", Style::default().fg(theme.highlight)),
             Span::styled(
                 format!(
                     "[\u{00b1}{}ms]",
@@ -229,37 +538,236 @@ fn render_edit_dialog(f: &mut Frame, editing: &crate::tui::app::EditingMacro, ar
                         &editing.jitter_ms
                     }
                 ),
-                if editing.field_index == 4 {
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD)
+                if editing.field_index == 9 {
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
                 } else {
-                    Style::default().fg(Color::Gray)
+                    Style::default().fg(theme.muted)
                 },
             ),
-            Span::raw(field_indicator(4)),
-            if editing.field_index == 4 {
+            Span::raw(field_indicator(9)),
+            if editing.field_index == 9 {
                 Span::styled(
                     "  (random timing variance)",
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(theme.hint),
+                )
+            } else {
+                Span::raw("")
+            },
+        ]),
+        Line::from(""),
+        number_field(
+            app,
+            editing,
+            "  Press:    ",
+            &editing.press_ms,
+            "ms",
+            10,
+            "  (key-down time per click, 0 = instant)",
+        ),
+        Line::from(""),
+        number_field(
+            app,
+            editing,
+            "  Release:  ",
+            &editing.release_ms,
+            "ms",
+            11,
+            "  (key-up time before repeating, 0 = interval)",
+        ),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Abort:    ", Style::default().fg(theme.highlight)),
+            Span::styled(
+                if editing.abort_on_release {
+                    "[on release]"
+                } else {
+                    "[never]"
+                },
+                if editing.field_index == 12 {
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme.muted)
+                },
+            ),
+            Span::raw(field_indicator(12)),
+            if editing.field_index == 12 {
+                Span::styled(
+                    "  (Tab; sequences stop when the button is released)",
+                    Style::default().fg(theme.hint),
+                )
+            } else {
+                Span::raw("")
+            },
+        ]),
+        Line::from(""),
+        number_field(
+            app,
+            editing,
+            "  Idle stop:",
+            &editing.idle_stop_min,
+            " min",
+            13,
+            "  (toggle stops after this long without input, 0 = never)",
+        ),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Params:   ", Style::default().fg(theme.highlight)),
+            Span::styled(
+                if editing.params.is_empty() {
+                    "[none]".to_string()
+                } else {
+                    format!("[{}]", editing.params)
+                },
+                if editing.field_index == EditingMacro::PARAMS_FIELD {
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme.muted)
+                },
+            ),
+            Span::raw(field_indicator(EditingMacro::PARAMS_FIELD)),
+            if editing.field_index == EditingMacro::PARAMS_FIELD {
+                Span::styled(
+                    "  (name=default, use {name} in keys)",
+                    Style::default().fg(theme.hint),
                 )
             } else {
                 Span::raw("")
             },
         ]),
         Line::from(""),
+        Line::from(vec![
+            Span::styled("  Description: ", Style::default().fg(theme.highlight)),
+            Span::styled(
+                if editing.description.is_empty() {
+                    "[none]".to_string()
+                } else {
+                    format!("[{}]", editing.description)
+                },
+                if editing.field_index == EditingMacro::DESCRIPTION_FIELD {
+                    Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme.muted)
+                },
+            ),
+            Span::raw(field_indicator(EditingMacro::DESCRIPTION_FIELD)),
+        ]),
+        Line::from(""),
         Line::from(Span::styled(
             "  Up/Down=navigate  Tab=cycle type  Enter=save  Esc=cancel",
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.hint),
         )),
-    ];
+    ]);
 
     let paragraph = Paragraph::new(lines).block(
         Block::default()
             .borders(Borders::ALL)
             .title(title)
-            .border_style(Style::default().fg(Color::Cyan)),
+            .border_style(Style::default().fg(theme.accent)),
     );
 
     f.render_widget(paragraph, dialog_area);
 }
+
+/// A numeric "[N<unit>]" field line; the hint shows only while it is focused
+fn number_field<'a>(
+    app: &App,
+    editing: &EditingMacro,
+    label: &'a str,
+    value: &str,
+    unit: &str,
+    index: usize,
+    hint: &'a str,
+) -> Line<'a> {
+    let theme = &app.theme;
+    let focused = editing.field_index == index;
+    Line::from(vec![
+        Span::styled(label, Style::default().fg(theme.highlight)),
+        Span::styled(
+            format!("[{}{}]", if value.is_empty() { "0" } else { value }, unit),
+            if focused {
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.muted)
+            },
+        ),
+        Span::raw(if focused { " <<" } else { "" }),
+        if focused {
+            Span::styled(hint, Style::default().fg(theme.hint))
+        } else {
+            Span::raw("")
+        },
+    ])
+}
+
+/// Render the scrollable step list shown under the Actions field
+fn render_steps<'a>(app: &App, editing: &EditingMacro) -> Vec<Line<'a>> {
+    let theme = &app.theme;
+    let mut lines = Vec::new();
+    let hint_style = Style::default().fg(theme.hint);
+    let (is_capturing, is_inserting) = match &app.input_mode {
+        InputMode::Capturing {
+            field: CaptureField::MacroInsert { .. },
+        } => (false, true),
+        InputMode::Capturing { .. } => (true, false),
+        _ => (false, false),
+    };
+    let waiting = "[Waiting for key/button press... (Esc to cancel)]";
+
+    if editing.actions.is_empty() {
+        let text = if is_inserting {
+            format!("  +  1. {}", waiting)
+        } else {
+            "    <no steps -- Enter, then 'a' to add or 'c' to capture>".to_string()
+        };
+        lines.push(Line::from(Span::styled(text, hint_style)));
+    } else {
+        // Scroll so the selected step stays visible
+        let start = editing
+            .action_index
+            .saturating_sub(VISIBLE_STEPS - 1)
+            .min(editing.actions.len().saturating_sub(VISIBLE_STEPS));
+        let end = (start + VISIBLE_STEPS).min(editing.actions.len());
+
+        for (i, action) in editing.actions[start..end].iter().enumerate() {
+            let i = start + i;
+            let is_selected = editing.actions_focused && i == editing.action_index;
+            let text = if is_selected && is_capturing {
+                waiting.to_string()
+            } else {
+                describe_action(action)
+            };
+            let prefix = if is_selected { "  > " } else { "    " };
+            let style = if is_selected {
+                Style::default()
+                    .fg(theme.accent)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.muted)
+            };
+            lines.push(Line::from(Span::styled(
+                format!("{}{:>2}. {}", prefix, i + 1, text),
+                style,
+            )));
+            if is_selected && is_inserting {
+                lines.push(Line::from(Span::styled(
+                    format!("  + {:>2}. {}", i + 2, waiting),
+                    Style::default().fg(theme.accent),
+                )));
+            }
+        }
+    }
+
+    let hints = if editing.actions_focused {
+        [
+            "    a=add c/p=capture r=run macro d=del J/K=move Tab=kind",
+            "    Enter=set key v=param 0-9=delay/hold/weight Esc=back",
+        ]
+    } else {
+        ["", ""]
+    };
+    for hint in hints {
+        lines.push(Line::from(Span::styled(hint, hint_style)));
+    }
+    lines.push(Line::from(""));
+    lines
+}
@@ -1,4 +1,6 @@
 pub mod bindings;
 pub mod devices;
+pub mod hardware;
+pub mod logs;
 pub mod macros;
 pub mod monitor;
@@ -1,96 +1,133 @@
 use crate::tui::app::{App, EngineMessage};
+use crate::tui::keymap::Action;
+use crate::tui::theme::Theme;
 use ratatui::{
-    layout::Rect,
-    style::{Color, Modifier, Style},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
 
 pub fn render(f: &mut Frame, app: &App, area: Rect) {
-    let title = if app.monitor_paused {
-        " Monitor [PAUSED] (p=toggle pause, c=clear) "
-    } else {
-        " Monitor [LIVE] (p=toggle pause, c=clear) "
-    };
+    let title = format!(
+        " Monitor [{}]{} ({}) ",
+        if app.monitor_paused { "PAUSED" } else { "LIVE" },
+        if app.monitor_hid.is_some() {
+            " +HID"
+        } else {
+            ""
+        },
+        app.keymap.hint(&[
+            (Action::PauseMonitor, "toggle pause"),
+            (Action::Clear, "clear"),
+            (Action::HidReports, "HID reports"),
+            (Action::SplitMonitor, "split"),
+        ])
+    );
 
     if app.monitor_events.is_empty() {
         let msg = Paragraph::new(vec![
             Line::from("No events captured yet."),
             Line::from(""),
-            Line::from("Start the engine (Space on Devices tab) to see live events."),
+            Line::from(format!(
+                "Start the engine ({} on Devices tab) to see live events.",
+                app.keymap.key(Action::Engine)
+            )),
             Line::from("This shows all raw input events from the grabbed device."),
             Line::from(""),
             Line::from("Useful for finding button codes for your mouse."),
+            Line::from(format!(
+                "Press {} to also show the raw HID reports of the selected device,",
+                app.keymap.key(Action::HidReports)
+            )),
+            Line::from("including buttons that never show up as input events."),
+            Line::from(format!(
+                "Press {} to give every device its own pane.",
+                app.keymap.key(Action::SplitMonitor)
+            )),
         ])
         .block(Block::default().borders(Borders::ALL).title(title));
         f.render_widget(msg, area);
         return;
     }
 
-    // Show the most recent events that fit in the area
-    let visible_height = area.height.saturating_sub(2) as usize; // account for borders
-    let start = if app.monitor_events.len() > visible_height {
-        app.monitor_events.len() - visible_height
-    } else {
-        0
-    };
+    let devices = monitor_devices(app);
+    if !app.monitor_split || devices.len() < 2 {
+        let events: Vec<&EngineMessage> = app.monitor_events.iter().collect();
+        render_pane(f, app, area, title, &events);
+        return;
+    }
 
-    let lines: Vec<Line> = app.monitor_events[start..]
-        .iter()
-        .map(|msg| match msg {
-            EngineMessage::RawEvent {
-                event_type,
-                code,
-                value,
-                timestamp,
-            } => {
-                let color = if event_type.contains("KEY") {
-                    if *value == 1 {
-                        Color::Green
-                    } else if *value == 0 {
-                        Color::Red
-                    } else {
-                        Color::Yellow
-                    }
-                } else if event_type.contains("REL") {
-                    Color::Cyan
-                } else {
-                    Color::DarkGray
-                };
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![
+            Constraint::Ratio(1, devices.len() as u32);
+            devices.len()
+        ])
+        .split(area);
+    for (device, column) in devices.iter().zip(columns.iter()) {
+        // Messages that belong to no device (macros, modes, ...) show in every pane
+        let events: Vec<&EngineMessage> = app
+            .monitor_events
+            .iter()
+            .filter(|msg| message_device(app, msg).is_none_or(|d| d == *device))
+            .collect();
+        let name = app
+            .devices
+            .iter()
+            .find(|d| d.path.to_str() == Some(device))
+            .map(|d| d.name.as_str())
+            .unwrap_or("?");
+        let title = format!(
+            " [{}] {} ({}) ",
+            if app.monitor_paused { "PAUSED" } else { "LIVE" },
+            name,
+            device
+        );
+        render_pane(f, app, *column, title, &events);
+    }
+}
 
-                let value_str = match *value {
-                    0 => "UP  ".to_string(),
-                    1 => "DOWN".to_string(),
-                    2 => "REPT".to_string(),
-                    v => format!("{:4}", v),
-                };
+/// Devices the monitor has a pane for: the ones the engine runs on and any
+/// other that sent events still in the buffer, by path
+fn monitor_devices(app: &App) -> Vec<&str> {
+    let mut devices: Vec<&str> = app
+        .engine_devices
+        .keys()
+        .filter_map(|p| p.to_str())
+        .chain(app.monitor_events.iter().filter_map(|msg| match msg {
+            EngineMessage::RawEvent { device, .. } => Some(device.as_str()),
+            _ => None,
+        }))
+        .collect();
+    devices.sort_unstable();
+    devices.dedup();
+    devices
+}
 
-                Line::from(vec![
-                    Span::styled(
-                        format!("{} ", timestamp),
-                        Style::default().fg(Color::DarkGray),
-                    ),
-                    Span::styled(
-                        format!("{:12} ", event_type),
-                        Style::default().fg(Color::Yellow),
-                    ),
-                    Span::styled(format!("{:20} ", code), Style::default().fg(color)),
-                    Span::styled(
-                        value_str,
-                        Style::default().fg(color).add_modifier(Modifier::BOLD),
-                    ),
-                ])
-            }
-            EngineMessage::StatusUpdate(s) => Line::from(Span::styled(
-                format!("  [STATUS] {}", s),
-                Style::default().fg(Color::Blue),
-            )),
-            EngineMessage::Error(e) => Line::from(Span::styled(
-                format!("  [ERROR] {}", e),
-                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-            )),
-        })
+/// Path of the device a monitor message is about, if it's about one
+fn message_device<'a>(app: &'a App, msg: &'a EngineMessage) -> Option<&'a str> {
+    match msg {
+        EngineMessage::RawEvent { device, .. } => Some(device),
+        EngineMessage::HidReport { .. } => app.monitor_hid.as_deref().and_then(|p| p.to_str()),
+        EngineMessage::State(state) => Some(&state.device),
+        EngineMessage::ConfigWarnings { device, .. } => Some(device),
+        EngineMessage::DeviceWaiting(path)
+        | EngineMessage::EngineRestart { path, .. }
+        | EngineMessage::DeviceError { path, .. } => Some(path),
+        _ => None,
+    }
+}
+
+/// One bordered list of the most recent events that fit in the area
+fn render_pane(f: &mut Frame, app: &App, area: Rect, title: String, events: &[&EngineMessage]) {
+    let theme = &app.theme;
+    let visible_height = area.height.saturating_sub(2) as usize; // account for borders
+    let start = events.len().saturating_sub(visible_height);
+    let lines: Vec<Line> = events[start..]
+        .iter()
+        .map(|msg| message_line(msg, theme))
         .collect();
 
     let paragraph = Paragraph::new(lines).block(
@@ -98,11 +135,157 @@ pub fn render(f: &mut Frame, app: &App, area: Rect) {
             .borders(Borders::ALL)
             .title(title)
             .border_style(if app.monitor_paused {
-                Style::default().fg(Color::Yellow)
+                Style::default().fg(theme.highlight)
             } else {
-                Style::default().fg(Color::Green)
+                Style::default().fg(theme.success)
             }),
     );
 
     f.render_widget(paragraph, area);
 }
+
+fn message_line(msg: &EngineMessage, theme: &Theme) -> Line<'static> {
+    match msg {
+        EngineMessage::RawEvent {
+            event_type,
+            code,
+            value,
+            timestamp,
+            ..
+        } => {
+            let color = if event_type.contains("KEY") {
+                if *value == 1 {
+                    theme.success
+                } else if *value == 0 {
+                    theme.error
+                } else {
+                    theme.highlight
+                }
+            } else if event_type.contains("REL") {
+                theme.accent
+            } else {
+                theme.hint
+            };
+
+            let value_str = match *value {
+                0 => "UP  ".to_string(),
+                1 => "DOWN".to_string(),
+                2 => "REPT".to_string(),
+                v => format!("{:4}", v),
+            };
+
+            Line::from(vec![
+                Span::styled(format!("{} ", timestamp), Style::default().fg(theme.hint)),
+                Span::styled(
+                    format!("{:12} ", event_type),
+                    Style::default().fg(theme.highlight),
+                ),
+                Span::styled(format!("{:20} ", code), Style::default().fg(color)),
+                Span::styled(
+                    value_str,
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                ),
+            ])
+        }
+        EngineMessage::HidReport {
+            hidraw,
+            report,
+            timestamp,
+        } => {
+            let hex: Vec<String> = report.iter().map(|b| format!("{:02x}", b)).collect();
+            Line::from(vec![
+                Span::styled(format!("{} ", timestamp), Style::default().fg(theme.hint)),
+                Span::styled(
+                    format!("{:12} ", "HID"),
+                    Style::default().fg(theme.highlight),
+                ),
+                Span::styled(format!("{:20} ", hidraw), Style::default().fg(theme.info)),
+                Span::styled(hex.join(" "), Style::default().fg(theme.text)),
+            ])
+        }
+        EngineMessage::StatusUpdate(s) => Line::from(Span::styled(
+            format!("  [STATUS] {}", s),
+            Style::default().fg(theme.info),
+        )),
+        EngineMessage::DpiStage(stage) => Line::from(Span::styled(
+            match stage {
+                Some((index, count, multiplier)) => {
+                    format!("  [DPI] stage {}/{} (x{})", index + 1, count, multiplier)
+                }
+                None => "  [DPI] no stages".to_string(),
+            },
+            Style::default().fg(theme.info),
+        )),
+        EngineMessage::Macro(event) => Line::from(Span::styled(
+            format!("  [MACRO] {:?}", event),
+            Style::default().fg(theme.info),
+        )),
+        EngineMessage::RunningMacros(names) => Line::from(Span::styled(
+            if names.is_empty() {
+                "  [MACRO] none running".to_string()
+            } else {
+                format!("  [MACRO] running: {}", names.join(", "))
+            },
+            Style::default().fg(theme.info),
+        )),
+        EngineMessage::Modes(modes) => Line::from(Span::styled(
+            if modes.is_empty() {
+                "  [MODE] none on".to_string()
+            } else {
+                format!("  [MODE] on: {}", modes.join(", "))
+            },
+            Style::default().fg(theme.info),
+        )),
+        EngineMessage::ConfigWarnings { device, warnings } => Line::from(Span::styled(
+            format!("  [WARN] {}: {}", device, warnings.join("; ")),
+            Style::default().fg(theme.error),
+        )),
+        EngineMessage::State(state) => Line::from(Span::styled(
+            format!(
+                "  [STATE] {} ({}): {}, profile {}, {} binding(s), {} macro(s)",
+                state.device_name,
+                state.device,
+                if state.grabbed {
+                    "grabbed"
+                } else {
+                    "not grabbed"
+                },
+                state.profile.as_deref().unwrap_or("(none)"),
+                state.bindings_loaded,
+                state.macros_loaded
+            ),
+            Style::default().fg(theme.info),
+        )),
+        EngineMessage::DeviceWaiting(path) => Line::from(Span::styled(
+            format!("  [STATUS] {} unplugged, waiting for it", path),
+            Style::default().fg(theme.info),
+        )),
+        EngineMessage::ProfileSwitched(name) => Line::from(Span::styled(
+            format!("  [STATUS] switched to profile {}", name),
+            Style::default().fg(theme.info),
+        )),
+        EngineMessage::Terminate(signal) => Line::from(Span::styled(
+            format!("  [STATUS] got {}, quitting", signal),
+            Style::default().fg(theme.info),
+        )),
+        EngineMessage::EngineRestart {
+            path,
+            attempt,
+            max_attempts,
+            delay_secs,
+            error,
+        } => Line::from(Span::styled(
+            format!(
+                "  [RESTART] {} failed ({}); attempt {}/{} in {}s",
+                path, error, attempt, max_attempts, delay_secs
+            ),
+            Style::default().fg(theme.error),
+        )),
+        EngineMessage::DeviceError { path, error } => Line::from(Span::styled(
+            format!("  [ERROR] {}: {}", path, error),
+            Style::default()
+                .fg(theme.error)
+                .add_modifier(Modifier::BOLD),
+        )),
+    }
+}
@@ -0,0 +1,245 @@
+use crate::config::{ClickStep, MacroAction, MacroDef, MacroType};
+use crate::engine::mapper::{key_name, parse_key_name};
+use std::collections::BTreeMap;
+
+/// A built-in starting point for a new macro
+pub struct MacroTemplate {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// What the add dialog asks for before building the macro
+    pub params: &'static [TemplateParam],
+    build: fn(&TemplateArgs) -> MacroDef,
+}
+
+/// A value a template is built with
+pub struct TemplateParam {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub default: &'static str,
+    pub kind: ParamKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    /// A key or button name, e.g. "BTN_LEFT" or "v"
+    Key,
+    /// A number of milliseconds
+    Millis,
+}
+
+/// The checked values of a template's parameters, by name
+#[derive(Default)]
+struct TemplateArgs {
+    keys: BTreeMap<&'static str, String>,
+    millis: BTreeMap<&'static str, u64>,
+}
+
+impl TemplateArgs {
+    fn key(&self, name: &str) -> String {
+        self.keys.get(name).cloned().unwrap_or_default()
+    }
+
+    fn ms(&self, name: &str) -> u64 {
+        self.millis.get(name).copied().unwrap_or_default()
+    }
+}
+
+impl MacroTemplate {
+    /// Build the macro from `values` for its params, in order; the defaults
+    /// stand in for missing or empty ones
+    pub fn build(&self, values: &[String]) -> Result<MacroDef, String> {
+        let mut args = TemplateArgs::default();
+        for (i, param) in self.params.iter().enumerate() {
+            let value = values
+                .get(i)
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .unwrap_or(param.default);
+            match param.kind {
+                ParamKind::Key => {
+                    let key = parse_key_name(value)
+                        .ok_or_else(|| format!("{}: unknown key '{}'", param.label, value))?;
+                    args.keys.insert(param.name, key_name(key));
+                }
+                ParamKind::Millis => {
+                    let ms = value.parse().map_err(|_| {
+                        format!("{}: '{}' is not a number of ms", param.label, value)
+                    })?;
+                    args.millis.insert(param.name, ms);
+                }
+            }
+        }
+        Ok((self.build)(&args))
+    }
+}
+
+const BUTTON: TemplateParam = TemplateParam {
+    name: "button",
+    label: "Button to click",
+    default: "BTN_LEFT",
+    kind: ParamKind::Key,
+};
+
+const fn interval(default: &'static str) -> TemplateParam {
+    TemplateParam {
+        name: "interval",
+        label: "Interval (ms)",
+        default,
+        kind: ParamKind::Millis,
+    }
+}
+
+/// All templates offered in the Macros tab's add dialog
+pub fn all() -> &'static [MacroTemplate] {
+    TEMPLATES
+}
+
+static TEMPLATES: &[MacroTemplate] = &[
+    MacroTemplate {
+        name: "Blank",
+        description: "Click while held, start from scratch",
+        params: &[BUTTON, interval("50")],
+        build: blank,
+    },
+    MacroTemplate {
+        name: "Auto-clicker",
+        description: "Press once to start clicking, again to stop",
+        params: &[BUTTON, interval("100")],
+        build: auto_clicker,
+    },
+    MacroTemplate {
+        name: "Double-click",
+        description: "Clean double-click from one press (worn switches)",
+        params: &[
+            BUTTON,
+            TemplateParam {
+                name: "gap",
+                label: "Gap between clicks (ms)",
+                default: "40",
+                kind: ParamKind::Millis,
+            },
+        ],
+        build: double_click,
+    },
+    MacroTemplate {
+        name: "Rapid-fire",
+        description: "Fast clicks while the button is held",
+        params: &[BUTTON, interval("30")],
+        build: rapid_fire,
+    },
+    MacroTemplate {
+        name: "Copy-paste",
+        description: "Ctrl+C, short pause, Ctrl+V",
+        params: &[TemplateParam {
+            name: "pause",
+            label: "Pause before pasting (ms)",
+            default: "50",
+            kind: ParamKind::Millis,
+        }],
+        build: copy_paste,
+    },
+    MacroTemplate {
+        name: "Push-to-talk",
+        description: "Hold a key down for as long as the button is held",
+        params: &[TemplateParam {
+            name: "key",
+            label: "Key to hold",
+            default: "KEY_V",
+            kind: ParamKind::Key,
+        }],
+        build: push_to_talk,
+    },
+];
+
+fn macro_def(
+    name: &str,
+    macro_type: MacroType,
+    actions: Vec<MacroAction>,
+    interval_ms: u64,
+    jitter_ms: u64,
+) -> MacroDef {
+    MacroDef {
+        name: name.to_string(),
+        macro_type,
+        actions,
+        interval_ms,
+        jitter_ms,
+        ..MacroDef::default()
+    }
+}
+
+fn blank(args: &TemplateArgs) -> MacroDef {
+    macro_def(
+        "",
+        MacroType::RepeatOnHold,
+        vec![MacroAction::Click(ClickStep::new(args.key("button")))],
+        args.ms("interval"),
+        10,
+    )
+}
+
+fn auto_clicker(args: &TemplateArgs) -> MacroDef {
+    macro_def(
+        "auto_clicker",
+        MacroType::Toggle,
+        vec![MacroAction::Click(ClickStep::new(args.key("button")))],
+        args.ms("interval"),
+        15,
+    )
+}
+
+fn double_click(args: &TemplateArgs) -> MacroDef {
+    let button = args.key("button");
+    macro_def(
+        "double_click",
+        MacroType::Sequence,
+        vec![
+            MacroAction::Click(ClickStep::new(button.clone())),
+            MacroAction::Delay(args.ms("gap")),
+            MacroAction::Click(ClickStep::new(button)),
+        ],
+        50,
+        0,
+    )
+}
+
+fn rapid_fire(args: &TemplateArgs) -> MacroDef {
+    macro_def(
+        "rapid_fire",
+        MacroType::RepeatOnHold,
+        vec![MacroAction::Click(ClickStep::new(args.key("button")))],
+        args.ms("interval"),
+        5,
+    )
+}
+
+fn copy_paste(args: &TemplateArgs) -> MacroDef {
+    macro_def(
+        "copy_paste",
+        MacroType::Sequence,
+        vec![
+            MacroAction::Press("KEY_LEFTCTRL".to_string()),
+            MacroAction::Click(ClickStep::new("KEY_C")),
+            MacroAction::Release("KEY_LEFTCTRL".to_string()),
+            MacroAction::Delay(args.ms("pause")),
+            MacroAction::Press("KEY_LEFTCTRL".to_string()),
+            MacroAction::Click(ClickStep::new("KEY_V")),
+            MacroAction::Release("KEY_LEFTCTRL".to_string()),
+        ],
+        50,
+        0,
+    )
+}
+
+fn push_to_talk(args: &TemplateArgs) -> MacroDef {
+    // One press holds the key for as long as the trigger button is: the
+    // presses of the later rounds change nothing (the key is already down),
+    // and the engine releases it when the macro stops.
+    macro_def(
+        "push_to_talk",
+        MacroType::RepeatOnHold,
+        vec![MacroAction::Press(args.key("key"))],
+        250,
+        0,
+    )
+}
@@ -0,0 +1,117 @@
+use crate::config::{Profile, ThemeConfig};
+use ratatui::style::{Color, Modifier, Style};
+use std::str::FromStr;
+
+/// Resolved TUI color palette
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub accent: Color,
+    pub highlight: Color,
+    pub success: Color,
+    pub error: Color,
+    pub info: Color,
+    pub text: Color,
+    pub muted: Color,
+    pub hint: Color,
+    pub selection: Color,
+}
+
+impl Theme {
+    /// The original palette, meant for dark terminal backgrounds
+    pub fn dark() -> Self {
+        Self {
+            accent: Color::Cyan,
+            highlight: Color::Yellow,
+            success: Color::Green,
+            error: Color::Red,
+            info: Color::Blue,
+            text: Color::White,
+            muted: Color::Gray,
+            hint: Color::DarkGray,
+            selection: Color::DarkGray,
+        }
+    }
+
+    /// For light terminal backgrounds where white/gray text disappears
+    pub fn light() -> Self {
+        Self {
+            accent: Color::Blue,
+            highlight: Color::Magenta,
+            success: Color::Green,
+            error: Color::Red,
+            info: Color::Blue,
+            text: Color::Black,
+            muted: Color::Indexed(238),
+            hint: Color::Indexed(242),
+            selection: Color::Indexed(153),
+        }
+    }
+
+    /// Bright colors only, no dim grays
+    pub fn high_contrast() -> Self {
+        Self {
+            accent: Color::LightCyan,
+            highlight: Color::LightYellow,
+            success: Color::LightGreen,
+            error: Color::LightRed,
+            info: Color::LightBlue,
+            text: Color::White,
+            muted: Color::White,
+            hint: Color::Gray,
+            selection: Color::Blue,
+        }
+    }
+
+    /// Build the palette from the `[theme]` config section
+    pub fn from_config(config: &ThemeConfig) -> Self {
+        let mut theme = match config.preset.as_deref() {
+            None | Some("dark") | Some("default") => Self::dark(),
+            Some("light") => Self::light(),
+            Some("high-contrast") => Self::high_contrast(),
+            Some(other) => {
+                log::warn!("Unknown theme preset '{}', using dark", other);
+                Self::dark()
+            }
+        };
+
+        let overrides = [
+            (&config.accent, &mut theme.accent),
+            (&config.highlight, &mut theme.highlight),
+            (&config.success, &mut theme.success),
+            (&config.error, &mut theme.error),
+            (&config.info, &mut theme.info),
+            (&config.text, &mut theme.text),
+            (&config.muted, &mut theme.muted),
+            (&config.hint, &mut theme.hint),
+            (&config.selection, &mut theme.selection),
+        ];
+        for (value, slot) in overrides {
+            if let Some(value) = value {
+                match Color::from_str(value) {
+                    Ok(color) => *slot = color,
+                    Err(_) => log::warn!("Invalid theme color '{}', ignoring", value),
+                }
+            }
+        }
+
+        theme
+    }
+
+    /// Style of a profile's name: its own color in bold if it sets a valid
+    /// one, the accent color otherwise
+    pub fn profile_style(&self, profile: Option<&Profile>) -> Style {
+        match profile
+            .and_then(|p| p.color.as_deref())
+            .and_then(|c| Color::from_str(c).ok())
+        {
+            Some(color) => Style::default().fg(color).add_modifier(Modifier::BOLD),
+            None => Style::default().fg(self.accent),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
@@ -1,4 +1,8 @@
-use crate::tui::app::{App, Tab};
+use crate::engine::macros;
+use crate::tui::app::{App, InputMode, SavePreview, Severity, Tab};
+use crate::tui::keymap::Action;
+use crate::tui::palette;
+use crate::tui::theme::Theme;
 use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
@@ -9,15 +13,16 @@ use ratatui::{
 
 /// Render the top tab bar
 pub fn render_tabs(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let titles: Vec<Line> = Tab::all()
         .iter()
         .map(|t| {
             let style = if *t == app.current_tab {
                 Style::default()
-                    .fg(Color::Cyan)
+                    .fg(theme.accent)
                     .add_modifier(Modifier::BOLD)
             } else {
-                Style::default().fg(Color::Gray)
+                Style::default().fg(theme.muted)
             };
             Line::from(Span::styled(t.title(), style))
         })
@@ -28,16 +33,25 @@ pub fn render_tabs(f: &mut Frame, app: &App, area: Rect) {
         .position(|t| *t == app.current_tab)
         .unwrap_or(0);
 
+    // The active profile in its color on the right, to tell it at a glance
+    let profile = app.config.active_profile();
+    let profile_title = Line::from(Span::styled(
+        format!(" {} ", profile.map(|p| p.label()).unwrap_or_default()),
+        theme.profile_style(profile),
+    ))
+    .right_aligned();
+
     let tabs = Tabs::new(titles)
         .select(selected)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" Mouse Mapper "),
+                .title(" Mouse Mapper ")
+                .title(profile_title),
         )
         .highlight_style(
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme.accent)
                 .add_modifier(Modifier::BOLD),
         )
         .divider(Span::raw(" | "));
@@ -47,12 +61,26 @@ pub fn render_tabs(f: &mut Frame, app: &App, area: Rect) {
 
 /// Render the bottom status bar
 pub fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
-    let engine_status = if app.engine_running {
+    let theme = &app.theme;
+    let status_color = match app.notifications.back().map(|n| n.severity) {
+        Some(Severity::Error) => theme.error,
+        Some(Severity::Warning) => theme.highlight,
+        _ => theme.text,
+    };
+    let engine_status = if app.engine_observing {
+        Span::styled(
+            " ENGINE: OBSERVING ",
+            Style::default()
+                .fg(Color::Black)
+                .bg(theme.highlight)
+                .add_modifier(Modifier::BOLD),
+        )
+    } else if app.engine_running {
         Span::styled(
             " ENGINE: RUNNING ",
             Style::default()
                 .fg(Color::Black)
-                .bg(Color::Green)
+                .bg(theme.success)
                 .add_modifier(Modifier::BOLD),
         )
     } else {
@@ -60,7 +88,7 @@ pub fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
             " ENGINE: STOPPED ",
             Style::default()
                 .fg(Color::White)
-                .bg(Color::Red)
+                .bg(theme.error)
                 .add_modifier(Modifier::BOLD),
         )
     };
@@ -68,29 +96,135 @@ pub fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
     let device_info = if let Some(ref device) = app.selected_device {
         Span::styled(
             format!(" Device: {} ", device.name),
-            Style::default().fg(Color::Green),
+            Style::default().fg(theme.success),
         )
     } else {
-        Span::styled(" No device selected ", Style::default().fg(Color::Yellow))
+        Span::styled(" No device selected ", Style::default().fg(theme.highlight))
+    };
+
+    let battery = match app.selected_battery() {
+        Some(info) => Span::styled(
+            format!(
+                " | Battery: {}%{}",
+                info.percent,
+                if info.charging { " (charging)" } else { "" }
+            ),
+            if info.is_low() {
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text)
+            },
+        ),
+        None => Span::raw(""),
+    };
+
+    let dpi = match app.dpi_stage {
+        Some((index, count, multiplier)) => Span::styled(
+            format!(" | DPI {}/{} (x{})", index + 1, count, multiplier),
+            Style::default().fg(theme.info),
+        ),
+        None => Span::raw(""),
+    };
+
+    let modes = if app.active_modes.is_empty() {
+        Span::raw("")
+    } else {
+        Span::styled(
+            format!(" | Modes: {}", app.active_modes.join(",")),
+            Style::default().fg(theme.info),
+        )
     };
 
-    let profile_name = app
-        .config
-        .active_profile()
-        .map(|p| p.name.clone())
+    let paused = macros::paused();
+    let speed = Span::styled(
+        format!(
+            " | Macros x{}{}",
+            app.config.macro_speed,
+            if paused { " (paused)" } else { "" }
+        ),
+        if !paused && (app.config.macro_speed - 1.0).abs() < f64::EPSILON {
+            Style::default().fg(theme.muted)
+        } else {
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD)
+        },
+    );
+
+    let running = if app.running_macros.is_empty() {
+        Span::raw("")
+    } else {
+        Span::styled(
+            format!(
+                " | Running {}: {}",
+                app.running_macros.len(),
+                app.running_macros.join(",")
+            ),
+            Style::default()
+                .fg(theme.success)
+                .add_modifier(Modifier::BOLD),
+        )
+    };
+
+    let profile = app.config.active_profile();
+    let profile_name = profile
+        .map(|p| p.label())
         .unwrap_or_else(|| "None".to_string());
 
+    // What the engine reported loading, flagged when it isn't what the
+    // profile has enabled (e.g. edits the engine hasn't picked up)
+    let profile_bindings = profile.map_or(0, |p| p.enabled_bindings().count());
+    let bindings = match app
+        .engine_states
+        .values()
+        .map(|(state, _)| state.bindings_loaded)
+        .max()
+    {
+        Some(loaded) if loaded != profile_bindings => Span::styled(
+            format!(" | {}/{} bindings loaded", loaded, profile_bindings),
+            Style::default()
+                .fg(theme.highlight)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Some(loaded) => Span::styled(
+            format!(" | {} bindings loaded", loaded),
+            Style::default().fg(theme.success),
+        ),
+        None => Span::styled(
+            format!(" | {} bindings", profile_bindings),
+            Style::default().fg(theme.muted),
+        ),
+    };
+
     let status = Line::from(vec![
         engine_status,
         Span::raw(" "),
         device_info,
+        battery,
+        dpi,
+        modes,
+        speed,
+        running,
         Span::raw(" | "),
         Span::styled(
             format!("Profile: {}", profile_name),
-            Style::default().fg(Color::Cyan),
+            theme.profile_style(profile),
         ),
+        if app.is_dirty() {
+            Span::styled(
+                " *",
+                Style::default()
+                    .fg(theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Span::raw("")
+        },
+        bindings,
         Span::raw(" | "),
-        Span::styled(&app.status_message, Style::default().fg(Color::White)),
+        Span::styled(&app.status_message, Style::default().fg(status_color)),
     ]);
 
     let paragraph = Paragraph::new(status).block(Block::default().borders(Borders::TOP));
@@ -98,71 +232,586 @@ pub fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, area);
 }
 
-/// Render a help overlay
-pub fn render_help(f: &mut Frame, area: Rect) {
-    let help_text = vec![
+/// Shortcut sections of the help overlay: a title and (keys, description) rows
+type HelpSection = (&'static str, &'static [(&'static str, &'static str)]);
+
+/// Main screen sections: their keys come from the keymap, so each row names
+/// the actions instead
+type ActionHelpSection = (&'static str, &'static [(&'static [Action], &'static str)]);
+
+const HELP_GLOBAL: ActionHelpSection = (
+    "Global",
+    &[
+        (&[Action::PrevTab, Action::NextTab], "Switch tabs"),
+        (&[Action::Quit], "Quit (asks to save if unsaved)"),
+        (
+            &[Action::Save],
+            "Save config to disk, after showing the diff",
+        ),
+        (&[Action::Help], "Toggle this help (F1 also in dialogs)"),
+        (&[Action::Notifications], "Message history"),
+        (
+            &[Action::CommandPalette],
+            "Command palette: search all actions",
+        ),
+        (
+            &[Action::MacroSlower, Action::MacroFaster],
+            "Slow down / speed up all macros",
+        ),
+        (&[Action::PauseMacros], "Pause / resume running macros"),
+    ],
+);
+
+const HELP_DEVICES: ActionHelpSection = (
+    "Devices Tab",
+    &[
+        (&[Action::Up, Action::Down], "Navigate device list"),
+        (&[Action::Select], "Select device"),
+        (&[Action::Tag], "Tag device (select grabs all tagged)"),
+        (&[Action::Engine], "Start/stop engine"),
+        (
+            &[Action::Observe],
+            "Observe only: read without grab, no mapping",
+        ),
+        (&[Action::Refresh], "Refresh device list"),
+    ],
+);
+
+const HELP_BINDINGS: ActionHelpSection = (
+    "Bindings Tab",
+    &[
+        (&[Action::Up, Action::Down], "Navigate list"),
+        (&[Action::PageUp, Action::PageDown], "Page through the list"),
+        (&[Action::Top, Action::Bottom], "Jump to the first / last"),
+        (&[Action::Add], "Add new binding"),
+        (&[Action::Wizard], "Guided new binding, step by step"),
+        (&[Action::BulkEditor], "Bulk editor for button grids"),
+        (&[Action::Edit], "Edit selected binding"),
+        (&[Action::Delete], "Delete selected binding"),
+        (
+            &[Action::Filter],
+            "Filter the list (Esc while typing clears it)",
+        ),
+        (
+            &[Action::Import],
+            "Import bindings (input-remapper/xbindkeys)",
+        ),
+        (&[Action::SwapButtons], "Toggle left-handed button swap"),
+        (
+            &[Action::TagFilter],
+            "Show only one tag's bindings (cycles)",
+        ),
+        (
+            &[Action::ToggleTag],
+            "Enable / disable the shown tag's bindings",
+        ),
+    ],
+);
+
+const HELP_MACROS: ActionHelpSection = (
+    "Macros Tab",
+    &[
+        (&[Action::Up, Action::Down], "Navigate list"),
+        (&[Action::PageUp, Action::PageDown], "Page through the list"),
+        (&[Action::Top, Action::Bottom], "Jump to the first / last"),
+        (&[Action::Add], "Add new macro from a template"),
+        (&[Action::Edit], "Edit selected macro"),
+        (&[Action::Delete], "Delete selected macro"),
+        (
+            &[Action::Filter],
+            "Filter the list (Esc while typing clears it)",
+        ),
+    ],
+);
+
+const HELP_MONITOR: ActionHelpSection = (
+    "Monitor Tab",
+    &[
+        (&[Action::PauseMonitor], "Pause/resume"),
+        (&[Action::Clear], "Clear events"),
+        (&[Action::HidReports], "Show/hide raw HID reports"),
+        (
+            &[Action::SplitMonitor],
+            "Split into a pane per device / one stream",
+        ),
+    ],
+);
+
+const HELP_LOGS: ActionHelpSection = (
+    "Logs Tab",
+    &[
+        (&[Action::Up, Action::Down], "Scroll"),
+        (&[Action::PageUp, Action::PageDown], "Scroll a page"),
+        (
+            &[Action::Top, Action::Bottom],
+            "Oldest line / follow new ones",
+        ),
+        (&[Action::LogLevel], "Cycle minimum level"),
+        (&[Action::Filter], "Filter by text"),
+        (&[Action::Clear], "Clear the text filter"),
+        (&[Action::LogVerbosity], "Cycle log file verbosity"),
+        (&[Action::EngineDebug], "Toggle engine debug logging"),
+    ],
+);
+
+const HELP_HARDWARE: ActionHelpSection = (
+    "Hardware Tab (ratbagd)",
+    &[
+        (&[Action::NextSection], "Switch profiles/DPI/buttons"),
+        (&[Action::Select], "Activate profile or DPI stage"),
+        (
+            &[Action::Increase, Action::Decrease],
+            "Change rate, DPI or button",
+        ),
+        (&[Action::DefaultDpi], "Make DPI stage the default"),
+        (&[Action::Commit], "Write changes to the mouse"),
+        (&[Action::NextDevice], "Next device"),
+        (&[Action::Refresh], "Refresh"),
+    ],
+);
+
+const HELP_BINDING_DIALOG: HelpSection = (
+    "Binding Dialog",
+    &[
+        ("Up/Down", "Navigate fields (macro list on Target)"),
+        ("Enter", "Capture button/key, or save"),
+        ("Tab", "Cycle action type / target options"),
+        ("c/s/a/m", "Require Ctrl/Shift/Alt/Meta (input field)"),
+        ("Backspace", "Clear or erase the field"),
+        ("When mode", "Only apply while a mode is on (!name = off)"),
+        ("Ctrl+S", "Save"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const HELP_MACRO_DIALOG: HelpSection = (
+    "Macro Dialog",
+    &[
+        ("Up/Down", "Navigate fields"),
+        ("Tab", "Cycle type / flip yes-no fields"),
+        ("Typing", "Name, parameters, numbers and patterns"),
+        ("Enter on Actions", "Edit the steps"),
+        ("Enter / Ctrl+S", "Save"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const HELP_MACRO_STEPS: HelpSection = (
+    "Macro Steps",
+    &[
+        ("Up/Down", "Select step"),
+        ("a / d", "Add / delete step"),
+        ("J / K", "Move step down / up"),
+        ("Tab", "Cycle Click/Press/Release/Delay/Choice"),
+        ("Enter", "Capture key for step"),
+        ("c / p", "Capture a key as a new Click / Press step"),
+        ("r", "Run another macro (again: cycle which)"),
+        ("v", "Use a macro parameter as the step's key"),
+        ("0-9 / Backspace", "Edit delay, click hold or weight"),
+        ("Ctrl+S", "Save the macro"),
+        ("Esc", "Back to the fields"),
+    ],
+);
+
+const HELP_WIZARD: HelpSection = (
+    "Binding Wizard",
+    &[
+        ("Enter", "Capture (when empty) / next step"),
+        ("c", "Capture the button or key again"),
+        ("Up/Down", "Choose the action or target"),
+        ("Tab", "Flip scroll axis / mode switch"),
+        ("Typing", "Scroll amount, mode name or warp target"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const HELP_GRID_EDITOR: HelpSection = (
+    "Bulk Editor",
+    &[
+        ("Up/Down or J/K", "Select row"),
+        ("i", "Capture the row's button"),
+        ("Enter", "Capture the key the button sends"),
+        ("l", "Learn buttons in order from the selected row"),
+        ("Tab", "Cycle a macro as the output"),
+        ("d / Del", "Clear the row's output"),
+        ("+", "Add a row"),
+        ("Ctrl+S", "Apply to the profile"),
+        ("Esc", "Close without changes"),
+    ],
+);
+
+const HELP_TEMPLATES: HelpSection = (
+    "Macro Templates",
+    &[
+        ("Up/Down or J/K", "Choose a template"),
+        ("Enter", "Start a macro from it"),
+        ("Esc", "Cancel"),
+    ],
+);
+
+const HELP_PALETTE: HelpSection = (
+    "Command Palette",
+    &[
+        ("Typing", "Filter (letters in order, e.g. \"swp\")"),
+        ("Up/Down", "Select"),
+        ("Enter", "Run the selected action"),
+        ("Esc", "Close"),
+    ],
+);
+
+const HELP_TEXT_PROMPT: HelpSection = (
+    "Text Entry",
+    &[
+        ("Typing / Backspace", "Edit the text"),
+        ("Enter", "Accept"),
+        ("Esc", "Cancel (filters: clear)"),
+    ],
+);
+
+/// The help sections for what's on screen: the open dialog's keys, or the
+/// global ones and the current tab's
+fn help_sections(app: &App) -> Vec<(&'static str, Vec<(String, &'static str)>)> {
+    let fixed = |(title, rows): HelpSection| {
+        let rows = rows.iter().map(|(k, d)| (k.to_string(), *d)).collect();
+        (title, rows)
+    };
+    let from_keymap = |(title, rows): ActionHelpSection| {
+        let rows = rows
+            .iter()
+            .map(|(actions, d)| {
+                let keys: Vec<String> = actions.iter().map(|a| app.keymap.keys(*a)).collect();
+                (keys.join(", "), *d)
+            })
+            .collect();
+        (title, rows)
+    };
+    if app.input_mode == InputMode::Normal {
+        let tab = match app.current_tab {
+            Tab::Devices => HELP_DEVICES,
+            Tab::Bindings => HELP_BINDINGS,
+            Tab::Macros => HELP_MACROS,
+            Tab::Monitor => HELP_MONITOR,
+            Tab::Logs => HELP_LOGS,
+            Tab::Hardware => HELP_HARDWARE,
+        };
+        return vec![from_keymap(HELP_GLOBAL), from_keymap(tab)];
+    }
+    let dialog = if app.command_palette.is_some() {
+        HELP_PALETTE
+    } else if app.wizard_step.is_some() {
+        HELP_WIZARD
+    } else if app.grid_editor.is_some() {
+        HELP_GRID_EDITOR
+    } else if app.editing_binding.is_some() {
+        HELP_BINDING_DIALOG
+    } else if let Some(editing) = &app.editing_macro {
+        if editing.actions_focused {
+            HELP_MACRO_STEPS
+        } else {
+            HELP_MACRO_DIALOG
+        }
+    } else if app.template_picker.is_some() {
+        HELP_TEMPLATES
+    } else {
+        HELP_TEXT_PROMPT
+    };
+    vec![fixed(dialog)]
+}
+
+/// Render the help overlay for the current tab or dialog
+pub fn render_help(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let sections = help_sections(app);
+    let mut help_text = vec![
         Line::from(Span::styled(
             " Mouse Mapper - Keyboard Shortcuts ",
             Style::default()
-                .fg(Color::Cyan)
+                .fg(theme.accent)
                 .add_modifier(Modifier::BOLD),
         )),
         Line::from(""),
-        Line::from(Span::styled(" Global:", Style::default().fg(Color::Yellow))),
-        Line::from("   Left/Right or H/L  Switch tabs"),
-        Line::from("   q                   Quit"),
-        Line::from("   s                   Save config to disk"),
-        Line::from("   ?                   Toggle this help"),
+    ];
+    for (i, (title, rows)) in sections.iter().enumerate() {
+        if i > 0 {
+            help_text.push(Line::from(""));
+        }
+        help_text.push(Line::from(Span::styled(
+            format!(" {}:", title),
+            Style::default().fg(theme.highlight),
+        )));
+        for (keys, description) in rows.iter() {
+            help_text.push(Line::from(format!("   {:<22}{}", keys, description)));
+        }
+    }
+
+    // Center the help dialog
+    let dialog_width = 66.min(area.width.saturating_sub(4));
+    let dialog_height = (help_text.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    f.render_widget(ratatui::widgets::Clear, dialog_area);
+
+    let paragraph = Paragraph::new(help_text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Help (any key to close) ")
+            .border_style(Style::default().fg(theme.accent)),
+    );
+
+    f.render_widget(paragraph, dialog_area);
+}
+
+/// Render the command palette: the query and the actions matching it
+pub fn render_command_palette(f: &mut Frame, app: &App, area: Rect) {
+    let Some(ref state) = app.command_palette else {
+        return;
+    };
+    let theme = &app.theme;
+    let matches = palette::matches(app, &state.query);
+
+    let dialog_width = 70.min(area.width.saturating_sub(4));
+    let dialog_height = 22.min(area.height.saturating_sub(4));
+    // Query, blank line above and below the list, hint
+    let rows = dialog_height.saturating_sub(6) as usize;
+    let offset = (state.selected + 1).saturating_sub(rows);
+
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("  > {}_", state.query),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    if matches.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "    No command matches",
+            Style::default().fg(theme.hint),
+        )));
+    }
+    let label_width = (dialog_width as usize).saturating_sub(22);
+    for (i, entry) in matches.iter().enumerate().skip(offset).take(rows) {
+        let is_selected = i == state.selected;
+        let prefix = if is_selected { "  > " } else { "    " };
+        let style = if is_selected {
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme.text)
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{}{:<width$}", prefix, entry.label, width = label_width),
+                style,
+            ),
+            Span::styled(entry.key.clone(), Style::default().fg(theme.hint)),
+        ]));
+    }
+    while lines.len() < rows + 2 {
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!(
+            "  {} match(es)  Up/Down=select  Enter=run  Esc=close",
+            matches.len()
+        ),
+        Style::default().fg(theme.hint),
+    )));
+
+    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    f.render_widget(ratatui::widgets::Clear, dialog_area);
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Command Palette ")
+            .border_style(Style::default().fg(theme.accent)),
+    );
+
+    f.render_widget(paragraph, dialog_area);
+}
+
+/// Render the status message history, newest first
+pub fn render_notifications(f: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let dialog_width = 90.min(area.width.saturating_sub(4));
+    let dialog_height = 24.min(area.height.saturating_sub(4));
+    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    let lines: Vec<Line> = if app.notifications.is_empty() {
+        vec![Line::styled(
+            "  No messages yet",
+            Style::default().fg(theme.hint),
+        )]
+    } else {
+        app.notifications
+            .iter()
+            .rev()
+            .take(dialog_height.saturating_sub(2) as usize)
+            .map(|n| {
+                let (label, color) = match n.severity {
+                    Severity::Info => ("info ", theme.info),
+                    Severity::Warning => ("warn ", theme.highlight),
+                    Severity::Error => ("error", theme.error),
+                };
+                let secs = n.at.elapsed().as_secs();
+                let ago = if secs < 60 {
+                    format!("{}s", secs)
+                } else if secs < 3600 {
+                    format!("{}m", secs / 60)
+                } else {
+                    format!("{}h", secs / 3600)
+                };
+                Line::from(vec![
+                    Span::styled(
+                        format!(" {:>4} ago ", ago),
+                        Style::default().fg(theme.muted),
+                    ),
+                    Span::styled(format!("{} ", label), Style::default().fg(color)),
+                    Span::styled(n.text.clone(), Style::default().fg(theme.text)),
+                ])
+            })
+            .collect()
+    };
+
+    f.render_widget(ratatui::widgets::Clear, dialog_area);
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Messages (newest first, any key to close) ")
+            .border_style(Style::default().fg(theme.accent)),
+    );
+
+    f.render_widget(paragraph, dialog_area);
+}
+
+/// Render the diff of a pending save with its confirm keys
+pub fn render_save_preview(f: &mut Frame, theme: &Theme, preview: &SavePreview, area: Rect) {
+    let dialog_width = 100.min(area.width.saturating_sub(4));
+    let dialog_height = 40.min(area.height.saturating_sub(4));
+    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    let lines: Vec<Line> = preview
+        .lines
+        .iter()
+        .skip(preview.scroll)
+        .take(dialog_height.saturating_sub(2) as usize)
+        .map(|line| {
+            let style = if line.starts_with("+++") || line.starts_with("---") {
+                Style::default().fg(theme.text).add_modifier(Modifier::BOLD)
+            } else if line.starts_with("@@") {
+                Style::default().fg(theme.accent)
+            } else if line.starts_with('+') {
+                Style::default().fg(theme.success)
+            } else if line.starts_with('-') {
+                Style::default().fg(theme.error)
+            } else {
+                Style::default().fg(theme.muted)
+            };
+            Line::styled(line.clone(), style)
+        })
+        .collect();
+
+    f.render_widget(ratatui::widgets::Clear, dialog_area);
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                " Save changes? ({}/{}) ",
+                (preview.scroll + 1).min(preview.lines.len()),
+                preview.lines.len()
+            ))
+            .title_bottom(" y/Enter=save, Esc/n=cancel, Up/Down/PgUp/PgDn=scroll ")
+            .border_style(Style::default().fg(theme.highlight)),
+    );
+
+    f.render_widget(paragraph, dialog_area);
+}
+
+/// Render a small centered yes/no prompt
+pub fn render_confirm(f: &mut Frame, theme: &Theme, message: &str, hint: &str, area: Rect) {
+    let lines = vec![
         Line::from(""),
         Line::from(Span::styled(
-            " Devices Tab:",
-            Style::default().fg(Color::Yellow),
+            format!("  {}", message),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
         )),
-        Line::from("   Up/Down or J/K      Navigate device list"),
-        Line::from("   Enter               Select device"),
-        Line::from("   Space               Start/stop engine"),
-        Line::from("   r                   Refresh device list"),
         Line::from(""),
         Line::from(Span::styled(
-            " Bindings/Macros Tab:",
-            Style::default().fg(Color::Yellow),
+            format!("  {}", hint),
+            Style::default().fg(theme.hint),
         )),
-        Line::from("   Up/Down or J/K      Navigate list"),
-        Line::from("   a                   Add new entry"),
-        Line::from("   e                   Edit selected entry"),
-        Line::from("   d                   Delete selected entry"),
+    ];
+
+    let dialog_width = 50.min(area.width.saturating_sub(4));
+    let dialog_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
+    let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
+
+    f.render_widget(ratatui::widgets::Clear, dialog_area);
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(" Confirm ")
+            .border_style(Style::default().fg(theme.highlight)),
+    );
+
+    f.render_widget(paragraph, dialog_area);
+}
+
+/// Render a small centered single-line text input
+pub fn render_prompt(
+    f: &mut Frame,
+    theme: &Theme,
+    title: &str,
+    label: &str,
+    value: &str,
+    area: Rect,
+) {
+    let lines = vec![
         Line::from(""),
         Line::from(Span::styled(
-            " Edit Dialog:",
-            Style::default().fg(Color::Yellow),
+            format!("  {}", label),
+            Style::default().fg(theme.highlight),
+        )),
+        Line::from(Span::styled(
+            format!("  [{}_]", value),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
         )),
-        Line::from("   Up/Down             Navigate fields"),
-        Line::from("   Tab                 Cycle through options"),
-        Line::from("   Enter               Save"),
-        Line::from("   Esc                 Cancel"),
         Line::from(""),
         Line::from(Span::styled(
-            " Monitor Tab:",
-            Style::default().fg(Color::Yellow),
+            "  Enter=confirm  Esc=cancel",
+            Style::default().fg(theme.hint),
         )),
-        Line::from("   p                   Pause/resume"),
-        Line::from("   c                   Clear events"),
     ];
 
-    // Center the help dialog
-    let dialog_width = 55.min(area.width.saturating_sub(4));
-    let dialog_height = (help_text.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let dialog_width = 70.min(area.width.saturating_sub(4));
+    let dialog_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(4));
     let x = area.x + (area.width.saturating_sub(dialog_width)) / 2;
     let y = area.y + (area.height.saturating_sub(dialog_height)) / 2;
     let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
 
     f.render_widget(ratatui::widgets::Clear, dialog_area);
 
-    let paragraph = Paragraph::new(help_text).block(
+    let paragraph = Paragraph::new(lines).block(
         Block::default()
             .borders(Borders::ALL)
-            .title(" Help ")
-            .border_style(Style::default().fg(Color::Cyan)),
+            .title(title.to_string())
+            .border_style(Style::default().fg(theme.accent)),
     );
 
     f.render_widget(paragraph, dialog_area);